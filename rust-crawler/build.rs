@@ -0,0 +1,34 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // Migrations are embedded with `sqlx::migrate!`
+    println!("cargo:rerun-if-changed=migrations");
+    #[cfg(feature = "grpc")]
+    grpc::generate();
+}
+
+/// Server stubs for `proto/crawler.proto`. The message types are hand-written in
+/// `src/grpc.rs` (prost derives), so building doesn't need protoc.
+#[cfg(feature = "grpc")]
+mod grpc {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    fn method(name: &str, route: &str, input: &str, output: &str) -> tonic_build::manual::MethodBuilder {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("crate::grpc::proto::{}", input))
+            .output_type(format!("crate::grpc::proto::{}", output))
+            .codec_path("tonic::codec::ProstCodec")
+    }
+
+    pub fn generate() {
+        let service = Service::builder()
+            .name("Crawler")
+            .package("crawler.v1")
+            .method(method("submit_crawl", "SubmitCrawl", "SubmitCrawlRequest", "SubmitCrawlResponse").build())
+            .method(method("get_task", "GetTask", "GetTaskRequest", "Task").build())
+            .method(method("stream_task_events", "StreamTaskEvents", "StreamTaskEventsRequest", "TaskEvent").server_streaming().build())
+            .build();
+        Builder::new().build_client(false).compile(&[service]);
+    }
+}
@@ -9,7 +9,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
     
     let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    println!("🔌 Connecting to: {}", db_url.split('@').last().unwrap_or("???")); 
+    println!("🔌 Connecting to: {}", db_url.split('@').next_back().unwrap_or("???")); 
 
     let opts = sqlx::postgres::PgConnectOptions::from_url(&db_url.parse().unwrap())
         .expect("Invalid DATABASE_URL")
@@ -23,12 +23,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .connect_with(opts)
         .await?;
 
-    println!("✅ Connected! Applying Migration...");
+    println!("✅ Connected! Applying migrations...");
 
-    match sqlx::query("ALTER TABLE tasks ADD COLUMN IF NOT EXISTS marketing_data JSONB;").execute(&pool).await {
-        Ok(_) => println!("✅ Migration Success: 'marketing_data' column added (or already existed)."),
-        Err(e) => println!("❌ Migration Failed: {}", e),
+    rust_crawler::db::run_migrations(&pool).await?;
+    let version = rust_crawler::db::schema_version(&pool).await?;
+    for migration in &version.migrations {
+        println!("- {:04} {}: {:?}", migration.version, migration.description, migration.state);
     }
+    println!("✅ Schema at version {:?} (build ships {})", version.current, version.expected);
 
     Ok(())
 }
@@ -1,6 +1,4 @@
 use rust_crawler::crawler;
-use rust_crawler::stealth;
-use rust_crawler::ml;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -11,7 +9,7 @@ async fn main() -> anyhow::Result<()> {
     
     // 1. Run Search
     println!("🔎 Searching for: {}", keyword);
-    let result = crawler::search_google(keyword).await;
+    let result = crawler::search_google(keyword, &Default::default()).await;
     
     match result {
         Ok(data) => {
@@ -24,7 +22,7 @@ async fn main() -> anyhow::Result<()> {
             
             if let Some(first_result) = data.results.first() {
                 println!("🌐 Visiting first result: {}", first_result.link);
-                match crawler::extract_website_data(&first_result.link).await {
+                match crawler::extract_website_data(&first_result.link, &Default::default()).await {
                     Ok(site_data) => {
                         println!("✅ Extraction SUCCESS!");
                         println!("Title: {}", site_data.title);
@@ -0,0 +1,61 @@
+-- Crawl tasks and their extracted data
+CREATE TABLE IF NOT EXISTS tasks (
+    id VARCHAR PRIMARY KEY,
+    keyword VARCHAR NOT NULL,
+    engine VARCHAR NOT NULL DEFAULT 'bing',
+    status VARCHAR NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    results_json TEXT,
+    extracted_text TEXT,
+    first_page_html TEXT,
+    meta_description TEXT,
+    meta_author TEXT,
+    meta_date TEXT,
+    emails JSONB,
+    phone_numbers JSONB,
+    outbound_links JSONB,
+    images JSONB,
+    sentiment TEXT,
+    entities JSONB,
+    category TEXT,
+    marketing_data JSONB,
+    -- Retry bookkeeping
+    attempts INTEGER DEFAULT 0,
+    last_error TEXT,
+    -- Progress of running tasks (stage name + percent)
+    stage TEXT,
+    progress INTEGER DEFAULT 0,
+    -- Owner (submitting user); tasks from before ownership tracking have none and only admins see them
+    user_id VARCHAR,
+    -- When the task last completed; Parquet dumps pick up tasks completed since the previous dump
+    completed_at TIMESTAMP
+);
+
+-- Databases created by the old startup code have the original columns only
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS emails JSONB;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS phone_numbers JSONB;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS outbound_links JSONB;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS images JSONB;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS sentiment TEXT;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS entities JSONB;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS category TEXT;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS marketing_data JSONB;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS attempts INTEGER DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS last_error TEXT;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS stage TEXT;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS progress INTEGER DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS user_id VARCHAR;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS completed_at TIMESTAMP;
+
+-- Full-text search (GET /tasks/search): keyword first, then meta description, then page text.
+-- Text is capped so very large pages stay under Postgres' tsvector size limit.
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', coalesce(keyword, '')), 'A') ||
+    setweight(to_tsvector('simple', coalesce(meta_description, '')), 'B') ||
+    setweight(to_tsvector('simple', left(coalesce(extracted_text, ''), 200000)), 'C')
+) STORED;
+
+CREATE INDEX IF NOT EXISTS tasks_user_idx ON tasks (user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS tasks_search_idx ON tasks USING GIN (search_vector);
+-- Admin listings sort the whole table by creation time
+CREATE INDEX IF NOT EXISTS tasks_created_idx ON tasks (created_at DESC);
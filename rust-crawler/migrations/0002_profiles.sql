@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS profiles (
+    id VARCHAR PRIMARY KEY,
+    email VARCHAR NOT NULL UNIQUE,
+    name VARCHAR,
+    avatar_url TEXT,
+    bio TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    -- Account (JWT subject) the profile belongs to; set when users create their own profile
+    user_id VARCHAR
+);
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS user_id VARCHAR;
@@ -0,0 +1,30 @@
+CREATE TABLE IF NOT EXISTS organizations (
+    id VARCHAR PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    daily_crawl_quota INTEGER,
+    monthly_crawl_quota INTEGER,
+    proxy_ids TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- One organization per user
+CREATE TABLE IF NOT EXISTS organization_members (
+    user_id VARCHAR PRIMARY KEY,
+    org_id VARCHAR NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    email VARCHAR,
+    role VARCHAR(10) NOT NULL DEFAULT 'member',
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS organization_members_org_idx ON organization_members (org_id);
+
+CREATE TABLE IF NOT EXISTS organization_invites (
+    id VARCHAR PRIMARY KEY,
+    org_id VARCHAR NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    email VARCHAR NOT NULL,
+    role VARCHAR(10) NOT NULL DEFAULT 'member',
+    token VARCHAR NOT NULL UNIQUE,
+    invited_by VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    accepted_at TIMESTAMPTZ
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS crawl_usage (
+    user_id VARCHAR NOT NULL,
+    day DATE NOT NULL,
+    count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, day)
+);
+
+-- Per-profile overrides; NULL means the default
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS daily_crawl_quota INTEGER;
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS monthly_crawl_quota INTEGER;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS payments (
+    id VARCHAR PRIMARY KEY,
+    user_id VARCHAR NOT NULL,
+    amount INTEGER NOT NULL,
+    currency VARCHAR(3) DEFAULT 'USD',
+    status VARCHAR(20) DEFAULT 'pending',
+    stripe_id VARCHAR(100),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    provider VARCHAR(20),
+    provider_ref VARCHAR(100),
+    refund_ref VARCHAR(100)
+);
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS provider VARCHAR(20);
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS provider_ref VARCHAR(100);
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS refund_ref VARCHAR(100);
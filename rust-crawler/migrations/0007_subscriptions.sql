@@ -0,0 +1,28 @@
+CREATE TABLE IF NOT EXISTS plans (
+    id VARCHAR(20) PRIMARY KEY,
+    name VARCHAR(50) NOT NULL,
+    price_cents INTEGER NOT NULL DEFAULT 0,
+    monthly_crawl_quota INTEGER NOT NULL,
+    proxies_allowed BOOLEAN NOT NULL DEFAULT FALSE,
+    max_crawl_depth INTEGER NOT NULL DEFAULT 1,
+    stripe_price_id VARCHAR(100)
+);
+
+-- Defaults; edit the rows to retune tiers without a deploy
+INSERT INTO plans (id, name, price_cents, monthly_crawl_quota, proxies_allowed, max_crawl_depth) VALUES
+    ('free', 'Free', 0, 1000, FALSE, 3),
+    ('pro', 'Pro', 4900, 20000, TRUE, 10),
+    ('business', 'Business', 19900, 200000, TRUE, 50)
+ON CONFLICT (id) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS subscriptions (
+    user_id VARCHAR PRIMARY KEY,
+    plan_id VARCHAR(20) NOT NULL REFERENCES plans(id),
+    status VARCHAR(20) NOT NULL,
+    stripe_customer_id VARCHAR(100),
+    stripe_subscription_id VARCHAR(100) UNIQUE,
+    current_period_end TIMESTAMPTZ,
+    cancel_at_period_end BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ DEFAULT now(),
+    updated_at TIMESTAMPTZ DEFAULT now()
+);
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS credit_balances (
+    user_id VARCHAR PRIMARY KEY,
+    balance BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS credit_ledger (
+    id BIGSERIAL PRIMARY KEY,
+    user_id VARCHAR NOT NULL,
+    delta BIGINT NOT NULL,
+    reason VARCHAR(20) NOT NULL,
+    crawl_kind VARCHAR(20),
+    task_id VARCHAR,
+    payment_id VARCHAR,
+    balance_after BIGINT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS credit_ledger_user_idx ON credit_ledger (user_id, id);
+-- At most one debit and one refund per task, and one purchase and one reversal per payment
+CREATE UNIQUE INDEX IF NOT EXISTS credit_ledger_task_idx ON credit_ledger (task_id, reason) WHERE task_id IS NOT NULL;
+DROP INDEX IF EXISTS credit_ledger_payment_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS credit_ledger_payment_reason_idx ON credit_ledger (payment_id, reason) WHERE payment_id IS NOT NULL;
+
+-- Credits bought with each payment
+ALTER TABLE payments ADD COLUMN IF NOT EXISTS credits INTEGER;
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS usage_events (
+    id BIGSERIAL PRIMARY KEY,
+    user_id VARCHAR NOT NULL,
+    metric VARCHAR(20) NOT NULL,
+    engine VARCHAR(20),
+    task_id VARCHAR,
+    quantity BIGINT NOT NULL,
+    recorded_at TIMESTAMPTZ DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS usage_events_user_idx ON usage_events (user_id, recorded_at);
@@ -0,0 +1,35 @@
+CREATE TABLE IF NOT EXISTS notifications (
+    id VARCHAR PRIMARY KEY,
+    user_id VARCHAR NOT NULL,
+    notification_type VARCHAR(20) DEFAULT 'email',
+    subject VARCHAR(255),
+    message TEXT NOT NULL,
+    read BOOLEAN DEFAULT FALSE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    email_status VARCHAR(20),
+    email_attempts INTEGER,
+    email_id VARCHAR(100),
+    email_error TEXT
+);
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS email_status VARCHAR(20);
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS email_attempts INTEGER;
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS email_id VARCHAR(100);
+ALTER TABLE notifications ADD COLUMN IF NOT EXISTS email_error TEXT;
+CREATE INDEX IF NOT EXISTS notifications_unread_idx ON notifications (user_id) WHERE read = FALSE;
+
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id VARCHAR NOT NULL,
+    event VARCHAR(20) NOT NULL,
+    destinations TEXT[] NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT now(),
+    PRIMARY KEY (user_id, event)
+);
+
+CREATE TABLE IF NOT EXISTS notification_channels (
+    id VARCHAR PRIMARY KEY,
+    user_id VARCHAR NOT NULL,
+    kind VARCHAR(20) NOT NULL,
+    target TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ DEFAULT now()
+);
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS schedules (
+    id VARCHAR PRIMARY KEY,
+    owner VARCHAR NOT NULL,
+    cron VARCHAR NOT NULL,
+    keyword TEXT NOT NULL,
+    engine VARCHAR NOT NULL DEFAULT 'bing',
+    options JSONB NOT NULL DEFAULT '{}',
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMPTZ,
+    last_run_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT now(),
+    updated_at TIMESTAMPTZ,
+    timezone VARCHAR NOT NULL DEFAULT 'UTC',
+    catch_up VARCHAR NOT NULL DEFAULT 'run_once'
+);
+ALTER TABLE schedules ADD COLUMN IF NOT EXISTS timezone VARCHAR NOT NULL DEFAULT 'UTC';
+ALTER TABLE schedules ADD COLUMN IF NOT EXISTS catch_up VARCHAR NOT NULL DEFAULT 'run_once';
+CREATE INDEX IF NOT EXISTS schedules_due_idx ON schedules (next_run_at) WHERE enabled;
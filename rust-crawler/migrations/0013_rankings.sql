@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS tracked_domains (
+    id VARCHAR PRIMARY KEY,
+    user_id VARCHAR NOT NULL,
+    domain VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT now(),
+    competitor BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE (user_id, domain)
+);
+ALTER TABLE tracked_domains ADD COLUMN IF NOT EXISTS competitor BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS rankings (
+    id BIGSERIAL PRIMARY KEY,
+    task_id VARCHAR NOT NULL,
+    user_id VARCHAR NOT NULL,
+    keyword TEXT NOT NULL,
+    engine VARCHAR NOT NULL,
+    country VARCHAR,
+    domain VARCHAR NOT NULL,
+    position INTEGER,
+    url TEXT,
+    checked_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS rankings_history_idx ON rankings (user_id, domain, keyword, checked_at);
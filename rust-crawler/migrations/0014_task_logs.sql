@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS task_logs (
+    id BIGSERIAL PRIMARY KEY,
+    task_id VARCHAR NOT NULL,
+    user_id VARCHAR,
+    attempt INTEGER NOT NULL,
+    level VARCHAR(8) NOT NULL,
+    message TEXT NOT NULL,
+    fields JSONB NOT NULL DEFAULT '{}',
+    logged_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS task_logs_task_idx ON task_logs (task_id, id);
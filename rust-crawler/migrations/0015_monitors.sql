@@ -0,0 +1,29 @@
+CREATE TABLE IF NOT EXISTS page_monitors (
+    id VARCHAR PRIMARY KEY,
+    owner VARCHAR NOT NULL,
+    url TEXT NOT NULL,
+    cron VARCHAR NOT NULL,
+    timezone VARCHAR NOT NULL DEFAULT 'UTC',
+    threshold DOUBLE PRECISION NOT NULL DEFAULT 0.05,
+    options JSONB NOT NULL DEFAULT '{}',
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMPTZ,
+    last_checked_at TIMESTAMPTZ,
+    last_changed_at TIMESTAMPTZ,
+    content_hash VARCHAR,
+    content TEXT,
+    created_at TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS page_monitor_changes (
+    id BIGSERIAL PRIMARY KEY,
+    monitor_id VARCHAR NOT NULL,
+    task_id VARCHAR NOT NULL,
+    change_ratio DOUBLE PRECISION NOT NULL,
+    added_lines INTEGER NOT NULL,
+    removed_lines INTEGER NOT NULL,
+    summary TEXT NOT NULL,
+    notified BOOLEAN NOT NULL DEFAULT FALSE,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS page_monitor_changes_monitor_idx ON page_monitor_changes (monitor_id, detected_at);
@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS alert_rules (
+    id VARCHAR PRIMARY KEY,
+    owner VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    keyword TEXT,
+    engine VARCHAR,
+    condition JSONB NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_triggered_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS alert_rules_owner_idx ON alert_rules (owner) WHERE enabled;
+
+-- Whether each rule's condition held on the last crawl of an engine/keyword
+CREATE TABLE IF NOT EXISTS alert_rule_states (
+    rule_id VARCHAR NOT NULL,
+    subject TEXT NOT NULL,
+    matched BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (rule_id, subject)
+);
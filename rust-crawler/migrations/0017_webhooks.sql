@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS webhook_secrets (
+    user_id VARCHAR PRIMARY KEY,
+    secret VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT now()
+);
+
+-- One row per attempt; attempts of the same event share a delivery_id
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    delivery_id VARCHAR NOT NULL,
+    user_id VARCHAR NOT NULL,
+    task_id VARCHAR NOT NULL,
+    event VARCHAR NOT NULL,
+    url TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    status_code INTEGER,
+    error TEXT,
+    attempted_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS webhook_deliveries_user_idx ON webhook_deliveries (user_id, delivery_id, attempt);
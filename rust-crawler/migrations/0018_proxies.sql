@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS proxies (
+    id VARCHAR PRIMARY KEY,
+    host VARCHAR NOT NULL,
+    port INTEGER NOT NULL,
+    protocol VARCHAR NOT NULL DEFAULT 'http',
+    username VARCHAR,
+    password VARCHAR,
+    healthy BOOLEAN NOT NULL DEFAULT TRUE,
+    fail_count INTEGER NOT NULL DEFAULT 0,
+    success_count BIGINT NOT NULL DEFAULT 0,
+    total_requests BIGINT NOT NULL DEFAULT 0,
+    last_used BIGINT NOT NULL DEFAULT 0,
+    latency_ms BIGINT NOT NULL DEFAULT 0,
+    last_checked BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP,
+    source VARCHAR,
+    country VARCHAR(2)
+);
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS source VARCHAR;
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS country VARCHAR(2);
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS exports (
+    id VARCHAR PRIMARY KEY,
+    user_id VARCHAR NOT NULL,
+    -- Export everyone's tasks (requested by an admin)
+    all_users BOOLEAN NOT NULL DEFAULT FALSE,
+    format VARCHAR(10) NOT NULL,
+    filters JSONB NOT NULL DEFAULT '{}',
+    status VARCHAR(10) NOT NULL DEFAULT 'pending',
+    task_count INTEGER,
+    size_bytes BIGINT,
+    storage_key VARCHAR,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+CREATE INDEX IF NOT EXISTS exports_user_idx ON exports (user_id, created_at DESC);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS parquet_dumps (
+    id VARCHAR PRIMARY KEY,
+    -- Tasks completed after the previous completed dump's window_end, up to this one
+    window_end TIMESTAMP NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'running',
+    row_count BIGINT,
+    files TEXT[] NOT NULL DEFAULT '{}',
+    error TEXT,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    completed_at TIMESTAMPTZ
+);
+CREATE INDEX IF NOT EXISTS tasks_completed_idx ON tasks (completed_at) WHERE status = 'completed';
@@ -0,0 +1,33 @@
+-- Used by the Postgres queue backend (QUEUE_BACKEND=postgres); empty with Redis
+CREATE TABLE IF NOT EXISTS crawl_job_queue (
+    id BIGSERIAL PRIMARY KEY,
+    lane VARCHAR NOT NULL,
+    priority SMALLINT NOT NULL,
+    payload TEXT NOT NULL,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    claimed_by VARCHAR,
+    claimed_at TIMESTAMPTZ
+);
+CREATE INDEX IF NOT EXISTS crawl_job_queue_ready_idx ON crawl_job_queue (lane, priority, run_at) WHERE claimed_by IS NULL;
+
+CREATE TABLE IF NOT EXISTS crawl_dedup (
+    key VARCHAR PRIMARY KEY,
+    task_id VARCHAR NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS crawl_job_outcomes (
+    minute TIMESTAMPTZ PRIMARY KEY,
+    processed INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS crawl_queue_flags (
+    name VARCHAR PRIMARY KEY,
+    enabled BOOLEAN NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS crawl_worker_heartbeats (
+    worker_id VARCHAR PRIMARY KEY,
+    expires_at TIMESTAMPTZ NOT NULL
+);
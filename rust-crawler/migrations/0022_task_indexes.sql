@@ -0,0 +1,18 @@
+-- Indexes for task listings and lookups. tasks(created_at DESC) and
+-- tasks(user_id, created_at DESC) come from 0001_tasks.
+-- Builds in the migration's transaction, blocking writes to tasks until done.
+
+-- GET /tasks?status= and completed-task lookups (SERP diffs, search backfill); newest first within a status
+CREATE INDEX IF NOT EXISTS tasks_status_idx ON tasks (status, created_at DESC);
+
+-- Exact keyword lookups (SERP diffs, rankings history)
+CREATE INDEX IF NOT EXISTS tasks_keyword_idx ON tasks (keyword);
+
+-- GET /tasks?q= matches keywords with ILIKE '%q%', which only a trigram index can serve
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE INDEX IF NOT EXISTS tasks_keyword_trgm_idx ON tasks USING GIN (keyword gin_trgm_ops);
+
+-- Containment and key lookups on extracted data, e.g. emails ? 'sales@example.com'
+-- or entities @> '[{"label": "ORG"}]'
+CREATE INDEX IF NOT EXISTS tasks_entities_idx ON tasks USING GIN (entities);
+CREATE INDEX IF NOT EXISTS tasks_emails_idx ON tasks USING GIN (emails);
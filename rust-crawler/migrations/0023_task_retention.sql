@@ -0,0 +1,9 @@
+-- Days a plan's finished tasks are kept before archiving; NULL uses the global
+-- `[retention] days` / RETENTION_DAYS
+ALTER TABLE plans ADD COLUMN IF NOT EXISTS retention_days INTEGER;
+
+-- Set once a task's page text and HTML have moved to object storage
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS archived_at TIMESTAMPTZ;
+
+-- Tasks still waiting for the retention job, oldest first
+CREATE INDEX IF NOT EXISTS tasks_unarchived_idx ON tasks (created_at, id) WHERE archived_at IS NULL;
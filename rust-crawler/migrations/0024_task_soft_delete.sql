@@ -0,0 +1,5 @@
+-- Set by DELETE /tasks/{id}; the task is hidden and can be restored until the
+-- retention job purges it `[retention] deleted_grace_days` later
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS tasks_deleted_idx ON tasks (deleted_at) WHERE deleted_at IS NOT NULL;
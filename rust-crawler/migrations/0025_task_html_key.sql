@@ -0,0 +1,4 @@
+-- Raw HTML lives in object storage only; the row keeps its key. first_page_html
+-- is only read for rows written before this migration, until
+-- `cargo run --bin html_backfill` moves them to storage.
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS html_key TEXT;
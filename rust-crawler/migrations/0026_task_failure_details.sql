@@ -0,0 +1,4 @@
+-- Where the last failed attempt broke (serp, extract or store), next to
+-- last_error, and the proxy the last attempt went through
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS failure_stage TEXT;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS proxy_id TEXT;
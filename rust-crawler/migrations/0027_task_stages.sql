@@ -0,0 +1,5 @@
+-- How each stage of a finished job went, e.g.
+-- {"serp": "succeeded", "extract": "failed", "store": "skipped"}. Jobs whose
+-- results page was saved but whose page extraction or HTML upload failed
+-- finish as `partial` instead of `completed`.
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS stages JSONB;
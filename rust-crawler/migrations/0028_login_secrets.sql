@@ -0,0 +1,10 @@
+-- Passwords of scripted logins, held here instead of in the queued job, which
+-- carries the row's ID as `login.password_ref`. One row per task, deleted when
+-- the task finishes or fails for good.
+CREATE TABLE IF NOT EXISTS login_secrets (
+    id VARCHAR PRIMARY KEY,
+    user_id VARCHAR NOT NULL,
+    password TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+CREATE INDEX IF NOT EXISTS login_secrets_user_idx ON login_secrets (user_id);
@@ -0,0 +1,7 @@
+-- Proxy credentials are no longer stored: they come back from PROXY_LIST, the
+-- provider or a fresh POST /proxies after a restart. Only whether a proxy needs
+-- them is kept, so its row isn't loaded as an unauthenticated proxy.
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS requires_auth BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE proxies SET requires_auth = TRUE WHERE username IS NOT NULL AND password IS NOT NULL;
+ALTER TABLE proxies DROP COLUMN IF EXISTS username;
+ALTER TABLE proxies DROP COLUMN IF EXISTS password;
@@ -0,0 +1,6 @@
+-- Unix timestamp at which a proxy was taken out of rotation (0 = healthy), so its
+-- cooldown carries on across restarts. Proxies already disabled start their
+-- cooldown from their last update.
+ALTER TABLE proxies ADD COLUMN IF NOT EXISTS disabled_at BIGINT NOT NULL DEFAULT 0;
+UPDATE proxies SET disabled_at = COALESCE(EXTRACT(EPOCH FROM updated_at)::BIGINT, 0)
+WHERE NOT healthy AND disabled_at = 0;
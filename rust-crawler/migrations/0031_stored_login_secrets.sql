@@ -0,0 +1,17 @@
+-- Schedules and monitors keep their login password in login_secrets too, under
+-- `schedule:<id>` / `monitor:<id>`, with only `login.password_ref` in their options.
+INSERT INTO login_secrets (id, user_id, password)
+SELECT 'schedule:' || id, owner, options->'login'->>'password' FROM schedules
+WHERE COALESCE(options->'login'->>'password', '') <> ''
+ON CONFLICT (id) DO UPDATE SET user_id = EXCLUDED.user_id, password = EXCLUDED.password;
+UPDATE schedules
+SET options = jsonb_set(options #- '{login,password}', '{login,password_ref}', to_jsonb('schedule:' || id))
+WHERE COALESCE(options->'login'->>'password', '') <> '';
+
+INSERT INTO login_secrets (id, user_id, password)
+SELECT 'monitor:' || id, owner, options->'login'->>'password' FROM page_monitors
+WHERE COALESCE(options->'login'->>'password', '') <> ''
+ON CONFLICT (id) DO UPDATE SET user_id = EXCLUDED.user_id, password = EXCLUDED.password;
+UPDATE page_monitors
+SET options = jsonb_set(options #- '{login,password}', '{login,password_ref}', to_jsonb('monitor:' || id))
+WHERE COALESCE(options->'login'->>'password', '') <> '';
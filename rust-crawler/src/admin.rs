@@ -0,0 +1,209 @@
+//! Operations overview for admins.
+//!
+//! `GET /admin/status` gathers what's otherwise spread over logs and several
+//! endpoints: this instance's worker (pause flag, lane concurrency, running
+//! browsers), queue depth, the proxy pool, the next scheduled crawls and monitor
+//! checks, and the last hour's failure rate. Each part is read independently, so
+//! one unavailable dependency leaves its part empty and is listed in `errors`.
+//! `GET /admin/schema` compares the database's applied migrations with this build's.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use tracing::error;
+use utoipa::ToSchema;
+use crate::api::AppState;
+use crate::auth::AdminUser;
+use crate::db::SchemaVersion;
+use crate::proxy::{ProxyStats, PROXY_MANAGER};
+use crate::queue::QueueSnapshot;
+use crate::worker::LaneConcurrency;
+
+/// Upcoming scheduled runs listed
+const NEXT_RUNS_LIMIT: i64 = 10;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WorkerStatus {
+    /// This process's worker ID (`WORKER_ID`)
+    pub worker_id: String,
+    /// Workers have been paused via `POST /worker/pause`; None if the queue is unreachable
+    pub paused: Option<bool>,
+    pub lanes: Vec<LaneConcurrency>,
+    /// Chrome instances running in this process
+    pub active_browsers: usize,
+    /// `CHROME_CONCURRENCY`
+    pub browser_limit: usize,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct ScheduledRun {
+    /// `schedule` or `monitor`
+    #[schema(example = "schedule")]
+    pub kind: String,
+    pub id: String,
+    /// Keyword of a schedule, URL of a monitor
+    pub target: String,
+    #[schema(value_type = String)]
+    pub next_run_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorRate {
+    /// Jobs completed in the last hour
+    pub completed: u64,
+    /// Jobs that failed for good (retries exhausted) in the last hour
+    pub failed: u64,
+    /// failed / (completed + failed); 0 without any finished job
+    pub failure_rate: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminStatusResponse {
+    pub worker: WorkerStatus,
+    /// Queue backend in use: redis, postgres or memory
+    pub queue_backend: String,
+    pub queue: Option<QueueSnapshot>,
+    pub proxies: ProxyStats,
+    /// Next runs of enabled schedules and page monitors, soonest first
+    pub next_runs: Vec<ScheduledRun>,
+    /// Enabled schedules and monitors more than two minutes past due (the scheduler is behind)
+    pub overdue_runs: Option<i64>,
+    /// Last hour, from the queue's counters
+    pub error_rate: Option<ErrorRate>,
+    /// Parts that couldn't be read
+    pub errors: Vec<String>,
+}
+
+impl ErrorRate {
+    fn from_snapshot(snapshot: &QueueSnapshot) -> Self {
+        let (completed, failed) = (snapshot.processed_last_hour, snapshot.failed_last_hour);
+        let finished = completed + failed;
+        let failure_rate = if finished > 0 { failed as f64 / finished as f64 } else { 0.0 };
+        Self { completed, failed, failure_rate }
+    }
+}
+
+async fn next_runs(pool: &PgPool) -> Result<Vec<ScheduledRun>, sqlx::Error> {
+    sqlx::query_as::<_, ScheduledRun>(
+        r#"SELECT * FROM (
+               SELECT 'schedule' AS kind, id, keyword AS target, next_run_at FROM schedules
+               WHERE enabled AND next_run_at IS NOT NULL
+               UNION ALL
+               SELECT 'monitor' AS kind, id, url AS target, next_run_at FROM page_monitors
+               WHERE enabled AND next_run_at IS NOT NULL
+           ) runs
+           ORDER BY next_run_at
+           LIMIT $1"#,
+    )
+    .bind(NEXT_RUNS_LIMIT)
+    .fetch_all(pool)
+    .await
+}
+
+async fn overdue_runs(pool: &PgPool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"SELECT (SELECT COUNT(*) FROM schedules WHERE enabled AND next_run_at < now() - interval '2 minutes')
+                + (SELECT COUNT(*) FROM page_monitors WHERE enabled AND next_run_at < now() - interval '2 minutes')"#,
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Keep a part's value, or note why it's missing
+fn collect<T, E: std::fmt::Display>(part: &str, result: Result<T, E>, errors: &mut Vec<String>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            error!("❌ [Admin] Failed to read {}: {}", part, e);
+            errors.push(format!("{}: {}", part, e));
+            None
+        }
+    }
+}
+
+/// At-a-glance operations view: workers, browsers, queue, proxies, schedules and failures. Admin only.
+#[utoipa::path(
+    get,
+    path = "/admin/status",
+    tag = "crawler",
+    responses(
+        (status = 200, description = "System status", body = AdminStatusResponse),
+        (status = 403, description = "Caller is not an admin")
+    )
+)]
+pub async fn admin_status(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminUser,
+) -> Json<AdminStatusResponse> {
+    let (snapshot, paused, runs, overdue) = tokio::join!(
+        state.queue.stats(),
+        state.queue.is_paused(),
+        next_runs(&state.pool),
+        overdue_runs(&state.pool),
+    );
+    let mut errors = Vec::new();
+    let queue = collect("queue stats", snapshot, &mut errors);
+    let paused = collect("pause flag", paused, &mut errors);
+    let next_runs = collect("scheduled runs", runs, &mut errors).unwrap_or_default();
+    let overdue_runs = collect("overdue runs", overdue, &mut errors);
+
+    let (active_browsers, browser_limit) = crate::crawler::browser_usage();
+    Json(AdminStatusResponse {
+        worker: WorkerStatus {
+            worker_id: crate::worker::worker_id().to_string(),
+            paused,
+            lanes: crate::worker::lane_concurrency(),
+            active_browsers,
+            browser_limit,
+        },
+        queue_backend: state.queue.backend_name().to_string(),
+        error_rate: queue.as_ref().map(ErrorRate::from_snapshot),
+        queue,
+        proxies: PROXY_MANAGER.get_stats(),
+        next_runs,
+        overdue_runs,
+        errors,
+    })
+}
+
+/// Database schema version: applied, pending and modified migrations. Admin only.
+#[utoipa::path(
+    get,
+    path = "/admin/schema",
+    tag = "crawler",
+    responses(
+        (status = 200, description = "Migration state of the database", body = SchemaVersion),
+        (status = 403, description = "Caller is not an admin"),
+        (status = 500, description = "Database unavailable")
+    )
+)]
+pub async fn schema_version(
+    State(state): State<Arc<AppState>>,
+    _admin: AdminUser,
+) -> Result<Json<SchemaVersion>, (StatusCode, String)> {
+    let version = crate::db::schema_version(&state.pool).await.map_err(|e| {
+        error!("❌ [Admin] Failed to read schema version: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read schema version".to_string())
+    })?;
+    Ok(Json(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_rate_from_queue_counters() {
+        let mut snapshot = QueueSnapshot::default();
+        assert_eq!(ErrorRate::from_snapshot(&snapshot).failure_rate, 0.0);
+
+        snapshot.processed_last_hour = 30;
+        snapshot.failed_last_hour = 10;
+        let rate = ErrorRate::from_snapshot(&snapshot);
+        assert_eq!((rate.completed, rate.failed, rate.failure_rate), (30, 10, 0.25));
+    }
+}
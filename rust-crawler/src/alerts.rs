@@ -0,0 +1,464 @@
+//! Alerting rules.
+//!
+//! Users define conditions on their crawls ("example.com is outside the top 10
+//! for 'rust crawler'", "the page sentiment is Negative"). The worker evaluates
+//! the owner's rules after every completed crawl and notifies through the
+//! notifications module. Rules are edge-triggered per engine and keyword: they
+//! fire when the condition starts holding, not on the first observation and not
+//! again while it keeps holding.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json as SqlJson, FromRow, PgPool};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::crawler::SearchResult;
+use crate::notifications::{NotificationDetails, NotificationEvent};
+use crate::queue::CrawlJob;
+use crate::rankings::{find_positions, normalize_domain};
+use tracing::{error, info};
+
+const SENTIMENT_LABELS: [&str; 3] = ["Positive", "Neutral", "Negative"];
+
+/// What a rule watches for
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// The domain is not among the first `top` organic results (SERP crawls only)
+    RankOutsideTop {
+        #[schema(example = "example.com")]
+        domain: String,
+        #[schema(example = 10)]
+        top: i32,
+    },
+    /// The crawled page's sentiment has this label (Positive, Neutral or Negative)
+    Sentiment {
+        #[schema(example = "Negative")]
+        label: String,
+    },
+}
+
+impl AlertCondition {
+    /// Normalize the domain/label and check bounds
+    pub fn validate(self) -> Result<Self, String> {
+        match self {
+            AlertCondition::RankOutsideTop { domain, top } => {
+                let domain = normalize_domain(&domain).ok_or(format!("Invalid domain '{}'", domain))?;
+                if !(1..=100).contains(&top) {
+                    return Err("top must be between 1 and 100".to_string());
+                }
+                Ok(AlertCondition::RankOutsideTop { domain, top })
+            }
+            AlertCondition::Sentiment { label } => SENTIMENT_LABELS
+                .iter()
+                .find(|l| l.eq_ignore_ascii_case(label.trim()))
+                .map(|l| AlertCondition::Sentiment { label: l.to_string() })
+                .ok_or(format!("Sentiment label must be one of {}", SENTIMENT_LABELS.join(", "))),
+        }
+    }
+
+    /// Whether the condition holds for a crawl; `None` when the crawl can't tell
+    /// (no sentiment, or a rank condition on a generic crawl)
+    pub fn matches(&self, results: Option<&[SearchResult]>, sentiment: Option<&str>) -> Option<bool> {
+        match self {
+            AlertCondition::RankOutsideTop { domain, top } => {
+                let positions = find_positions(results?, std::slice::from_ref(domain));
+                let position = positions.first().and_then(|(_, hit)| hit.as_ref()).map(|(p, _)| *p);
+                Some(position.is_none_or(|p| p > *top))
+            }
+            // Stored as e.g. "Negative (0.72)"
+            AlertCondition::Sentiment { label } => {
+                Some(sentiment?.split_whitespace().next()?.eq_ignore_ascii_case(label))
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            AlertCondition::RankOutsideTop { domain, top } => format!("{} dropped out of the top {}", domain, top),
+            AlertCondition::Sentiment { label } => format!("sentiment turned {}", label),
+        }
+    }
+}
+
+/// Fire only when the condition starts holding after a known non-matching crawl
+pub fn should_fire(previous: Option<bool>, matched: bool) -> bool {
+    matched && previous == Some(false)
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema, FromRow)]
+pub struct AlertRule {
+    pub id: String,
+    pub owner: String,
+    #[schema(example = "Homepage out of top 10")]
+    pub name: String,
+    /// Only crawls of this keyword (case-insensitive); any keyword when null
+    pub keyword: Option<String>,
+    /// Only crawls on this engine; any engine when null
+    pub engine: Option<String>,
+    #[schema(value_type = AlertCondition)]
+    pub condition: SqlJson<AlertCondition>,
+    pub enabled: bool,
+    #[schema(value_type = Option<String>)]
+    pub last_triggered_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateAlertRuleRequest {
+    #[schema(example = "Homepage out of top 10")]
+    pub name: String,
+    #[schema(example = "rust crawler")]
+    pub keyword: Option<String>,
+    #[schema(example = "google")]
+    pub engine: Option<String>,
+    pub condition: AlertCondition,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateAlertRuleRequest {
+    pub name: Option<String>,
+    /// Replacing the condition resets its per-keyword state
+    pub condition: Option<AlertCondition>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AlertRuleResponse {
+    pub success: bool,
+    pub rule: Option<AlertRule>,
+    pub message: Option<String>,
+}
+
+type ApiError = (StatusCode, String);
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+/// Evaluate the job owner's rules against a completed crawl and notify for each
+/// one that fires. Returns the number of alerts sent.
+pub async fn evaluate(
+    pool: &PgPool,
+    job: &CrawlJob,
+    results: &[SearchResult],
+    sentiment: Option<&str>,
+) -> Result<usize, sqlx::Error> {
+    let rules: Vec<AlertRule> = sqlx::query_as(&format!(
+        r#"SELECT {} FROM alert_rules
+           WHERE owner = $1 AND enabled
+             AND (keyword IS NULL OR lower(keyword) = lower($2))
+             AND (engine IS NULL OR engine = $3)"#,
+        RULE_COLUMNS
+    ))
+    .bind(&job.user_id)
+    .bind(&job.keyword)
+    .bind(&job.engine)
+    .fetch_all(pool)
+    .await?;
+
+    let results = (job.engine != "generic").then_some(results);
+    let subject = format!("{}:{}", job.engine, job.keyword.to_lowercase());
+    let mut fired = 0;
+    for rule in rules {
+        let Some(matched) = rule.condition.matches(results, sentiment) else {
+            continue;
+        };
+
+        // The CTE reads the state as it was before this statement's upsert
+        let previous: Option<bool> = sqlx::query_scalar(
+            r#"WITH previous AS (SELECT matched FROM alert_rule_states WHERE rule_id = $1 AND subject = $2)
+               INSERT INTO alert_rule_states (rule_id, subject, matched) VALUES ($1, $2, $3)
+               ON CONFLICT (rule_id, subject) DO UPDATE SET matched = EXCLUDED.matched, updated_at = now()
+               RETURNING (SELECT matched FROM previous)"#,
+        )
+        .bind(&rule.id)
+        .bind(&subject)
+        .bind(matched)
+        .fetch_one(pool)
+        .await?;
+        if !should_fire(previous, matched) {
+            continue;
+        }
+
+        let message = format!(
+            "{} for '{}' on {} (task {})",
+            rule.condition.describe(),
+            job.keyword,
+            job.engine,
+            job.id
+        );
+        let details = NotificationDetails {
+            keyword: Some(job.keyword.clone()),
+            task_id: Some(job.id.clone()),
+            ..Default::default()
+        };
+        crate::notifications::notify_with(pool, &rule.owner, NotificationEvent::RankAlert, &format!("Alert: {}", rule.name), &message, details)
+            .await?;
+        sqlx::query("UPDATE alert_rules SET last_triggered_at = now() WHERE id = $1")
+            .bind(&rule.id)
+            .execute(pool)
+            .await?;
+        info!("🚨 [Alerts] Rule '{}' fired: {}", rule.name, message);
+        fired += 1;
+    }
+    Ok(fired)
+}
+
+// ============================================================================
+// CRUD API
+// ============================================================================
+
+const RULE_COLUMNS: &str = "id, owner, name, keyword, engine, condition, enabled, last_triggered_at, created_at";
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Alerts] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+/// Load a rule the caller owns (admins may load any)
+async fn owned_rule(pool: &PgPool, id: &str, user: &AuthUser) -> Result<AlertRule, ApiError> {
+    let rule: Option<AlertRule> = sqlx::query_as(&format!("SELECT {} FROM alert_rules WHERE id = $1", RULE_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?;
+    match rule {
+        Some(rule) if rule.owner == user.id || user.is_admin() => Ok(rule),
+        _ => Err((StatusCode::NOT_FOUND, "Alert rule not found".to_string())),
+    }
+}
+
+/// Trimmed, or `None` when blank
+fn non_blank(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// List the caller's alert rules
+#[utoipa::path(
+    get,
+    path = "/alerts",
+    tag = "alerts",
+    responses(
+        (status = 200, description = "Alert rules", body = Vec<AlertRule>)
+    )
+)]
+pub async fn list_alert_rules(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<Vec<AlertRule>>, ApiError> {
+    let rules: Vec<AlertRule> = sqlx::query_as(&format!(
+        "SELECT {} FROM alert_rules WHERE owner = $1 ORDER BY created_at",
+        RULE_COLUMNS
+    ))
+    .bind(&user.id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(rules))
+}
+
+/// Get an alert rule
+#[utoipa::path(
+    get,
+    path = "/alerts/{id}",
+    tag = "alerts",
+    params(
+        ("id" = String, Path, description = "Alert rule ID")
+    ),
+    responses(
+        (status = 200, description = "Alert rule", body = AlertRuleResponse),
+        (status = 404, description = "Alert rule not found")
+    )
+)]
+pub async fn get_alert_rule(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<AlertRuleResponse>, ApiError> {
+    let rule = owned_rule(&state.pool, &id, &user).await?;
+    Ok(Json(AlertRuleResponse {
+        success: true,
+        rule: Some(rule),
+        message: None,
+    }))
+}
+
+/// Create an alert rule
+#[utoipa::path(
+    post,
+    path = "/alerts",
+    tag = "alerts",
+    request_body = CreateAlertRuleRequest,
+    responses(
+        (status = 200, description = "Alert rule created", body = AlertRuleResponse),
+        (status = 400, description = "Invalid name or condition")
+    )
+)]
+pub async fn create_alert_rule(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<CreateAlertRuleRequest>,
+) -> Result<Json<AlertRuleResponse>, ApiError> {
+    let name = non_blank(Some(req.name)).ok_or((StatusCode::BAD_REQUEST, "Name is required".to_string()))?;
+    let condition = req.condition.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let rule: AlertRule = sqlx::query_as(&format!(
+        r#"INSERT INTO alert_rules (id, owner, name, keyword, engine, condition, enabled)
+           VALUES ($1, $2, $3, $4, $5, $6, $7)
+           RETURNING {}"#,
+        RULE_COLUMNS
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(&user.id)
+    .bind(&name)
+    .bind(non_blank(req.keyword))
+    .bind(non_blank(req.engine).map(|e| e.to_lowercase()))
+    .bind(SqlJson(&condition))
+    .bind(req.enabled.unwrap_or(true))
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(AlertRuleResponse {
+        success: true,
+        rule: Some(rule),
+        message: Some("Alert rule created".to_string()),
+    }))
+}
+
+/// Rename, enable/disable or change the condition of an alert rule
+#[utoipa::path(
+    patch,
+    path = "/alerts/{id}",
+    tag = "alerts",
+    params(
+        ("id" = String, Path, description = "Alert rule ID")
+    ),
+    request_body = UpdateAlertRuleRequest,
+    responses(
+        (status = 200, description = "Alert rule updated", body = AlertRuleResponse),
+        (status = 400, description = "Invalid name or condition"),
+        (status = 404, description = "Alert rule not found")
+    )
+)]
+pub async fn update_alert_rule(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateAlertRuleRequest>,
+) -> Result<Json<AlertRuleResponse>, ApiError> {
+    let mut rule = owned_rule(&state.pool, &id, &user).await?;
+    if let Some(name) = req.name {
+        rule.name = non_blank(Some(name)).ok_or((StatusCode::BAD_REQUEST, "Name is required".to_string()))?;
+    }
+    if let Some(enabled) = req.enabled {
+        rule.enabled = enabled;
+    }
+    if let Some(condition) = req.condition {
+        rule.condition = SqlJson(condition.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?);
+        sqlx::query("DELETE FROM alert_rule_states WHERE rule_id = $1")
+            .bind(&id)
+            .execute(&state.pool)
+            .await
+            .map_err(db_error)?;
+    }
+
+    let rule: AlertRule = sqlx::query_as(&format!(
+        "UPDATE alert_rules SET name = $2, condition = $3, enabled = $4 WHERE id = $1 RETURNING {}",
+        RULE_COLUMNS
+    ))
+    .bind(&id)
+    .bind(&rule.name)
+    .bind(&rule.condition)
+    .bind(rule.enabled)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Alert rule not found".to_string()))?;
+
+    Ok(Json(AlertRuleResponse {
+        success: true,
+        rule: Some(rule),
+        message: Some("Alert rule updated".to_string()),
+    }))
+}
+
+/// Delete an alert rule
+#[utoipa::path(
+    delete,
+    path = "/alerts/{id}",
+    tag = "alerts",
+    params(
+        ("id" = String, Path, description = "Alert rule ID")
+    ),
+    responses(
+        (status = 200, description = "Alert rule deleted", body = AlertRuleResponse),
+        (status = 404, description = "Alert rule not found")
+    )
+)]
+pub async fn delete_alert_rule(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<AlertRuleResponse>, ApiError> {
+    owned_rule(&state.pool, &id, &user).await?;
+    sqlx::query("DELETE FROM alert_rule_states WHERE rule_id = $1")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    sqlx::query("DELETE FROM alert_rules WHERE id = $1")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    Ok(Json(AlertRuleResponse {
+        success: true,
+        rule: None,
+        message: Some("Alert rule deleted".to_string()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditions_match_crawl() {
+        let results: Vec<SearchResult> = ["https://a.com", "https://b.com", "https://www.example.com/x"]
+            .iter()
+            .map(|l| SearchResult { title: String::new(), link: l.to_string(), snippet: String::new() })
+            .collect();
+        let rank = |top| AlertCondition::RankOutsideTop { domain: "https://Example.com".to_string(), top }.validate().unwrap();
+        assert_eq!(rank(3).matches(Some(&results), None), Some(false));
+        assert_eq!(rank(2).matches(Some(&results), None), Some(true));
+        assert_eq!(rank(2).matches(None, None), None);
+
+        let negative = AlertCondition::Sentiment { label: "negative".to_string() }.validate().unwrap();
+        assert_eq!(negative, AlertCondition::Sentiment { label: "Negative".to_string() });
+        assert_eq!(negative.matches(None, Some("Negative (0.72)")), Some(true));
+        assert_eq!(negative.matches(None, Some("Neutral (0.50)")), Some(false));
+        assert_eq!(negative.matches(None, None), None);
+        assert!(AlertCondition::Sentiment { label: "angry".to_string() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_rules_fire_on_transition_only() {
+        assert!(should_fire(Some(false), true));
+        assert!(!should_fire(None, true));
+        assert!(!should_fire(Some(true), true));
+        assert!(!should_fire(Some(true), false));
+    }
+}
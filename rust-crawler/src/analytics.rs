@@ -0,0 +1,586 @@
+//! Crawl analytics: summary statistics and Parquet dumps of completed tasks.
+//!
+//! `GET /analytics/summary` aggregates the caller's (and teammates') tasks over a
+//! date range in Postgres: counts by engine, status and day, mean time from
+//! submission to completion, the share of tasks that ran into challenge pages,
+//! and the most crawled keywords.
+//!
+//! With `PARQUET_EXPORT_CRON` set, the scheduler periodically writes every task
+//! completed since the previous dump to object storage as Parquet, partitioned by
+//! dump date (`<prefix>/dt=YYYY-MM-DD/tasks-<time>-<part>.parquet`), ready for
+//! `read_parquet('s3://bucket/analytics/tasks/*/*.parquet')` in DuckDB or Spark.
+//! Marketing data is kept as JSON text.
+//!
+//! Dumps leave the bucket's access control behind and outlive account erasure,
+//! so they carry no personal data: no user IDs, no extracted emails, phone
+//! numbers or named entities, and no raw results (only their count and top
+//! URL). With `PARQUET_EXPORT_USER_KEY` set, a `user_key` column holds a keyed
+//! hash of the submitter, stable across dumps for per-user aggregates but not
+//! reversible without the key; without it the column is null.
+
+use arrow_array::{ArrayRef, Int32Array, RecordBatch, StringArray, TimestampMicrosecondArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::query::QueryAs;
+use sqlx::{FromRow, Postgres};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::crawler::SerpData;
+use tracing::info;
+
+/// Postgres advisory lock held while dumping, so only one replica's scheduler writes each window
+const DUMP_LOCK_ID: i64 = 0x7061_7271_7565_7401;
+/// Tasks read per query
+const PAGE_SIZE: i64 = 1000;
+
+/// Default summary range when `from` isn't given
+const DEFAULT_RANGE_DAYS: i64 = 30;
+/// Longest summary range, so a request can't aggregate the whole table by day
+const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SummaryQuery {
+    /// Start of the range (RFC 3339; default 30 days before `to`)
+    pub from: Option<DateTime<Utc>>,
+    /// End of the range, exclusive (RFC 3339; default now)
+    pub to: Option<DateTime<Utc>>,
+    /// Only tasks of this engine
+    pub engine: Option<String>,
+    /// Number of top keywords (default 10, max 100)
+    pub top: Option<i64>,
+}
+
+/// Tasks with one engine or status
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct GroupCount {
+    #[schema(example = "bing")]
+    pub key: String,
+    pub count: i64,
+}
+
+/// Tasks submitted on one day (UTC); days without tasks are included with zeros
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct DailyCount {
+    #[schema(value_type = String, example = "2024-05-01")]
+    pub day: NaiveDate,
+    pub total: i64,
+    pub completed: i64,
+    pub failed: i64,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct KeywordCount {
+    #[schema(example = "rust jobs")]
+    pub keyword: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CrawlSummary {
+    #[schema(value_type = String)]
+    pub from: DateTime<Utc>,
+    #[schema(value_type = String)]
+    pub to: DateTime<Utc>,
+    /// Tasks submitted in the range
+    pub total: i64,
+    /// Most tasks first
+    pub by_engine: Vec<GroupCount>,
+    /// Most tasks first
+    pub by_status: Vec<GroupCount>,
+    pub by_day: Vec<DailyCount>,
+    /// Mean seconds from submission to completion of completed and partial tasks, queue time included
+    pub avg_duration_secs: Option<f64>,
+    /// Tasks whose last failed attempt hit a challenge or captcha page
+    pub challenged: i64,
+    /// `challenged / total`, 0 without tasks
+    pub challenge_rate: f64,
+    /// Most crawled keywords first
+    pub top_keywords: Vec<KeywordCount>,
+}
+
+#[derive(Debug, FromRow)]
+struct Totals {
+    total: i64,
+    challenged: i64,
+    avg_duration_secs: Option<f64>,
+}
+
+#[derive(Debug, FromRow)]
+struct GroupRow {
+    /// Engine row of the grouping sets, else status
+    by_engine: bool,
+    key: String,
+    count: i64,
+}
+
+/// The summary's `[from, to)` range: defaults filled in, checked for order and length
+fn summary_range(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let to = to.unwrap_or(now);
+    let from = from.unwrap_or(to - chrono::Duration::days(DEFAULT_RANGE_DAYS));
+    if from >= to {
+        return Err("from must be before to".to_string());
+    }
+    if to - from > chrono::Duration::days(MAX_RANGE_DAYS) {
+        return Err(format!("The range can span at most {} days", MAX_RANGE_DAYS));
+    }
+    Ok((from, to))
+}
+
+fn challenge_rate(challenged: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        challenged as f64 / total as f64
+    }
+}
+
+/// Tasks the summary covers: not deleted, visible to user `$1` (admin if `$2`),
+/// submitted in [`$3`, `$4`), of engine `$5` if set
+fn scope_sql() -> String {
+    format!(
+        r#"deleted_at IS NULL
+           AND (user_id = $1 OR $2 OR {})
+           AND created_at >= $3 AND created_at < $4
+           AND ($5::text IS NULL OR engine = $5)"#,
+        crate::organizations::teammates_filter("$1")
+    )
+}
+
+/// `sql` with the [`scope_sql`] parameters bound
+fn scoped<'q, O>(
+    sql: &'q str,
+    user: &AuthUser,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    engine: Option<String>,
+) -> QueryAs<'q, Postgres, O, PgArguments>
+where
+    O: for<'r> FromRow<'r, PgRow>,
+{
+    sqlx::query_as(sql)
+        .bind(user.id.clone())
+        .bind(user.is_admin())
+        .bind(from.naive_utc())
+        .bind(to.naive_utc())
+        .bind(engine)
+}
+
+/// Task counts, durations, challenge rate and top keywords over a date range
+#[utoipa::path(
+    get,
+    path = "/analytics/summary",
+    tag = "crawler",
+    params(SummaryQuery),
+    responses(
+        (status = 200, description = "Summary of your and your teammates' tasks (everyone's for admins)", body = CrawlSummary),
+        (status = 400, description = "from isn't before to, or the range is longer than 366 days")
+    )
+)]
+pub async fn summary(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Query(query): Query<SummaryQuery>,
+) -> Result<Json<CrawlSummary>, (StatusCode, String)> {
+    let (from, to) = summary_range(query.from, query.to, Utc::now()).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let engine = query.engine.filter(|e| !e.is_empty());
+    let top = query.top.unwrap_or(10).clamp(1, 100);
+    let scope = scope_sql();
+
+    let totals_sql = format!(
+        r#"SELECT COUNT(*) AS total,
+                  COUNT(*) FILTER (WHERE last_error ~* '(challenge|captcha)') AS challenged,
+                  (AVG(EXTRACT(EPOCH FROM completed_at - created_at))
+                      FILTER (WHERE status IN ('completed', 'partial') AND completed_at >= created_at))::float8 AS avg_duration_secs
+           FROM tasks WHERE {}"#,
+        scope
+    );
+    // Both breakdowns in one scan; in each grouping set the other column is NULL
+    let groups_sql = format!(
+        r#"SELECT GROUPING(engine) = 0 AS by_engine, COALESCE(engine, status) AS key, COUNT(*) AS count
+           FROM tasks WHERE {}
+           GROUP BY GROUPING SETS ((engine), (status))
+           ORDER BY count DESC, key"#,
+        scope
+    );
+    let daily_sql = format!(
+        r#"SELECT d::date AS day, COALESCE(c.total, 0) AS total,
+                  COALESCE(c.completed, 0) AS completed, COALESCE(c.failed, 0) AS failed
+           FROM generate_series($3::date, ($4 - interval '1 microsecond')::date, interval '1 day') d
+           LEFT JOIN (
+               SELECT created_at::date AS day, COUNT(*) AS total,
+                      COUNT(*) FILTER (WHERE status = 'completed') AS completed,
+                      COUNT(*) FILTER (WHERE status = 'failed') AS failed
+               FROM tasks WHERE {}
+               GROUP BY 1
+           ) c ON c.day = d::date
+           ORDER BY 1"#,
+        scope
+    );
+    let keywords_sql = format!(
+        "SELECT keyword, COUNT(*) AS count FROM tasks WHERE {} GROUP BY keyword ORDER BY count DESC, keyword LIMIT $6",
+        scope
+    );
+
+    let (totals, groups, by_day, top_keywords) = tokio::try_join!(
+        scoped::<Totals>(&totals_sql, &user, from, to, engine.clone()).fetch_one(&state.pool),
+        scoped::<GroupRow>(&groups_sql, &user, from, to, engine.clone()).fetch_all(&state.pool),
+        scoped::<DailyCount>(&daily_sql, &user, from, to, engine.clone()).fetch_all(&state.pool),
+        scoped::<KeywordCount>(&keywords_sql, &user, from, to, engine).bind(top).fetch_all(&state.pool),
+    )
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let (by_engine, by_status): (Vec<GroupRow>, Vec<GroupRow>) = groups.into_iter().partition(|g| g.by_engine);
+    let counts = |rows: Vec<GroupRow>| rows.into_iter().map(|g| GroupCount { key: g.key, count: g.count }).collect();
+    Ok(Json(CrawlSummary {
+        from,
+        to,
+        total: totals.total,
+        by_engine: counts(by_engine),
+        by_status: counts(by_status),
+        by_day,
+        avg_duration_secs: totals.avg_duration_secs,
+        challenged: totals.challenged,
+        challenge_rate: challenge_rate(totals.challenged, totals.total),
+        top_keywords,
+    }))
+}
+
+/// Six-field cron (with seconds) of the dump job; dumps are off without it
+pub fn dump_cron() -> Option<String> {
+    std::env::var("PARQUET_EXPORT_CRON").ok().filter(|s| !s.trim().is_empty())
+}
+
+fn dump_prefix() -> String {
+    std::env::var("PARQUET_EXPORT_PREFIX")
+        .map(|p| p.trim_matches('/').to_string())
+        .ok()
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| "analytics/tasks".to_string())
+}
+
+/// Rows per Parquet file; larger dumps are split into parts
+fn rows_per_file() -> usize {
+    std::env::var("PARQUET_EXPORT_ROWS_PER_FILE").ok().and_then(|s| s.parse().ok()).filter(|&n| n > 0).unwrap_or(100_000)
+}
+
+/// Key of the `user_key` pseudonyms; without it dumps don't identify submitters at all
+fn user_key_secret() -> Option<String> {
+    std::env::var("PARQUET_EXPORT_USER_KEY").ok().filter(|k| !k.is_empty())
+}
+
+/// Keyed hash standing in for a user ID in the dumps
+fn pseudonymize(secret: &str, user_id: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(user_id.as_bytes());
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The task columns a dump row is built from
+#[derive(Debug, Default, FromRow)]
+struct TaskRow {
+    id: String,
+    user_id: Option<String>,
+    keyword: String,
+    engine: String,
+    created_at: Option<NaiveDateTime>,
+    completed_at: Option<NaiveDateTime>,
+    attempts: Option<i32>,
+    results_json: Option<String>,
+    meta_description: Option<String>,
+    sentiment: Option<String>,
+    category: Option<String>,
+    marketing_data: Option<serde_json::Value>,
+}
+
+const ROW_COLUMNS: &str = "id, user_id, keyword, engine, created_at, completed_at, attempts, results_json, \
+    meta_description, sentiment, category, marketing_data";
+
+fn timestamp() -> DataType {
+    DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+}
+
+/// Columns of the dumped files
+fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("task_id", DataType::Utf8, false),
+        Field::new("user_key", DataType::Utf8, true),
+        Field::new("keyword", DataType::Utf8, false),
+        Field::new("engine", DataType::Utf8, false),
+        Field::new("created_at", timestamp(), true),
+        Field::new("completed_at", timestamp(), true),
+        Field::new("attempts", DataType::Int32, true),
+        Field::new("result_count", DataType::Int32, false),
+        Field::new("total_results", DataType::Utf8, true),
+        Field::new("top_url", DataType::Utf8, true),
+        Field::new("meta_description", DataType::Utf8, true),
+        Field::new("sentiment", DataType::Utf8, true),
+        Field::new("category", DataType::Utf8, true),
+        Field::new("marketing_data", DataType::Utf8, true),
+    ]))
+}
+
+/// Dump rows of `rows`; `user_key` is the pseudonym key, if any
+fn to_batch(rows: &[TaskRow], user_key: Option<&str>) -> Result<RecordBatch, ArrowError> {
+    let serps: Vec<SerpData> = rows
+        .iter()
+        .map(|r| r.results_json.as_deref().and_then(|j| serde_json::from_str(j).ok()).unwrap_or_default())
+        .collect();
+    let text = |values: Vec<Option<String>>| -> ArrayRef { Arc::new(StringArray::from(values)) };
+    let json = |f: fn(&TaskRow) -> Option<&serde_json::Value>| {
+        text(rows.iter().map(|r| f(r).map(|v| v.to_string())).collect())
+    };
+    let time = |f: fn(&TaskRow) -> Option<NaiveDateTime>| -> ArrayRef {
+        let micros: Vec<Option<i64>> = rows.iter().map(|r| f(r).map(|t| t.and_utc().timestamp_micros())).collect();
+        Arc::new(TimestampMicrosecondArray::from(micros).with_timezone("UTC"))
+    };
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.id.as_str()))),
+        text(rows.iter().map(|r| Some(pseudonymize(user_key?, r.user_id.as_deref()?))).collect()),
+        Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.keyword.as_str()))),
+        Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.engine.as_str()))),
+        time(|r| r.created_at),
+        time(|r| r.completed_at),
+        Arc::new(Int32Array::from(rows.iter().map(|r| r.attempts).collect::<Vec<_>>())),
+        Arc::new(Int32Array::from_iter_values(serps.iter().map(|s| s.results.len() as i32))),
+        text(serps.iter().map(|s| s.total_results.clone()).collect()),
+        text(serps.iter().map(|s| s.results.first().map(|r| r.link.clone())).collect()),
+        text(rows.iter().map(|r| r.meta_description.clone()).collect()),
+        text(rows.iter().map(|r| r.sentiment.clone()).collect()),
+        text(rows.iter().map(|r| r.category.clone()).collect()),
+        json(|r| r.marketing_data.as_ref()),
+    ];
+    RecordBatch::try_new(schema(), columns)
+}
+
+/// One Parquet file being written in memory
+struct Part {
+    writer: ArrowWriter<Vec<u8>>,
+    rows: usize,
+    user_key: Option<String>,
+}
+
+impl Part {
+    fn new(user_key: Option<String>) -> Result<Self, ParquetError> {
+        let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        Ok(Self { writer: ArrowWriter::try_new(Vec::new(), schema(), Some(props))?, rows: 0, user_key })
+    }
+
+    fn write(&mut self, rows: &[TaskRow]) -> Result<(), ParquetError> {
+        self.writer.write(&to_batch(rows, self.user_key.as_deref())?)?;
+        self.rows += rows.len();
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Vec<u8>, ParquetError> {
+        self.writer.into_inner()
+    }
+}
+
+/// What one dump wrote
+#[derive(Debug)]
+pub struct DumpSummary {
+    pub rows: usize,
+    pub files: Vec<String>,
+}
+
+/// Dump tasks completed since the last dump; `None` if another replica is already dumping
+pub async fn dump_completed_tasks(state: &AppState) -> anyhow::Result<Option<DumpSummary>> {
+    // Session lock on a connection of its own, released below or when the connection drops
+    let mut lock = state.pool.acquire().await?;
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+        .bind(DUMP_LOCK_ID)
+        .fetch_one(&mut *lock)
+        .await?;
+    if !locked {
+        info!("📊 [Analytics] Another instance is writing the Parquet dump, skipping");
+        return Ok(None);
+    }
+    let result = run_dump(state).await;
+    let _ = sqlx::query("SELECT pg_advisory_unlock($1)").bind(DUMP_LOCK_ID).execute(&mut *lock).await;
+    result.map(Some)
+}
+
+async fn run_dump(state: &AppState) -> anyhow::Result<DumpSummary> {
+    let pool = &state.pool;
+    let window_start: Option<NaiveDateTime> =
+        sqlx::query_scalar("SELECT max(window_end) FROM parquet_dumps WHERE status = 'completed'")
+            .fetch_one(pool)
+            .await?;
+    let window_end: NaiveDateTime = sqlx::query_scalar("SELECT now()::timestamp").fetch_one(pool).await?;
+    let dump_id = uuid::Uuid::new_v4().to_string();
+    sqlx::query("INSERT INTO parquet_dumps (id, window_end) VALUES ($1, $2)")
+        .bind(&dump_id)
+        .bind(window_end)
+        .execute(pool)
+        .await?;
+
+    match write_window(state, window_start, window_end).await {
+        Ok(summary) => {
+            sqlx::query(
+                "UPDATE parquet_dumps SET status = 'completed', row_count = $2, files = $3, completed_at = now() WHERE id = $1",
+            )
+            .bind(&dump_id)
+            .bind(summary.rows as i64)
+            .bind(&summary.files)
+            .execute(pool)
+            .await?;
+            Ok(summary)
+        }
+        Err(e) => {
+            let _ = sqlx::query("UPDATE parquet_dumps SET status = 'failed', error = $2, completed_at = now() WHERE id = $1")
+                .bind(&dump_id)
+                .bind(format!("{:#}", e))
+                .execute(pool)
+                .await;
+            Err(e)
+        }
+    }
+}
+
+/// Storage key of one part of a dump
+fn part_key(prefix: &str, window_end: NaiveDateTime, part: usize) -> String {
+    format!(
+        "{}/dt={}/tasks-{}-{:03}.parquet",
+        prefix,
+        window_end.format("%Y-%m-%d"),
+        window_end.format("%Y%m%dT%H%M%S"),
+        part
+    )
+}
+
+async fn write_window(state: &AppState, window_start: Option<NaiveDateTime>, window_end: NaiveDateTime) -> anyhow::Result<DumpSummary> {
+    // Tasks completed before `completed_at` existed count as completed when created
+    let sql = format!(
+        r#"SELECT {} FROM tasks
+           WHERE status IN ('completed', 'partial') AND deleted_at IS NULL
+             AND COALESCE(completed_at, created_at) <= $1
+             AND ($2::timestamp IS NULL OR COALESCE(completed_at, created_at) > $2)
+           ORDER BY COALESCE(completed_at, created_at), id
+           LIMIT $3 OFFSET $4"#,
+        ROW_COLUMNS
+    );
+    let prefix = dump_prefix();
+    let max_rows = rows_per_file();
+    let user_key = user_key_secret();
+    let mut summary = DumpSummary { rows: 0, files: Vec::new() };
+    let mut part: Option<Part> = None;
+
+    loop {
+        let page: Vec<TaskRow> = sqlx::query_as(&sql)
+            .bind(window_end)
+            .bind(window_start)
+            .bind(PAGE_SIZE)
+            .bind(summary.rows as i64)
+            .fetch_all(&state.pool)
+            .await?;
+        if page.is_empty() {
+            break;
+        }
+        let current = match part.as_mut() {
+            Some(current) => current,
+            None => part.insert(Part::new(user_key.clone())?),
+        };
+        current.write(&page)?;
+        summary.rows += page.len();
+
+        if current.rows >= max_rows {
+            let key = part_key(&prefix, window_end, summary.files.len());
+            let body = part.take().expect("part was just written").finish()?;
+            state.storage.put_object(&key, body, "application/vnd.apache.parquet").await?;
+            summary.files.push(key);
+        }
+        if (page.len() as i64) < PAGE_SIZE {
+            break;
+        }
+    }
+    if let Some(last) = part {
+        let key = part_key(&prefix, window_end, summary.files.len());
+        state.storage.put_object(&key, last.finish()?, "application/vnd.apache.parquet").await?;
+        summary.files.push(key);
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    #[test]
+    fn test_summary_range() {
+        let now = DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let (from, to) = summary_range(None, None, now).unwrap();
+        assert_eq!((from.to_rfc3339(), to), ("2024-05-02T00:00:00+00:00".to_string(), now));
+        assert!(summary_range(Some(now), Some(now), now).unwrap_err().contains("before"));
+        assert!(summary_range(Some(now - chrono::Duration::days(400)), None, now).unwrap_err().contains("366 days"));
+        assert_eq!(challenge_rate(0, 0), 0.0);
+        assert_eq!(challenge_rate(1, 4), 0.25);
+    }
+
+    #[test]
+    fn test_parquet_part() {
+        let completed = NaiveDateTime::parse_from_str("2024-05-01 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let rows = vec![
+            TaskRow {
+                id: "t1".to_string(),
+                user_id: Some("user-1".to_string()),
+                keyword: "rust jobs".to_string(),
+                engine: "bing".to_string(),
+                completed_at: Some(completed),
+                results_json: Some(
+                    r#"{"results": [{"title": "A", "link": "https://a.example", "snippet": ""}], "people_also_ask": [],
+                        "related_searches": [], "featured_snippet": null, "total_results": "10"}"#
+                        .to_string(),
+                ),
+                ..Default::default()
+            },
+            TaskRow { id: "t2".to_string(), keyword: "pizza".to_string(), engine: "google".to_string(), ..Default::default() },
+        ];
+        let mut part = Part::new(Some("dump-key".to_string())).unwrap();
+        part.write(&rows).unwrap();
+        let file = axum::body::Bytes::from(part.finish().unwrap());
+
+        let batches: Vec<RecordBatch> =
+            ParquetRecordBatchReaderBuilder::try_new(file).unwrap().build().unwrap().map(|b| b.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema(), schema());
+
+        let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+        let counts = column("result_count");
+        let counts = counts.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!((counts.value(0), counts.value(1)), (1, 0));
+        // Submitters are pseudonymous, and personal data isn't dumped at all
+        let users = column("user_key");
+        let users = users.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(users.value(0), pseudonymize("dump-key", "user-1"));
+        assert_ne!(users.value(0), pseudonymize("other-key", "user-1"));
+        assert!(users.is_null(1));
+        for name in ["user_id", "emails", "phone_numbers", "entities", "results_json"] {
+            assert!(batch.column_by_name(name).is_none(), "{} is dumped", name);
+        }
+        assert!(to_batch(&rows, None).unwrap().column_by_name("user_key").unwrap().is_null(0));
+
+        assert_eq!(
+            part_key("analytics/tasks", completed, 2),
+            "analytics/tasks/dt=2024-05-01/tasks-20240501T120000-002.parquet"
+        );
+    }
+}
@@ -1,24 +1,29 @@
 use axum::{
     extract::{Path, State},
     Json,
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::sync::Arc;
 use uuid::Uuid;
-use crate::crawler;
-use utoipa::{ToSchema, OpenApi};
-use chrono::NaiveDateTime;
-use crate::proxy::{PROXY_MANAGER, ProxyInfo, ProxyStats};
+use utoipa::ToSchema;
+use crate::proxy::{PROXY_MANAGER, ProxyInfo, ProxyStats, ProxyTestResult, RotationStrategy};
 use crate::storage::StorageManager;
 use crate::queue::QueueManager;
+use crate::quotas;
+use crate::auth::AdminUser;
+use tracing::{error, info, warn};
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
     pub storage: StorageManager,
     pub queue: QueueManager,
+    /// Revoked tokens and users, checked on every authenticated request
+    pub denylist: crate::revocation::Denylist,
+    pub config: Arc<crate::config::Config>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -27,8 +32,23 @@ pub struct CrawlRequest {
     pub keyword: String,
     #[schema(example = "bing", default = "bing")]
     pub engine: Option<String>,
-    #[schema(example = "{\"title\": \"h1\", \"content\": \".post-body\"}")]
-    pub selectors: Option<std::collections::HashMap<String, String>>, 
+    /// Per-job options (selectors, login, ...)
+    #[serde(flatten)]
+    pub options: crate::crawler::CrawlOptions,
+    /// Retries after a failed attempt (default 2)
+    #[schema(example = 2)]
+    pub max_retries: Option<u32>,
+    /// Base retry delay in seconds, doubled on each further attempt (default 30)
+    #[schema(example = 30)]
+    pub retry_backoff_secs: Option<u64>,
+    /// Queue priority: high, normal (default) or low
+    pub priority: Option<crate::queue::Priority>,
+    /// Start the crawl at this time (RFC 3339) instead of immediately
+    #[schema(value_type = Option<String>, example = "2026-01-01T03:00:00Z")]
+    pub run_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// POST the task result here when the crawl completes or finally fails
+    #[schema(example = "https://hooks.example.com/crawl-done")]
+    pub callback_url: Option<String>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -37,6 +57,35 @@ pub struct CrawlResponse {
     pub task_id: String,
     #[schema(example = "Crawl started")]
     pub message: String,
+    /// True when an identical submission was already queued; `task_id` is that task
+    pub duplicate: bool,
+}
+
+/// Seconds a submission is remembered for duplicate suppression (`IDEMPOTENCY_WINDOW_SECS`)
+fn dedup_window_secs() -> u64 {
+    std::env::var("IDEMPOTENCY_WINDOW_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(600)
+}
+
+/// Dedup key for a submission: the caller's `Idempotency-Key` if given, otherwise
+/// a hash of what would be crawled. Scoped per user either way.
+pub fn dedup_key(user_id: &str, idempotency_key: Option<&str>, keyword: &str, engine: &str, options: &crate::crawler::CrawlOptions) -> String {
+    use sha2::{Digest, Sha256};
+    match idempotency_key {
+        Some(key) => format!("{}:key:{}", user_id, key),
+        None => {
+            let mut hasher = Sha256::new();
+            hasher.update(keyword.trim().to_lowercase());
+            hasher.update([0]);
+            hasher.update(engine);
+            hasher.update([0]);
+            hasher.update(serde_json::to_vec(options).unwrap_or_default());
+            let digest: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+            format!("{}:hash:{}", user_id, digest)
+        }
+    }
 }
 
 #[derive(Serialize, sqlx::FromRow, ToSchema)]
@@ -47,27 +96,184 @@ pub struct TaskResult {
     pub keyword: String,
     #[schema(example = "bing")]
     pub engine: String,
+    /// running, retrying, completed, partial (results page only, see `stages`) or failed
     #[schema(example = "completed")]
     pub status: String,
+    /// Current worker stage: searching, extracting, storing, enriching, done
+    #[schema(example = "extracting")]
+    pub stage: Option<String>,
+    /// Completion estimate, 0-100
+    #[schema(example = 40)]
+    pub progress: Option<i32>,
+    /// Attempts made so far
+    pub attempts: Option<i32>,
+    /// Error of the last failed attempt, or of the stage a `partial` task is missing
+    pub last_error: Option<String>,
+    /// Where the last failed attempt broke, or what a `partial` task is missing: `serp` (results page), `extract` (page crawl) or `store` (saving results)
+    #[schema(example = "serp")]
+    pub failure_stage: Option<String>,
+    /// Proxy the last attempt went through (`host:port`); none for direct connections
+    #[schema(example = "1.2.3.4:8080")]
+    pub proxy_id: Option<String>,
+    /// Outcome of each stage of a finished job (`succeeded`, `failed` or `skipped`)
+    #[schema(example = json!({"serp": "succeeded", "extract": "failed", "store": "skipped"}))]
+    pub stages: Option<serde_json::Value>,
     pub results_json: Option<String>,
     pub extracted_text: Option<String>,
+    /// Raw HTML of the first result page; only with `include_html=true`, read from object storage
+    #[sqlx(skip)]
     pub first_page_html: Option<String>,
+    /// Object key of the stored HTML, served by `GET /tasks/{task_id}/html`
+    pub html_key: Option<String>,
     pub meta_description: Option<String>,
     pub meta_author: Option<String>,
     pub meta_date: Option<String>,
     pub entities: Option<serde_json::Value>,
     pub category: Option<String>,
+    /// When the page text and HTML moved to object storage under the retention policy
+    #[schema(value_type = Option<String>)]
+    pub archived_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// A task in listings; results are left out, fetch them with `GET /crawl/{task_id}`
 #[derive(Serialize, sqlx::FromRow, utoipa::ToSchema)]
 pub struct TaskSummary {
     pub id: String,
     pub keyword: String,
     pub engine: String,
     pub status: String,
+    pub stage: Option<String>,
+    pub progress: Option<i32>,
+    /// Who submitted the task (a teammate's ID in organization listings)
+    pub user_id: Option<String>,
     pub created_at: Option<chrono::NaiveDateTime>,
-    pub results_json: Option<String>,
-    pub extracted_text: Option<String>,
+    /// When the task was moved to the trash; set only in `deleted=true` listings
+    #[schema(value_type = Option<String>)]
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSort {
+    #[default]
+    CreatedAt,
+    Keyword,
+    Status,
+    Engine,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskListQuery {
+    /// Only tasks with this status, e.g. `completed` or `failed`
+    pub status: Option<String>,
+    /// Only tasks of this engine (google, bing, generic)
+    pub engine: Option<String>,
+    /// Created at or after this time (RFC 3339)
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Created before this time (RFC 3339)
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+    /// Keyword contains this text (case-insensitive)
+    pub q: Option<String>,
+    /// Page number, starting at 1
+    pub page: Option<i64>,
+    /// Page size (default 50, max 200)
+    pub limit: Option<i64>,
+    /// Sort field (default `created_at`)
+    #[param(inline)]
+    pub sort: Option<TaskSort>,
+    /// Sort direction (default `desc`)
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+    /// List the trash instead: deleted tasks that can still be restored
+    pub deleted: Option<bool>,
+    /// Extracted an entity with exactly this text, e.g. `OpenAI`
+    pub entity: Option<String>,
+    /// Extracted an entity of this type (`ORG`, `PERSON`, `GPE`, ...); with `entity`, the same entity
+    pub entity_type: Option<String>,
+    /// Content category, e.g. `SaaS`
+    pub category: Option<String>,
+    /// The page lists this email address
+    pub email: Option<String>,
+    /// The page lists at least one email address (`true`) or none (`false`)
+    pub has_email: Option<bool>,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct TaskPage {
+    pub tasks: Vec<TaskSummary>,
+    pub page: i64,
+    pub limit: i64,
+    /// Tasks matching the filters across all pages
+    pub total: i64,
+}
+
+/// `ORDER BY` clause for a task listing; ties are broken by newest first, then ID, so pages are stable
+fn task_order_clause(sort: TaskSort, order: SortOrder) -> String {
+    let column = match sort {
+        TaskSort::CreatedAt => "created_at",
+        TaskSort::Keyword => "keyword",
+        TaskSort::Status => "status",
+        TaskSort::Engine => "engine",
+    };
+    let direction = match order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    };
+    match sort {
+        TaskSort::CreatedAt => format!("created_at {} NULLS LAST, id {}", direction, direction),
+        _ => format!("{} {}, created_at DESC NULLS LAST, id DESC", column, direction),
+    }
+}
+
+/// Conditions of task listings and bulk exports: not deleted, visible to user `$1` (admin if `$2`),
+/// with status `$3`, engine `$4`, created in [`$5`, `$6`) and keyword `ILIKE $7`; `NULL` filters match all
+pub fn task_filters_sql() -> String {
+    filters_sql("deleted_at IS NULL")
+}
+
+/// [`task_filters_sql`] for the trash: deleted tasks not yet purged
+pub fn deleted_task_filters_sql() -> String {
+    filters_sql("deleted_at IS NOT NULL")
+}
+
+fn filters_sql(deleted: &str) -> String {
+    format!(
+        r#"{}
+           AND (user_id = $1 OR $2 OR {})
+           AND ($3::text IS NULL OR status = $3)
+           AND ($4::text IS NULL OR engine = $4)
+           AND ($5::timestamp IS NULL OR created_at >= $5)
+           AND ($6::timestamp IS NULL OR created_at < $6)
+           AND ($7::text IS NULL OR keyword ILIKE $7 ESCAPE '\')"#,
+        deleted,
+        crate::organizations::teammates_filter("$1")
+    )
+}
+
+/// Conditions on a task listing's extracted data, after [`task_filters_sql`]: an entity
+/// with text `$8` and type `$9`, category `$10`, email `$11` and some email (or none) by `$12`.
+/// Entity and email filters are JSONB containment and key lookups, served by the GIN indexes.
+pub fn result_filters_sql() -> &'static str {
+    r#"(($8::text IS NULL AND $9::text IS NULL)
+                OR entities @> jsonb_build_array(jsonb_strip_nulls(jsonb_build_object('text', $8::text, 'label', $9::text))))
+           AND ($10::text IS NULL OR category = $10)
+           AND ($11::text IS NULL OR emails ? $11)
+           AND ($12::bool IS NULL OR (emails IS NOT NULL AND emails <> '[]'::jsonb) = $12)"#
+}
+
+/// `ILIKE` pattern matching `text` anywhere, with wildcards in it taken literally
+pub fn contains_pattern(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
 }
 
 
@@ -75,93 +281,871 @@ pub struct TaskSummary {
     post,
     path = "/crawl",
     request_body = CrawlRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Repeat submissions with the same key return the original task")
+    ),
     responses(
-        (status = 200, description = "Crawl started successfully", body = CrawlResponse)
+        (status = 200, description = "Crawl started successfully", body = CrawlResponse),
+        (status = 400, description = "Invalid callback_url"),
+        (status = 402, description = "Not enough credits for this crawl type"),
+        (status = 403, description = "Options not included in the caller's plan (proxies, crawl depth)"),
+        (status = 422, description = "Invalid keyword, URL, engine, selectors, option bounds or pinned proxy", body = crate::validation::ValidationErrors),
+        (status = 429, description = "Daily or monthly crawl quota exceeded")
     )
 )]
 pub async fn trigger_crawl(
     State(state): State<Arc<AppState>>,
     user: crate::auth::AuthUser, // Require Auth
+    headers: HeaderMap,
     Json(payload): Json<CrawlRequest>,
-) -> Json<CrawlResponse> {
+) -> Result<(HeaderMap, Json<CrawlResponse>), Response> {
+    let idempotency_key = headers
+        .get("idempotency-key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|k| !k.is_empty());
+    let (headers, response) = submit_crawl(&state, &user, payload, idempotency_key).await?;
+    Ok((headers, Json(response)))
+}
+
+/// Check plan, quota and credits, then queue one crawl; returns the quota headers.
+/// Shared by `POST /crawl` and the gRPC `SubmitCrawl`.
+pub async fn submit_crawl(
+    state: &AppState,
+    user: &crate::auth::AuthUser,
+    payload: CrawlRequest,
+    idempotency_key: Option<&str>,
+) -> Result<(HeaderMap, CrawlResponse), Response> {
     let task_id = Uuid::new_v4().to_string();
+    crate::telemetry::record_task_id(&task_id);
     let keyword = payload.keyword.clone();
-    let engine = payload.engine.unwrap_or_else(|| "bing".to_string());
+    let engine = payload.engine.clone().unwrap_or_else(|| "bing".to_string());
+    crate::validation::validate_crawl_request(&payload, &engine, &state.config.engines).await.map_err(IntoResponse::into_response)?;
+    let callback_url = match payload.callback_url.as_deref() {
+        Some(url) => Some(crate::webhooks::validate_callback_url(url).await.map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?),
+        None => None,
+    };
+
+    let dedup_key = dedup_key(&user.id, idempotency_key, &keyword, &engine, &payload.options);
+    match state.queue.claim_dedup_key(&dedup_key, &task_id, dedup_window_secs()).await {
+        Ok(Some(existing)) => {
+            info!("♻️ [API] Duplicate crawl request, returning task {}", existing);
+            let headers = match quotas::Quota::load(&state.pool, &user.id).await {
+                Ok(quota) => quota.headers(),
+                Err(_) => HeaderMap::new(),
+            };
+            return Ok((headers, CrawlResponse {
+                task_id: existing,
+                message: "Duplicate request; returning the existing task".to_string(),
+                duplicate: true,
+            }));
+        }
+        Ok(None) => {}
+        // Dedup is best-effort; don't refuse crawls because of it
+        Err(e) => warn!("⚠️ [API] Duplicate check failed: {}", e),
+    }
+
+    let mut options = payload.options;
+    if let Err(e) = crate::subscriptions::enforce_plan(&state.pool, &user.id, &mut options).await {
+        let _ = state.queue.release_dedup_key(&dedup_key).await;
+        return Err(e.into_response());
+    }
+    if let Err(e) = crate::organizations::check_pinned_proxy(&state.pool, &user.id, options.proxy_id.as_deref()).await {
+        let _ = state.queue.release_dedup_key(&dedup_key).await;
+        return Err(e.into_response());
+    }
+
+    let mut quota = match quotas::reserve(&state.pool, &user.id, 1).await {
+        Ok(quota) => quota,
+        Err(e) => {
+            let _ = state.queue.release_dedup_key(&dedup_key).await;
+            return Err(e.into_response());
+        }
+    };
+    let kind = crate::credits::CrawlKind::of(&engine, &options);
+    if let Err(e) = crate::credits::debit(&state.pool, &user.id, &task_id, kind).await {
+        let _ = state.queue.release_dedup_key(&dedup_key).await;
+        if let Err(e) = quotas::release(&state.pool, &user.id, 1).await {
+            warn!("⚠️ [API] Failed to release quota for {}: {}", user.id, e);
+        }
+        return Err(e.into_response());
+    }
+
+    if let Err(e) = crate::login_secrets::seal(&state.pool, &task_id, &user.id, &mut options).await {
+        error!("❌ [API] Failed to store the login password of {}: {}", task_id, e);
+        let _ = state.queue.release_dedup_key(&dedup_key).await;
+        if let Err(e) = crate::credits::refund(&state.pool, &task_id).await {
+            warn!("⚠️ [API] Failed to refund credits for {}: {}", task_id, e);
+        }
+        if let Err(e) = quotas::release(&state.pool, &user.id, 1).await {
+            warn!("⚠️ [API] Failed to release quota for {}: {}", user.id, e);
+        }
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string()).into_response());
+    }
 
     let job = crate::queue::CrawlJob {
         id: task_id.clone(),
         user_id: user.id.clone(), // Pass user ID to worker
         keyword,
         engine,
-        selectors: payload.selectors,
+        options,
+        max_retries: payload.max_retries.unwrap_or(crate::queue::DEFAULT_MAX_RETRIES),
+        backoff_secs: payload.retry_backoff_secs.unwrap_or(crate::queue::DEFAULT_BACKOFF_SECS),
+        attempt: 0,
+        priority: payload.priority.unwrap_or_default(),
+        run_at: payload.run_at,
+        monitor_id: None,
+        callback_url,
+        receipt: None,
     };
 
+    let queued_event = crate::events::JobEvent::new(crate::events::JobEventKind::Queued, &job);
+
     // Push to Redis Queue
     match state.queue.push_job(job).await {
         Ok(_) => {
-            println!("✅ [API] Job pushed to queue: {}", task_id);
-            Json(CrawlResponse {
+            info!("✅ [API] Job pushed to queue: {}", task_id);
+            crate::events::publish(queued_event);
+            let message = match payload.run_at {
+                Some(run_at) if run_at > chrono::Utc::now() => format!("Crawl job scheduled for {}", run_at.to_rfc3339()),
+                _ => "Crawl job queued successfully".to_string(),
+            };
+            Ok((quota.headers(), CrawlResponse {
                 task_id,
-                message: "Crawl job queued successfully".to_string(),
-            })
+                message,
+                duplicate: false,
+            }))
         },
         Err(e) => {
-            eprintln!("❌ [API] Failed to queue job: {}", e);
-            Json(CrawlResponse {
+            error!("❌ [API] Failed to queue job: {}", e);
+            let _ = state.queue.release_dedup_key(&dedup_key).await;
+            let _ = crate::login_secrets::discard(&state.pool, &task_id).await;
+            if let Err(e) = crate::credits::refund(&state.pool, &task_id).await {
+                warn!("⚠️ [API] Failed to refund credits for {}: {}", task_id, e);
+            }
+            if let Err(e) = quotas::release(&state.pool, &user.id, 1).await {
+                warn!("⚠️ [API] Failed to release quota for {}: {}", user.id, e);
+            }
+            quota.unreserve(1);
+            Ok((quota.headers(), CrawlResponse {
                 task_id,
                 message: "Failed to queue job".to_string(),
-            })
+                duplicate: false,
+            }))
+        }
+    }
+}
+
+/// Largest accepted batch upload
+const MAX_BATCH_ROWS: usize = 1000;
+
+/// One validated CSV row
+#[derive(Debug, PartialEq)]
+pub struct BatchRow {
+    pub keyword: String,
+    pub engine: String,
+    pub country: Option<String>,
+}
+
+/// Per-row outcome of a batch upload
+#[derive(Serialize, ToSchema)]
+pub struct BatchRowResult {
+    /// 1-based data row number (header excluded)
+    pub row: usize,
+    pub keyword: Option<String>,
+    pub accepted: bool,
+    /// The queued task; for a duplicate row, the recent task it matched
+    pub task_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchCrawlResponse {
+    pub success: bool,
+    pub accepted: usize,
+    pub rejected: usize,
+    pub rows: Vec<BatchRowResult>,
+    pub message: Option<String>,
+}
+
+/// Parse a `keyword,engine,country` CSV (header required; engine and country optional)
+pub fn parse_batch_csv(data: &[u8], engines: &crate::config::EnginesConfig) -> Result<Vec<Result<BatchRow, String>>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(data);
+    let headers = reader.headers().map_err(|e| format!("Invalid CSV header: {}", e))?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let keyword_col = column("keyword").ok_or("CSV must have a 'keyword' column")?;
+    let (engine_col, country_col) = (column("engine"), column("country"));
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        if rows.len() >= MAX_BATCH_ROWS {
+            return Err(format!("Batch exceeds {} rows", MAX_BATCH_ROWS));
+        }
+        let row = record.map_err(|e| format!("Malformed row: {}", e)).and_then(|record| {
+            let field = |col: Option<usize>| col.and_then(|c| record.get(c)).filter(|v| !v.is_empty());
+
+            let keyword = field(Some(keyword_col)).ok_or("keyword is empty")?.to_string();
+            let engine = field(engine_col).unwrap_or("bing").to_lowercase();
+            engines.check(&engine)?;
+            let country = match field(country_col) {
+                Some(c) if c.len() == 2 && c.chars().all(|ch| ch.is_ascii_alphabetic()) => Some(c.to_lowercase()),
+                Some(c) => return Err(format!("country '{}' is not a 2-letter ISO code", c)),
+                None => None,
+            };
+            Ok(BatchRow { keyword, engine, country })
+        });
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// A batch row that passed the `POST /crawl` checks, with the plan applied
+struct CheckedRow {
+    row: BatchRow,
+    options: crate::crawler::CrawlOptions,
+    dedup_key: String,
+}
+
+/// Run a parsed row through crawl validation and the caller's plan; a refusal
+/// comes back with the row's keyword and the reason
+async fn check_batch_row(
+    user_id: &str,
+    plan: &crate::subscriptions::Plan,
+    engines: &crate::config::EnginesConfig,
+    row: BatchRow,
+) -> Result<CheckedRow, (Option<String>, String)> {
+    let mut options = crate::crawler::CrawlOptions { gl: row.country.clone(), ..Default::default() };
+    if let Err(e) = crate::validation::validate_crawl(&row.keyword, &row.engine, &options, engines).await {
+        let (_, message) = <(StatusCode, String)>::from(e);
+        return Err((Some(row.keyword), message));
+    }
+    let dedup_key = dedup_key(user_id, None, &row.keyword, &row.engine, &options);
+    if let Err(e) = plan.enforce(&mut options) {
+        return Err((Some(row.keyword), e));
+    }
+    Ok(CheckedRow { row, options, dedup_key })
+}
+
+/// Upload a CSV of keywords and enqueue one crawl job per valid row
+#[utoipa::path(
+    post,
+    path = "/crawl/batch",
+    tag = "crawler",
+    request_body(content = String, content_type = "multipart/form-data", description = "`file` field containing a keyword,engine,country CSV"),
+    responses(
+        (status = 200, description = "Per-row acceptance details", body = BatchCrawlResponse),
+        (status = 429, description = "The batch doesn't fit in the remaining crawl quota")
+    )
+)]
+pub async fn batch_crawl(
+    State(state): State<Arc<AppState>>,
+    user: crate::auth::AuthUser,
+    mut multipart: axum::extract::Multipart,
+) -> Result<(HeaderMap, Json<BatchCrawlResponse>), Response> {
+    let mut data = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()).into_response())?
+    {
+        if field.name() == Some("file") {
+            data = Some(field.bytes().await.map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()).into_response())?);
+            break;
+        }
+    }
+    let data = data.ok_or_else(|| (StatusCode::BAD_REQUEST, "Missing 'file' field".to_string()).into_response())?;
+    let parsed = parse_batch_csv(&data, &state.config.engines).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+
+    let plan = crate::subscriptions::current_plan(&state.pool, &user.id).await.map_err(|e| {
+        error!("❌ [API] Failed to load plan for {}: {}", user.id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to load subscription plan".to_string()).into_response()
+    })?;
+    let mut checked = Vec::with_capacity(parsed.len());
+    for row in parsed {
+        checked.push(match row {
+            Ok(row) => check_batch_row(&user.id, &plan, &state.config.engines, row).await,
+            Err(e) => Err((None, e)),
+        });
+    }
+
+    // All-or-nothing: a batch that doesn't fit is refused before anything is queued
+    let valid_rows = checked.iter().filter(|r| r.is_ok()).count() as i64;
+    let mut quota = quotas::reserve(&state.pool, &user.id, valid_rows).await.map_err(IntoResponse::into_response)?;
+
+    let mut rows = Vec::with_capacity(checked.len());
+    for (i, row) in checked.into_iter().enumerate() {
+        let row_number = i + 1;
+        let CheckedRow { row, options, dedup_key } = match row {
+            Ok(row) => row,
+            Err((keyword, e)) => {
+                rows.push(BatchRowResult { row: row_number, keyword, accepted: false, task_id: None, error: Some(e) });
+                continue;
+            }
+        };
+
+        let task_id = Uuid::new_v4().to_string();
+        match state.queue.claim_dedup_key(&dedup_key, &task_id, dedup_window_secs()).await {
+            Ok(Some(existing)) => {
+                rows.push(BatchRowResult { row: row_number, keyword: Some(row.keyword), accepted: false, task_id: Some(existing), error: Some("Duplicate of a recent crawl".to_string()) });
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("⚠️ [API] Duplicate check failed for batch row {}: {}", row_number, e),
         }
+        let kind = crate::credits::CrawlKind::of(&row.engine, &options);
+        match crate::credits::debit(&state.pool, &user.id, &task_id, kind).await {
+            Ok(_) => {}
+            Err(crate::credits::CreditError::Insufficient { .. }) => {
+                let _ = state.queue.release_dedup_key(&dedup_key).await;
+                rows.push(BatchRowResult { row: row_number, keyword: Some(row.keyword), accepted: false, task_id: None, error: Some("Not enough credits".to_string()) });
+                continue;
+            }
+            Err(crate::credits::CreditError::Database(e)) => {
+                error!("❌ [API] Failed to charge batch row {}: {}", row_number, e);
+                let _ = state.queue.release_dedup_key(&dedup_key).await;
+                rows.push(BatchRowResult { row: row_number, keyword: Some(row.keyword), accepted: false, task_id: None, error: Some("Failed to charge credits".to_string()) });
+                continue;
+            }
+        }
+        let job = crate::queue::CrawlJob {
+            id: task_id.clone(),
+            user_id: user.id.clone(),
+            keyword: row.keyword.clone(),
+            engine: row.engine,
+            options,
+            max_retries: crate::queue::DEFAULT_MAX_RETRIES,
+            backoff_secs: crate::queue::DEFAULT_BACKOFF_SECS,
+            attempt: 0,
+            // Bulk uploads shouldn't starve interactive crawls
+            priority: crate::queue::Priority::Low,
+            run_at: None,
+            monitor_id: None,
+            callback_url: None,
+            receipt: None,
+        };
+        let queued_event = crate::events::JobEvent::new(crate::events::JobEventKind::Queued, &job);
+
+        match state.queue.push_job(job).await {
+            Ok(()) => {
+                crate::events::publish(queued_event);
+                rows.push(BatchRowResult { row: row_number, keyword: Some(row.keyword), accepted: true, task_id: Some(task_id), error: None });
+            }
+            Err(e) => {
+                error!("❌ [API] Failed to queue batch row {}: {}", row_number, e);
+                let _ = crate::credits::refund(&state.pool, &task_id).await;
+                let _ = state.queue.release_dedup_key(&dedup_key).await;
+                rows.push(BatchRowResult { row: row_number, keyword: Some(row.keyword), accepted: false, task_id: None, error: Some("Failed to queue job".to_string()) });
+            }
+        }
+    }
+
+    let accepted = rows.iter().filter(|r| r.accepted).count();
+    let rejected = rows.len() - accepted;
+    info!("✅ [API] Batch upload: {} queued, {} rejected", accepted, rejected);
+    // Rows refused along the way give their reservation back
+    let unused = valid_rows - accepted as i64;
+    if unused > 0 {
+        if let Err(e) = quotas::release(&state.pool, &user.id, unused).await {
+            warn!("⚠️ [API] Failed to release quota for {}: {}", user.id, e);
+        }
+        quota.unreserve(unused);
     }
+    Ok((quota.headers(), Json(BatchCrawlResponse {
+        success: accepted > 0,
+        accepted,
+        rejected,
+        rows,
+        message: None,
+    })))
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskStatusQuery {
+    /// Include the raw HTML of the first result page (`first_page_html`), fetched from object storage
+    pub include_html: Option<bool>,
 }
 
 #[utoipa::path(
     get,
     path = "/crawl/{task_id}",
     params(
-        ("task_id" = String, Path, description = "Task ID")
+        ("task_id" = String, Path, description = "Task ID"),
+        TaskStatusQuery
     ),
     responses(
-        (status = 200, description = "Crawl status/results", body = Option<TaskResult>)
+        (status = 200, description = "Crawl status/results; null if unknown, deleted or a task outside your organization", body = Option<TaskResult>)
     )
 )]
-
-
 pub async fn get_crawl_status(
-// ... existing code ...
     State(state): State<Arc<AppState>>,
+    user: crate::auth::AuthUser,
     Path(task_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<TaskStatusQuery>,
 ) -> Json<Option<TaskResult>> {
-    let rec = sqlx::query_as::<_, TaskResult>(
-        "SELECT id, keyword, engine, status, results_json, extracted_text, first_page_html, meta_description, meta_author, meta_date, entities, category FROM tasks WHERE id = $1"
+    crate::telemetry::record_task_id(&task_id);
+    let visible: Option<bool> = sqlx::query_scalar(&format!(
+        "SELECT user_id = $2 OR $3 OR {} FROM tasks WHERE id = $1 AND deleted_at IS NULL",
+        crate::organizations::teammates_filter("$2")
+    ))
+    .bind(&task_id)
+    .bind(&user.id)
+    .bind(user.is_admin())
+    .fetch_optional(&state.pool)
+    .await
+    .unwrap_or(None);
+    if visible != Some(true) {
+        return Json(None);
+    }
+    let mut task = load_task(&state.pool, &task_id).await.unwrap_or(None);
+    if let Some(task) = task.as_mut() {
+        crate::retention::restore(&state.storage, task).await;
+        if !query.include_html.unwrap_or(false) {
+            task.first_page_html = None;
+        } else if task.first_page_html.is_none() {
+            match load_task_html(&state, &task.id, &task.engine).await {
+                Ok(object) => task.first_page_html = object.map(|o| String::from_utf8_lossy(&o.body).into_owned()),
+                Err(e) => warn!("⚠️ Failed to read stored HTML of task {}: {:#}", task.id, e),
+            }
+        }
+    }
+    Json(task)
+}
+
+/// Engine of a task the user may read (their own, a teammate's, or any for admins);
+/// `None` for unknown and deleted tasks and tasks outside the user's organization
+pub async fn visible_task_engine(pool: &sqlx::PgPool, user: &crate::auth::AuthUser, task_id: &str) -> Result<Option<String>, sqlx::Error> {
+    let task: Option<(String, bool)> = sqlx::query_as(&format!(
+        "SELECT engine, user_id = $2 OR $3 OR {} FROM tasks WHERE id = $1 AND deleted_at IS NULL",
+        crate::organizations::teammates_filter("$2")
+    ))
+    .bind(task_id)
+    .bind(&user.id)
+    .bind(user.is_admin())
+    .fetch_optional(pool)
+    .await?;
+    Ok(task.filter(|(_, visible)| *visible).map(|(engine, _)| engine))
+}
+
+/// The raw HTML the worker stored for a task (its first result page)
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/html",
+    tag = "crawler",
+    params(
+        ("task_id" = String, Path, description = "Task ID")
+    ),
+    responses(
+        (status = 200, description = "Stored HTML", content_type = "text/html", body = String),
+        (status = 404, description = "Unknown task, a task outside your organization, or no HTML stored for it")
+    )
+)]
+pub async fn get_task_html(
+    State(state): State<Arc<AppState>>,
+    user: crate::auth::AuthUser,
+    Path(task_id): Path<String>,
+) -> Result<Response, (StatusCode, String)> {
+    let not_found = || (StatusCode::NOT_FOUND, "No stored HTML for this task".to_string());
+    let engine = visible_task_engine(&state.pool, &user, &task_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(not_found)?;
+
+    let object = load_task_html(&state, &task_id, &engine)
+        .await
+        .map_err(|e| {
+            error!("❌ Failed to read stored HTML of task {}: {}", task_id, e);
+            (StatusCode::BAD_GATEWAY, "Failed to read stored HTML".to_string())
+        })?
+        .ok_or_else(not_found)?;
+
+    let content_type = match object.content_type {
+        Some(ct) if ct != "text/html" => ct,
+        _ => "text/html; charset=utf-8".to_string(),
+    };
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, content_type),
+            (axum::http::header::CONTENT_LENGTH, object.body.len().to_string()),
+        ],
+        object.body,
+    )
+        .into_response())
+}
+
+/// Load a task's status and results (also sent to completion webhooks); the HTML is
+/// left out, see [`load_task_html`]
+pub async fn load_task(pool: &PgPool, task_id: &str) -> Result<Option<TaskResult>, sqlx::Error> {
+    sqlx::query_as::<_, TaskResult>(
+        "SELECT id, keyword, engine, status, stage, progress, attempts, last_error, failure_stage, proxy_id, stages, results_json, extracted_text, html_key, meta_description, meta_author, meta_date, entities, category, archived_at FROM tasks WHERE id = $1"
     )
     .bind(task_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// A task's raw HTML, fetched when asked for. Read from object storage under the
+/// row's `html_key` (or the conventional key for rows written before it existed);
+/// rows `html_backfill` hasn't moved yet still have it in `first_page_html`.
+pub async fn load_task_html(state: &AppState, task_id: &str, engine: &str) -> anyhow::Result<Option<crate::storage::StoredObject>> {
+    let row: Option<(Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT html_key, first_page_html FROM tasks WHERE id = $1")
+            .bind(task_id)
+            .fetch_optional(&state.pool)
+            .await?;
+    let Some((key, legacy)) = row else { return Ok(None) };
+    if let Some(html) = legacy {
+        return Ok(Some(crate::storage::StoredObject { body: html.into_bytes(), content_type: Some("text/html".to_string()) }));
+    }
+    let key = key.unwrap_or_else(|| crate::storage::html_key(engine, task_id));
+    state.storage.get_object(&key).await
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskSearchQuery {
+    /// Words to find; `"quoted phrases"`, `or` and `-excluded` words work as in web search
+    pub q: String,
+    /// Page number, starting at 1
+    pub page: Option<i64>,
+    /// Page size (default 20, max 100)
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct TaskSearchHit {
+    pub id: String,
+    pub keyword: String,
+    pub engine: String,
+    pub status: String,
+    pub created_at: Option<chrono::NaiveDateTime>,
+    /// Relevance; matches in the keyword weigh most, then the meta description, then the page text
+    pub rank: f32,
+    /// Matching passages of the extracted text (HTML-escaped), with matches wrapped in `<mark>`
+    pub snippet: Option<String>,
+}
+
+/// Full-text search over your and your teammates' crawls, best matches first
+#[utoipa::path(
+    get,
+    path = "/tasks/search",
+    tag = "crawler",
+    params(TaskSearchQuery),
+    responses(
+        (status = 200, description = "Matching tasks, best first", body = Vec<TaskSearchHit>),
+        (status = 400, description = "Empty query")
+    )
+)]
+pub async fn search_tasks(
+    State(state): State<Arc<AppState>>,
+    user: crate::auth::AuthUser,
+    axum::extract::Query(query): axum::extract::Query<TaskSearchQuery>,
+) -> Result<Json<Vec<TaskSearchHit>>, (StatusCode, String)> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "q must not be empty".to_string()));
+    }
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    // Snippets are only built for the page of hits; the text is escaped before <mark> is added
+    let hits = sqlx::query_as::<sqlx::Postgres, TaskSearchHit>(&format!(
+        r#"SELECT id, keyword, engine, status, created_at, rank,
+               ts_headline('simple',
+                   replace(replace(replace(left(coalesce(extracted_text, ''), 200000), '&', '&amp;'), '<', '&lt;'), '>', '&gt;'),
+                   query, 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=30, MinWords=10') AS snippet
+           FROM (
+               SELECT id, keyword, engine, status, created_at, extracted_text, query,
+                      ts_rank_cd(search_vector, query) AS rank
+               FROM tasks, websearch_to_tsquery('simple', $3) query
+               WHERE search_vector @@ query AND deleted_at IS NULL AND (user_id = $1 OR $2 OR {})
+               ORDER BY rank DESC, created_at DESC
+               LIMIT $4 OFFSET $5
+           ) hits
+           ORDER BY rank DESC, created_at DESC"#,
+        crate::organizations::teammates_filter("$1")
+    ))
+    .bind(&user.id)
+    .bind(user.is_admin())
+    .bind(q)
+    .bind(limit)
+    .bind((page - 1) * limit)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(hits))
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct TaskDeletion {
+    pub task_id: String,
+    /// Deleted for good (`purge=true`) rather than moved to the trash
+    pub purged: bool,
+    /// Until when `POST /tasks/{task_id}/restore` brings the task back; it is purged afterwards
+    #[schema(value_type = Option<String>)]
+    pub restorable_until: Option<chrono::DateTime<chrono::Utc>>,
+    /// Stored objects (raw HTML and other artifacts) that were deleted; none until the task is purged
+    pub stored_objects: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskDeleteQuery {
+    /// Delete the task and its stored objects now instead of moving it to the trash
+    pub purge: Option<bool>,
+}
+
+/// Last moment a task deleted at `deleted_at` can be restored
+pub fn restorable_until(deleted_at: chrono::DateTime<chrono::Utc>, grace_days: u32) -> chrono::DateTime<chrono::Utc> {
+    deleted_at + chrono::Duration::days(grace_days as i64)
+}
+
+#[derive(Debug)]
+pub enum PurgeError {
+    /// Stored objects couldn't be deleted; the row is kept so the purge can be retried
+    Storage(anyhow::Error),
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for PurgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PurgeError::Storage(e) => write!(f, "failed to delete stored objects: {}", e),
+            PurgeError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<PurgeError> for (StatusCode, String) {
+    fn from(e: PurgeError) -> Self {
+        match e {
+            PurgeError::Storage(_) => (StatusCode::BAD_GATEWAY, "Failed to delete stored objects; try again".to_string()),
+            PurgeError::Database(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        }
+    }
+}
+
+/// Delete a task for good: its stored objects, rank records, logs, row and search index entry.
+/// Returns the deleted object keys.
+pub async fn purge_task(state: &AppState, task_id: &str, engine: &str) -> Result<Vec<String>, PurgeError> {
+    // Objects first: if that fails the row stays, so the purge can be retried
+    let stored_objects = state
+        .storage
+        .delete_prefix(&crate::storage::task_prefix(engine, task_id))
+        .await
+        .map_err(PurgeError::Storage)?;
+
+    let mut tx = state.pool.begin().await.map_err(PurgeError::Database)?;
+    for sql in [
+        "DELETE FROM rankings WHERE task_id = $1",
+        "DELETE FROM task_logs WHERE task_id = $1",
+        "DELETE FROM tasks WHERE id = $1",
+    ] {
+        sqlx::query(sql).bind(task_id).execute(&mut *tx).await.map_err(PurgeError::Database)?;
+    }
+    tx.commit().await.map_err(PurgeError::Database)?;
+    if let Err(e) = crate::search_index::remove_task(task_id).await {
+        warn!("⚠️ Failed to remove task {} from Elasticsearch: {}", task_id, e);
+    }
+    Ok(stored_objects)
+}
+
+/// Move one of your tasks to the trash, or with `purge=true` delete it with its stored objects and rank records
+#[utoipa::path(
+    delete,
+    path = "/tasks/{task_id}",
+    tag = "crawler",
+    params(
+        ("task_id" = String, Path, description = "Task ID"),
+        TaskDeleteQuery
+    ),
+    responses(
+        (status = 200, description = "Task moved to the trash, or purged", body = TaskDeletion),
+        (status = 403, description = "Another user's task"),
+        (status = 404, description = "Task not found"),
+        (status = 409, description = "Task is still running or waiting for a retry"),
+        (status = 502, description = "Stored objects couldn't be deleted; the task is kept")
+    )
+)]
+pub async fn delete_task(
+    State(state): State<Arc<AppState>>,
+    user: crate::auth::AuthUser,
+    Path(task_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<TaskDeleteQuery>,
+) -> Result<Json<TaskDeletion>, (StatusCode, String)> {
+    let db_error = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let task: Option<(String, String, Option<String>)> =
+        sqlx::query_as("SELECT engine, status, user_id FROM tasks WHERE id = $1")
+            .bind(&task_id)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_error)?;
+    let (engine, status, owner) = task.ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))?;
+    if owner.as_deref() != Some(user.id.as_str()) && !user.is_admin() {
+        return Err((StatusCode::FORBIDDEN, "Only the task's owner can delete it".to_string()));
+    }
+    // The worker would write the row back when the job finishes
+    if status == "running" || status == "retrying" {
+        return Err((StatusCode::CONFLICT, format!("Task is {}; delete it once it has finished", status)));
+    }
+
+    if query.purge.unwrap_or(false) {
+        let stored_objects = purge_task(&state, &task_id, &engine).await.map_err(|e| {
+            error!("❌ Failed to purge task {}: {}", task_id, e);
+            <(StatusCode, String)>::from(e)
+        })?;
+        info!("🗑️ {} purged task {} ({} stored objects)", user.id, task_id, stored_objects.len());
+        return Ok(Json(TaskDeletion { task_id, purged: true, restorable_until: None, stored_objects }));
+    }
+
+    // Deleting a task already in the trash keeps its original deletion time
+    let deleted_at: chrono::DateTime<chrono::Utc> =
+        sqlx::query_scalar("UPDATE tasks SET deleted_at = COALESCE(deleted_at, now()) WHERE id = $1 RETURNING deleted_at")
+            .bind(&task_id)
+            .fetch_one(&state.pool)
+            .await
+            .map_err(db_error)?;
+    if let Err(e) = crate::search_index::remove_task(&task_id).await {
+        warn!("⚠️ Failed to remove task {} from Elasticsearch: {}", task_id, e);
+    }
+
+    info!("🗑️ {} moved task {} to the trash", user.id, task_id);
+    Ok(Json(TaskDeletion {
+        task_id,
+        purged: false,
+        restorable_until: Some(restorable_until(deleted_at, state.config.retention.deleted_grace_days)),
+        stored_objects: Vec::new(),
+    }))
+}
+
+/// Bring a deleted task back from the trash
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/restore",
+    tag = "crawler",
+    params(
+        ("task_id" = String, Path, description = "Task ID")
+    ),
+    responses(
+        (status = 200, description = "Task restored", body = TaskSummary),
+        (status = 403, description = "Another user's task"),
+        (status = 404, description = "Task not found"),
+        (status = 409, description = "Task isn't deleted"),
+        (status = 410, description = "The grace period has ended; the task is about to be purged")
+    )
+)]
+pub async fn restore_task(
+    State(state): State<Arc<AppState>>,
+    user: crate::auth::AuthUser,
+    Path(task_id): Path<String>,
+) -> Result<Json<TaskSummary>, (StatusCode, String)> {
+    let db_error = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let task: Option<(Option<String>, Option<chrono::DateTime<chrono::Utc>>)> =
+        sqlx::query_as("SELECT user_id, deleted_at FROM tasks WHERE id = $1")
+            .bind(&task_id)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_error)?;
+    let (owner, deleted_at) = task.ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))?;
+    if owner.as_deref() != Some(user.id.as_str()) && !user.is_admin() {
+        return Err((StatusCode::FORBIDDEN, "Only the task's owner can restore it".to_string()));
+    }
+    let deleted_at = deleted_at.ok_or((StatusCode::CONFLICT, "Task isn't deleted".to_string()))?;
+    if restorable_until(deleted_at, state.config.retention.deleted_grace_days) <= chrono::Utc::now() {
+        return Err((StatusCode::GONE, "The grace period has ended; the task can no longer be restored".to_string()));
+    }
+
+    let task = sqlx::query_as::<_, TaskSummary>(
+        "UPDATE tasks SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL
+         RETURNING id, keyword, engine, status, stage, progress, user_id, created_at, deleted_at",
+    )
+    .bind(&task_id)
     .fetch_optional(&state.pool)
     .await
-    .unwrap_or(None);
+    .map_err(db_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))?;
+    if matches!(task.status.as_str(), "completed" | "partial") {
+        if let Err(e) = crate::search_index::index_task(&state.pool, &task_id).await {
+            warn!("⚠️ Failed to re-index task {} in Elasticsearch: {}", task_id, e);
+        }
+    }
 
-    Json(rec)
+    info!("♻️ {} restored task {}", user.id, task_id);
+    Ok(Json(task))
 }
 
 #[utoipa::path(
     get,
     path = "/tasks",
     tag = "crawler",
+    params(TaskListQuery),
     responses(
-        (status = 200, description = "List recent tasks", body = Vec<TaskSummary>)
+        (status = 200, description = "A page of your and your teammates' tasks (everyone's for admins)", body = TaskPage)
     )
 )]
 pub async fn list_tasks(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<TaskSummary>>, (StatusCode, String)> {
-    let tasks = sqlx::query_as::<sqlx::Postgres, TaskSummary>(
-        "SELECT id, keyword, engine, status, created_at, results_json, left(extracted_text, 1000) as extracted_text FROM tasks ORDER BY created_at DESC LIMIT 50"
-    )
+    user: crate::auth::AuthUser,
+    axum::extract::Query(query): axum::extract::Query<TaskListQuery>,
+) -> Result<Json<TaskPage>, (StatusCode, String)> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let filters = format!(
+        "{} AND {}",
+        if query.deleted.unwrap_or(false) { deleted_task_filters_sql() } else { task_filters_sql() },
+        result_filters_sql()
+    );
+    let pattern = query.q.as_deref().filter(|q| !q.is_empty()).map(contains_pattern);
+    let non_empty = |v: &Option<String>| v.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+    let (entity, entity_type, category, email) =
+        (non_empty(&query.entity), non_empty(&query.entity_type), non_empty(&query.category), non_empty(&query.email));
+    let db_error = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+
+    let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM tasks WHERE {}", filters))
+        .bind(&user.id)
+        .bind(user.is_admin())
+        .bind(&query.status)
+        .bind(&query.engine)
+        .bind(query.from.map(|t| t.naive_utc()))
+        .bind(query.to.map(|t| t.naive_utc()))
+        .bind(&pattern)
+        .bind(&entity)
+        .bind(&entity_type)
+        .bind(&category)
+        .bind(&email)
+        .bind(query.has_email)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    let tasks = sqlx::query_as::<sqlx::Postgres, TaskSummary>(&format!(
+        "SELECT id, keyword, engine, status, stage, progress, user_id, created_at, deleted_at FROM tasks WHERE {} ORDER BY {} LIMIT $13 OFFSET $14",
+        filters,
+        task_order_clause(query.sort.unwrap_or_default(), query.order.unwrap_or_default())
+    ))
+    .bind(&user.id)
+    .bind(user.is_admin())
+    .bind(&query.status)
+    .bind(&query.engine)
+    .bind(query.from.map(|t| t.naive_utc()))
+    .bind(query.to.map(|t| t.naive_utc()))
+    .bind(&pattern)
+    .bind(&entity)
+    .bind(&entity_type)
+    .bind(&category)
+    .bind(&email)
+    .bind(query.has_email)
+    .bind(limit)
+    .bind((page - 1) * limit)
     .fetch_all(&state.pool)
     .await
-    .map_err(|e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(db_error)?;
 
-    Ok(Json(tasks))
+    Ok(Json(TaskPage { tasks, page, limit, total }))
 }
 
 // ============================================================================
@@ -180,10 +1164,11 @@ pub async fn list_tasks(
     path = "/proxies",
     tag = "proxy",
     responses(
-        (status = 200, description = "List all proxies", body = Vec<ProxyInfo>)
+        (status = 200, description = "List all proxies", body = Vec<ProxyInfo>),
+        (status = 403, description = "Caller is not an admin")
     )
 )]
-pub async fn list_proxies() -> Json<Vec<ProxyInfo>> {
+pub async fn list_proxies(_admin: AdminUser) -> Json<Vec<ProxyInfo>> {
     Json(PROXY_MANAGER.list_proxies())
 }
 
@@ -193,6 +1178,9 @@ pub struct AddProxyRequest {
     /// Proxy string: host:port or user:pass@host:port
     #[schema(example = "user:pass@1.2.3.4:8080")]
     pub proxy: String,
+    /// Exit country code (ISO 3166-1 alpha-2) for country-scoped selection
+    #[schema(example = "US")]
+    pub country: Option<String>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -208,13 +1196,15 @@ pub struct AddProxyResponse {
     tag = "proxy",
     request_body = AddProxyRequest,
     responses(
-        (status = 200, description = "Add a new proxy", body = AddProxyResponse)
+        (status = 200, description = "Add a new proxy", body = AddProxyResponse),
+        (status = 403, description = "Caller is not an admin")
     )
 )]
 pub async fn add_proxy(
+    _admin: AdminUser,
     Json(payload): Json<AddProxyRequest>,
 ) -> Json<AddProxyResponse> {
-    match PROXY_MANAGER.add_proxy(&payload.proxy) {
+    match PROXY_MANAGER.add_proxy(&payload.proxy, payload.country.as_deref()) {
         Ok(info) => Json(AddProxyResponse {
             success: true,
             proxy: Some(info),
@@ -243,10 +1233,12 @@ pub struct RemoveProxyResponse {
         ("proxy_id" = String, Path, description = "Proxy ID (e.g., host:port)")
     ),
     responses(
-        (status = 200, description = "Remove a proxy", body = RemoveProxyResponse)
+        (status = 200, description = "Remove a proxy", body = RemoveProxyResponse),
+        (status = 403, description = "Caller is not an admin")
     )
 )]
 pub async fn remove_proxy(
+    _admin: AdminUser,
     Path(proxy_id): Path<String>,
 ) -> Json<RemoveProxyResponse> {
     match PROXY_MANAGER.remove_proxy(&proxy_id) {
@@ -270,10 +1262,12 @@ pub async fn remove_proxy(
         ("proxy_id" = String, Path, description = "Proxy ID")
     ),
     responses(
-        (status = 200, description = "Re-enable a proxy", body = RemoveProxyResponse)
+        (status = 200, description = "Re-enable a proxy", body = RemoveProxyResponse),
+        (status = 403, description = "Caller is not an admin")
     )
 )]
 pub async fn enable_proxy(
+    _admin: AdminUser,
     Path(proxy_id): Path<String>,
 ) -> Json<RemoveProxyResponse> {
     match PROXY_MANAGER.enable_proxy(&proxy_id) {
@@ -288,15 +1282,244 @@ pub async fn enable_proxy(
     }
 }
 
+/// Run a live check through a proxy (exit IP, country, latency) and update its health
+#[utoipa::path(
+    post,
+    path = "/proxies/{proxy_id}/test",
+    tag = "proxy",
+    params(
+        ("proxy_id" = String, Path, description = "Proxy ID")
+    ),
+    responses(
+        (status = 200, description = "Proxy test result", body = ProxyTestResult),
+        (status = 403, description = "Caller is not an admin")
+    )
+)]
+pub async fn test_proxy(
+    _admin: AdminUser,
+    Path(proxy_id): Path<String>,
+) -> Json<ProxyTestResult> {
+    let Some(proxy) = PROXY_MANAGER.find_proxy(&proxy_id) else {
+        return Json(ProxyTestResult {
+            success: false,
+            proxy_id: proxy_id.clone(),
+            exit_ip: None,
+            country: None,
+            latency_ms: None,
+            google_reachable: false,
+            error: Some(format!("Proxy {} not found", proxy_id)),
+        });
+    };
+    Json(crate::proxy::test_proxy(&proxy, std::time::Duration::from_secs(15)).await)
+}
+
 /// Get aggregate proxy stats
 #[utoipa::path(
     get,
     path = "/proxies/stats",
     tag = "proxy",
     responses(
-        (status = 200, description = "Get proxy statistics", body = ProxyStats)
+        (status = 200, description = "Get proxy statistics", body = ProxyStats),
+        (status = 403, description = "Caller is not an admin")
     )
 )]
-pub async fn proxy_stats() -> Json<ProxyStats> {
+pub async fn proxy_stats(_admin: AdminUser) -> Json<ProxyStats> {
     Json(PROXY_MANAGER.get_stats())
 }
+
+#[derive(Serialize, ToSchema)]
+pub struct QueueStatsResponse {
+    pub success: bool,
+    /// Queue backend in use: redis, postgres or memory
+    pub backend: String,
+    /// Workers have been paused via `POST /worker/pause`
+    pub paused: bool,
+    pub queue: Option<crate::queue::QueueSnapshot>,
+    /// Lanes served by this instance's worker
+    pub workers: Vec<crate::worker::LaneConcurrency>,
+    pub error: Option<String>,
+}
+
+/// Queue depth per lane, oldest job age, last-hour throughput and worker concurrency
+#[utoipa::path(
+    get,
+    path = "/queue/stats",
+    tag = "crawler",
+    responses(
+        (status = 200, description = "Queue metrics", body = QueueStatsResponse)
+    )
+)]
+pub async fn queue_stats(
+    State(state): State<Arc<AppState>>,
+    _user: crate::auth::AuthUser,
+) -> Json<QueueStatsResponse> {
+    let (queue, error) = match state.queue.stats().await {
+        Ok(snapshot) => (Some(snapshot), None),
+        Err(e) => {
+            error!("❌ [API] Failed to read queue stats: {}", e);
+            (None, Some(e.to_string()))
+        }
+    };
+    Json(QueueStatsResponse {
+        success: queue.is_some(),
+        backend: state.queue.backend_name().to_string(),
+        paused: state.queue.is_paused().await.unwrap_or(false),
+        queue,
+        workers: crate::worker::lane_concurrency(),
+        error,
+    })
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WorkerStateResponse {
+    pub success: bool,
+    pub paused: bool,
+    pub error: Option<String>,
+}
+
+async fn set_workers_paused(state: &AppState, user: &crate::auth::AuthUser, paused: bool) -> Result<Json<WorkerStateResponse>, (StatusCode, String)> {
+    state.queue.set_paused(paused).await.map_err(|e| {
+        error!("❌ [API] Failed to update pause flag: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update pause flag: {}", e))
+    })?;
+    info!("{} [API] Workers {} by {}", if paused { "⏸️" } else { "▶️" }, if paused { "paused" } else { "resumed" }, user.id);
+    Ok(Json(WorkerStateResponse { success: true, paused, error: None }))
+}
+
+/// Stop all workers from claiming new jobs (running jobs finish). Admin only.
+#[utoipa::path(
+    post,
+    path = "/worker/pause",
+    tag = "crawler",
+    responses(
+        (status = 200, description = "Workers paused", body = WorkerStateResponse),
+        (status = 403, description = "Caller is not an admin")
+    )
+)]
+pub async fn pause_workers(
+    State(state): State<Arc<AppState>>,
+    AdminUser(user): AdminUser,
+) -> Result<Json<WorkerStateResponse>, (StatusCode, String)> {
+    set_workers_paused(&state, &user, true).await
+}
+
+/// Let workers claim jobs again. Admin only.
+#[utoipa::path(
+    post,
+    path = "/worker/resume",
+    tag = "crawler",
+    responses(
+        (status = 200, description = "Workers resumed", body = WorkerStateResponse),
+        (status = 403, description = "Caller is not an admin")
+    )
+)]
+pub async fn resume_workers(
+    State(state): State<Arc<AppState>>,
+    AdminUser(user): AdminUser,
+) -> Result<Json<WorkerStateResponse>, (StatusCode, String)> {
+    set_workers_paused(&state, &user, false).await
+}
+
+/// Switch the proxy rotation strategy at runtime
+#[derive(Deserialize, ToSchema)]
+pub struct SetStrategyRequest {
+    pub strategy: RotationStrategy,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SetStrategyResponse {
+    pub success: bool,
+    pub strategy: RotationStrategy,
+}
+
+#[utoipa::path(
+    put,
+    path = "/proxies/strategy",
+    tag = "proxy",
+    request_body = SetStrategyRequest,
+    responses(
+        (status = 200, description = "Rotation strategy updated", body = SetStrategyResponse),
+        (status = 403, description = "Caller is not an admin")
+    )
+)]
+pub async fn set_proxy_strategy(
+    _admin: AdminUser,
+    Json(payload): Json<SetStrategyRequest>,
+) -> Json<SetStrategyResponse> {
+    PROXY_MANAGER.set_strategy(payload.strategy);
+    Json(SetStrategyResponse {
+        success: true,
+        strategy: PROXY_MANAGER.strategy(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_listing_order_and_search() {
+        assert_eq!(task_order_clause(TaskSort::CreatedAt, SortOrder::Desc), "created_at DESC NULLS LAST, id DESC");
+        assert_eq!(task_order_clause(TaskSort::Keyword, SortOrder::Asc), "keyword ASC, created_at DESC NULLS LAST, id DESC");
+        assert_eq!(contains_pattern("rust"), "%rust%");
+        assert_eq!(contains_pattern("100%_off\\"), "%100\\%\\_off\\\\%");
+        assert!(task_filters_sql().trim_start().starts_with("deleted_at IS NULL"));
+        assert!(deleted_task_filters_sql().trim_start().starts_with("deleted_at IS NOT NULL"));
+        let result_filters = result_filters_sql();
+        assert!(["$8", "$9", "$10", "$11", "$12"].iter().all(|p| result_filters.contains(p)) && !result_filters.contains("$13"));
+    }
+
+    #[test]
+    fn test_restorable_until() {
+        let deleted_at = chrono::DateTime::parse_from_rfc3339("2024-03-01T12:00:00Z").unwrap().with_timezone(&chrono::Utc);
+        assert_eq!(restorable_until(deleted_at, 30).to_rfc3339(), "2024-03-31T12:00:00+00:00");
+        assert_eq!(restorable_until(deleted_at, 0), deleted_at);
+    }
+
+    #[test]
+    fn test_parse_batch_csv_validates_rows() {
+        let csv = b"keyword,engine,country\nrust jobs,google,DE\n,bing,us\nbest pizza,,\nseo,yahoo,us\nweather,bing,USA\n";
+        let rows = parse_batch_csv(csv, &Default::default()).unwrap();
+        assert_eq!(rows.len(), 5);
+        assert_eq!(
+            rows[0],
+            Ok(BatchRow { keyword: "rust jobs".into(), engine: "google".into(), country: Some("de".into()) })
+        );
+        assert!(rows[1].is_err());
+        assert_eq!(rows[2].as_ref().unwrap().engine, "bing");
+        assert!(rows[3].as_ref().unwrap_err().contains("yahoo"));
+        assert!(rows[4].is_err());
+
+        assert!(parse_batch_csv(b"term,engine\nrust,bing\n", &Default::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_batch_row_applies_crawl_rules() {
+        let (plan, engines) = (crate::subscriptions::Plan::free(), Default::default());
+        let row = |keyword: &str, engine: &str| BatchRow { keyword: keyword.into(), engine: engine.into(), country: Some("de".into()) };
+
+        let checked = check_batch_row("u1", &plan, &engines, row("rust jobs", "bing")).await.ok().unwrap();
+        assert_eq!(checked.options.direct, Some(true));
+        assert_eq!(checked.dedup_key, dedup_key("u1", None, "Rust Jobs", "bing", &crate::crawler::CrawlOptions { gl: Some("de".into()), ..Default::default() }));
+
+        let long = "x".repeat(crate::validation::MAX_KEYWORD_LEN + 1);
+        let (keyword, error) = check_batch_row("u1", &plan, &engines, row(&long, "bing")).await.err().unwrap();
+        assert_eq!(keyword.as_deref(), Some(long.as_str()));
+        assert!(error.starts_with("keyword:"), "{}", error);
+        let (_, error) = check_batch_row("u1", &plan, &engines, row("http://127.0.0.1:6379/", "generic")).await.err().unwrap();
+        assert!(error.contains("non-public"), "{}", error);
+    }
+
+    #[test]
+    fn test_dedup_key_scopes_and_fingerprints() {
+        let options = crate::crawler::CrawlOptions::default();
+        let a = dedup_key("u1", None, "Rust Jobs ", "bing", &options);
+        assert_eq!(a, dedup_key("u1", None, "rust jobs", "bing", &options));
+        assert_ne!(a, dedup_key("u2", None, "rust jobs", "bing", &options));
+        assert_ne!(a, dedup_key("u1", None, "rust jobs", "google", &options));
+
+        let geo = crate::crawler::CrawlOptions { gl: Some("de".into()), ..Default::default() };
+        assert_ne!(a, dedup_key("u1", None, "rust jobs", "bing", &geo));
+        assert_eq!(dedup_key("u1", Some("abc"), "x", "bing", &options), "u1:key:abc");
+    }
+}
@@ -0,0 +1,387 @@
+//! API keys for server-to-server access.
+//!
+//! A key is sent as `X-Api-Key: ak_<hex>` instead of a Supabase bearer token and
+//! authenticates as the user who created it, with the role they had at the time.
+//! Only a SHA-256 hash is stored; the key itself is returned once, on creation.
+//! Revoked or expired keys stop working immediately.
+//!
+//! Keys created with `scopes` only reach the endpoints those scopes cover (see
+//! [`required_scope`]); every other endpoint, key management included, answers
+//! 403. Keys without scopes have the creator's full access.
+
+use axum::{
+    extract::{Path, State},
+    http::{Method, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use tracing::{error, info};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+const KEY_PREFIX: &str = "ak_";
+/// Characters of the key kept in clear so users can tell their keys apart
+const DISPLAY_PREFIX_LEN: usize = 10;
+const MAX_KEYS_PER_USER: i64 = 20;
+
+/// What a scoped key may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum Scope {
+    /// Submit crawls (`POST /crawl`, `POST /crawl/batch`) and delete tasks
+    #[serde(rename = "crawl:write")]
+    CrawlWrite,
+    /// Read tasks, results, keyword diffs and queue stats
+    #[serde(rename = "tasks:read")]
+    TasksRead,
+    /// Manage proxies (the key's owner must also be an admin)
+    #[serde(rename = "proxies:admin")]
+    ProxiesAdmin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::CrawlWrite => "crawl:write",
+            Scope::TasksRead => "tasks:read",
+            Scope::ProxiesAdmin => "proxies:admin",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "crawl:write" => Some(Scope::CrawlWrite),
+            "tasks:read" => Some(Scope::TasksRead),
+            "proxies:admin" => Some(Scope::ProxiesAdmin),
+            _ => None,
+        }
+    }
+}
+
+/// The scope an endpoint (method and route pattern) requires of a scoped key;
+/// `None` means scoped keys can't use it at all
+pub fn required_scope(method: &Method, route: &str) -> Option<Scope> {
+    match (method.as_str(), route) {
+        ("POST", "/crawl") | ("POST", "/crawl/batch") | ("DELETE", "/tasks/:task_id") => Some(Scope::CrawlWrite),
+        ("GET", "/tasks")
+        | ("GET", "/tasks/search")
+        | ("GET", "/tasks/:task_id/html")
+        | ("GET", "/tasks/:task_id/logs")
+        | ("GET", "/tasks/:task_id/artifacts")
+        | ("GET", "/tasks/:task_id/artifacts/*name")
+        | ("GET", "/tasks/:task_id/export")
+        | ("GET", "/crawl/:task_id")
+        | ("GET", "/keywords/:keyword/diff")
+        | ("GET", "/queue/stats") => Some(Scope::TasksRead),
+        // GraphQL is read-only (proxies are still admin-only inside it)
+        ("POST", "/graphql") => Some(Scope::TasksRead),
+        // Bulk exports only read tasks
+        (_, route) if route == "/exports" || route.starts_with("/exports/") => Some(Scope::TasksRead),
+        (_, route) if route == "/proxies" || route.starts_with("/proxies/") => Some(Scope::ProxiesAdmin),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    /// Start of the key, e.g. `ak_3f9a1c2`
+    pub prefix: String,
+    /// Scopes the key is limited to; null for full access
+    #[schema(value_type = Option<Vec<String>>, example = json!(["tasks:read"]))]
+    pub scopes: Option<Vec<String>>,
+    #[schema(value_type = String)]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = Option<String>)]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>)]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>)]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    #[schema(example = "reporting-server")]
+    pub name: String,
+    /// Days until the key expires (never, if omitted)
+    #[schema(example = 90)]
+    pub expires_in_days: Option<i64>,
+    /// Limit the key to these scopes (full access if omitted)
+    pub scopes: Option<Vec<Scope>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateApiKeyResponse {
+    /// The full key; it cannot be retrieved again
+    pub key: String,
+    pub api_key: ApiKey,
+}
+
+type ApiError = (StatusCode, String);
+
+/// A fresh key: `ak_` followed by 32 random bytes in hex
+fn generate_key() -> String {
+    let bytes: [u8; 32] = rand::random();
+    format!("{}{}", KEY_PREFIX, bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A valid key: who it acts as and, for scoped keys, what it may do
+#[derive(Debug)]
+pub struct KeyGrant {
+    pub user: AuthUser,
+    /// Creation time (unix seconds); the key counts as issued then for account revocations
+    pub issued_at: i64,
+    pub scopes: Option<Vec<Scope>>,
+}
+
+impl KeyGrant {
+    /// Whether the key may call the endpoint matched by `method` and `route`
+    pub fn allows(&self, method: &Method, route: &str) -> bool {
+        match &self.scopes {
+            None => true,
+            Some(scopes) => required_scope(method, route).is_some_and(|needed| scopes.contains(&needed)),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct KeyOwner {
+    user_id: String,
+    created_at: Option<DateTime<Utc>>,
+    email: Option<String>,
+    role: String,
+    scopes: Option<Vec<String>>,
+}
+
+/// Resolve an `X-Api-Key` value to the user it acts as; `None` if unknown, revoked or expired
+pub async fn authenticate_key(pool: &PgPool, key: &str) -> Result<Option<KeyGrant>, sqlx::Error> {
+    if !key.starts_with(KEY_PREFIX) {
+        return Ok(None);
+    }
+    let row: Option<KeyOwner> = sqlx::query_as(
+        r#"UPDATE api_keys SET last_used_at = now()
+           WHERE key_hash = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > now())
+           RETURNING user_id, created_at, email, role, scopes"#,
+    )
+    .bind(hash_key(key))
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|row| KeyGrant {
+        user: AuthUser { id: row.user_id, email: row.email, role: row.role },
+        // A key without a creation time counts as older than any revocation
+        issued_at: row.created_at.map_or(0, |t| t.timestamp()),
+        // Unknown scope names (e.g. from a newer version) grant nothing
+        scopes: row.scopes.map(|s| s.iter().filter_map(|s| Scope::parse(s)).collect()),
+    }))
+}
+
+/// ID of a valid key (not revoked or expired), without marking it used
+pub async fn active_key_id(pool: &PgPool, key: &str) -> Result<Option<String>, sqlx::Error> {
+    if !key.starts_with(KEY_PREFIX) {
+        return Ok(None);
+    }
+    sqlx::query_scalar(
+        "SELECT id FROM api_keys WHERE key_hash = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > now())",
+    )
+    .bind(hash_key(key))
+    .fetch_optional(pool)
+    .await
+}
+
+// ============================================================================
+// API
+// ============================================================================
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [ApiKeys] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+const KEY_COLUMNS: &str = "id, name, prefix, scopes, created_at, last_used_at, expires_at, revoked_at";
+
+/// Create an API key; the response holds the only copy of the key
+#[utoipa::path(
+    post,
+    path = "/api-keys",
+    tag = "auth",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "Key created", body = CreateApiKeyResponse),
+        (status = 400, description = "Invalid name or expiry"),
+        (status = 409, description = "Too many active keys")
+    )
+)]
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> Result<Json<CreateApiKeyResponse>, ApiError> {
+    let name = req.name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return Err((StatusCode::BAD_REQUEST, "name must be 1-100 characters".to_string()));
+    }
+    let expires_at = match req.expires_in_days {
+        Some(days) if !(1..=3650).contains(&days) => {
+            return Err((StatusCode::BAD_REQUEST, "expires_in_days must be between 1 and 3650".to_string()));
+        }
+        Some(days) => Some(Utc::now() + Duration::days(days)),
+        None => None,
+    };
+    let scopes: Option<Vec<&str>> = match req.scopes {
+        Some(scopes) if scopes.is_empty() => {
+            return Err((StatusCode::BAD_REQUEST, "scopes must not be empty; omit it for full access".to_string()));
+        }
+        Some(scopes) => {
+            let mut names: Vec<&str> = scopes.iter().map(Scope::as_str).collect();
+            names.sort_unstable();
+            names.dedup();
+            Some(names)
+        }
+        None => None,
+    };
+
+    let active: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM api_keys WHERE user_id = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > now())",
+    )
+    .bind(&user.id)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+    if active >= MAX_KEYS_PER_USER {
+        return Err((StatusCode::CONFLICT, format!("At most {} active keys per user; revoke one first", MAX_KEYS_PER_USER)));
+    }
+
+    let key = generate_key();
+    let api_key: ApiKey = sqlx::query_as(&format!(
+        r#"INSERT INTO api_keys (id, user_id, email, role, name, prefix, key_hash, expires_at, scopes)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+           RETURNING {}"#,
+        KEY_COLUMNS
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(&user.id)
+    .bind(&user.email)
+    .bind(&user.role)
+    .bind(name)
+    .bind(&key[..DISPLAY_PREFIX_LEN])
+    .bind(hash_key(&key))
+    .bind(expires_at)
+    .bind(scopes)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    info!("🔑 [ApiKeys] {} created key {} ({})", user.id, api_key.prefix, api_key.name);
+    Ok(Json(CreateApiKeyResponse { key, api_key }))
+}
+
+/// The caller's API keys, including revoked ones, newest first
+#[utoipa::path(
+    get,
+    path = "/api-keys",
+    tag = "auth",
+    responses(
+        (status = 200, description = "API keys (without the secret part)", body = Vec<ApiKey>)
+    )
+)]
+pub async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<Vec<ApiKey>>, ApiError> {
+    let keys = sqlx::query_as(&format!(
+        "SELECT {} FROM api_keys WHERE user_id = $1 ORDER BY created_at DESC",
+        KEY_COLUMNS
+    ))
+    .bind(&user.id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(keys))
+}
+
+/// Revoke an API key; requests using it are rejected from now on
+#[utoipa::path(
+    delete,
+    path = "/api-keys/{id}",
+    tag = "auth",
+    params(
+        ("id" = String, Path, description = "API key ID")
+    ),
+    responses(
+        (status = 200, description = "Key revoked", body = ApiKey),
+        (status = 404, description = "Key not found or already revoked")
+    )
+)]
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<ApiKey>, ApiError> {
+    let revoked: Option<ApiKey> = sqlx::query_as(&format!(
+        "UPDATE api_keys SET revoked_at = now() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL RETURNING {}",
+        KEY_COLUMNS
+    ))
+    .bind(&id)
+    .bind(&user.id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let revoked = revoked.ok_or((StatusCode::NOT_FOUND, "API key not found".to_string()))?;
+    info!("🔑 [ApiKeys] {} revoked key {}", user.id, revoked.prefix);
+    Ok(Json(revoked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_keys() {
+        let key = generate_key();
+        assert!(key.starts_with(KEY_PREFIX));
+        assert_eq!(key.len(), KEY_PREFIX.len() + 64);
+        assert_ne!(key, generate_key());
+        // Only the hash is stored, and it must be stable for lookups
+        assert_eq!(hash_key(&key), hash_key(&key));
+        assert_eq!(hash_key(&key).len(), 64);
+    }
+
+    #[test]
+    fn test_scoped_key_routes() {
+        let user = AuthUser { id: "u1".to_string(), email: None, role: "authenticated".to_string() };
+        let read_only = KeyGrant { user: user.clone(), issued_at: 0, scopes: Some(vec![Scope::TasksRead]) };
+        assert!(read_only.allows(&Method::GET, "/tasks"));
+        assert!(read_only.allows(&Method::GET, "/crawl/:task_id"));
+        assert!(read_only.allows(&Method::GET, "/tasks/search"));
+        assert!(read_only.allows(&Method::POST, "/exports"));
+        assert!(read_only.allows(&Method::GET, "/exports/:id/download"));
+        assert!(read_only.allows(&Method::POST, "/graphql"));
+        assert!(read_only.allows(&Method::GET, "/tasks/:task_id/logs"));
+        assert!(read_only.allows(&Method::GET, "/tasks/:task_id/artifacts/*name"));
+        assert!(!read_only.allows(&Method::DELETE, "/tasks/:task_id"));
+        assert!(!read_only.allows(&Method::POST, "/crawl"));
+        assert!(!read_only.allows(&Method::DELETE, "/proxies/:proxy_id"));
+        // Endpoints outside every scope, such as key management, are closed to scoped keys
+        assert!(!read_only.allows(&Method::POST, "/api-keys"));
+
+        let full = KeyGrant { user, issued_at: 0, scopes: None };
+        assert!(full.allows(&Method::POST, "/api-keys"));
+
+        let scopes: Vec<Scope> = serde_json::from_str(r#"["crawl:write", "proxies:admin"]"#).unwrap();
+        assert_eq!(scopes, vec![Scope::CrawlWrite, Scope::ProxiesAdmin]);
+        assert!(scopes.iter().all(|s| Scope::parse(s.as_str()) == Some(*s)));
+    }
+}
@@ -0,0 +1,183 @@
+//! Debug artifacts of tasks.
+//!
+//! Screenshots and page dumps the crawler takes when something looks wrong
+//! (challenge pages, empty result pages, verification screenshots) are stored
+//! with the task in object storage instead of a container-local `debug/`
+//! directory, under `<engine>/<task_id>.debug/<attempt>/<name>`. They're listed by
+//! `GET /tasks/:task_id/artifacts`, served by `GET /tasks/:task_id/artifacts/*name`
+//! and deleted with the task.
+
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+use crate::api::AppState;
+use crate::crawler::DebugArtifact;
+use crate::queue::CrawlJob;
+use crate::storage::{artifacts_prefix, StorageManager};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskArtifact {
+    /// Attempt and file name, e.g. `1/bing_challenge.png`
+    #[schema(example = "1/bing_challenge.png")]
+    pub name: String,
+    /// Attempt the artifact was taken in (1 = first run)
+    pub attempt: i32,
+    pub size: i64,
+    #[schema(example = "/tasks/3f6c.../artifacts/1/bing_challenge.png")]
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskArtifactsResponse {
+    pub task_id: String,
+    pub artifacts: Vec<TaskArtifact>,
+}
+
+/// Artifact file names, numbered when the same name was captured more than once
+fn unique_names(artifacts: &[DebugArtifact]) -> Vec<String> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    artifacts
+        .iter()
+        .map(|artifact| {
+            let count = seen.entry(artifact.name.as_str()).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                return artifact.name.clone();
+            }
+            match artifact.name.rsplit_once('.') {
+                Some((stem, ext)) => format!("{}-{}.{}", stem, count, ext),
+                None => format!("{}-{}", artifact.name, count),
+            }
+        })
+        .collect()
+}
+
+/// Upload the artifacts of one job attempt; returns the stored keys
+pub async fn store(storage: &StorageManager, job: &CrawlJob, artifacts: Vec<DebugArtifact>) -> Vec<String> {
+    let prefix = format!("{}{}/", artifacts_prefix(&job.engine, &job.id), job.attempt + 1);
+    let names = unique_names(&artifacts);
+    let mut keys = Vec::new();
+    for (artifact, name) in artifacts.into_iter().zip(names) {
+        let key = format!("{}{}", prefix, name);
+        match storage.put_object(&key, artifact.body, artifact.content_type).await {
+            Ok(()) => keys.push(key),
+            Err(e) => warn!(task_id = %job.id, "⚠️ [Artifacts] Failed to store {}: {}", key, e),
+        }
+    }
+    if !keys.is_empty() {
+        info!(task_id = %job.id, "📸 [Artifacts] Stored {} debug artifact(s) for {}", keys.len(), job.id);
+    }
+    keys
+}
+
+fn storage_error(e: anyhow::Error) -> (StatusCode, String) {
+    error!("❌ Failed to read debug artifacts: {}", e);
+    (StatusCode::BAD_GATEWAY, "Failed to read debug artifacts".to_string())
+}
+
+async fn visible_engine(state: &AppState, user: &crate::auth::AuthUser, task_id: &str) -> Result<String, (StatusCode, String)> {
+    crate::api::visible_task_engine(&state.pool, user, task_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))
+}
+
+/// Debug screenshots and page dumps stored for a task
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/artifacts",
+    tag = "crawler",
+    params(
+        ("task_id" = String, Path, description = "Task ID")
+    ),
+    responses(
+        (status = 200, description = "Stored artifacts, by attempt", body = TaskArtifactsResponse),
+        (status = 404, description = "Unknown task or a task outside your organization")
+    )
+)]
+pub async fn list_artifacts(
+    State(state): State<Arc<AppState>>,
+    user: crate::auth::AuthUser,
+    Path(task_id): Path<String>,
+) -> Result<Json<TaskArtifactsResponse>, (StatusCode, String)> {
+    let engine = visible_engine(&state, &user, &task_id).await?;
+    let prefix = artifacts_prefix(&engine, &task_id);
+    let objects = state.storage.list_prefix(&prefix).await.map_err(storage_error)?;
+    let artifacts = objects
+        .into_iter()
+        .filter_map(|object| {
+            let name = object.key.strip_prefix(&prefix)?.to_string();
+            let attempt = name.split('/').next()?.parse().ok()?;
+            let url = format!("/tasks/{}/artifacts/{}", task_id, name);
+            Some(TaskArtifact { name, attempt, size: object.size, url })
+        })
+        .collect();
+    Ok(Json(TaskArtifactsResponse { task_id, artifacts }))
+}
+
+/// One stored artifact, with its content type
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/artifacts/{name}",
+    tag = "crawler",
+    params(
+        ("task_id" = String, Path, description = "Task ID"),
+        ("name" = String, Path, description = "Artifact name from the listing, e.g. `1/bing_challenge.png`")
+    ),
+    responses(
+        (status = 200, description = "The artifact (PNG screenshot or HTML dump)"),
+        (status = 404, description = "Unknown task or artifact")
+    )
+)]
+pub async fn get_artifact(
+    State(state): State<Arc<AppState>>,
+    user: crate::auth::AuthUser,
+    Path((task_id, name)): Path<(String, String)>,
+) -> Result<Response, (StatusCode, String)> {
+    let not_found = || (StatusCode::NOT_FOUND, "Artifact not found".to_string());
+    if name.split('/').any(|part| part.is_empty() || part == "..") {
+        return Err(not_found());
+    }
+    let engine = visible_engine(&state, &user, &task_id).await?;
+    let object = state
+        .storage
+        .get_object(&format!("{}{}", artifacts_prefix(&engine, &task_id), name))
+        .await
+        .map_err(storage_error)?
+        .ok_or_else(not_found)?;
+    let content_type = object.content_type.unwrap_or_else(|| "application/octet-stream".to_string());
+    Ok(([(header::CONTENT_TYPE, content_type), (header::CONTENT_LENGTH, object.body.len().to_string())], object.body).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(name: &str) -> DebugArtifact {
+        DebugArtifact { name: name.to_string(), content_type: "image/png", body: b"png".to_vec() }
+    }
+
+    #[tokio::test]
+    async fn test_store_numbers_repeated_names() {
+        let artifacts = vec![artifact("bing_challenge.png"), artifact("bing_challenge.png"), artifact("dump")];
+        assert_eq!(unique_names(&artifacts), vec!["bing_challenge.png", "bing_challenge-2.png", "dump"]);
+
+        let job: CrawlJob = serde_json::from_value(serde_json::json!({
+            "id": "t1", "user_id": "u1", "keyword": "rust", "engine": "bing", "attempt": 1
+        }))
+        .unwrap();
+        let storage = StorageManager::in_memory();
+        let keys = store(&storage, &job, artifacts).await;
+        assert_eq!(keys[1], "bing/t1.debug/2/bing_challenge-2.png");
+        let listed = storage.list_prefix(&artifacts_prefix("bing", "t1")).await.unwrap();
+        assert_eq!(listed.len(), 3);
+        // Deleting the task removes its artifacts too
+        assert_eq!(storage.delete_prefix(&crate::storage::task_prefix("bing", "t1")).await.unwrap().len(), 3);
+    }
+}
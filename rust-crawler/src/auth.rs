@@ -1,11 +1,37 @@
 //! Authentication module using Supabase JWT verification.
+//!
+//! Tokens are HS256 with the project's shared `SUPABASE_JWT_SECRET` by default.
+//! Projects on asymmetric signing keys set `SUPABASE_JWT_ALG=RS256` (or `ES256`):
+//! tokens are then checked against the project's JWKS, fetched from
+//! `SUPABASE_JWKS_URL` (default `$SUPABASE_URL/auth/v1/.well-known/jwks.json`)
+//! and cached for `SUPABASE_JWKS_TTL_SECS`. A token signed with a key the cache
+//! doesn't know triggers a refetch, so key rotation needs no restart.
+//!
+//! Supabase tokens only carry the Postgres role (`authenticated`, `anon` or
+//! `service_role`). Operators are marked with `"role": "admin"` in the user's
+//! `app_metadata`, which only the service key can set.
+//!
+//! For local development `AUTH_DISABLED=true` lets requests without credentials
+//! through as an admin "local-dev" user. Credentials that are sent are still verified.
+//!
+//! Bearer tokens and API keys are both checked against the revocation denylist
+//! (API keys by their creation time). When the denylist can't be read the request
+//! is refused with 503 rather than let through unchecked.
 
 use axum::{
     http::StatusCode,
     Json,
 };
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, DecodingKey, Validation, Algorithm};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+
+const DEFAULT_JWKS_TTL_SECS: u64 = 600;
+/// Minimum time between refetches triggered by unknown key IDs
+const JWKS_REFRESH_COOLDOWN: Duration = Duration::from_secs(30);
 
 /// JWT Claims from Supabase
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -15,6 +41,23 @@ pub struct Claims {
     pub role: Option<String>,
     pub exp: usize,
     pub iat: usize,
+    /// Token ID, used to revoke a single token
+    #[serde(default)]
+    pub jti: Option<String>,
+    /// Server-managed user metadata; users can't change it themselves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_metadata: Option<serde_json::Value>,
+}
+
+impl Claims {
+    /// The caller's role: `admin` when granted in `app_metadata`, otherwise the token's role
+    fn effective_role(&self) -> String {
+        let granted = self.app_metadata.as_ref().and_then(|m| m.get("role")).and_then(|r| r.as_str());
+        match granted {
+            Some("admin") => "admin".to_string(),
+            _ => self.role.clone().unwrap_or_else(|| "user".to_string()),
+        }
+    }
 }
 
 /// User context extracted from JWT
@@ -25,6 +68,48 @@ pub struct AuthUser {
     pub role: String,
 }
 
+impl AuthUser {
+    /// Operators: the `admin` role (from `app_metadata`), or Supabase's `service_role` key
+    pub fn is_admin(&self) -> bool {
+        matches!(self.role.as_str(), "admin" | "service_role")
+    }
+}
+
+/// Why a caller wasn't authenticated
+#[derive(Debug)]
+pub enum AuthError {
+    /// Bad, expired or revoked credentials (401)
+    Invalid(String),
+    /// The revocation denylist couldn't be checked (503)
+    Unavailable(String),
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::Invalid(message) | AuthError::Unavailable(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Refuse credentials of `user_id` issued at `issued_at` (and the token `jti`, if
+/// any) once revoked; a denylist that can't be read refuses them too
+pub async fn check_revocation(
+    denylist: &crate::revocation::Denylist,
+    user_id: &str,
+    jti: Option<&str>,
+    issued_at: i64,
+) -> Result<(), AuthError> {
+    match denylist.is_revoked(user_id, jti, issued_at).await {
+        Ok(true) => Err(AuthError::Invalid(format!("Credentials of {} have been revoked", user_id))),
+        Ok(false) => Ok(()),
+        Err(e) => {
+            error!("❌ [Auth] Denylist lookup failed, refusing request: {}", e);
+            Err(AuthError::Unavailable("Token revocation list unavailable".to_string()))
+        }
+    }
+}
+
 /// Auth Response
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
@@ -43,13 +128,155 @@ pub fn verify_token(token: &str, secret: &str) -> Result<Claims, String> {
         .map_err(|e| format!("JWT verification failed: {}", e))
 }
 
+/// Verify an asymmetrically signed JWT against the key its `kid` names in `jwks`
+pub fn verify_token_jwks(token: &str, jwks: &JwkSet, algorithm: Algorithm) -> Result<Claims, String> {
+    let header = decode_header(token).map_err(|e| format!("JWT verification failed: {}", e))?;
+    if header.alg != algorithm {
+        return Err(format!("JWT verification failed: expected {:?}, got {:?}", algorithm, header.alg));
+    }
+    let kid = header.kid.ok_or("JWT verification failed: token has no kid")?;
+    let jwk = jwks.find(&kid).ok_or_else(|| format!("JWT verification failed: unknown signing key {}", kid))?;
+    let key = DecodingKey::from_jwk(jwk).map_err(|e| format!("JWT verification failed: bad key {}: {}", kid, e))?;
+
+    let mut validation = Validation::new(algorithm);
+    validation.validate_exp = true;
+    validation.validate_aud = false;
+    decode::<Claims>(token, &key, &validation)
+        .map(|data| data.claims)
+        .map_err(|e| format!("JWT verification failed: {}", e))
+}
+
+// ============================================================================
+// JWKS cache
+// ============================================================================
+
+struct CachedJwks {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+static JWKS_CACHE: Lazy<RwLock<Option<CachedJwks>>> = Lazy::new(|| RwLock::new(None));
+
+fn jwks_url() -> Result<String, String> {
+    if let Ok(url) = std::env::var("SUPABASE_JWKS_URL") {
+        return Ok(url);
+    }
+    let base = std::env::var("SUPABASE_URL").map_err(|_| "SUPABASE_JWKS_URL or SUPABASE_URL must be set for asymmetric JWTs")?;
+    Ok(format!("{}/auth/v1/.well-known/jwks.json", base.trim_end_matches('/')))
+}
+
+fn jwks_ttl() -> Duration {
+    let secs = std::env::var("SUPABASE_JWKS_TTL_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(DEFAULT_JWKS_TTL_SECS);
+    Duration::from_secs(secs)
+}
+
+async fn fetch_jwks() -> Result<JwkSet, String> {
+    let url = jwks_url()?;
+    let keys: JwkSet = reqwest::Client::new()
+        .get(&url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("JWKS fetch from {} failed: {}", url, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid JWKS from {}: {}", url, e))?;
+    info!("🔑 Loaded {} signing key(s) from {}", keys.keys.len(), url);
+    Ok(keys)
+}
+
+/// The cached key set, refetched once stale or when `kid` isn't in it
+async fn jwks_for(kid: Option<&str>) -> Result<JwkSet, String> {
+    {
+        let cache = JWKS_CACHE.read().await;
+        if let Some(cached) = cache.as_ref() {
+            let fresh = cached.fetched_at.elapsed() < jwks_ttl();
+            let knows_kid = kid.is_none_or(|kid| cached.keys.find(kid).is_some());
+            let cooling_down = cached.fetched_at.elapsed() < JWKS_REFRESH_COOLDOWN;
+            if fresh && (knows_kid || cooling_down) {
+                return Ok(cached.keys.clone());
+            }
+        }
+    }
+
+    let mut cache = JWKS_CACHE.write().await;
+    // Another request may have refreshed it while we waited for the lock
+    if let Some(cached) = cache.as_ref() {
+        if cached.fetched_at.elapsed() < JWKS_REFRESH_COOLDOWN {
+            return Ok(cached.keys.clone());
+        }
+    }
+    match fetch_jwks().await {
+        Ok(keys) => {
+            *cache = Some(CachedJwks { keys: keys.clone(), fetched_at: Instant::now() });
+            Ok(keys)
+        }
+        // Keep serving the last known keys if the endpoint is briefly down
+        Err(e) => match cache.as_ref() {
+            Some(cached) => {
+                warn!("⚠️ {}; using cached keys", e);
+                Ok(cached.keys.clone())
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// `SUPABASE_JWT_ALG`: HS256 (shared secret, default), RS256 or ES256 (JWKS)
+fn configured_algorithm() -> Result<Algorithm, String> {
+    match std::env::var("SUPABASE_JWT_ALG").ok().as_deref().map(str::to_uppercase).as_deref() {
+        None | Some("") | Some("HS256") => Ok(Algorithm::HS256),
+        Some("RS256") => Ok(Algorithm::RS256),
+        Some("ES256") => Ok(Algorithm::ES256),
+        Some(other) => Err(format!("Unsupported SUPABASE_JWT_ALG {}", other)),
+    }
+}
+
+/// `AUTH_DISABLED=true`: requests without credentials run as [`dev_user`]
+pub fn auth_disabled() -> bool {
+    std::env::var("AUTH_DISABLED").map(|v| v == "true" || v == "1").unwrap_or(false)
+}
+
+/// The caller assumed for credential-less requests when auth is disabled
+pub fn dev_user() -> AuthUser {
+    AuthUser {
+        id: "local-dev".to_string(),
+        email: None,
+        role: "admin".to_string(),
+    }
+}
+
 /// Extract Bearer token from Authorization header
 pub fn extract_bearer_token(auth_header: &str) -> Option<&str> {
-    if auth_header.starts_with("Bearer ") {
-        Some(&auth_header[7..])
-    } else {
-        None
-    }
+    auth_header.strip_prefix("Bearer ")
+}
+
+/// Verify a bearer token (shared secret or JWKS, per `SUPABASE_JWT_ALG`), reject revoked
+/// tokens and build the user context
+pub async fn authenticate(token: &str, denylist: &crate::revocation::Denylist) -> Result<AuthUser, AuthError> {
+    let verified = match configured_algorithm() {
+        Ok(Algorithm::HS256) => {
+            let secret = std::env::var("SUPABASE_JWT_SECRET")
+                .unwrap_or_else(|_| "demo-secret".to_string());
+            verify_token(token, &secret)
+        }
+        Ok(algorithm) => {
+            let kid = decode_header(token).ok().and_then(|h| h.kid);
+            match jwks_for(kid.as_deref()).await {
+                Ok(jwks) => verify_token_jwks(token, &jwks, algorithm),
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+    let claims = verified.map_err(AuthError::Invalid)?;
+    check_revocation(denylist, &claims.sub, claims.jti.as_deref(), claims.iat as i64).await?;
+    Ok(AuthUser {
+        role: claims.effective_role(),
+        id: claims.sub,
+        email: claims.email,
+    })
 }
 
 /// Health check for auth service
@@ -63,61 +290,171 @@ pub async fn auth_status() -> Json<AuthResponse> {
 
 use axum::{
     async_trait,
-    extract::FromRequestParts,
+    extract::{FromRequestParts, MatchedPath},
     http::{request::Parts, header},
 };
+use std::sync::Arc;
+use crate::api::AppState;
 
+/// Authenticates with `X-Api-Key` when present, otherwise with the bearer JWT
 #[async_trait]
-impl<S> FromRequestParts<S> for AuthUser
-where
-    S: Send + Sync,
-{
+impl FromRequestParts<Arc<AppState>> for AuthUser {
     type Rejection = (StatusCode, Json<AuthResponse>);
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        let auth_header = parts
-            .headers
-            .get(header::AUTHORIZATION)
-            .and_then(|value| value.to_str().ok())
-            .ok_or_else(|| {
-                (
-                    StatusCode::UNAUTHORIZED,
-                    Json(AuthResponse {
-                        message: "Missing Authorization header".to_string(),
-                        user: None,
-                    }),
-                )
-            })?;
-
-        let token = extract_bearer_token(auth_header).ok_or_else(|| {
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let unauthorized = |message: &str| {
             (
                 StatusCode::UNAUTHORIZED,
                 Json(AuthResponse {
-                    message: "Invalid Authorization header format".to_string(),
+                    message: message.to_string(),
                     user: None,
                 }),
             )
-        })?;
+        };
+        let rejection = |e: AuthError| match e {
+            AuthError::Invalid(message) => {
+                warn!("⚠️ Auth Failed: {}", message);
+                unauthorized("Invalid, expired or revoked credentials")
+            }
+            AuthError::Unavailable(message) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(AuthResponse {
+                    message,
+                    user: None,
+                }),
+            ),
+        };
 
-        let secret = std::env::var("SUPABASE_JWT_SECRET")
-            .unwrap_or_else(|_| "demo-secret".to_string());
+        if let Some(key) = parts.headers.get(crate::api_keys::API_KEY_HEADER) {
+            let key = key.to_str().map_err(|_| unauthorized("Invalid API key"))?;
+            return match crate::api_keys::authenticate_key(&state.pool, key.trim()).await {
+                Ok(Some(grant)) => {
+                    let route = parts.extensions.get::<MatchedPath>().map(|p| p.as_str()).unwrap_or_else(|| parts.uri.path());
+                    check_revocation(&state.denylist, &grant.user.id, None, grant.issued_at).await.map_err(rejection)?;
+                    if !grant.allows(&parts.method, route) {
+                        return Err((
+                            StatusCode::FORBIDDEN,
+                            Json(AuthResponse {
+                                message: "API key scopes don't cover this endpoint".to_string(),
+                                user: None,
+                            }),
+                        ));
+                    }
+                    Ok(grant.user)
+                }
+                Ok(None) => Err(unauthorized("Invalid, revoked or expired API key")),
+                Err(e) => {
+                    error!("❌ [ApiKeys] Key lookup failed: {}", e);
+                    Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(AuthResponse {
+                            message: "Database error".to_string(),
+                            user: None,
+                        }),
+                    ))
+                }
+            };
+        }
 
-        let claims = verify_token(token, &secret).map_err(|e| {
-            println!("⚠️ Auth Failed: {}", e);
-            (
-                StatusCode::UNAUTHORIZED,
+        let auth_header = match parts.headers.get(header::AUTHORIZATION) {
+            None if auth_disabled() => return Ok(dev_user()),
+            header => header
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| unauthorized("Missing Authorization header"))?,
+        };
+
+        let token = extract_bearer_token(auth_header)
+            .ok_or_else(|| unauthorized("Invalid Authorization header format"))?;
+
+        authenticate(token, &state.denylist).await.map_err(rejection)
+    }
+}
+
+/// An authenticated caller with an admin role; anyone else gets 403
+#[derive(Debug, Clone)]
+pub struct AdminUser(pub AuthUser);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for AdminUser {
+    type Rejection = (StatusCode, Json<AuthResponse>);
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+        if !user.is_admin() {
+            warn!("🚫 {} (role {}) denied {} {}", user.id, user.role, parts.method, parts.uri.path());
+            return Err((
+                StatusCode::FORBIDDEN,
                 Json(AuthResponse {
-                    message: "Invalid or expired token".to_string(),
+                    message: "Admin role required".to_string(),
                     user: None,
                 }),
-            )
-        })?;
-
-        Ok(AuthUser {
-            id: claims.sub,
-            email: claims.email,
-            role: claims.role.unwrap_or_else(|| "user".to_string()),
-        })
+            ));
+        }
+        Ok(AdminUser(user))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    #[test]
+    fn test_verify_token_against_jwks() {
+        // Symmetric JWK keeps the fixture free of private keys; lookup and checks are the same
+        let jwks: JwkSet = serde_json::from_value(serde_json::json!({
+            "keys": [{"kty": "oct", "kid": "key-1", "k": "c2lnbmluZy1zZWNyZXQ"}]
+        }))
+        .unwrap();
+        let claims = Claims {
+            sub: "user-1".to_string(),
+            email: None,
+            role: Some("authenticated".to_string()),
+            exp: 4_000_000_000,
+            iat: 1_700_000_000,
+            jti: None,
+            app_metadata: None,
+        };
+        let sign = |kid: Option<&str>| {
+            let header = Header { kid: kid.map(str::to_string), ..Header::new(Algorithm::HS256) };
+            encode(&header, &claims, &EncodingKey::from_secret(b"signing-secret")).unwrap()
+        };
+
+        assert_eq!(verify_token_jwks(&sign(Some("key-1")), &jwks, Algorithm::HS256).unwrap().sub, "user-1");
+        assert!(verify_token_jwks(&sign(Some("key-2")), &jwks, Algorithm::HS256).unwrap_err().contains("unknown signing key"));
+        assert!(verify_token_jwks(&sign(None), &jwks, Algorithm::HS256).unwrap_err().contains("no kid"));
+        // The configured algorithm wins over the token's header
+        assert!(verify_token_jwks(&sign(Some("key-1")), &jwks, Algorithm::RS256).is_err());
+    }
+
+    #[test]
+    fn test_admin_role_comes_from_app_metadata() {
+        let claims: Claims = serde_json::from_value(serde_json::json!({
+            "sub": "user-1", "role": "authenticated", "exp": 4_000_000_000u64, "iat": 1_700_000_000,
+            "app_metadata": {"provider": "email", "role": "admin"},
+            "user_metadata": {"role": "admin"}
+        }))
+        .unwrap();
+        assert_eq!(claims.effective_role(), "admin");
+
+        // user_metadata is editable by the user and must not grant anything
+        let claims: Claims = serde_json::from_value(serde_json::json!({
+            "sub": "user-1", "role": "authenticated", "exp": 4_000_000_000u64, "iat": 1_700_000_000,
+            "app_metadata": {"provider": "email"},
+            "user_metadata": {"role": "admin"}
+        }))
+        .unwrap();
+        assert_eq!(claims.effective_role(), "authenticated");
+    }
+
+    #[tokio::test]
+    async fn test_check_revocation_covers_api_keys() {
+        let denylist = crate::revocation::Denylist::in_memory();
+        let created_at = chrono::Utc::now().timestamp() - 60;
+        assert!(check_revocation(&denylist, "user-1", None, created_at).await.is_ok());
+        denylist.revoke_user("user-1").await.unwrap();
+        // A key created before the account was locked out is refused; one created later works
+        assert!(matches!(check_revocation(&denylist, "user-1", None, created_at).await, Err(AuthError::Invalid(_))));
+        assert!(check_revocation(&denylist, "user-1", None, created_at + 3600).await.is_ok());
+    }
+}
@@ -8,7 +8,7 @@ async fn main() -> Result<()> {
     println!("🕵️ Starting Stealth Debugger...");
     
     // Exact args from crawler.rs
-    let mut args = vec![
+    let args = vec![
         std::ffi::OsStr::new("--disable-blink-features=AutomationControlled"),
         std::ffi::OsStr::new("--no-sandbox"),
         std::ffi::OsStr::new("--disable-dev-shm-usage"),
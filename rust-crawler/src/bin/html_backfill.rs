@@ -0,0 +1,70 @@
+//! Move raw HTML still stored in `tasks.first_page_html` to object storage.
+//!
+//! `cargo run --bin html_backfill` uses the same `DATABASE_URL` and storage
+//! settings as the service. Each row's HTML is written under its task's key, the
+//! key recorded in `html_key` and the column cleared. Re-running it is safe: rows
+//! already moved are skipped, and a row whose upload fails keeps its HTML.
+
+use rust_crawler::config::{Config, StorageBackendKind};
+use rust_crawler::storage::{html_key, StorageManager};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::ConnectOptions;
+
+const BATCH_SIZE: i64 = 100;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv::dotenv().ok();
+    let config = Config::load()?;
+    if config.storage.backend() == StorageBackendKind::Memory {
+        anyhow::bail!("Storage backend is in-memory; point STORAGE_BACKEND at MinIO/S3 first");
+    }
+    let storage = StorageManager::new(&config.storage).await?;
+
+    // Same connection settings as the service (Supabase's transaction pooler can't cache statements)
+    let db_url = std::env::var("DATABASE_URL")?;
+    let opts = sqlx::postgres::PgConnectOptions::from_url(&db_url.parse()?)?.statement_cache_capacity(0);
+    let pool = PgPoolOptions::new().max_connections(2).connect_with(opts).await?;
+
+    println!("📦 Moving stored HTML from Postgres to {}...", storage.backend_name());
+    let (mut moved, mut failed) = (0, 0);
+    // Keyset cursor, so rows that fail aren't fetched again
+    let mut after = String::new();
+    loop {
+        let rows: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT id, engine, first_page_html FROM tasks WHERE first_page_html IS NOT NULL AND id > $1 ORDER BY id LIMIT $2",
+        )
+        .bind(&after)
+        .bind(BATCH_SIZE)
+        .fetch_all(&pool)
+        .await?;
+        let Some((last, _, _)) = rows.last() else { break };
+        after = last.clone();
+        let count = rows.len();
+
+        for (id, engine, html) in rows {
+            let key = html_key(&engine, &id);
+            if let Err(e) = storage.store_html(&key, &html).await {
+                eprintln!("   ⚠️ {}: upload failed: {}", id, e);
+                failed += 1;
+                continue;
+            }
+            sqlx::query("UPDATE tasks SET html_key = $2, first_page_html = NULL WHERE id = $1")
+                .bind(&id)
+                .bind(&key)
+                .execute(&pool)
+                .await?;
+            moved += 1;
+        }
+        println!("   {} moved, {} failed", moved, failed);
+        if (count as i64) < BATCH_SIZE {
+            break;
+        }
+    }
+
+    println!("✅ Backfill finished: {} tasks moved, {} failed", moved, failed);
+    if moved > 0 {
+        println!("   Run VACUUM FULL tasks (locks the table) or pg_repack to return the space to the OS");
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! Index completed tasks into Elasticsearch / OpenSearch.
+//!
+//! `cargo run --bin search_backfill [--since YYYY-MM-DD]` uses the same
+//! `DATABASE_URL` and `ELASTICSEARCH_*` settings as the service. Re-running it is
+//! safe: documents are keyed by task ID and simply overwritten.
+
+use rust_crawler::search_index::{IndexRow, INDEX_COLUMNS, SEARCH_INDEX};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::ConnectOptions;
+
+const BATCH_SIZE: i64 = 500;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv::dotenv().ok();
+    let Some(index) = SEARCH_INDEX.as_ref() else {
+        anyhow::bail!("ELASTICSEARCH_URL must be set");
+    };
+
+    let args: Vec<String> = std::env::args().collect();
+    let since = match args.iter().position(|a| a == "--since") {
+        Some(i) => {
+            let date = args.get(i + 1).ok_or_else(|| anyhow::anyhow!("--since needs a date (YYYY-MM-DD)"))?;
+            Some(chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")?.and_hms_opt(0, 0, 0).expect("midnight is valid"))
+        }
+        None => None,
+    };
+
+    // Same connection settings as the service (Supabase's transaction pooler can't cache statements)
+    let db_url = std::env::var("DATABASE_URL")?;
+    let opts = sqlx::postgres::PgConnectOptions::from_url(&db_url.parse()?)?.statement_cache_capacity(0);
+    let pool = PgPoolOptions::new().max_connections(2).connect_with(opts).await?;
+
+    index.ensure_index().await?;
+    println!("🔎 Backfilling completed tasks into {}...", index.index_name());
+
+    let sql = format!(
+        "SELECT {} FROM tasks WHERE status IN ('completed', 'partial') AND deleted_at IS NULL AND ($1::timestamp IS NULL OR created_at >= $1) \
+         ORDER BY created_at, id LIMIT $2 OFFSET $3",
+        INDEX_COLUMNS
+    );
+    let (mut indexed, mut failed) = (0, 0);
+    loop {
+        let rows: Vec<IndexRow> = sqlx::query_as(&sql)
+            .bind(since)
+            .bind(BATCH_SIZE)
+            .bind((indexed + failed) as i64)
+            .fetch_all(&pool)
+            .await?;
+        let count = rows.len();
+        let docs: Vec<_> = rows.into_iter().map(IndexRow::into_document).collect();
+        let batch_failed = index.bulk_index(&docs).await?;
+        failed += batch_failed;
+        indexed += count - batch_failed;
+        println!("   {} indexed, {} failed", indexed, failed);
+        if (count as i64) < BATCH_SIZE {
+            break;
+        }
+    }
+
+    println!("✅ Backfill finished: {} tasks indexed, {} failed", indexed, failed);
+    Ok(())
+}
@@ -7,9 +7,35 @@ use std::time::Duration;
 use tokio::time::sleep;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use utoipa::ToSchema;
+use tracing::{info, warn};
+
+/// Maximum of simultaneous Chrome instances across all concurrent jobs (`CHROME_CONCURRENCY`)
+static BROWSER_LIMIT: Lazy<usize> = Lazy::new(|| {
+    std::env::var("CHROME_CONCURRENCY")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(2)
+});
+
+static BROWSER_SLOTS: Lazy<tokio::sync::Semaphore> = Lazy::new(|| tokio::sync::Semaphore::new(*BROWSER_LIMIT));
+
+/// Chrome instances running in this process, and the limit
+pub fn browser_usage() -> (usize, usize) {
+    let limit = *BROWSER_LIMIT;
+    (limit.saturating_sub(BROWSER_SLOTS.available_permits()), limit)
+}
+
+/// Wait for a free Chrome slot; the browser must be dropped before the permit
+async fn acquire_browser_slot() -> tokio::sync::SemaphorePermit<'static> {
+    BROWSER_SLOTS.acquire().await.expect("browser semaphore is never closed")
+}
 
 // Import from new proxy module
-use crate::proxy::{PROXY_MANAGER, generate_proxy_auth_extension};
+use crate::proxy::{PROXY_MANAGER, Proxy};
+use crate::proxy_forwarder::LocalForwarder;
+use crate::validation::MAX_CUSTOM_SCRIPT_LEN;
 
 static USER_AGENTS: Lazy<Vec<&'static str>> = Lazy::new(|| {
     vec![
@@ -22,6 +48,18 @@ static USER_AGENTS: Lazy<Vec<&'static str>> = Lazy::new(|| {
     ]
 });
 
+/// A desktop user agent: one of `[stealth] user_agents` if configured, else of the built-in list
+fn random_user_agent() -> String {
+    use rand::seq::SliceRandom;
+    let config = crate::config::get();
+    let agents: Vec<&str> = if config.stealth.user_agents.is_empty() {
+        USER_AGENTS.clone()
+    } else {
+        config.stealth.user_agents.iter().map(String::as_str).collect()
+    };
+    agents.choose(&mut rand::thread_rng()).unwrap_or(&USER_AGENTS[0]).to_string()
+}
+
 // ============================================================================
 // Enhanced Data Structures for Deep Extraction
 // ============================================================================
@@ -47,6 +85,12 @@ pub struct SerpData {
     pub featured_snippet: Option<FeaturedSnippet>,
     /// Total results count (if shown)
     pub total_results: Option<String>,
+    /// Structured fields extracted by a recipe (generic crawls; one object per page when paginating)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extracted_fields: Option<serde_json::Value>,
+    /// JSON return value of the job's `custom_script` (generic crawls)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script_result: Option<serde_json::Value>,
 }
 
 /// Featured snippet content
@@ -153,6 +197,206 @@ pub struct Cookie {
 // Map domain to list of cookies
 pub type CookieMap = std::collections::HashMap<String, Vec<Cookie>>;
 
+// ============================================================================
+// Per-Job Crawl Options
+// ============================================================================
+
+/// Per-job options carried from the API request through the queue to the crawler
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+pub struct CrawlOptions {
+    /// Named CSS selectors for generic crawls (field name -> selector)
+    #[schema(example = json!({"title": "h1", "content": ".post-body"}))]
+    pub selectors: Option<std::collections::HashMap<String, String>>,
+    /// Scripted login performed before generic extraction
+    pub login: Option<LoginFlow>,
+    /// Ordered form interactions (search boxes, filters) run after page load
+    pub steps: Option<Vec<InteractionStep>>,
+    /// Stored extraction recipe to use instead of inline selectors
+    pub recipe_id: Option<String>,
+    /// Typed extraction fields (inline, or resolved from `recipe_id` by the worker)
+    pub fields: Option<Vec<crate::recipes::RecipeField>>,
+    /// "Next page" link/button to follow on listing pages
+    #[schema(example = "a.next, a[rel='next']")]
+    pub next_page_selector: Option<String>,
+    /// Maximum pages to visit when following `next_page_selector` (default 5)
+    pub max_pages: Option<u32>,
+    /// Keep scrolling and harvesting items on feed-style pages
+    pub infinite_scroll: Option<InfiniteScroll>,
+    /// How long to wait for the page to render before extracting
+    pub wait_for: Option<WaitFor>,
+    /// JS function body evaluated in an isolated world after load; its JSON return value is attached to the result
+    #[schema(example = "return Array.from(document.querySelectorAll('.post')).length;")]
+    pub custom_script: Option<String>,
+    /// Extra HTTP headers sent with the requests to the job's target origin: the search
+    /// engine for SERP jobs, the URL's for generic crawls (never to other sites)
+    #[schema(example = json!({"Referer": "https://www.google.com/"}))]
+    pub headers: Option<std::collections::HashMap<String, String>>,
+    /// Cookies (name -> value) set for the job's target host only before loading it
+    #[schema(example = json!({"session_id": "abc123"}))]
+    pub cookies: Option<std::collections::HashMap<String, String>>,
+    /// Force a specific proxy (see GET /proxies) instead of the rotation strategy
+    #[schema(example = "1.2.3.4:8080")]
+    pub proxy_id: Option<String>,
+    /// Connect without a proxy (always set for plans without proxy access)
+    pub direct: Option<bool>,
+    /// Target market as an ISO country code: sets Google `gl` / Bing `cc` and prefers proxies exiting in that country
+    #[schema(example = "de")]
+    pub gl: Option<String>,
+    /// Proxy session key set by the worker when sticky sessions are enabled
+    #[serde(skip)]
+    pub proxy_session: Option<String>,
+    /// Counts bytes sent through proxies for the job; set by the worker for usage metering
+    #[serde(skip)]
+    pub proxy_meter: Option<std::sync::Arc<std::sync::atomic::AtomicU64>>,
+    /// Proxy IDs the job is limited to (its organization's dedicated pool); set by the worker
+    #[serde(skip)]
+    pub proxy_pool: Option<Vec<String>>,
+    /// Collects screenshots and page dumps to store with the task; set by the worker
+    #[serde(skip)]
+    pub debug_artifacts: Option<ArtifactSink>,
+    /// Receives the ID of the last proxy selected for the job; set by the worker to record it on the task
+    #[serde(skip)]
+    pub proxy_used: Option<ProxyRecorder>,
+}
+
+/// A screenshot or page dump captured while crawling
+#[derive(Debug, Clone)]
+pub struct DebugArtifact {
+    /// File name, e.g. `bing_challenge.png`
+    pub name: String,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+/// Debug artifacts of one job attempt
+pub type ArtifactSink = std::sync::Arc<std::sync::Mutex<Vec<DebugArtifact>>>;
+
+/// ID of the last proxy a job attempt went through
+pub type ProxyRecorder = std::sync::Arc<std::sync::Mutex<Option<String>>>;
+
+/// Most artifacts kept per attempt (each retry inside a crawl can add more)
+const MAX_DEBUG_ARTIFACTS: usize = 20;
+
+/// Keep a debug screenshot or page dump: with the task when the worker runs the
+/// crawl, else in the local `debug/` directory
+fn save_debug_artifact(options: &CrawlOptions, name: &str, content_type: &'static str, body: Vec<u8>) {
+    match options.debug_artifacts {
+        Some(ref sink) => {
+            let mut artifacts = sink.lock().unwrap();
+            if artifacts.len() < MAX_DEBUG_ARTIFACTS {
+                artifacts.push(DebugArtifact { name: name.to_string(), content_type, body });
+            }
+        }
+        None => {
+            let path = format!("debug/debug_{}", name);
+            if std::fs::write(&path, &body).is_ok() {
+                info!("Saved {}", path);
+            }
+        }
+    }
+}
+
+/// Page readiness strategy: wait for a selector, or just sleep `timeout_ms` when none is given
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct WaitFor {
+    #[schema(example = "#app .content")]
+    pub selector: Option<String>,
+    /// Max wait for the selector, or fixed delay without one (default 10000)
+    #[schema(example = 10000)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Default hydration wait when a job specifies no `wait_for`
+const DEFAULT_HYDRATION_WAIT: Duration = Duration::from_secs(4);
+
+/// Wait for the page to be ready according to the job's wait strategy
+pub async fn wait_for_page(tab: &std::sync::Arc<headless_chrome::Tab>, wait_for: Option<&WaitFor>) {
+    let Some(wait) = wait_for else {
+        // Wait for JS execution (Hydration)
+        sleep(DEFAULT_HYDRATION_WAIT).await;
+        return;
+    };
+    let timeout = Duration::from_millis(wait.timeout_ms.unwrap_or(10_000));
+    match wait.selector {
+        Some(ref selector) => match tab.wait_for_element_with_custom_timeout(selector, timeout) {
+            Ok(_) => info!("⏱️ Wait selector '{}' found.", selector),
+            Err(e) => warn!("⚠️ Wait selector '{}' timed out: {}. Extracting anyway...", selector, e),
+        },
+        None => sleep(timeout).await,
+    }
+}
+
+/// Infinite-scroll harvesting settings for feed-based pages
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct InfiniteScroll {
+    /// Selector matching one feed item (post, card, row)
+    #[schema(example = "div[role='article']")]
+    pub item_selector: String,
+    /// Stop after this many unique items (default 100)
+    pub max_items: Option<usize>,
+    /// Stop after this many scrolls (default 30)
+    pub max_scrolls: Option<u32>,
+    /// Stop after this many scrolls in a row yield nothing new (default 3)
+    pub idle_rounds: Option<u32>,
+}
+
+/// Single page interaction step for generic crawls
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum InteractionStep {
+    /// Click an element
+    Click { selector: String },
+    /// Focus an element and type text into it
+    Type { selector: String, text: String },
+    /// Choose an option of a <select> by value
+    Select { selector: String, value: String },
+    /// Wait until an element appears
+    WaitFor { selector: String, timeout_ms: Option<u64> },
+}
+
+/// Scripted login flow for forums/portals that require authentication
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct LoginFlow {
+    /// Login page URL (defaults to the crawl target)
+    #[schema(example = "https://forum.example.com/login")]
+    pub url: Option<String>,
+    pub username: String,
+    /// Write-only: moved into `login_secrets` when a job is queued or a schedule or
+    /// monitor is saved, and never returned. Leave it out on updates to keep the stored one.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub password: String,
+    /// Set by the server in place of `password`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_ref: Option<String>,
+    #[schema(example = "input[name='username']")]
+    pub username_selector: String,
+    #[schema(example = "input[name='password']")]
+    pub password_selector: String,
+    #[schema(example = "button[type='submit']")]
+    pub submit_selector: String,
+    /// Element that only exists once logged in (e.g. a logout link)
+    #[schema(example = "a[href*='logout']")]
+    pub success_selector: Option<String>,
+    /// Substring the URL must contain after submitting
+    pub success_url_contains: Option<String>,
+}
+
+impl std::fmt::Debug for LoginFlow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoginFlow")
+            .field("url", &self.url)
+            .field("username", &self.username)
+            .field("password", &if self.password.is_empty() { "" } else { "[redacted]" })
+            .field("password_ref", &self.password_ref)
+            .field("username_selector", &self.username_selector)
+            .field("password_selector", &self.password_selector)
+            .field("submit_selector", &self.submit_selector)
+            .field("success_selector", &self.success_selector)
+            .field("success_url_contains", &self.success_url_contains)
+            .finish()
+    }
+}
+
 // ============================================================================
 // Cookie Helper Functions
 // ============================================================================
@@ -161,7 +405,7 @@ pub type CookieMap = std::collections::HashMap<String, Vec<Cookie>>;
 pub fn load_cookies(domain_key: &str) -> Option<Vec<Cookie>> {
     let cookie_file = "cookies.json";
     if !std::path::Path::new(cookie_file).exists() {
-        println!("🍪 No cookies.json found. Skipping cookie injection.");
+        info!("🍪 No cookies.json found. Skipping cookie injection.");
         return None;
     }
 
@@ -170,16 +414,16 @@ pub fn load_cookies(domain_key: &str) -> Option<Vec<Cookie>> {
             match serde_json::from_str::<CookieMap>(&content) {
                 Ok(map) => {
                     if let Some(cookies) = map.get(domain_key) {
-                        println!("🍪 Found {} cookies for {}", cookies.len(), domain_key);
+                        info!("🍪 Found {} cookies for {}", cookies.len(), domain_key);
                         return Some(cookies.clone());
                     } else {
-                        println!("🍪 No cookies found for domain: {}", domain_key);
+                        info!("🍪 No cookies found for domain: {}", domain_key);
                     }
                 },
-                Err(e) => println!("⚠️ Failed to parse cookies.json: {}", e),
+                Err(e) => warn!("⚠️ Failed to parse cookies.json: {}", e),
             }
         },
-        Err(e) => println!("⚠️ Failed to read cookies.json: {}", e),
+        Err(e) => warn!("⚠️ Failed to read cookies.json: {}", e),
     }
     None
 }
@@ -188,7 +432,7 @@ pub fn load_cookies(domain_key: &str) -> Option<Vec<Cookie>> {
 pub fn inject_cookies(tab: &std::sync::Arc<headless_chrome::Tab>, cookies: &[Cookie]) -> Result<()> {
     use headless_chrome::protocol::cdp::Network;
     
-    println!("🍪 Injecting {} cookies...", cookies.len());
+    info!("🍪 Injecting {} cookies...", cookies.len());
     for cookie in cookies {
         // We use Network.setCookie for each cookie
         // Note: This is synchronous and might fail if domain doesn't match current context,
@@ -211,13 +455,195 @@ pub fn inject_cookies(tab: &std::sync::Arc<headless_chrome::Tab>, cookies: &[Coo
         });
 
         if let Err(e) = result {
-             println!("⚠️ Failed to set cookie {}: {}", cookie.name, e);
+             warn!("⚠️ Failed to set cookie {}: {}", cookie.name, e);
         }
     }
     
     Ok(())
 }
 
+/// Apply a job's extra headers and cookies via CDP before navigating to `target_url`,
+/// scoped to that URL's origin. Headers are added by request interception to the
+/// origin's requests only, so third-party subresources and other sites the tab
+/// reaches never see them; cookies are host-only cookies of the URL.
+pub fn apply_job_headers_and_cookies(tab: &std::sync::Arc<headless_chrome::Tab>, options: &CrawlOptions, target_url: &str) -> Result<()> {
+    use headless_chrome::browser::tab::RequestPausedDecision;
+    use headless_chrome::protocol::cdp::{Fetch, Network};
+
+    if let Some(headers) = options.headers.clone().filter(|h| !h.is_empty()) {
+        let origin = reqwest::Url::parse(target_url)?.origin();
+        if !origin.is_tuple() {
+            return Err(anyhow::anyhow!("Custom headers need an http(s) target, not {}", target_url));
+        }
+        info!("📨 Applying {} custom headers to {}", headers.len(), origin.ascii_serialization());
+        let pattern = Fetch::RequestPattern {
+            url_pattern: Some(format!("{}/*", origin.ascii_serialization())),
+            resource_Type: None,
+            request_stage: Some(Fetch::RequestStage::Request),
+        };
+        tab.enable_request_interception(std::sync::Arc::new(move |_transport, _session, event: Fetch::events::RequestPausedEvent| {
+            let request = event.params.request;
+            // The pattern already limits interception to the origin; redirects are paused again
+            let same_origin = reqwest::Url::parse(&request.url).is_ok_and(|url| url.origin() == origin);
+            let mut entries: Vec<Fetch::HeaderEntry> = request
+                .headers
+                .0
+                .as_ref()
+                .and_then(|h| h.as_object())
+                .into_iter()
+                .flatten()
+                .filter(|(name, _)| !same_origin || !headers.keys().any(|k| k.eq_ignore_ascii_case(name)))
+                .map(|(name, value)| Fetch::HeaderEntry { name: name.clone(), value: value.as_str().unwrap_or_default().to_string() })
+                .collect();
+            if same_origin {
+                entries.extend(headers.iter().map(|(name, value)| Fetch::HeaderEntry { name: name.clone(), value: value.clone() }));
+            }
+            RequestPausedDecision::Continue(Some(Fetch::ContinueRequest {
+                request_id: event.params.request_id,
+                url: None,
+                method: None,
+                post_data: None,
+                headers: Some(entries),
+                intercept_response: None,
+            }))
+        }))?;
+        tab.enable_fetch(Some(&[pattern]), None)?;
+    }
+
+    if let Some(ref cookies) = options.cookies {
+        info!("🍪 Applying {} job cookies for {}", cookies.len(), target_url);
+        for (name, value) in cookies {
+            let result = tab.call_method(Network::SetCookie {
+                name: name.clone(),
+                value: value.clone(),
+                url: Some(target_url.to_string()),
+                domain: None,
+                path: Some("/".to_string()),
+                secure: None,
+                http_only: None,
+                same_site: None,
+                expires: None,
+                priority: None,
+                same_party: None,
+                source_scheme: None,
+                source_port: None,
+                partition_key: None,
+            });
+            if let Err(e) = result {
+                warn!("⚠️ Failed to set cookie {}: {}", name, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Proxy Selection
+// ============================================================================
+
+/// Pick the proxy for a job (see [`pick_proxy`]), noting it in `options.proxy_used`
+pub fn select_proxy(options: &CrawlOptions) -> Result<Option<std::sync::Arc<Proxy>>> {
+    let proxy = pick_proxy(options)?;
+    if let (Some(recorder), Some(proxy)) = (&options.proxy_used, &proxy) {
+        *recorder.lock().unwrap() = Some(proxy.id.clone());
+    }
+    Ok(proxy)
+}
+
+/// None for `direct` jobs, the pinned `proxy_id` if set, then the task's sticky
+/// session proxy, otherwise the next one from rotation
+fn pick_proxy(options: &CrawlOptions) -> Result<Option<std::sync::Arc<Proxy>>> {
+    if options.direct == Some(true) {
+        return Ok(None);
+    }
+    if let Some(ref id) = options.proxy_id {
+        if !PROXY_MANAGER.allows(id, options.proxy_pool.as_deref()) {
+            anyhow::bail!("Pinned proxy {} isn't available to this account", id);
+        }
+        let proxy = PROXY_MANAGER
+            .get_proxy(id)
+            .ok_or_else(|| anyhow::anyhow!("Pinned proxy {} not found", id))?;
+        info!(proxy_id = %proxy.id, "📌 Using pinned proxy: {}", proxy.id);
+        return Ok(Some(proxy));
+    }
+    let pool = options.proxy_pool.as_deref();
+    if let Some(ref session) = options.proxy_session {
+        return Ok(PROXY_MANAGER.get_proxy_for_session(session, options.gl.as_deref(), pool));
+    }
+    if let Some(ref gl) = options.gl {
+        if let Some(proxy) = PROXY_MANAGER.get_next_proxy_for_country(gl, pool) {
+            info!(proxy_id = %proxy.id, "🌍 Using {} proxy: {}", gl.to_uppercase(), proxy.id);
+            return Ok(Some(proxy));
+        }
+        if PROXY_MANAGER.has_proxies() {
+            warn!("⚠️ No healthy proxy for country {}, falling back to rotation", gl.to_uppercase());
+        }
+    }
+    Ok(PROXY_MANAGER.get_next_proxy(pool))
+}
+
+/// Whether a crawl error points at the exit node (block, challenge, network) rather than the job itself
+fn is_proxy_fault(err: &anyhow::Error) -> bool {
+    let msg = format!("{:#}", err).to_lowercase();
+    ["challenge", "captcha", "checkpoint", "timeout", "timed out", "net::err", "proxy", "connection"]
+        .iter()
+        .any(|needle| msg.contains(needle))
+}
+
+/// Feed a crawl outcome back into the proxy's health tracking.
+/// Errors unrelated to the exit node (bad selectors, failed logins) are not counted.
+fn report_proxy_outcome<T>(proxy: Option<&Proxy>, result: &Result<T>) {
+    let Some(proxy) = proxy else { return };
+    match result {
+        Ok(_) => PROXY_MANAGER.mark_success(&proxy.id),
+        Err(e) if is_proxy_fault(e) => PROXY_MANAGER.mark_failure(&proxy.id),
+        Err(_) => {}
+    }
+}
+
+/// Like `report_proxy_outcome`, but an empty SERP is inconclusive (rare query or soft block)
+fn report_serp_outcome(proxy: Option<&Proxy>, result: &Result<SerpData>) {
+    if matches!(result, Ok(data) if data.results.is_empty()) {
+        return;
+    }
+    report_proxy_outcome(proxy, result);
+}
+
+/// Start Chrome, counting the launch (and whether it worked) for `/metrics`
+#[tracing::instrument(name = "browser.launch", skip(options))]
+fn launch_browser(kind: &str, options: LaunchOptions) -> Result<Browser> {
+    let browser = Browser::new(options);
+    let result = if browser.is_ok() { "ok" } else { "error" };
+    crate::metrics::CHROME_LAUNCHES.with_label_values(&[kind, result]).inc();
+    browser
+}
+
+/// Chrome proxy flags for one browser launch. Authenticated upstreams, and any
+/// upstream of a metered job, are reached through a local forwarder, which must
+/// stay alive as long as the browser.
+#[derive(Default)]
+struct ProxyLaunch {
+    flags: Vec<String>,
+    _forwarder: Option<LocalForwarder>,
+}
+
+fn proxy_launch_args(proxy: &std::sync::Arc<Proxy>, options: &CrawlOptions) -> Result<ProxyLaunch> {
+    if proxy.requires_auth() || options.proxy_meter.is_some() {
+        let meter = options.proxy_meter.clone().unwrap_or_default();
+        let forwarder = LocalForwarder::start(proxy.clone(), meter)?;
+        info!(proxy_id = %proxy.id, "🔐 Proxy via local forwarder on 127.0.0.1:{}", forwarder.port());
+        return Ok(ProxyLaunch {
+            flags: vec![format!("--proxy-server={}", forwarder.chrome_arg())],
+            _forwarder: Some(forwarder),
+        });
+    }
+    Ok(ProxyLaunch {
+        flags: vec![format!("--proxy-server={}", proxy.to_chrome_arg())],
+        _forwarder: None,
+    })
+}
+
 /// Random Sleep to simulate human specific behavior (High Latency)
 /// Used for Account Safety to prevent rate limit flags.
 pub async fn safe_sleep() {
@@ -228,13 +654,13 @@ pub async fn safe_sleep() {
         rng.gen_range(5.0..12.0)
     };
     
-    println!("🛡️ Safety Sleep: Pausing for {:.1}s...", sleep_secs);
+    info!("🛡️ Safety Sleep: Pausing for {:.1}s...", sleep_secs);
     sleep(Duration::from_millis((sleep_secs * 1000.0) as u64)).await;
 }
 
 /// Safe Human-Like Scrolling (Variable Speed/Length)
 pub async fn scroll_safe(tab: &std::sync::Arc<headless_chrome::Tab>) -> Result<()> {
-    println!("🛡️ Scrolling safely...");
+    info!("🛡️ Scrolling safely...");
     let script = r#"
         (async () => {
             const delay = ms => new Promise(res => setTimeout(res, ms));
@@ -271,13 +697,11 @@ pub fn check_for_ban(tab: &std::sync::Arc<headless_chrome::Tab>) -> Result<()> {
     }
 
     // Deep check content if URL is generic
-    match tab.get_content() {
-        Ok(html) => {
-            if html.contains("Verify it's you") || html.contains("security check") || html.contains("temporarily locked") {
-                 return Err(anyhow::anyhow!("🛑 CRITICAL: Checkpoint Content Detected"));
-            }
-        },
-        Err(_) => {} // Ignore content check failure
+    // Ignore content check failure
+    if let Ok(html) = tab.get_content() {
+        if html.contains("Verify it's you") || html.contains("security check") || html.contains("temporarily locked") {
+             return Err(anyhow::anyhow!("🛑 CRITICAL: Checkpoint Content Detected"));
+        }
     }
     
     Ok(())
@@ -387,31 +811,31 @@ pub fn extract_outbound_links(document: &Html, base_domain: &str) -> Vec<String>
 
 
 // Wrapper with Retry Logic for Bing
-pub async fn search_bing(keyword: &str) -> Result<SerpData> {
-    println!("🔎 Starting Bing Deep Search for: {}", keyword);
+pub async fn search_bing(keyword: &str, options: &CrawlOptions) -> Result<SerpData> {
+    info!("🔎 Starting Bing Deep Search for: {}", keyword);
     let mut last_error = String::from("No results found");
     
     // Max 3 attempts
     for attempt in 1..=3 {
-        if attempt > 1 { println!("🔄 Retry Attempt {}/3...", attempt); }
+        if attempt > 1 { info!(retry = attempt, "🔄 Retry Attempt {}/3...", attempt); }
 
-        match search_bing_attempt(keyword).await {
+        match search_bing_attempt(keyword, options).await {
             Ok(data) => {
                 if data.results.is_empty() {
-                    println!("⚠️ Attempt {}/3: Bing returned 0 results.", attempt);
+                    warn!(retry = attempt, "⚠️ Attempt {}/3: Bing returned 0 results.", attempt);
                     if attempt < 3 {
                         let wait_time = 5 * attempt as u64;
-                        println!("⏳ Waiting {}s before retry...", wait_time);
+                        info!("⏳ Waiting {}s before retry...", wait_time);
                         sleep(Duration::from_secs(wait_time)).await;
                         continue;
                     }
                 } else {
-                    println!("✅ Attempt {}/3: Success! Found {} results.", attempt, data.results.len());
+                    info!(retry = attempt, results = data.results.len(), "✅ Attempt {}/3: Success! Found {} results.", attempt, data.results.len());
                     return Ok(data);
                 }
             }
             Err(e) => {
-                println!("❌ Attempt {}/3: Error: {}", attempt, e);
+                warn!(retry = attempt, error = %e, "❌ Attempt {}/3: Error: {}", attempt, e);
                 last_error = e.to_string();
                 if attempt < 3 { sleep(Duration::from_secs(5)).await; }
             }
@@ -421,10 +845,17 @@ pub async fn search_bing(keyword: &str) -> Result<SerpData> {
 }
 
 // Internal attempt function for Bing
-async fn search_bing_attempt(keyword: &str) -> Result<SerpData> {
-    use rand::seq::SliceRandom;
-    let user_agent = USER_AGENTS.choose(&mut rand::thread_rng())
-        .unwrap_or(&"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Edge/123.0.0.0 Safari/537.36");
+async fn search_bing_attempt(keyword: &str, options: &CrawlOptions) -> Result<SerpData> {
+    let _slot = acquire_browser_slot().await;
+    let proxy = select_proxy(options)?;
+    let result = search_bing_attempt_via(keyword, options, proxy.clone()).await;
+    report_serp_outcome(proxy.as_deref(), &result);
+    result
+}
+
+#[tracing::instrument(name = "browser.bing", skip_all, fields(keyword = %keyword, proxy_id = current_proxy.as_ref().map(|p| p.id.as_str())))]
+async fn search_bing_attempt_via(keyword: &str, options: &CrawlOptions, current_proxy: Option<std::sync::Arc<Proxy>>) -> Result<SerpData> {
+    let user_agent = random_user_agent();
     
     // Use anonymous/incognito mode
     let mut args = vec![
@@ -441,21 +872,16 @@ async fn search_bing_attempt(keyword: &str) -> Result<SerpData> {
     args.push(std::ffi::OsStr::new(&ua_arg));
 
     // Proxy config (same as Google)
-    let current_proxy = PROXY_MANAGER.get_next_proxy();
-    // Keep string alive for args
-    let mut proxy_arg = String::new(); 
-    
-    if let Some(ref proxy) = current_proxy {
-        proxy_arg = format!("--proxy-server={}", proxy.to_chrome_arg());
-        args.push(std::ffi::OsStr::new(&proxy_arg));
-        // Auth extension logic omitted for brevity in this block but should ideally be shared
-    } else {
-        println!("📡 No proxies configured. Using direct connection.");
+    // Keep strings alive for args
+    let proxy_launch = current_proxy.as_ref().map(|p| proxy_launch_args(p, options)).transpose()?.unwrap_or_default();
+    args.extend(proxy_launch.flags.iter().map(std::ffi::OsStr::new));
+    if current_proxy.is_none() {
+        info!("📡 No proxies configured. Using direct connection.");
     }
 
-    let browser = Browser::new(LaunchOptions {
+    let browser = launch_browser("bing", LaunchOptions {
         headless: false, 
-        window_size: Some((1920, 1080)),
+        window_size: Some(crate::config::get().stealth.window_size),
         args,
         ..Default::default()
     })?;
@@ -473,19 +899,25 @@ async fn search_bing_attempt(keyword: &str) -> Result<SerpData> {
     })?;
 
     // Apply Fingerprint Overrides (Timezone/Locale) matching IP
-    if let Err(e) = crate::stealth::apply_stealth_settings(&tab, "Asia/Yangon", "en-US").await {
-         eprintln!("Failed to apply stealth settings: {}", e);
+    let config = crate::config::get();
+    if let Err(e) = crate::stealth::apply_stealth_settings(&tab, &config.stealth.timezone, &config.stealth.locale).await {
+         warn!("Failed to apply stealth settings: {}", e);
     }
 
     // 1. Navigate to Home (Force US Market)
-    println!("Navigating to Bing Home...");
-    tab.navigate_to("https://www.bing.com/?setmkt=en-US&setlang=en-us")?;
+    let mut bing_home = "https://www.bing.com/?setmkt=en-US&setlang=en-us".to_string();
+    if let Some(ref gl) = options.gl {
+        bing_home.push_str(&format!("&cc={}", gl.to_lowercase()));
+    }
+    apply_job_headers_and_cookies(&tab, options, &bing_home)?;
+    info!("Navigating to Bing Home...");
+    tab.navigate_to(&bing_home)?;
     tab.wait_until_navigated()?;
     
     sleep(Duration::from_millis(2000 + (rand::random::<u64>() % 2000))).await;
 
     // Handle Consent (Universal ID check)
-    println!("Checking for consent page...");
+    info!("Checking for consent page...");
     tab.evaluate(r#"
         (() => {
             const selectors = ['button[id="bnp_btn_accept"]', 'button[id="onetrust-accept-btn-handler"]'];
@@ -497,17 +929,17 @@ async fn search_bing_attempt(keyword: &str) -> Result<SerpData> {
     "#, false)?;
 
     // 2. Type Query
-    println!("Waiting for search box...");
-    let search_box = tab.wait_for_element("textarea[name='q'], input[name='q'], #sb_form_q")?;
+    info!("Waiting for search box...");
+    tab.wait_for_element("textarea[name='q'], input[name='q'], #sb_form_q")?;
     
-    println!("Clicking search box...");
+    info!("Clicking search box...");
     tab.evaluate(r#"
         const input = document.querySelector("textarea[name='q'], input[name='q'], #sb_form_q");
         if (input) { input.click(); input.focus(); input.value = ''; }
     "#, false)?;
     sleep(Duration::from_millis(500)).await;
 
-    println!("Typing query: {}...", keyword);
+    info!("Typing query: {}...", keyword);
     for char in keyword.chars() {
         tab.type_str(&char.to_string())?;
         sleep(Duration::from_millis(80 + (rand::random::<u64>() % 100))).await;
@@ -515,23 +947,24 @@ async fn search_bing_attempt(keyword: &str) -> Result<SerpData> {
     sleep(Duration::from_millis(500)).await;
 
     // 3. Submit
-    println!("Submitting search...");
+    info!("Submitting search...");
     tab.press_key("Enter")?;
     tab.wait_until_navigated()?;
-    println!("Search submitted.");
+    info!("Search submitted.");
 
     // Check for Challenge AFTER search
     sleep(Duration::from_secs(3)).await;
     let html_content = tab.get_content()?;
     if html_content.contains("Challenge") || html_content.contains("needs to review the security") {
-         println!("⚠️ CHALLENGE DETECTED: Bing served Challenge/Captcha page");
-         let _ = tab.capture_screenshot(headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption::Png, None, None, true)
-            .map(|s| std::fs::write("debug/debug_bing_challenge.png", s));
+         warn!("⚠️ CHALLENGE DETECTED: Bing served Challenge/Captcha page");
+         if let Ok(png) = tab.capture_screenshot(headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption::Png, None, None, true) {
+             save_debug_artifact(options, "bing_challenge.png", "image/png", png);
+         }
          return Err(anyhow::anyhow!("Bing Challenge Detected"));
     }
 
     // Extract Data
-    println!("Extraction method: dom");
+    info!("Extraction method: dom");
     let document = Html::parse_document(&html_content);
     let mut results = Vec::new();
     
@@ -555,37 +988,39 @@ async fn search_bing_attempt(keyword: &str) -> Result<SerpData> {
          related_searches: vec![],
          people_also_ask: vec![],
          total_results: None,
-         featured_snippet: None
+         featured_snippet: None,
+         extracted_fields: None,
+         script_result: None,
     })
 }
 
-pub async fn search_google(keyword: &str) -> Result<SerpData> {
-    println!("🔎 Starting Google Deep Search for: {}", keyword);
+pub async fn search_google(keyword: &str, options: &CrawlOptions) -> Result<SerpData> {
+    info!("🔎 Starting Google Deep Search for: {}", keyword);
     let mut last_error = String::from("No results found");
     
     // Max 3 attempts for resilience
     for attempt in 1..=3 {
         if attempt > 1 {
-             println!("🔄 Retry Attempt {}/3...", attempt);
+             info!(retry = attempt, "🔄 Retry Attempt {}/3...", attempt);
         }
 
-        match search_google_attempt(keyword, attempt).await {
+        match search_google_attempt(keyword, attempt, options).await {
             Ok(data) => {
                 if data.results.is_empty() {
-                    println!("⚠️ Attempt {}/3: Google returned 0 results (Block/Captcha?).", attempt);
+                    warn!(retry = attempt, "⚠️ Attempt {}/3: Google returned 0 results (Block/Captcha?).", attempt);
                     if attempt < 3 {
                         let wait_time = 5 * attempt as u64;
-                        println!("⏳ Waiting {}s before retry...", wait_time);
+                        info!("⏳ Waiting {}s before retry...", wait_time);
                         sleep(Duration::from_secs(wait_time)).await;
                         continue;
                     }
                 } else {
-                    println!("✅ Attempt {}/3: Success! Found {} results.", attempt, data.results.len());
+                    info!(retry = attempt, results = data.results.len(), "✅ Attempt {}/3: Success! Found {} results.", attempt, data.results.len());
                     return Ok(data);
                 }
             }
             Err(e) => {
-                println!("❌ Attempt {}/3: Error: {}", attempt, e);
+                warn!(retry = attempt, error = %e, "❌ Attempt {}/3: Error: {}", attempt, e);
                 last_error = e.to_string();
                 if attempt < 3 {
                     sleep(Duration::from_secs(5)).await;
@@ -598,7 +1033,16 @@ pub async fn search_google(keyword: &str) -> Result<SerpData> {
 }
 
 // Internal attempt function
-async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData> {
+async fn search_google_attempt(keyword: &str, attempt: u32, options: &CrawlOptions) -> Result<SerpData> {
+    let _slot = acquire_browser_slot().await;
+    let proxy = select_proxy(options)?;
+    let result = search_google_attempt_via(keyword, attempt, options, proxy.clone()).await;
+    report_serp_outcome(proxy.as_deref(), &result);
+    result
+}
+
+#[tracing::instrument(name = "browser.google", skip_all, fields(keyword = %keyword, attempt = attempt, proxy_id = current_proxy.as_ref().map(|p| p.id.as_str())))]
+async fn search_google_attempt_via(keyword: &str, attempt: u32, options: &CrawlOptions, current_proxy: Option<std::sync::Arc<Proxy>>) -> Result<SerpData> {
     use rand::seq::SliceRandom;
     let user_agent = if attempt == 3 {
         // Mobile Agents for Attempt 3
@@ -606,13 +1050,12 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
             "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Mobile Safari/537.36",
             "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4.1 Mobile/15E148 Safari/604.1",
         ];
-        MOBILE_AGENTS.choose(&mut rand::thread_rng()).unwrap()
+        MOBILE_AGENTS.choose(&mut rand::thread_rng()).unwrap().to_string()
     } else {
-        USER_AGENTS.choose(&mut rand::thread_rng())
-        .unwrap_or(&"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/123.0.0.0 Safari/537.36")
+        random_user_agent()
     };
     
-    println!("Using User-Agent (Attempt {}): {}", attempt, user_agent);
+    info!("Using User-Agent (Attempt {}): {}", attempt, user_agent);
 
     // Use anonymous/incognito mode (no profile persistence)
     let mut args = vec![
@@ -632,35 +1075,22 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
     args.push(std::ffi::OsStr::new("--headless=new"));
 
     // Add proxy if available (using new ProxyManager)
-    let proxy_arg: String;
-    let ext_arg: String;
-    let current_proxy = PROXY_MANAGER.get_next_proxy();
-    let _proxy_id = current_proxy.as_ref().map(|p| p.id.clone());
-    
     if let Some(ref proxy) = current_proxy {
-        println!("🔄 Using proxy: {} (healthy: {}, success_rate: {:.1}%)", 
-            proxy.id, 
-            proxy.healthy.load(std::sync::atomic::Ordering::Relaxed),
+        info!(
+            proxy_id = %proxy.id,
+            healthy = proxy.healthy.load(std::sync::atomic::Ordering::Relaxed),
+            success_rate = proxy.success_rate(),
+            "🔄 Using proxy: {} (success rate {:.1}%)",
+            proxy.id,
             proxy.success_rate() * 100.0
         );
-        proxy_arg = format!("--proxy-server={}", proxy.to_chrome_arg());
-        args.push(std::ffi::OsStr::new(&proxy_arg));
-        
-        // Add auth extension if proxy requires authentication
-        if proxy.requires_auth() {
-            let ext_path = generate_proxy_auth_extension(
-                proxy.username.as_ref().unwrap(),
-                proxy.password.as_ref().unwrap()
-            );
-            ext_arg = format!("--load-extension={}", ext_path);
-            args.push(std::ffi::OsStr::new(&ext_arg));
-            println!("🔐 Proxy auth extension loaded");
-        }
     }
+    let proxy_launch = current_proxy.as_ref().map(|p| proxy_launch_args(p, options)).transpose()?.unwrap_or_default();
+    args.extend(proxy_launch.flags.iter().map(std::ffi::OsStr::new));
 
-    let browser = Browser::new(LaunchOptions {
+    let browser = launch_browser("google", LaunchOptions {
         headless: false, // Use new headless mode via args
-        window_size: Some((1920, 1080)),
+        window_size: Some(crate::config::get().stealth.window_size),
         args,
         ..Default::default()
     })?;
@@ -681,23 +1111,28 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
     })?;
 
     // Apply Fingerprint Overrides (Timezone/Locale) for Residential IP
-    if let Err(e) = crate::stealth::apply_stealth_settings(&tab, "Asia/Yangon", "en-US").await {
-         eprintln!("Failed to apply stealth settings: {}", e);
+    let config = crate::config::get();
+    if let Err(e) = crate::stealth::apply_stealth_settings(&tab, &config.stealth.timezone, &config.stealth.locale).await {
+         warn!("Failed to apply stealth settings: {}", e);
     }
 
     // URL Construction Strategy
     let mut url = "https://www.google.com/?hl=en".to_string();
-    // Attempt 1: Force US (previous default). Attempts 2+: Local/No GL (avoid geo mismatch).
-    if attempt == 1 {
-        url.push_str("&gl=us");
+    // A job's market is forced on every attempt. Without one, attempt 1 forces US
+    // and attempts 2+ go Local/No GL (avoid geo mismatch).
+    match options.gl.as_deref() {
+        Some(gl) => url.push_str(&format!("&gl={}", gl.to_lowercase())),
+        None if attempt == 1 => url.push_str("&gl=us"),
+        None => {}
     }
     
     // Inject cookies for Google
     if let Some(cookies) = load_cookies("google.com") {
         let _ = inject_cookies(&tab, &cookies);
     }
+    apply_job_headers_and_cookies(&tab, options, &url)?;
     
-    println!("Navigating to Google Home (Attempt {}, URL: {})...", attempt, url);
+    info!("Navigating to Google Home (Attempt {}, URL: {})...", attempt, url);
     tab.navigate_to(&url)?;
     tab.wait_until_navigated()?;
     
@@ -705,7 +1140,7 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
     sleep(Duration::from_millis(3000 + (rand::random::<u64>() % 2000))).await;
 
     // Handle consent page (if present)
-    println!("Checking for consent page...");
+    info!("Checking for consent page...");
     let consent_result = tab.evaluate(r#"
         (() => {
             // Universal / Language Agnostic Consent Handlers
@@ -732,9 +1167,9 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
     "#, false)?;
     
     if let Some(serde_json::Value::String(result)) = consent_result.value {
-        println!("Consent check result: {}", result);
+        info!("Consent check result: {}", result);
         if result == "consent_clicked" {
-            println!("Consent accepted, waiting for redirect...");
+            info!("Consent accepted, waiting for redirect...");
             sleep(Duration::from_secs(2)).await;
             tab.wait_until_navigated()?;
         }
@@ -742,58 +1177,57 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
     
     // Human-like mouse movement (entropy)
     // Native Human Mouse Movement (CDP-based)
-    println!("Simulating native human mouse movements...");
+    info!("Simulating native human mouse movements...");
     // Move towards center 
     let start = crate::stealth::Point::new(100.0, 100.0);
     // Approx center
     let end = crate::stealth::Point::new(500.0, 300.0); 
     if let Err(e) = crate::stealth::move_mouse_human(&tab, start, end).await {
-         println!("Native mouse move failed: {}", e);
+         info!("Native mouse move failed: {}", e);
     }
 
     sleep(Duration::from_millis(1000)).await;
     
     // Take screenshot for debugging
-    println!("Capturing screenshot for debugging...");
+    info!("Capturing screenshot for debugging...");
     if let Ok(screenshot) = tab.capture_screenshot(
         headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption::Png,
         None,
         None,
         true
     ) {
-        let _ = std::fs::write("debug/debug_google_screenshot.png", &screenshot);
-        println!("Screenshot saved to debug/debug_google_screenshot.png");
+        save_debug_artifact(options, "google_screenshot.png", "image/png", screenshot);
     }
 
     // 2. Type Query (Layer 3: Typing Speed)
     // Google uses textarea[name='q'] or input[name='q'] depending on version/AB test.
     // Try multiple selectors with retries
-    println!("Waiting for search box...");
+    info!("Waiting for search box...");
     let selectors = ["textarea[name='q']", "input[name='q']", "textarea[title*='Search']", "input[title*='Search']"];
     let mut search_box_result = None;
     
     for selector in selectors {
-        println!("Trying selector: {}", selector);
+        info!("Trying selector: {}", selector);
         match tab.wait_for_element_with_custom_timeout(selector, std::time::Duration::from_secs(10)) {
             Ok(el) => {
-                println!("✅ Found search box with: {}", selector);
+                info!("✅ Found search box with: {}", selector);
                 search_box_result = Some(el);
                 break;
             },
             Err(e) => {
-                println!("⚠️ Selector '{}' failed: {}", selector, e);
+                warn!("⚠️ Selector '{}' failed: {}", selector, e);
             }
         }
     }
     
-    let search_box = search_box_result.ok_or_else(|| anyhow::anyhow!("No search box selector worked"))?;
+    search_box_result.ok_or_else(|| anyhow::anyhow!("No search box selector worked"))?;
     
     // Wait for React/JS to finish rendering
-    println!("Waiting for search box to become interactive...");
+    info!("Waiting for search box to become interactive...");
     sleep(Duration::from_millis(1000)).await;
     
     // Use JS to click and focus (more reliable than CDP click for dynamic elements)
-    println!("Clicking and focusing search box via JS...");
+    info!("Clicking and focusing search box via JS...");
     tab.evaluate(r#"
         const input = document.querySelector('textarea[name="q"]') || document.querySelector('input[name="q"]');
         if (input) { 
@@ -805,7 +1239,7 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
     sleep(Duration::from_millis(500)).await;
     
     // Type query naturally for personalized results (profile-based)
-    println!("Typing query: {}...", keyword);
+    info!("Typing query: {}...", keyword);
     for char in keyword.chars() {
         tab.type_str(&char.to_string())?;
         sleep(Duration::from_millis(100 + (rand::random::<u64>() % 150))).await;
@@ -814,18 +1248,19 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
     sleep(Duration::from_millis(500)).await;
 
     // 3. Submit
-    println!("Submitting search...");
+    info!("Submitting search...");
     tab.press_key("Enter")?;
     tab.wait_until_navigated()?;
-    println!("Search submitted.");
+    info!("Search submitted.");
 
     // Check for Challenge/Captcha immediately after navigation
     sleep(Duration::from_secs(2)).await;
     let html_content = tab.get_content()?;
     if html_content.contains("unusual traffic") || html_content.contains("captcha-form") || html_content.contains("systems have detected") {
-         println!("⚠️ CHALLENGE DETECTED: Google served Captcha/Unusual Traffic page");
-         let _ = tab.capture_screenshot(headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption::Png, None, None, true)
-            .map(|s| std::fs::write("debug/debug_google_challenge.png", s));
+         warn!("⚠️ CHALLENGE DETECTED: Google served Captcha/Unusual Traffic page");
+         if let Ok(png) = tab.capture_screenshot(headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption::Png, None, None, true) {
+             save_debug_artifact(options, "google_challenge.png", "image/png", png);
+         }
          return Err(anyhow::anyhow!("Google Challenge Detected"));
     }
     
@@ -869,9 +1304,9 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
     "#, false)?;
     
     if let Some(serde_json::Value::String(result)) = verbatim_result.value {
-        println!("Verbatim check result: {}", result);
+        info!("Verbatim check result: {}", result);
         if result != "no_autocorrect" {
-            println!("Clicked verbatim link, waiting for reload...");
+            info!("Clicked verbatim link, waiting for reload...");
             sleep(Duration::from_secs(2)).await;
             tab.wait_until_navigated()?;
         }
@@ -882,25 +1317,25 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
     let start = crate::stealth::Point::new(100.0, 100.0);
     let end = crate::stealth::Point::new(500.0, 400.0);
     if let Err(e) = crate::stealth::move_mouse_human(&tab, start, end).await {
-         println!("Native mouse move failed: {}", e);
+         info!("Native mouse move failed: {}", e);
     }
     
     sleep(Duration::from_millis(500)).await;
 
     // Native Human Scroll
     if let Err(e) = crate::stealth::scroll_human(&tab, 800.0).await {
-        println!("Native scroll failed: {}", e);
+        info!("Native scroll failed: {}", e);
     }
 
     // L3: Google Extraction Strategy (CDP-Based, Per Debug Sequence)
     // Step 1: ✅ Already navigating to homepage → typing → submit (not direct SERP URL)
     
     // Add static wait for Google JS to initialize before mutation observer
-    println!("Waiting 3s for Google JS to initialize...");
+    info!("Waiting 3s for Google JS to initialize...");
     sleep(Duration::from_secs(3)).await;
     
     // Step 2: Mutation observer with increased timeout (15s) and logging
-    println!("Waiting for Google DOM mutations to complete...");
+    info!("Waiting for Google DOM mutations to complete...");
     let wait_script = r#"
         new Promise((resolve) => {
             let timeout;
@@ -927,7 +1362,7 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
     "#;
     
     let wait_result = tab.evaluate(wait_script, true)?;
-    println!("DOM wait result: {:?}", wait_result.value);
+    info!("DOM wait result: {:?}", wait_result.value);
     
     // Step 3: Extract via semantic attributes (resilient to class changes)
     let extraction_method: String;
@@ -995,14 +1430,14 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
                 let parsed: serde_json::Value = serde_json::from_str(&value_str).unwrap_or_default();
                 extraction_method = parsed["method"].as_str().unwrap_or("unknown").to_string();
                 results = serde_json::from_value(parsed["results"].clone()).unwrap_or_default();
-                println!("Extracted {} results via method: {}", results.len(), extraction_method);
+                info!("Extracted {} results via method: {}", results.len(), extraction_method);
             } else {
                 extraction_method = "fallback".to_string();
                 results = Vec::new();
             }
         }
         Err(e) => {
-            eprintln!("DOM extraction failed: {}, trying JS context fallback", e);
+            warn!("DOM extraction failed: {}, trying JS context fallback", e);
             extraction_method = "js_context".to_string();
             
             // Method 2: JS Context fallback (window.google.search.cse)
@@ -1040,14 +1475,14 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
         }
     }
     
-    println!("Extraction method: {}", extraction_method);
+    info!("Extraction method: {}", extraction_method);
     
-    println!("Found {} results.", results.len());
+    info!("Found {} results.", results.len());
 
     if results.is_empty() {
         let html_content = tab.get_content().unwrap_or_default();
-        eprintln!("Google returned 0 results. HTML len: {}", html_content.len());
-        let _ = std::fs::write("debug/debug_google_tier1.html", &html_content);
+        warn!("Google returned 0 results. HTML len: {}", html_content.len());
+        save_debug_artifact(options, "google_tier1.html", "text/html", html_content.into_bytes());
     }
 
     // Extract People Also Ask
@@ -1095,21 +1530,21 @@ async fn search_google_attempt(keyword: &str, attempt: u32) -> Result<SerpData>
         related_searches,
         featured_snippet,
         total_results,
+        extracted_fields: None,
+        script_result: None,
     })
 }
 
 pub async fn extract_content(url: &str) -> Result<ExtractedContent> {
     // Decode Bing/Google redirect URLs to get actual destination
     let actual_url = decode_search_url(url);
-    println!("Extracting content from: {}", actual_url);
+    info!("Extracting content from: {}", actual_url);
     
     // Use proper User-Agent and follow redirects
-    use rand::seq::SliceRandom;
-    let user_agent = USER_AGENTS.choose(&mut rand::thread_rng())
-        .unwrap_or(&"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/123.0.0.0 Safari/537.36");
+    let user_agent = random_user_agent();
 
     let client = reqwest::Client::builder()
-        .user_agent(*user_agent)
+        .user_agent(user_agent)
         .redirect(reqwest::redirect::Policy::limited(10))
         .timeout(Duration::from_secs(30))
         .build()?;
@@ -1118,10 +1553,10 @@ pub async fn extract_content(url: &str) -> Result<ExtractedContent> {
         .header("Accept-Language", "en-US,en;q=0.9")
         .send().await?;
     let final_url = resp.url().to_string();
-    println!("Final URL after redirects: {}", final_url);
+    info!("Final URL after redirects: {}", final_url);
     
     let html = resp.text().await?;
-    println!("Fetched HTML size: {} bytes", html.len());
+    info!("Fetched HTML size: {} bytes", html.len());
     
     let mut reader = Cursor::new(html.as_bytes());
     
@@ -1156,14 +1591,21 @@ pub async fn extract_content(url: &str) -> Result<ExtractedContent> {
 }
 
 /// Deep extraction function that returns comprehensive WebsiteData using Headless Chrome
-pub async fn extract_website_data(url: &str) -> Result<WebsiteData> {
+pub async fn extract_website_data(url: &str, options: &CrawlOptions) -> Result<WebsiteData> {
+    let _slot = acquire_browser_slot().await;
+    let proxy = select_proxy(options)?;
+    let result = extract_website_data_via(url, options, proxy.clone()).await;
+    report_proxy_outcome(proxy.as_deref(), &result);
+    result
+}
+
+#[tracing::instrument(name = "browser.extract", skip_all, fields(url = %url, proxy_id = current_proxy.as_ref().map(|p| p.id.as_str())))]
+async fn extract_website_data_via(url: &str, options: &CrawlOptions, current_proxy: Option<std::sync::Arc<Proxy>>) -> Result<WebsiteData> {
     // Decode Bing/Google redirect URLs to get actual destination
     let actual_url = decode_search_url(url);
-    println!("🔍 Deep integration extracting data from: {}", actual_url);
+    info!("🔍 Deep integration extracting data from: {}", actual_url);
     
-    use rand::seq::SliceRandom;
-    let user_agent = USER_AGENTS.choose(&mut rand::thread_rng())
-        .unwrap_or(&"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/123.0.0.0 Safari/537.36");
+    let user_agent = random_user_agent();
 
     // Configure Chrome arguments for Stealth
     let mut args = vec![
@@ -1182,28 +1624,13 @@ pub async fn extract_website_data(url: &str) -> Result<WebsiteData> {
     args.push(std::ffi::OsStr::new("--headless=new"));
 
     // Add proxy if available
-    let current_proxy = PROXY_MANAGER.get_next_proxy();
-    let proxy_arg: String;
-    let ext_arg: String;
-    
-    if let Some(ref proxy) = current_proxy {
-        proxy_arg = format!("--proxy-server={}", proxy.to_chrome_arg());
-        args.push(std::ffi::OsStr::new(&proxy_arg));
-        
-        if proxy.requires_auth() {
-            let ext_path = generate_proxy_auth_extension(
-                proxy.username.as_ref().unwrap(),
-                proxy.password.as_ref().unwrap()
-            );
-            ext_arg = format!("--load-extension={}", ext_path);
-            args.push(std::ffi::OsStr::new(&ext_arg));
-        }
-    }
+    let proxy_launch = current_proxy.as_ref().map(|p| proxy_launch_args(p, options)).transpose()?.unwrap_or_default();
+    args.extend(proxy_launch.flags.iter().map(std::ffi::OsStr::new));
 
     // Launch Browser
-    let browser = Browser::new(LaunchOptions {
+    let browser = launch_browser("website", LaunchOptions {
         headless: false, // Use new headless mode via args
-        window_size: Some((1920, 1080)),
+        window_size: Some(crate::config::get().stealth.window_size),
         args,
         ..Default::default()
     })?;
@@ -1222,30 +1649,29 @@ pub async fn extract_website_data(url: &str) -> Result<WebsiteData> {
         run_immediately: None,
     })?;
 
-    // Navigate
-    println!("Navigating to: {}", actual_url);
+    // Navigate (without the job's headers and cookies: a result page is another site than the search engine)
+    info!("Navigating to: {}", actual_url);
     tab.navigate_to(&actual_url)?;
     
     // Use softer wait (wait for body) instead of strict load event to prevent timeouts on ads/tracking
     match tab.wait_for_element_with_custom_timeout("body", Duration::from_secs(15)) {
-        Ok(_) => println!("Page body loaded."),
-        Err(e) => println!("⚠️ Warning: Body wait timed out: {}. Attempting extraction anyway...", e),
+        Ok(_) => info!("Page body loaded."),
+        Err(e) => warn!("⚠️ Warning: Body wait timed out: {}. Attempting extraction anyway...", e),
     }
 
-    // Wait for JS execution (Hydration)
-    sleep(Duration::from_secs(4)).await;
+    wait_for_page(&tab, options.wait_for.as_ref()).await;
 
     // Extract Data via JS
     let html = tab.evaluate("document.documentElement.outerHTML", false)?.value.unwrap().as_str().unwrap().to_string();
     let final_url = tab.get_url();
     let html_size = html.len() as u32;
-    println!("Extracted HTML size via Browser: {} bytes", html_size);
+    info!("Extracted HTML size via Browser: {} bytes", html_size);
 
     // 10. Marketing Data Extraction (Async - must be done before parsing document)
     let marketing_data = match extract_marketing_data(&tab).await {
         Ok(data) => Some(data),
         Err(e) => {
-            println!("⚠️ Marketing extraction failed: {}", e);
+            warn!("⚠️ Marketing extraction failed: {}", e);
             None
         }
     };
@@ -1292,7 +1718,7 @@ pub async fn extract_website_data(url: &str) -> Result<WebsiteData> {
     // 4. Extract Schema.org/JSON-LD structured data
     let schema_org = extract_schema_org(&html);
     if !schema_org.is_empty() {
-        println!("📊 Found {} Schema.org objects", schema_org.len());
+        info!("📊 Found {} Schema.org objects", schema_org.len());
     }
     
     // 5. Extract Open Graph data
@@ -1311,7 +1737,7 @@ pub async fn extract_website_data(url: &str) -> Result<WebsiteData> {
     // 9. ML Sentiment Analysis
     let sentiment = crate::ml::analyze_sentiment(&main_text);
     if let Some(ref s) = sentiment {
-        println!("🧠 Sentiment Analysis Result: {}", s);
+        info!("🧠 Sentiment Analysis Result: {}", s);
     }
 
     Ok(WebsiteData {
@@ -1342,7 +1768,7 @@ pub async fn extract_website_data(url: &str) -> Result<WebsiteData> {
 
 /// Extract Marketing Data (Selling Points)
 pub async fn extract_marketing_data(tab: &std::sync::Arc<headless_chrome::Tab>) -> Result<MarketingData> {
-    println!("📢 Extracting Marketing Data (Selling Points)...");
+    info!("📢 Extracting Marketing Data (Selling Points)...");
     
     let script = r#"
         (() => {
@@ -1393,12 +1819,12 @@ pub async fn extract_marketing_data(tab: &std::sync::Arc<headless_chrome::Tab>)
     if let Some(value) = result.value {
         let data: MarketingData = serde_json::from_value(value)?;
              // Log findings
-        println!("📢 Marketing Data: {} headlines, {} benefits, {} CTAs", 
+        info!("📢 Marketing Data: {} headlines, {} benefits, {} CTAs", 
             data.headlines.len(), data.key_benefits.len(), data.ctas.len());
             
         Ok(data)
     } else {
-        println!("⚠️ Marketing extraction script returned no value.");
+        warn!("⚠️ Marketing extraction script returned no value.");
         Err(anyhow::anyhow!("No data returned from script"))
     }
 }
@@ -1410,15 +1836,11 @@ pub fn decode_search_url(url: &str) -> String {
         if let Some(u_param) = url.split("&u=").nth(1) {
             let encoded = u_param.split('&').next().unwrap_or(u_param);
             // Remove 'a1' prefix if present
-            let base64_part = if encoded.starts_with("a1") {
-                &encoded[2..]
-            } else {
-                encoded
-            };
+            let base64_part = encoded.strip_prefix("a1").unwrap_or(encoded);
             // Decode base64
             if let Ok(decoded) = base64_decode(base64_part) {
                 if let Ok(decoded_str) = String::from_utf8(decoded) {
-                    println!("Decoded Bing URL: {}", decoded_str);
+                    info!("Decoded Bing URL: {}", decoded_str);
                     return decoded_str;
                 }
             }
@@ -1467,18 +1889,287 @@ fn base64_decode(input: &str) -> Result<Vec<u8>> {
     Ok(output)
 }
 
+// ============================================================================
+// Scripted Login
+// ============================================================================
+
+/// Drive a login form with human-like input and verify the session was established
+pub async fn perform_login(tab: &std::sync::Arc<headless_chrome::Tab>, login: &LoginFlow, target_url: &str) -> Result<()> {
+    let login_url = login.url.as_deref().unwrap_or(target_url);
+    info!("🔑 Logging in via: {}", login_url);
+
+    tab.navigate_to(login_url)?;
+    tab.wait_until_navigated()?;
+    check_for_ban(tab)?;
+    sleep(Duration::from_millis(1500 + (rand::random::<u64>() % 1500))).await;
+
+    // Username
+    tab.wait_for_element_with_custom_timeout(&login.username_selector, Duration::from_secs(15))?;
+    if let Err(e) = crate::stealth::move_mouse_to_element(tab, &login.username_selector).await {
+        info!("Native mouse move failed: {}", e);
+    }
+    tab.find_element(&login.username_selector)?.click()?;
+    crate::stealth::type_human(tab, &login.username).await?;
+    sleep(Duration::from_millis(400 + (rand::random::<u64>() % 600))).await;
+
+    // Password
+    if let Err(e) = crate::stealth::move_mouse_to_element(tab, &login.password_selector).await {
+        info!("Native mouse move failed: {}", e);
+    }
+    tab.find_element(&login.password_selector)?.click()?;
+    crate::stealth::type_human(tab, &login.password).await?;
+    sleep(Duration::from_millis(400 + (rand::random::<u64>() % 600))).await;
+
+    // Submit
+    info!("🔑 Submitting login form...");
+    if let Err(e) = crate::stealth::move_mouse_to_element(tab, &login.submit_selector).await {
+        info!("Native mouse move failed: {}", e);
+    }
+    tab.find_element(&login.submit_selector)?.click()?;
+    tab.wait_until_navigated()?;
+    sleep(Duration::from_secs(2)).await;
+
+    check_for_ban(tab)?;
+
+    // Success checks
+    if let Some(ref selector) = login.success_selector {
+        tab.wait_for_element_with_custom_timeout(selector, Duration::from_secs(15))
+            .map_err(|_| anyhow::anyhow!("Login failed: success element '{}' not found", selector))?;
+    }
+    if let Some(ref fragment) = login.success_url_contains {
+        let url = tab.get_url();
+        if !url.contains(fragment.as_str()) {
+            return Err(anyhow::anyhow!("Login failed: landed on {} (expected '{}')", url, fragment));
+        }
+    }
+
+    info!("✅ Login successful");
+    Ok(())
+}
+
+// ============================================================================
+// Interaction Steps
+// ============================================================================
+
+/// Run the ordered interaction steps of a generic crawl against the current page
+pub async fn run_interaction_steps(tab: &std::sync::Arc<headless_chrome::Tab>, steps: &[InteractionStep]) -> Result<()> {
+    for (idx, step) in steps.iter().enumerate() {
+        info!("🧭 Step {}/{}: {:?}", idx + 1, steps.len(), step);
+        match step {
+            InteractionStep::Click { selector } => {
+                if let Err(e) = crate::stealth::move_mouse_to_element(tab, selector).await {
+                    info!("Native mouse move failed: {}", e);
+                }
+                tab.wait_for_element(selector)?.click()?;
+            }
+            InteractionStep::Type { selector, text } => {
+                tab.wait_for_element(selector)?.click()?;
+                crate::stealth::type_human(tab, text).await?;
+            }
+            InteractionStep::Select { selector, value } => {
+                tab.wait_for_element(selector)?;
+                let script = format!(
+                    r#"(() => {{
+                        const el = document.querySelector({});
+                        if (!el) return false;
+                        el.value = {};
+                        el.dispatchEvent(new Event('input', {{ bubbles: true }}));
+                        el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+                        return true;
+                    }})()"#,
+                    serde_json::to_string(selector)?,
+                    serde_json::to_string(value)?
+                );
+                tab.evaluate(&script, false)?;
+            }
+            InteractionStep::WaitFor { selector, timeout_ms } => {
+                let timeout = Duration::from_millis(timeout_ms.unwrap_or(10_000));
+                tab.wait_for_element_with_custom_timeout(selector, timeout)
+                    .map_err(|_| anyhow::anyhow!("Step {}: '{}' did not appear", idx + 1, selector))?;
+            }
+        }
+        // Give the page time to react (XHR filters, navigation)
+        sleep(Duration::from_millis(500 + (rand::random::<u64>() % 1000))).await;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Infinite Scroll Harvesting
+// ============================================================================
+
+/// Scroll a feed until it stops growing or a budget is hit, collecting item text as it loads.
+/// Items are captured every round because feeds often recycle DOM nodes that scroll out of view.
+pub async fn harvest_infinite_scroll(tab: &std::sync::Arc<headless_chrome::Tab>, config: &InfiniteScroll) -> Result<Vec<String>> {
+    let max_items = config.max_items.unwrap_or(100);
+    let max_scrolls = config.max_scrolls.unwrap_or(30);
+    let idle_limit = config.idle_rounds.unwrap_or(3);
+
+    let script = format!(
+        "Array.from(document.querySelectorAll({})).map(el => el.innerText.trim()).filter(t => t.length > 0)",
+        serde_json::to_string(&config.item_selector)?
+    );
+
+    let mut seen = std::collections::HashSet::new();
+    let mut items = Vec::new();
+    let mut idle_rounds = 0;
+
+    for scroll in 0..=max_scrolls {
+        let visible: Vec<String> = tab
+            .evaluate(&script, false)?
+            .value
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+
+        let before = items.len();
+        for text in visible {
+            if seen.insert(text.clone()) {
+                items.push(text);
+            }
+        }
+        let new_items = items.len() - before;
+        info!("📜 Scroll {}/{}: +{} items ({} total)", scroll, max_scrolls, new_items, items.len());
+
+        if items.len() >= max_items {
+            items.truncate(max_items);
+            info!("📜 Item budget reached.");
+            break;
+        }
+        if new_items == 0 && scroll > 0 {
+            idle_rounds += 1;
+            if idle_rounds >= idle_limit {
+                info!("📜 No new content after {} scrolls. Stopping.", idle_rounds);
+                break;
+            }
+        } else {
+            idle_rounds = 0;
+        }
+        if scroll == max_scrolls {
+            break;
+        }
+
+        let delta = 600.0 + (rand::random::<u64>() % 600) as f64;
+        crate::stealth::scroll_human(tab, delta).await?;
+        // Give lazy loaders time to fetch the next batch
+        sleep(Duration::from_millis(1500 + (rand::random::<u64>() % 1500))).await;
+    }
+
+    Ok(items)
+}
+
+// ============================================================================
+// Custom Script Hook
+// ============================================================================
+
+/// Wall-clock budget for a custom script (including awaited promises)
+const CUSTOM_SCRIPT_TIMEOUT_MS: u64 = 10_000;
+
+/// Fixed wrapper the custom script is handed to as an argument, so its text is
+/// compiled as a function body on its own and can't reach outside it
+const CUSTOM_SCRIPT_RUNNER: &str = r#"async function (source, timeoutMs) {
+    try {
+        const AsyncFunction = (async () => {}).constructor;
+        const value = await Promise.race([
+            new AsyncFunction(source)(),
+            new Promise((_, reject) => setTimeout(() => reject(new Error("timeout")), timeoutMs))
+        ]);
+        return JSON.stringify({ value: value === undefined ? null : value });
+    } catch (e) {
+        return JSON.stringify({ error: String(e) });
+    }
+}"#;
+
+/// Evaluate a job-supplied script in an isolated world (shared DOM, separate JS globals)
+/// so it cannot tamper with page scripts or our stealth overrides. Errors and timeouts
+/// are returned as `{"error": ...}` rather than failing the crawl. Scripts that never
+/// yield (e.g. a busy loop) are terminated once the budget, plus a second, runs out.
+pub fn run_custom_script(tab: &std::sync::Arc<headless_chrome::Tab>, script: &str) -> Result<serde_json::Value> {
+    use headless_chrome::protocol::cdp::{Page, Runtime};
+
+    if script.len() > MAX_CUSTOM_SCRIPT_LEN {
+        return Err(anyhow::anyhow!("custom_script exceeds {} bytes", MAX_CUSTOM_SCRIPT_LEN));
+    }
+
+    let frame_id = tab.call_method(Page::GetFrameTree(None))?.frame_tree.frame.id;
+    let context_id = tab.call_method(Page::CreateIsolatedWorld {
+        frame_id,
+        world_name: Some("crawler_custom_script".to_string()),
+        grant_univeral_access: Some(false),
+    })?.execution_context_id;
+
+    // The in-page timer only fires if the script yields; this one doesn't need it to
+    let (done, finished) = std::sync::mpsc::channel::<()>();
+    let watchdog = {
+        let tab = tab.clone();
+        std::thread::spawn(move || {
+            let budget = Duration::from_millis(CUSTOM_SCRIPT_TIMEOUT_MS + 1_000);
+            let overran = finished.recv_timeout(budget) == Err(std::sync::mpsc::RecvTimeoutError::Timeout);
+            if overran {
+                warn!("⏱️ Custom script overran its {}ms budget; terminating it", CUSTOM_SCRIPT_TIMEOUT_MS);
+                let _ = tab.call_method(Runtime::TerminateExecution(None));
+            }
+            overran
+        })
+    };
+    let argument = |value: serde_json::Value| Runtime::CallArgument { value: Some(value), unserializable_value: None, object_id: None };
+    let result = tab.call_method(Runtime::CallFunctionOn {
+        function_declaration: CUSTOM_SCRIPT_RUNNER.to_string(),
+        object_id: None,
+        arguments: Some(vec![argument(script.into()), argument(CUSTOM_SCRIPT_TIMEOUT_MS.into())]),
+        silent: Some(true),
+        return_by_value: Some(true),
+        generate_preview: None,
+        user_gesture: None,
+        await_promise: Some(true),
+        execution_context_id: Some(context_id),
+        object_group: None,
+        throw_on_side_effect: None,
+        unique_context_id: None,
+        serialization_options: None,
+    });
+    let _ = done.send(());
+    if watchdog.join().unwrap_or(false) {
+        return Ok(serde_json::json!({ "error": "Error: timeout" }));
+    }
+    let result = result?;
+
+    if let Some(details) = result.exception_details {
+        return Ok(serde_json::json!({ "error": details.text }));
+    }
+
+    let raw = result.result.value.and_then(|v| v.as_str().map(|s| s.to_string())).unwrap_or_default();
+    let parsed: serde_json::Value = serde_json::from_str(&raw)
+        .unwrap_or_else(|_| serde_json::json!({ "error": "script returned a non-serializable value" }));
+
+    Ok(match parsed.get("value") {
+        Some(value) => value.clone(),
+        None => parsed,
+    })
+}
+
 // ============================================================================
 // Generic Forum Crawler
 // ============================================================================
-pub async fn generic_crawl(url: &str, selectors: Option<std::collections::HashMap<String, String>>) -> Result<SerpData> {
-    println!("🌐 Starting Generic Crawl for: {}", url);
-    use rand::seq::SliceRandom;
+
+/// Pages visited when `next_page_selector` is set without `max_pages`
+pub const DEFAULT_MAX_PAGES: u32 = 5;
+/// Hard cap on pagination to keep a single job bounded
+pub const MAX_PAGES_LIMIT: u32 = 50;
+
+pub async fn generic_crawl(url: &str, options: &CrawlOptions) -> Result<SerpData> {
+    let _slot = acquire_browser_slot().await;
+    let proxy = select_proxy(options)?;
+    let result = generic_crawl_via(url, options, proxy.clone()).await;
+    report_proxy_outcome(proxy.as_deref(), &result);
+    result
+}
+
+#[tracing::instrument(name = "browser.generic", skip_all, fields(url = %url, proxy_id = current_proxy.as_ref().map(|p| p.id.as_str())))]
+async fn generic_crawl_via(url: &str, options: &CrawlOptions, current_proxy: Option<std::sync::Arc<Proxy>>) -> Result<SerpData> {
+    info!("🌐 Starting Generic Crawl for: {}", url);
     
-    // Minimal browser setup for brevity (reusing user agent list from top of file)
-    let user_agent = USER_AGENTS.choose(&mut rand::thread_rng())
-        .unwrap_or(&"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/123.0.0.0 Safari/537.36");
 
-    let args = vec![
+    let mut args = vec![
         std::ffi::OsStr::new("--disable-blink-features=AutomationControlled"),
         std::ffi::OsStr::new("--no-sandbox"),
         std::ffi::OsStr::new("--disable-dev-shm-usage"),
@@ -1486,10 +2177,14 @@ pub async fn generic_crawl(url: &str, selectors: Option<std::collections::HashMa
         std::ffi::OsStr::new("--ignore-certificate-errors"),
     ];
 
-    let browser = Browser::new(LaunchOptions {
+    // Forums with IP-bound sessions rely on proxy pinning here
+    let proxy_launch = current_proxy.as_ref().map(|p| proxy_launch_args(p, options)).transpose()?.unwrap_or_default();
+    args.extend(proxy_launch.flags.iter().map(std::ffi::OsStr::new));
+
+    let browser = launch_browser("generic", LaunchOptions {
         headless: true, 
         args,
-        window_size: Some((1920, 1080)),
+        window_size: Some(crate::config::get().stealth.window_size),
         ..Default::default()
     })?;
 
@@ -1505,21 +2200,43 @@ pub async fn generic_crawl(url: &str, selectors: Option<std::collections::HashMa
         let _ = inject_cookies(&tab, &cookies);
     }
 
+    apply_job_headers_and_cookies(&tab, options, url)?;
+
+    // Authenticate first if the target requires a session
+    if let Some(ref login) = options.login {
+        perform_login(&tab, login, url).await?;
+    }
+
     tab.navigate_to(url)?;
     tab.wait_until_navigated()?;
+    if options.wait_for.is_some() {
+        wait_for_page(&tab, options.wait_for.as_ref()).await;
+    }
     
     // Safety: Check for initial ban/checkpoint immediately after load
     if let Err(e) = check_for_ban(&tab) {
-        println!("{}", e);
+        info!("{}", e);
         return Err(e);
     }
     
     // Safety: Sleep before interaction
     safe_sleep().await;
+
+    // Drive search forms / filters before scraping
+    if let Some(ref steps) = options.steps {
+        run_interaction_steps(&tab, steps).await?;
+        check_for_ban(&tab)?;
+    }
     
-    // Special handling for Facebook
-    if url.contains("facebook.com") {
-        println!("📘 Facebook Domain Detected. Engaging Human Scroll Mode...");
+    // Feed harvesting replaces the one-shot scroll
+    let mut harvested = Vec::new();
+    if let Some(ref config) = options.infinite_scroll {
+        info!("📜 Infinite scroll mode: harvesting '{}'", config.item_selector);
+        harvested = harvest_infinite_scroll(&tab, config).await?;
+        check_for_ban(&tab)?;
+    } else if url.contains("facebook.com") {
+        // Special handling for Facebook
+        info!("📘 Facebook Domain Detected. Engaging Human Scroll Mode...");
         scroll_safe(&tab).await?;
     } else {
         // Generic Scroll
@@ -1532,24 +2249,102 @@ pub async fn generic_crawl(url: &str, selectors: Option<std::collections::HashMa
     // Capture verification screenshot (Critical for User Assurance)
 
     // Capture verification screenshot (Critical for User Assurance)
-    println!("📸 Capturing Generic Verification Screenshot...");
+    info!("📸 Capturing Generic Verification Screenshot...");
     if let Ok(screenshot) = tab.capture_screenshot(
         headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption::Png,
         None, None, true
     ) {
-        let _ = std::fs::write("debug/debug_generic_stealth.png", &screenshot);
-        println!("✅ Screenshot saved to debug/debug_generic_stealth.png");
+        save_debug_artifact(options, "generic_stealth.png", "image/png", screenshot);
     }
 
-    let html_content = tab.get_content()?;
-    let document = Html::parse_document(&html_content);
-    
+    let max_pages = match options.next_page_selector {
+        Some(_) => options.max_pages.unwrap_or(DEFAULT_MAX_PAGES).clamp(1, MAX_PAGES_LIMIT),
+        None => 1,
+    };
+
     let mut results = Vec::new();
+    let mut page_fields = Vec::new();
+
+    for page in 1..=max_pages {
+        let page_url = tab.get_url();
+        let html_content = tab.get_content()?;
+        let (result, fields) = extract_generic_page(&html_content, &page_url, page, options);
+        results.push(result);
+        if let Some(f) = fields {
+            page_fields.push(f);
+        }
+
+        if page == max_pages {
+            break;
+        }
+        let next_selector = match options.next_page_selector {
+            Some(ref sel) => sel,
+            None => break,
+        };
+
+        // Follow the "next" link if present
+        if tab.find_element(next_selector).is_err() {
+            info!("📄 No next page link on page {}. Stopping pagination.", page);
+            break;
+        }
+        info!("📄 Following next page ({}/{})...", page + 1, max_pages);
+        if let Err(e) = crate::stealth::move_mouse_to_element(&tab, next_selector).await {
+            info!("Native mouse move failed: {}", e);
+        }
+        tab.find_element(next_selector)?.click()?;
+        // Listing pages may paginate via XHR without a navigation event
+        let _ = tab.wait_until_navigated();
+        safe_sleep().await;
+        check_for_ban(&tab)?;
+
+        if tab.get_url() == page_url && tab.get_content().map(|h| h == html_content).unwrap_or(false) {
+            info!("📄 Page did not change after clicking next. Stopping pagination.");
+            break;
+        }
+    }
+
+    // Site-specific extraction hook
+    let script_result = match options.custom_script {
+        Some(ref script) => {
+            info!("🧪 Running custom script ({} bytes)...", script.len());
+            Some(run_custom_script(&tab, script)?)
+        }
+        None => None,
+    };
+
+    let page_url = tab.get_url();
+    for (idx, text) in harvested.into_iter().enumerate() {
+        results.push(SearchResult {
+            title: format!("Feed Item {}", idx + 1),
+            link: page_url.clone(),
+            snippet: text,
+        });
+    }
+
+    // Typed recipe fields: one object, or one per page when paginating
+    let extracted_fields = match page_fields.len() {
+        0 => None,
+        1 => page_fields.pop(),
+        _ => Some(serde_json::Value::Array(page_fields)),
+    };
+
+    Ok(SerpData {
+        total_results: Some(results.len().to_string()),
+        results,
+        extracted_fields,
+        script_result,
+        ..Default::default()
+    })
+}
+
+/// Extract selector text and recipe fields from a single generic crawl page
+fn extract_generic_page(html: &str, page_url: &str, page: u32, options: &CrawlOptions) -> (SearchResult, Option<serde_json::Value>) {
+    let document = Html::parse_document(html);
     let mut snippet_acc = String::new();
 
-    if let Some(sel_map) = selectors {
+    if let Some(ref sel_map) = options.selectors {
         for (key, selector_str) in sel_map {
-             if let Ok(selector) = Selector::parse(&selector_str) {
+             if let Ok(selector) = Selector::parse(selector_str) {
                  snippet_acc.push_str(&format!("--- {} ---\n", key));
                  for element in document.select(&selector) {
                      snippet_acc.push_str(&element.text().collect::<String>());
@@ -1566,23 +2361,18 @@ pub async fn generic_crawl(url: &str, selectors: Option<std::collections::HashMa
         }
     }
 
-    results.push(SearchResult {
-        title: "Forum Data".to_string(),
-        link: url.to_string(),
-        snippet: snippet_acc,
+    let fields = options.fields.as_ref().map(|fields| {
+        info!("📋 Applying {} recipe fields", fields.len());
+        crate::recipes::apply_fields(&document, fields)
     });
 
-    Ok(SerpData {
-        results,
-        total_results: Some("1".to_string()),
-        ..Default::default()
-    })
+    let title = if page == 1 { "Forum Data".to_string() } else { format!("Forum Data (page {})", page) };
+    (
+        SearchResult {
+            title,
+            link: page_url.to_string(),
+            snippet: snippet_acc,
+        },
+        fields,
+    )
 }
-
-
-
-
-
-
-
-
@@ -0,0 +1,417 @@
+//! Crawl credits.
+//!
+//! Submitting a crawl debits credits by crawl type: a SERP search costs 1, a
+//! deep crawl of a single page (`generic` engine) 3 and a spider that follows
+//! pagination or infinite scroll 10. Credits are topped up by purchases of a
+//! pack from [`CREDIT_PACKS`] (`payments` with a `credits` amount; the price is
+//! the pack's, never the client's), new accounts start with
+//! `CREDITS_SIGNUP_GRANT`, and the worker refunds a task's debit when the task
+//! finally fails. Refunding a payment takes its purchased credits back out
+//! (a `reversal`). Every change is a row in `credit_ledger`.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::crawler::CrawlOptions;
+use tracing::{error, info};
+
+const DEFAULT_SIGNUP_GRANT: i64 = 100;
+
+/// Credits sold together at a fixed price
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct CreditPack {
+    #[schema(example = 500)]
+    pub credits: i32,
+    /// Price in US cents
+    #[schema(example = 500)]
+    pub price_cents: i32,
+}
+
+/// Packs `/payments/checkout` sells, smallest first
+pub const CREDIT_PACKS: [CreditPack; 3] = [
+    CreditPack { credits: 500, price_cents: 500 },
+    CreditPack { credits: 2_500, price_cents: 2_000 },
+    CreditPack { credits: 10_000, price_cents: 7_000 },
+];
+
+/// Currency pack prices are in
+pub const CREDIT_PACK_CURRENCY: &str = "USD";
+
+/// The pack of exactly `credits` credits
+pub fn credit_pack(credits: i32) -> Option<CreditPack> {
+    CREDIT_PACKS.iter().copied().find(|p| p.credits == credits)
+}
+
+/// What a crawl does, which decides its price
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrawlKind {
+    /// Bing or Google search
+    Serp,
+    /// Single-page extraction with the generic engine
+    DeepCrawl,
+    /// Generic crawl following pagination or infinite scroll
+    Spider,
+}
+
+impl CrawlKind {
+    pub fn of(engine: &str, options: &CrawlOptions) -> Self {
+        match engine {
+            "generic" if options.next_page_selector.is_some() || options.infinite_scroll.is_some() => CrawlKind::Spider,
+            "generic" => CrawlKind::DeepCrawl,
+            _ => CrawlKind::Serp,
+        }
+    }
+
+    pub fn cost(&self) -> i64 {
+        match self {
+            CrawlKind::Serp => 1,
+            CrawlKind::DeepCrawl => 3,
+            CrawlKind::Spider => 10,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CrawlKind::Serp => "serp",
+            CrawlKind::DeepCrawl => "deep_crawl",
+            CrawlKind::Spider => "spider",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema, FromRow)]
+pub struct LedgerEntry {
+    pub id: i64,
+    /// Credits added (positive) or spent (negative)
+    pub delta: i64,
+    /// grant, purchase, debit, refund (of a failed crawl) or reversal (of a refunded payment)
+    pub reason: String,
+    /// Crawl type of a debit: serp, deep_crawl or spider
+    pub crawl_kind: Option<String>,
+    pub task_id: Option<String>,
+    pub payment_id: Option<String>,
+    pub balance_after: i64,
+    #[schema(value_type = String)]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreditBalanceResponse {
+    pub success: bool,
+    pub balance: i64,
+    /// Credits per crawl type
+    #[schema(example = json!({"serp": 1, "deep_crawl": 3, "spider": 10}))]
+    pub prices: std::collections::BTreeMap<String, i64>,
+    /// Credit packs for sale through `/payments/checkout`
+    pub packs: Vec<CreditPack>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LedgerQuery {
+    /// Most recent entries to return (default 50, max 200)
+    pub limit: Option<i64>,
+}
+
+type ApiError = (StatusCode, String);
+
+fn signup_grant() -> i64 {
+    std::env::var("CREDITS_SIGNUP_GRANT").ok().and_then(|s| s.parse().ok()).unwrap_or(DEFAULT_SIGNUP_GRANT)
+}
+
+/// Create the user's balance on first use, with the signup grant
+async fn ensure_account(tx: &mut Transaction<'_, Postgres>, user_id: &str) -> Result<(), sqlx::Error> {
+    let grant = signup_grant().max(0);
+    let created = sqlx::query("INSERT INTO credit_balances (user_id, balance) VALUES ($1, $2) ON CONFLICT (user_id) DO NOTHING")
+        .bind(user_id)
+        .bind(grant)
+        .execute(&mut **tx)
+        .await?;
+    if created.rows_affected() > 0 && grant > 0 {
+        sqlx::query("INSERT INTO credit_ledger (user_id, delta, reason, balance_after) VALUES ($1, $2, 'grant', $2)")
+            .bind(user_id)
+            .bind(grant)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Current balance (creating the account if needed)
+pub async fn balance(pool: &PgPool, user_id: &str) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    ensure_account(&mut tx, user_id).await?;
+    let balance = sqlx::query_scalar("SELECT balance FROM credit_balances WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(balance)
+}
+
+#[derive(Serialize)]
+struct CreditErrorBody {
+    success: bool,
+    error: String,
+    balance: Option<i64>,
+}
+
+/// Why a crawl couldn't be paid for: not enough credits (402) or a database error (500)
+pub enum CreditError {
+    Insufficient { balance: i64, cost: i64 },
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for CreditError {
+    fn from(e: sqlx::Error) -> Self {
+        CreditError::Database(e)
+    }
+}
+
+impl IntoResponse for CreditError {
+    fn into_response(self) -> Response {
+        match self {
+            CreditError::Insufficient { balance, cost } => {
+                let body = CreditErrorBody {
+                    success: false,
+                    error: format!("Not enough credits: this crawl costs {}, balance is {}", cost, balance),
+                    balance: Some(balance),
+                };
+                (StatusCode::PAYMENT_REQUIRED, Json(body)).into_response()
+            }
+            CreditError::Database(e) => {
+                error!("❌ [Credits] Debit failed: {}", e);
+                let body = CreditErrorBody {
+                    success: false,
+                    error: "Failed to charge credits".to_string(),
+                    balance: None,
+                };
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+            }
+        }
+    }
+}
+
+/// Charge a task's crawl, refusing it when the balance doesn't cover the cost
+pub async fn debit(pool: &PgPool, user_id: &str, task_id: &str, kind: CrawlKind) -> Result<i64, CreditError> {
+    let cost = kind.cost();
+    let mut tx = pool.begin().await?;
+    ensure_account(&mut tx, user_id).await?;
+    let balance: Option<i64> = sqlx::query_scalar(
+        "UPDATE credit_balances SET balance = balance - $2 WHERE user_id = $1 AND balance >= $2 RETURNING balance",
+    )
+    .bind(user_id)
+    .bind(cost)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(balance) = balance else {
+        let balance = sqlx::query_scalar("SELECT balance FROM credit_balances WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        return Err(CreditError::Insufficient { balance, cost });
+    };
+
+    sqlx::query(
+        "INSERT INTO credit_ledger (user_id, delta, reason, crawl_kind, task_id, balance_after) VALUES ($1, $2, 'debit', $3, $4, $5)",
+    )
+    .bind(user_id)
+    .bind(-cost)
+    .bind(kind.as_str())
+    .bind(task_id)
+    .bind(balance)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(balance)
+}
+
+/// Give back what a task was charged. Tasks without a debit (or already
+/// refunded) are left alone; returns whether credits were refunded.
+pub async fn refund(pool: &PgPool, task_id: &str) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let debit: Option<(String, i64)> = sqlx::query_as(
+        r#"SELECT user_id, -delta FROM credit_ledger d
+           WHERE task_id = $1 AND reason = 'debit'
+             AND NOT EXISTS (SELECT 1 FROM credit_ledger r WHERE r.task_id = $1 AND r.reason = 'refund')
+           FOR UPDATE"#,
+    )
+    .bind(task_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((user_id, amount)) = debit else {
+        return Ok(false);
+    };
+
+    let balance: i64 = sqlx::query_scalar("UPDATE credit_balances SET balance = balance + $2 WHERE user_id = $1 RETURNING balance")
+        .bind(&user_id)
+        .bind(amount)
+        .fetch_one(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO credit_ledger (user_id, delta, reason, task_id, balance_after) VALUES ($1, $2, 'refund', $3, $4)")
+        .bind(&user_id)
+        .bind(amount)
+        .bind(task_id)
+        .bind(balance)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    info!("↩️ [Credits] Refunded {} credits to {} for task {}", amount, user_id, task_id);
+    Ok(true)
+}
+
+/// Add purchased credits once per payment
+pub async fn top_up(pool: &PgPool, user_id: &str, payment_id: &str, credits: i64) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    ensure_account(&mut tx, user_id).await?;
+    let already: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM credit_ledger WHERE payment_id = $1 AND reason = 'purchase')")
+        .bind(payment_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if already {
+        return Ok(());
+    }
+
+    let balance: i64 = sqlx::query_scalar("UPDATE credit_balances SET balance = balance + $2 WHERE user_id = $1 RETURNING balance")
+        .bind(user_id)
+        .bind(credits)
+        .fetch_one(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO credit_ledger (user_id, delta, reason, payment_id, balance_after) VALUES ($1, $2, 'purchase', $3, $4)")
+        .bind(user_id)
+        .bind(credits)
+        .bind(payment_id)
+        .bind(balance)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    info!("💳 [Credits] {} bought {} credits (payment {})", user_id, credits, payment_id);
+    Ok(())
+}
+
+/// Take back the credits bought with a refunded payment, as far as the balance
+/// allows (spent credits aren't clawed into a negative balance). Returns the
+/// credits removed.
+pub async fn reverse_purchase(pool: &PgPool, payment_id: &str) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let purchase: Option<(String, i64)> = sqlx::query_as(
+        r#"SELECT user_id, delta FROM credit_ledger
+           WHERE payment_id = $1 AND reason = 'purchase'
+             AND NOT EXISTS (SELECT 1 FROM credit_ledger WHERE payment_id = $1 AND reason = 'reversal')
+           FOR UPDATE"#,
+    )
+    .bind(payment_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some((user_id, credits)) = purchase else {
+        return Ok(0);
+    };
+
+    let current: i64 = sqlx::query_scalar("SELECT balance FROM credit_balances WHERE user_id = $1 FOR UPDATE")
+        .bind(&user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let removed = credits.min(current).max(0);
+    let balance: i64 = sqlx::query_scalar("UPDATE credit_balances SET balance = balance - $2 WHERE user_id = $1 RETURNING balance")
+        .bind(&user_id)
+        .bind(removed)
+        .fetch_one(&mut *tx)
+        .await?;
+    sqlx::query("INSERT INTO credit_ledger (user_id, delta, reason, payment_id, balance_after) VALUES ($1, $2, 'reversal', $3, $4)")
+        .bind(&user_id)
+        .bind(-removed)
+        .bind(payment_id)
+        .bind(balance)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    info!("↩️ [Credits] Reversed {} of {} credits from {} (payment {} refunded)", removed, credits, user_id, payment_id);
+    Ok(removed)
+}
+
+// ============================================================================
+// API
+// ============================================================================
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Credits] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+/// The caller's credit balance and crawl prices
+#[utoipa::path(
+    get,
+    path = "/credits",
+    tag = "payments",
+    responses(
+        (status = 200, description = "Credit balance", body = CreditBalanceResponse)
+    )
+)]
+pub async fn get_balance(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<CreditBalanceResponse>, ApiError> {
+    let balance = balance(&state.pool, &user.id).await.map_err(db_error)?;
+    let prices = [CrawlKind::Serp, CrawlKind::DeepCrawl, CrawlKind::Spider]
+        .iter()
+        .map(|kind| (kind.as_str().to_string(), kind.cost()))
+        .collect();
+    Ok(Json(CreditBalanceResponse { success: true, balance, prices, packs: CREDIT_PACKS.to_vec() }))
+}
+
+/// The caller's credit ledger, newest first
+#[utoipa::path(
+    get,
+    path = "/credits/ledger",
+    tag = "payments",
+    params(LedgerQuery),
+    responses(
+        (status = 200, description = "Ledger entries", body = Vec<LedgerEntry>)
+    )
+)]
+pub async fn get_ledger(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Query(query): Query<LedgerQuery>,
+) -> Result<Json<Vec<LedgerEntry>>, ApiError> {
+    let entries: Vec<LedgerEntry> = sqlx::query_as(
+        r#"SELECT id, delta, reason, crawl_kind, task_id, payment_id, balance_after, created_at
+           FROM credit_ledger WHERE user_id = $1 ORDER BY id DESC LIMIT $2"#,
+    )
+    .bind(&user.id)
+    .bind(query.limit.unwrap_or(50).clamp(1, 200))
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crawl_kind_pricing() {
+        let plain = CrawlOptions::default();
+        assert_eq!(CrawlKind::of("bing", &plain).cost(), 1);
+        assert_eq!(CrawlKind::of("generic", &plain), CrawlKind::DeepCrawl);
+
+        let paginated = CrawlOptions { next_page_selector: Some("a.next".to_string()), ..Default::default() };
+        assert_eq!(CrawlKind::of("generic", &paginated).cost(), 10);
+        // Pagination options don't make a search a spider
+        assert_eq!(CrawlKind::of("google", &paginated), CrawlKind::Serp);
+
+        assert_eq!(credit_pack(2_500).map(|p| p.price_cents), Some(2_000));
+        assert_eq!(credit_pack(1_000_000), None);
+    }
+}
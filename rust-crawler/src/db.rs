@@ -1,60 +1,177 @@
-use sqlx::{postgres::PgPool, Row};
+//! Database schema.
+//!
+//! The schema lives in numbered SQL files under `migrations/`, embedded into the
+//! binary and applied at startup; `_sqlx_migrations` records which ran. The
+//! baseline migrations use `CREATE TABLE IF NOT EXISTS` followed by `ADD COLUMN
+//! IF NOT EXISTS` for every column the old startup code added later, so a
+//! database set up by that code is brought up to the current schema rather than
+//! left with its older tables. A released migration is never edited: its
+//! checksum no longer matching stops startup. Add a new file with the next
+//! number instead.
+
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::migrate::{Migration, Migrator};
+use sqlx::postgres::PgPool;
+use sqlx::FromRow;
+use utoipa::ToSchema;
 
-pub async fn init_db(pool: &PgPool) -> Result<()> {
-    // 1. Create table if not exists (Base schema)
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS tasks (
-            id VARCHAR PRIMARY KEY,
-            keyword VARCHAR NOT NULL,
-            engine VARCHAR NOT NULL DEFAULT 'bing',
-            status VARCHAR NOT NULL,
-            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-            results_json TEXT,
-            extracted_text TEXT,
-            first_page_html TEXT,
-            meta_description TEXT,
-            meta_author TEXT,
-            meta_date TEXT
-        );
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    // 2. Schema Evolution: Add new columns if they don't exist
-    // We use a separate query for each column to handle potential partial migrations gracefully
-    
-    // Emails (JSONB)
-    sqlx::query("ALTER TABLE tasks ADD COLUMN IF NOT EXISTS emails JSONB;").execute(pool).await.ok();
-
-    // Phone Numbers (JSONB)
-    sqlx::query("ALTER TABLE tasks ADD COLUMN IF NOT EXISTS phone_numbers JSONB;").execute(pool).await.ok();
-
-    // Outbound Links (JSONB)
-    sqlx::query("ALTER TABLE tasks ADD COLUMN IF NOT EXISTS outbound_links JSONB;").execute(pool).await.ok();
-        
-    // Images (JSONB)
-    sqlx::query("ALTER TABLE tasks ADD COLUMN IF NOT EXISTS images JSONB;").execute(pool).await.ok();
-
-    // Sentiment (TEXT)
-    sqlx::query("ALTER TABLE tasks ADD COLUMN IF NOT EXISTS sentiment TEXT;").execute(pool).await.ok();
-
-    // ML Entities (JSONB)
-    let _ = sqlx::query("ALTER TABLE tasks ADD COLUMN IF NOT EXISTS entities JSONB;")
-        .execute(pool)
-        .await;
-
-    // ML Category (TEXT)
-    let _ = sqlx::query("ALTER TABLE tasks ADD COLUMN IF NOT EXISTS category TEXT;")
-        .execute(pool)
-        .await;
-
-    // Marketing Data (JSONB)
-    let _ = sqlx::query("ALTER TABLE tasks ADD COLUMN IF NOT EXISTS marketing_data JSONB;")
-        .execute(pool)
-        .await;
+/// Migrations in `migrations/`, embedded at build time
+pub static MIGRATOR: Migrator = sqlx::migrate!();
 
+/// Apply pending migrations. Replicas starting together wait on an advisory lock.
+pub async fn run_migrations(pool: &PgPool) -> Result<()> {
+    MIGRATOR.run(pool).await?;
     Ok(())
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Started but didn't complete
+    Failed,
+    /// The file changed after it was applied
+    Modified,
+    /// Applied by a newer build; this one doesn't ship it
+    Unknown,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MigrationStatus {
+    pub version: i64,
+    #[schema(example = "tasks")]
+    pub description: String,
+    pub state: MigrationState,
+    #[schema(value_type = Option<String>)]
+    pub installed_on: Option<DateTime<Utc>>,
+    pub execution_ms: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SchemaVersion {
+    /// Highest migration applied to the database
+    pub current: Option<i64>,
+    /// Latest migration this build ships
+    pub expected: i64,
+    /// Every shipped migration is applied unchanged and nothing newer is
+    pub up_to_date: bool,
+    pub migrations: Vec<MigrationStatus>,
+}
+
+/// A row of `_sqlx_migrations`
+#[derive(Debug, FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub installed_on: DateTime<Utc>,
+    pub success: bool,
+    pub checksum: Vec<u8>,
+    /// Nanoseconds
+    pub execution_time: i64,
+}
+
+/// Compare the shipped migrations with those recorded in the database
+pub fn compare(shipped: &[Migration], applied: Vec<AppliedMigration>) -> SchemaVersion {
+    let mut applied: std::collections::BTreeMap<i64, AppliedMigration> =
+        applied.into_iter().map(|m| (m.version, m)).collect();
+    let mut migrations: Vec<MigrationStatus> = shipped
+        .iter()
+        .map(|migration| {
+            let row = applied.remove(&migration.version);
+            let state = match &row {
+                None => MigrationState::Pending,
+                Some(row) if !row.success => MigrationState::Failed,
+                Some(row) if row.checksum != *migration.checksum => MigrationState::Modified,
+                Some(_) => MigrationState::Applied,
+            };
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+                installed_on: row.as_ref().map(|r| r.installed_on),
+                execution_ms: row.as_ref().map(|r| r.execution_time / 1_000_000),
+            }
+        })
+        .collect();
+    migrations.extend(applied.into_values().map(|row| MigrationStatus {
+        version: row.version,
+        description: row.description,
+        state: if row.success { MigrationState::Unknown } else { MigrationState::Failed },
+        installed_on: Some(row.installed_on),
+        execution_ms: Some(row.execution_time / 1_000_000),
+    }));
+
+    let current = migrations.iter().filter(|m| m.installed_on.is_some()).map(|m| m.version).max();
+    SchemaVersion {
+        current,
+        expected: shipped.iter().map(|m| m.version).max().unwrap_or(0),
+        up_to_date: migrations.iter().all(|m| m.state == MigrationState::Applied),
+        migrations,
+    }
+}
+
+/// Migration state of the database against this build
+pub async fn schema_version(pool: &PgPool) -> Result<SchemaVersion, sqlx::Error> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    let applied = if exists {
+        sqlx::query_as::<_, AppliedMigration>(
+            "SELECT version, description, installed_on, success, checksum, execution_time FROM _sqlx_migrations ORDER BY version",
+        )
+        .fetch_all(pool)
+        .await?
+    } else {
+        Vec::new()
+    };
+    Ok(compare(MIGRATOR.migrations.as_ref(), applied))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::migrate::MigrationType;
+
+    fn applied(migration: &Migration, success: bool) -> AppliedMigration {
+        AppliedMigration {
+            version: migration.version,
+            description: migration.description.to_string(),
+            installed_on: Utc::now(),
+            success,
+            checksum: migration.checksum.to_vec(),
+            execution_time: 2_000_000,
+        }
+    }
+
+    #[test]
+    fn test_schema_version_against_shipped_migrations() {
+        let versions: Vec<i64> = MIGRATOR.iter().map(|m| m.version).collect();
+        assert_eq!(versions, (1..=versions.len() as i64).collect::<Vec<_>>(), "migrations are numbered without gaps");
+
+        let shipped = vec![
+            Migration::new(1, "tasks".into(), MigrationType::Simple, "CREATE TABLE tasks ();".into()),
+            Migration::new(2, "profiles".into(), MigrationType::Simple, "CREATE TABLE profiles ();".into()),
+        ];
+        let fresh = compare(&shipped, vec![]);
+        assert_eq!((fresh.current, fresh.expected, fresh.up_to_date), (None, 2, false));
+
+        let partial = compare(&shipped, vec![applied(&shipped[0], true)]);
+        assert_eq!(partial.current, Some(1));
+        assert_eq!(partial.migrations[1].state, MigrationState::Pending);
+        assert_eq!(partial.migrations[0].execution_ms, Some(2));
+
+        let done = compare(&shipped, vec![applied(&shipped[0], true), applied(&shipped[1], true)]);
+        assert!(done.up_to_date);
+
+        let mut edited = applied(&shipped[1], true);
+        edited.checksum = vec![0];
+        let newer = Migration::new(3, "later".into(), MigrationType::Simple, "SELECT 1;".into());
+        let drifted = compare(&shipped, vec![applied(&shipped[0], true), edited, applied(&newer, true)]);
+        let states: Vec<MigrationState> = drifted.migrations.iter().map(|m| m.state).collect();
+        assert_eq!(states, vec![MigrationState::Applied, MigrationState::Modified, MigrationState::Unknown]);
+        assert_eq!((drifted.current, drifted.up_to_date), (Some(3), false));
+    }
+}
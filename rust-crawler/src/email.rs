@@ -0,0 +1,141 @@
+//! HTML email templates for notifications.
+//!
+//! Templates live in `templates/email/` and are compiled into the binary by
+//! askama. Each notification event has its own template extending the branded
+//! `base.html`; anything else (test messages, manual sends) uses
+//! `notification.html`.
+
+use askama::Template;
+use crate::notifications::{NotificationEvent, OutgoingMessage};
+
+#[derive(Template)]
+#[template(path = "email/crawl_completed.html")]
+struct CrawlCompletedEmail<'a> {
+    subject: &'a str,
+    task_url: Option<String>,
+    keyword: &'a str,
+    result_count: usize,
+    category: Option<&'a str>,
+    failed_stage: Option<&'a str>,
+}
+
+#[derive(Template)]
+#[template(path = "email/crawl_failed.html")]
+struct CrawlFailedEmail<'a> {
+    subject: &'a str,
+    task_url: Option<String>,
+    keyword: &'a str,
+    error: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email/rank_alert.html")]
+struct RankAlertEmail<'a> {
+    subject: &'a str,
+    task_url: Option<String>,
+    message: &'a str,
+    keyword: Option<&'a str>,
+}
+
+#[derive(Template)]
+#[template(path = "email/page_changed.html")]
+struct PageChangedEmail<'a> {
+    subject: &'a str,
+    task_url: Option<String>,
+    message: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email/billing.html")]
+struct BillingEmail<'a> {
+    subject: &'a str,
+    task_url: Option<String>,
+    message: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "email/notification.html")]
+struct NotificationEmail<'a> {
+    subject: &'a str,
+    task_url: Option<String>,
+    message: &'a str,
+}
+
+/// HTML body for a notification, using the template of its event
+pub fn render_html(msg: &OutgoingMessage) -> Result<String, askama::Error> {
+    let subject = msg.subject.as_str();
+    let task_url = msg.task_url();
+    let details = &msg.details;
+    let keyword = details.keyword.as_deref();
+
+    match (msg.event, keyword) {
+        (Some(NotificationEvent::CrawlCompleted), Some(keyword)) => CrawlCompletedEmail {
+            subject,
+            task_url,
+            keyword,
+            result_count: details.result_count.unwrap_or(0),
+            category: details.category.as_deref(),
+            failed_stage: details.failed_stage.as_deref(),
+        }
+        .render(),
+        (Some(NotificationEvent::CrawlFailed), Some(keyword)) => CrawlFailedEmail {
+            subject,
+            task_url,
+            keyword,
+            error: details.error.as_deref().unwrap_or(&msg.message),
+        }
+        .render(),
+        (Some(NotificationEvent::RankAlert), _) => RankAlertEmail { subject, task_url, message: &msg.message, keyword }.render(),
+        (Some(NotificationEvent::PageChanged), _) => PageChangedEmail { subject, task_url, message: &msg.message }.render(),
+        (Some(NotificationEvent::Billing), _) => BillingEmail { subject, task_url, message: &msg.message }.render(),
+        _ => NotificationEmail { subject, task_url, message: &msg.message }.render(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::NotificationDetails;
+
+    #[test]
+    fn test_render_crawl_completed_email() {
+        let msg = OutgoingMessage {
+            notification_id: None,
+            event: Some(NotificationEvent::CrawlCompleted),
+            subject: "Crawl Completed".to_string(),
+            message: "Crawl finished".to_string(),
+            details: NotificationDetails {
+                keyword: Some("rust <crawler>".to_string()),
+                task_id: Some("task-1".to_string()),
+                result_count: Some(12),
+                ..Default::default()
+            },
+        };
+        let html = render_html(&msg).unwrap();
+        assert!(html.contains("<strong>rust &lt;crawler&gt;</strong>"), "keyword is escaped");
+        assert!(html.contains("<strong>12</strong> results"));
+        assert!(html.contains("/crawl/task-1\""));
+        assert!(!html.contains("Category:"));
+        assert!(!html.contains("stage failed"));
+
+        let partial = OutgoingMessage {
+            details: NotificationDetails { failed_stage: Some("extract".to_string()), ..msg.details.clone() },
+            ..msg
+        };
+        assert!(render_html(&partial).unwrap().contains("<strong>extract</strong> stage failed"));
+    }
+
+    #[test]
+    fn test_render_falls_back_to_generic_template() {
+        let msg = OutgoingMessage {
+            notification_id: None,
+            event: None,
+            subject: "Test notification".to_string(),
+            message: "Hello".to_string(),
+            details: NotificationDetails::default(),
+        };
+        let html = render_html(&msg).unwrap();
+        assert!(html.contains("<h1 style=\"margin:0 0 16px; font-size:20px;\">Test notification</h1>"));
+        assert!(!html.contains("View task"));
+    }
+}
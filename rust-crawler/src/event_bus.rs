@@ -0,0 +1,195 @@
+//! Task events for other services.
+//!
+//! With `EVENT_BUS=kafka` or `EVENT_BUS=nats`, the worker publishes a compact
+//! JSON event whenever a task completes (fully or partially) or finally fails, so
+//! downstream services can react without polling Postgres. Kafka messages go to
+//! `EVENT_BUS_TOPIC` (default `crawl.tasks`) keyed by task ID; NATS messages go to
+//! `<EVENT_BUS_TOPIC>.completed` / `.partial` / `.failed`. Publishing is best effort: a broker
+//! outage is logged and never fails the crawl.
+
+use anyhow::Result;
+use axum::async_trait;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Where task events are published
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn publish(&self, event: &TaskEvent) -> Result<()>;
+}
+
+/// The configured sink; unset (no events) until `connect` succeeds
+static EVENT_BUS: OnceCell<Arc<dyn EventSink>> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskEvent {
+    /// `task.completed`, `task.partial` or `task.failed`
+    pub event: &'static str,
+    pub task_id: String,
+    pub user_id: String,
+    pub keyword: String,
+    pub engine: String,
+    /// completed, partial or failed
+    pub status: &'static str,
+    /// How each stage went, for completed and partial tasks (as in the task's `stages`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stages: Option<serde_json::Value>,
+    /// Objects stored for the task (raw HTML)
+    pub s3_keys: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Unix timestamp (ms)
+    pub timestamp: i64,
+}
+
+impl TaskEvent {
+    /// A saved task; `status` is `completed` or `partial`
+    pub fn finished(job: &crate::queue::CrawlJob, status: &'static str, stages: serde_json::Value, s3_keys: Vec<String>) -> Self {
+        Self { stages: Some(stages), ..Self::new(job, status, s3_keys) }
+    }
+
+    pub fn failed(job: &crate::queue::CrawlJob, error: &str) -> Self {
+        Self { error: Some(error.to_string()), ..Self::new(job, "failed", Vec::new()) }
+    }
+
+    fn new(job: &crate::queue::CrawlJob, status: &'static str, s3_keys: Vec<String>) -> Self {
+        Self {
+            event: event_name(status),
+            task_id: job.id.clone(),
+            user_id: job.user_id.clone(),
+            keyword: job.keyword.clone(),
+            engine: job.engine.clone(),
+            status,
+            stages: None,
+            s3_keys,
+            error: None,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// Event of a task status, also the `X-Crawler-Event` of its webhook
+pub fn event_name(status: &str) -> &'static str {
+    match status {
+        "completed" => "task.completed",
+        "partial" => "task.partial",
+        _ => "task.failed",
+    }
+}
+
+fn topic() -> String {
+    std::env::var("EVENT_BUS_TOPIC").ok().filter(|s| !s.is_empty()).unwrap_or_else(|| "crawl.tasks".to_string())
+}
+
+pub struct KafkaSink {
+    producer: rdkafka::producer::FutureProducer,
+    topic: String,
+}
+
+impl KafkaSink {
+    pub fn connect() -> Result<Self> {
+        let brokers = std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".to_string());
+        let producer = rdkafka::ClientConfig::new()
+            .set("bootstrap.servers", &brokers)
+            .set("message.timeout.ms", "10000")
+            .create()?;
+        Ok(Self { producer, topic: topic() })
+    }
+}
+
+#[async_trait]
+impl EventSink for KafkaSink {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    async fn publish(&self, event: &TaskEvent) -> Result<()> {
+        let payload = serde_json::to_vec(event)?;
+        let record = rdkafka::producer::FutureRecord::to(&self.topic).key(&event.task_id).payload(&payload);
+        self.producer
+            .send(record, Duration::from_secs(5))
+            .await
+            .map_err(|(e, _)| anyhow::anyhow!("Kafka delivery failed: {}", e))?;
+        Ok(())
+    }
+}
+
+pub struct NatsSink {
+    client: async_nats::Client,
+    prefix: String,
+}
+
+impl NatsSink {
+    pub async fn connect() -> Result<Self> {
+        let url = std::env::var("NATS_URL").unwrap_or_else(|_| "nats://localhost:4222".to_string());
+        Ok(Self { client: async_nats::connect(url).await?, prefix: topic() })
+    }
+}
+
+#[async_trait]
+impl EventSink for NatsSink {
+    fn name(&self) -> &'static str {
+        "nats"
+    }
+
+    async fn publish(&self, event: &TaskEvent) -> Result<()> {
+        let subject = format!("{}.{}", self.prefix, event.status);
+        self.client.publish(subject, serde_json::to_vec(event)?.into()).await?;
+        Ok(())
+    }
+}
+
+/// Connect the sink selected by `EVENT_BUS`; call once at startup
+pub async fn connect() -> Result<()> {
+    let configured = std::env::var("EVENT_BUS").unwrap_or_default().to_lowercase();
+    let sink: Arc<dyn EventSink> = match configured.as_str() {
+        "" | "none" => return Ok(()),
+        "kafka" => Arc::new(KafkaSink::connect()?),
+        "nats" => Arc::new(NatsSink::connect().await?),
+        other => anyhow::bail!("Unknown EVENT_BUS '{}' (expected kafka or nats)", other),
+    };
+    info!("📣 Event bus: {} ({})", sink.name(), topic());
+    let _ = EVENT_BUS.set(sink);
+    Ok(())
+}
+
+/// Publish in the background; a no-op without an event bus
+pub fn publish(event: TaskEvent) {
+    let Some(sink) = EVENT_BUS.get().cloned() else { return };
+    tokio::spawn(async move {
+        if let Err(e) = sink.publish(&event).await {
+            warn!("⚠️ [EventBus] Failed to publish {} for {}: {}", event.event, event.task_id, e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_event_payload() {
+        let job: crate::queue::CrawlJob = serde_json::from_value(serde_json::json!({
+            "id": "t1", "user_id": "u1", "keyword": "rust jobs", "engine": "bing"
+        }))
+        .unwrap();
+        let stages = serde_json::json!({"serp": "succeeded", "extract": "succeeded", "store": "succeeded"});
+        let completed = serde_json::to_value(TaskEvent::finished(&job, "completed", stages, vec!["bing/t1.html".to_string()])).unwrap();
+        assert_eq!(completed["event"], "task.completed");
+        assert_eq!(completed["s3_keys"][0], "bing/t1.html");
+        assert!(completed.get("error").is_none());
+
+        let stages = serde_json::json!({"serp": "succeeded", "extract": "failed", "store": "skipped"});
+        let partial = serde_json::to_value(TaskEvent::finished(&job, "partial", stages, Vec::new())).unwrap();
+        assert_eq!((partial["event"].as_str(), partial["status"].as_str()), (Some("task.partial"), Some("partial")));
+        assert_eq!(partial["stages"]["extract"], "failed");
+
+        let failed = serde_json::to_value(TaskEvent::failed(&job, "captcha")).unwrap();
+        assert_eq!((failed["status"].as_str(), failed["error"].as_str()), (Some("failed"), Some("captcha")));
+        assert!(failed.get("stages").is_none());
+    }
+}
@@ -0,0 +1,136 @@
+//! Live job lifecycle events.
+//!
+//! The API and worker publish events on an in-process broadcast channel; the
+//! `/ws` endpoint streams the events of the authenticated user's own jobs.
+
+use axum::{
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::queue::CrawlJob;
+use tracing::{info, warn};
+
+/// Global event bus (slow subscribers skip events instead of blocking the worker)
+pub static EVENTS: Lazy<broadcast::Sender<JobEvent>> = Lazy::new(|| broadcast::channel(1024).0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobEventKind {
+    Queued,
+    Started,
+    ChallengeDetected,
+    Retrying,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobEvent {
+    pub event: JobEventKind,
+    pub task_id: String,
+    /// Owner of the job; used for routing, not sent to clients
+    #[serde(skip)]
+    pub user_id: String,
+    pub keyword: String,
+    pub engine: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Unix timestamp (ms)
+    pub timestamp: i64,
+}
+
+impl JobEvent {
+    pub fn new(event: JobEventKind, job: &CrawlJob) -> Self {
+        Self {
+            event,
+            task_id: job.id.clone(),
+            user_id: job.user_id.clone(),
+            keyword: job.keyword.clone(),
+            engine: job.engine.clone(),
+            message: None,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+/// Broadcast an event; a no-op when nobody is listening
+pub fn publish(event: JobEvent) {
+    let _ = EVENTS.send(event);
+}
+
+#[derive(Deserialize)]
+pub struct WsParams {
+    /// Browsers can't set headers on WebSocket requests, so the JWT may come as `?token=`
+    token: Option<String>,
+}
+
+/// Upgrade to a WebSocket streaming the caller's job events as JSON text frames
+pub async fn ws_handler(
+    State(state): State<Arc<AppState>>,
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(params): Query<WsParams>,
+) -> Response {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(crate::auth::extract_bearer_token)
+        .map(|t| t.to_string())
+        .or(params.token);
+
+    let user = match token {
+        None if crate::auth::auth_disabled() => Ok(crate::auth::dev_user()),
+        None => return (StatusCode::UNAUTHORIZED, "Missing token").into_response(),
+        Some(token) => crate::auth::authenticate(&token, &state.denylist).await,
+    };
+    let user = match user {
+        Ok(user) => user,
+        Err(crate::auth::AuthError::Unavailable(message)) => return (StatusCode::SERVICE_UNAVAILABLE, message).into_response(),
+        Err(e) => {
+            warn!("⚠️ WebSocket auth failed: {}", e);
+            return (StatusCode::UNAUTHORIZED, "Invalid or expired token").into_response();
+        }
+    };
+
+    ws.on_upgrade(move |socket| stream_events(socket, user))
+}
+
+async fn stream_events(mut socket: WebSocket, user: AuthUser) {
+    let mut rx = EVENTS.subscribe();
+    info!("🔌 WebSocket subscriber connected: {}", user.id);
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Ok(event) if event.user_id == user.id => {
+                    let Ok(text) = serde_json::to_string(&event) else { continue };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("⚠️ WebSocket subscriber {} lagged, skipped {} events", user.id, skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+        }
+    }
+    info!("🔌 WebSocket subscriber disconnected: {}", user.id);
+}
@@ -0,0 +1,387 @@
+//! Task exports for people who live in spreadsheets.
+//!
+//! `GET /tasks/:task_id/export?format=csv|xlsx|json` flattens a completed task
+//! (SERP results, featured snippet, questions, related searches, and the first
+//! result's contacts and marketing data) into rows of
+//! `section, position, title, url, text`. CSV has them all in one table, XLSX has
+//! one sheet per section, and JSON returns the rows under the task's metadata.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::crawler::{MarketingData, SerpData};
+use tracing::error;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Xlsx,
+    Json,
+}
+
+impl ExportFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// csv (default), xlsx or json
+    #[param(inline)]
+    pub format: Option<ExportFormat>,
+}
+
+/// One flattened record of a task
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ExportRow {
+    /// overview, result, featured_snippet, people_also_ask, related_search, email, phone, headline, benefit or cta
+    pub section: &'static str,
+    /// 1-based rank within the section (results, questions, ...)
+    pub position: Option<usize>,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub text: Option<String>,
+}
+
+impl ExportRow {
+    fn new(section: &'static str) -> Self {
+        Self { section, position: None, title: None, url: None, text: None }
+    }
+}
+
+/// Sheet titles in XLSX exports, in sheet order
+const SECTIONS: &[(&str, &str)] = &[
+    ("overview", "Overview"),
+    ("result", "Results"),
+    ("featured_snippet", "Featured snippet"),
+    ("people_also_ask", "People also ask"),
+    ("related_search", "Related searches"),
+    ("email", "Emails"),
+    ("phone", "Phones"),
+    ("headline", "Headlines"),
+    ("benefit", "Benefits"),
+    ("cta", "Calls to action"),
+];
+
+const COLUMNS: [&str; 5] = ["section", "position", "title", "url", "text"];
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskExport {
+    pub task_id: String,
+    pub keyword: String,
+    pub engine: String,
+    pub created_at: Option<String>,
+    pub rows: Vec<ExportRow>,
+}
+
+impl TaskExport {
+    pub fn new(task: &ExportSource, rows: Vec<ExportRow>) -> Self {
+        Self {
+            task_id: task.id.clone(),
+            keyword: task.keyword.clone(),
+            engine: task.engine.clone(),
+            created_at: task.created_at.clone(),
+            rows,
+        }
+    }
+}
+
+/// The task columns an export is built from
+#[derive(Debug, Default, FromRow)]
+pub struct ExportSource {
+    pub id: String,
+    pub keyword: String,
+    pub engine: String,
+    pub status: String,
+    pub created_at: Option<String>,
+    pub results_json: Option<String>,
+    pub meta_description: Option<String>,
+    pub meta_author: Option<String>,
+    pub meta_date: Option<String>,
+    pub sentiment: Option<String>,
+    pub category: Option<String>,
+    pub emails: Option<serde_json::Value>,
+    pub phone_numbers: Option<serde_json::Value>,
+    pub marketing_data: Option<serde_json::Value>,
+}
+
+/// Selects an `ExportSource` from `tasks`
+pub const SOURCE_COLUMNS: &str = r#"id, keyword, engine, status, to_char(created_at, 'YYYY-MM-DD HH24:MI:SS') AS created_at,
+    results_json, meta_description, meta_author, meta_date, sentiment, category,
+    emails, phone_numbers, marketing_data"#;
+
+fn strings(value: Option<&serde_json::Value>) -> Vec<String> {
+    value.and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or_default()
+}
+
+fn listed(section: &'static str, items: Vec<String>) -> impl Iterator<Item = ExportRow> {
+    items.into_iter().enumerate().map(move |(i, text)| ExportRow {
+        position: Some(i + 1),
+        text: Some(text),
+        ..ExportRow::new(section)
+    })
+}
+
+/// Flatten a task into export rows
+pub fn flatten(task: &ExportSource) -> Vec<ExportRow> {
+    let serp: SerpData = task.results_json.as_deref().and_then(|j| serde_json::from_str(j).ok()).unwrap_or_default();
+    let marketing: MarketingData = task
+        .marketing_data
+        .as_ref()
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+
+    let overview = [
+        ("keyword", Some(task.keyword.clone())),
+        ("engine", Some(task.engine.clone())),
+        ("crawled_at", task.created_at.clone()),
+        ("total_results", serp.total_results.clone()),
+        ("meta_description", task.meta_description.clone()),
+        ("meta_author", task.meta_author.clone()),
+        ("meta_date", task.meta_date.clone()),
+        ("sentiment", task.sentiment.clone()),
+        ("category", task.category.clone()),
+    ];
+    let mut rows: Vec<ExportRow> = overview
+        .into_iter()
+        .filter_map(|(field, value)| {
+            Some(ExportRow { title: Some(field.to_string()), text: Some(value?), ..ExportRow::new("overview") })
+        })
+        .collect();
+
+    rows.extend(serp.results.iter().enumerate().map(|(i, r)| ExportRow {
+        position: Some(i + 1),
+        title: Some(r.title.clone()),
+        url: Some(r.link.clone()),
+        text: Some(r.snippet.clone()),
+        ..ExportRow::new("result")
+    }));
+    if let Some(ref snippet) = serp.featured_snippet {
+        rows.push(ExportRow {
+            title: snippet.source_title.clone(),
+            url: snippet.source_url.clone(),
+            text: Some(snippet.content.clone()),
+            ..ExportRow::new("featured_snippet")
+        });
+    }
+    rows.extend(listed("people_also_ask", serp.people_also_ask));
+    rows.extend(listed("related_search", serp.related_searches));
+    rows.extend(listed("email", strings(task.emails.as_ref())));
+    rows.extend(listed("phone", strings(task.phone_numbers.as_ref())));
+    rows.extend(listed("headline", marketing.headlines));
+    rows.extend(listed("benefit", marketing.key_benefits));
+    rows.extend(listed("cta", marketing.ctas));
+    rows
+}
+
+/// Spreadsheets run cells starting with these as formulas; crawled text is prefixed with `'`
+fn csv_safe(cell: &str) -> String {
+    if cell.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", cell)
+    } else {
+        cell.to_string()
+    }
+}
+
+fn row_cells(row: &ExportRow) -> [String; 5] {
+    [
+        row.section.to_string(),
+        row.position.map(|p| p.to_string()).unwrap_or_default(),
+        row.title.clone().unwrap_or_default(),
+        row.url.clone().unwrap_or_default(),
+        row.text.clone().unwrap_or_default(),
+    ]
+}
+
+pub fn to_csv(rows: &[ExportRow]) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(COLUMNS)?;
+    for row in rows {
+        writer.write_record(row_cells(row).iter().map(|cell| csv_safe(cell)))?;
+    }
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
+
+pub fn to_xlsx(rows: &[ExportRow]) -> Result<Vec<u8>, XlsxError> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    for (section, title) in SECTIONS {
+        let section_rows: Vec<&ExportRow> = rows.iter().filter(|r| r.section == *section).collect();
+        if section_rows.is_empty() && *section != "overview" {
+            continue;
+        }
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(*title)?;
+        // The section is the sheet itself
+        for (col, name) in COLUMNS[1..].iter().enumerate() {
+            sheet.write_string_with_format(0, col as u16, *name, &bold)?;
+        }
+        for (i, row) in section_rows.iter().enumerate() {
+            let line = i as u32 + 1;
+            if let Some(position) = row.position {
+                sheet.write_number(line, 0, position as f64)?;
+            }
+            for (col, cell) in row_cells(row)[2..].iter().enumerate() {
+                if !cell.is_empty() {
+                    sheet.write_string(line, col as u16 + 1, cell)?;
+                }
+            }
+        }
+        sheet.set_column_width(1, 40)?;
+        sheet.set_column_width(2, 50)?;
+        sheet.set_column_width(3, 80)?;
+        sheet.set_freeze_panes(1, 0)?;
+    }
+    workbook.save_to_buffer()
+}
+
+/// `keyword` reduced to a file-name-safe slug
+fn file_slug(keyword: &str) -> String {
+    let slug: String = keyword
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let slug = slug.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-");
+    if slug.is_empty() { "task".to_string() } else { slug.chars().take(60).collect() }
+}
+
+/// File name (without extension) of a task's export: keyword slug and short task ID
+pub fn file_name(task: &ExportSource) -> String {
+    let short_id: String = task.id.chars().take(8).collect();
+    format!("{}-{}", file_slug(&task.keyword), short_id)
+}
+
+/// Download a completed task as CSV, XLSX or JSON
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/export",
+    tag = "crawler",
+    params(
+        ("task_id" = String, Path, description = "Task ID"),
+        ExportQuery
+    ),
+    responses(
+        (status = 200, description = "The export file (JSON: TaskExport)", body = TaskExport),
+        (status = 404, description = "Unknown task or a task outside your organization"),
+        (status = 409, description = "Task hasn't completed")
+    )
+)]
+pub async fn export_task(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(task_id): Path<String>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let failed = |e: String| {
+        error!("❌ Export of task {} failed: {}", task_id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Export failed".to_string())
+    };
+    let task: Option<ExportSource> = sqlx::query_as(&format!(
+        "SELECT {} FROM tasks WHERE id = $1 AND deleted_at IS NULL AND (user_id = $2 OR $3 OR {})",
+        SOURCE_COLUMNS,
+        crate::organizations::teammates_filter("$2")
+    ))
+    .bind(&task_id)
+    .bind(&user.id)
+    .bind(user.is_admin())
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| failed(e.to_string()))?;
+    let task = task.ok_or((StatusCode::NOT_FOUND, "Task not found".to_string()))?;
+    if task.status != "completed" {
+        return Err((StatusCode::CONFLICT, format!("Task is {}; only completed tasks can be exported", task.status)));
+    }
+
+    let format = query.format.unwrap_or_default();
+    let rows = flatten(&task);
+    let body = match format {
+        ExportFormat::Csv => to_csv(&rows).map_err(|e| failed(e.to_string()))?,
+        ExportFormat::Xlsx => to_xlsx(&rows).map_err(|e| failed(e.to_string()))?,
+        ExportFormat::Json => serde_json::to_vec_pretty(&TaskExport::new(&task, rows)).map_err(|e| failed(e.to_string()))?,
+    };
+
+    let disposition = format!("attachment; filename=\"{}.{}\"", file_name(&task), format.extension());
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source() -> ExportSource {
+        ExportSource {
+            id: "t1".to_string(),
+            keyword: "Best Pizza / NYC".to_string(),
+            engine: "google".to_string(),
+            status: "completed".to_string(),
+            results_json: Some(
+                r#"{"results": [{"title": "Joe's", "link": "https://joes.example", "snippet": "=HYPERLINK(\"x\")"}],
+                    "people_also_ask": ["Where is the best pizza?"], "related_searches": [], "featured_snippet": null,
+                    "total_results": "1,000"}"#
+                    .to_string(),
+            ),
+            emails: Some(serde_json::json!(["info@joes.example"])),
+            marketing_data: Some(serde_json::json!({"headlines": ["Since 1975"], "key_benefits": [], "ctas": ["Order now"]})),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_flatten_task() {
+        let rows = flatten(&source());
+        let sections: Vec<&str> = rows.iter().map(|r| r.section).collect();
+        assert_eq!(
+            sections,
+            ["overview", "overview", "overview", "result", "people_also_ask", "email", "headline", "cta"]
+        );
+        let result = &rows[3];
+        assert_eq!((result.position, result.url.as_deref()), (Some(1), Some("https://joes.example")));
+        assert_eq!(rows[2].text.as_deref(), Some("1,000"));
+    }
+
+    #[test]
+    fn test_export_files() {
+        let rows = flatten(&source());
+        let csv = String::from_utf8(to_csv(&rows).unwrap()).unwrap();
+        assert!(csv.starts_with("section,position,title,url,text\n"));
+        // Crawled text can't smuggle formulas into spreadsheets
+        assert!(csv.contains("'=HYPERLINK"));
+
+        let xlsx = to_xlsx(&rows).unwrap();
+        assert!(xlsx.starts_with(b"PK"));
+        assert_eq!(file_slug("Best Pizza / NYC"), "best-pizza-nyc");
+        assert_eq!(file_slug("日本"), "task");
+    }
+}
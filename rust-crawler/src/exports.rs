@@ -0,0 +1,505 @@
+//! Bulk exports.
+//!
+//! `POST /exports` queues an export of every completed task matching the same
+//! filters as `GET /tasks` (engine, date range, keyword). The worker builds it in
+//! the background, as newline-delimited JSON (one `TaskExport` per line) or a zip
+//! with one CSV per task, stores it in object storage and sends an `export_ready`
+//! notification. `GET /exports/:id/download` then returns the file.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashSet;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::export::{self, ExportSource, TaskExport};
+use crate::notifications::{notify, NotificationEvent};
+use tracing::{error, info, warn};
+
+/// Tasks read per query while building an export
+const PAGE_SIZE: i64 = 200;
+/// Exports running longer than this are assumed abandoned (worker restarted) and claimed again
+const STALE_AFTER_MINUTES: i32 = 30;
+
+fn max_tasks() -> i64 {
+    std::env::var("EXPORT_MAX_TASKS").ok().and_then(|s| s.parse().ok()).unwrap_or(5000)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkFormat {
+    /// One JSON object per line
+    #[default]
+    Ndjson,
+    /// A zip with one CSV per task
+    CsvZip,
+}
+
+impl BulkFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BulkFormat::Ndjson => "ndjson",
+            BulkFormat::CsvZip => "csv_zip",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "csv_zip" => BulkFormat::CsvZip,
+            _ => BulkFormat::Ndjson,
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            BulkFormat::Ndjson => "application/x-ndjson",
+            BulkFormat::CsvZip => "application/zip",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            BulkFormat::Ndjson => "ndjson",
+            BulkFormat::CsvZip => "zip",
+        }
+    }
+}
+
+/// Which tasks to export; completed tasks only
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExportFilters {
+    /// Only tasks of this engine (google, bing, generic)
+    pub engine: Option<String>,
+    /// Created at or after this time (RFC 3339)
+    #[schema(value_type = Option<String>)]
+    pub from: Option<DateTime<Utc>>,
+    /// Created before this time (RFC 3339)
+    #[schema(value_type = Option<String>)]
+    pub to: Option<DateTime<Utc>>,
+    /// Keyword contains this text (case-insensitive)
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateExportRequest {
+    /// ndjson (default) or csv_zip
+    pub format: Option<BulkFormat>,
+    #[serde(flatten)]
+    pub filters: ExportFilters,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct BulkExport {
+    pub id: String,
+    /// ndjson or csv_zip
+    pub format: String,
+    #[schema(value_type = Object)]
+    pub filters: sqlx::types::Json<ExportFilters>,
+    /// pending, running, completed or failed
+    pub status: String,
+    pub task_count: Option<i32>,
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    #[schema(value_type = String)]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = Option<String>)]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+const EXPORT_COLUMNS: &str = "id, format, filters, status, task_count, size_bytes, error, created_at, completed_at";
+
+type ApiError = (StatusCode, String);
+
+// ============================================================================
+// Building exports (worker)
+// ============================================================================
+
+#[derive(Debug, FromRow)]
+struct ClaimedExport {
+    id: String,
+    user_id: String,
+    all_users: bool,
+    format: String,
+    filters: sqlx::types::Json<ExportFilters>,
+}
+
+/// The artifact being written, one task at a time
+enum Artifact {
+    Ndjson(Vec<u8>),
+    CsvZip {
+        zip: Box<zip::ZipWriter<std::io::Cursor<Vec<u8>>>>,
+        names: HashSet<String>,
+    },
+}
+
+impl Artifact {
+    fn new(format: BulkFormat) -> Self {
+        match format {
+            BulkFormat::Ndjson => Artifact::Ndjson(Vec::new()),
+            BulkFormat::CsvZip => Artifact::CsvZip {
+                zip: Box::new(zip::ZipWriter::new(std::io::Cursor::new(Vec::new()))),
+                names: HashSet::new(),
+            },
+        }
+    }
+
+    fn add(&mut self, task: &ExportSource) -> anyhow::Result<()> {
+        let rows = export::flatten(task);
+        match self {
+            Artifact::Ndjson(out) => {
+                serde_json::to_writer(&mut *out, &TaskExport::new(task, rows))?;
+                out.push(b'\n');
+            }
+            Artifact::CsvZip { zip, names } => {
+                // Short IDs can collide; zip entries can't
+                let mut name = format!("{}.csv", export::file_name(task));
+                if !names.insert(name.clone()) {
+                    name = format!("{}.csv", task.id);
+                    names.insert(name.clone());
+                }
+                let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+                zip.start_file(name, options)?;
+                zip.write_all(&export::to_csv(&rows)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            Artifact::Ndjson(out) => out,
+            Artifact::CsvZip { zip, .. } => zip.finish()?.into_inner(),
+        })
+    }
+}
+
+/// Claim the oldest pending export (or one abandoned mid-run)
+async fn claim_export(pool: &PgPool) -> Result<Option<ClaimedExport>, sqlx::Error> {
+    sqlx::query_as(
+        r#"UPDATE exports SET status = 'running', started_at = now()
+           WHERE id = (
+               SELECT id FROM exports
+               WHERE status = 'pending'
+                  OR (status = 'running' AND started_at < now() - make_interval(mins => $1))
+               ORDER BY created_at
+               LIMIT 1
+               FOR UPDATE SKIP LOCKED
+           )
+           RETURNING id, user_id, all_users, format, filters"#,
+    )
+    .bind(STALE_AFTER_MINUTES)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Write every matching task into the artifact; returns the file and the number of tasks
+async fn build(pool: &PgPool, export: &ClaimedExport) -> anyhow::Result<(Vec<u8>, i64)> {
+    let filters = &export.filters.0;
+    let pattern = filters.q.as_deref().filter(|q| !q.is_empty()).map(crate::api::contains_pattern);
+    let sql = format!(
+        "SELECT {} FROM tasks WHERE {} ORDER BY created_at, id LIMIT $8 OFFSET $9",
+        export::SOURCE_COLUMNS,
+        crate::api::task_filters_sql()
+    );
+
+    let limit = max_tasks();
+    let mut artifact = Artifact::new(BulkFormat::parse(&export.format));
+    let mut count = 0;
+    while count < limit {
+        let page: Vec<ExportSource> = sqlx::query_as(&sql)
+            .bind(&export.user_id)
+            .bind(export.all_users)
+            .bind("completed")
+            .bind(&filters.engine)
+            .bind(filters.from.map(|t| t.naive_utc()))
+            .bind(filters.to.map(|t| t.naive_utc()))
+            .bind(&pattern)
+            .bind(PAGE_SIZE.min(limit - count))
+            .bind(count)
+            .fetch_all(pool)
+            .await?;
+        for task in &page {
+            artifact.add(task)?;
+        }
+        count += page.len() as i64;
+        if (page.len() as i64) < PAGE_SIZE {
+            break;
+        }
+    }
+    Ok((artifact.finish()?, count))
+}
+
+/// Key prefix of every export file a user requested
+pub fn user_prefix(user_id: &str) -> String {
+    format!("exports/{}/", user_id)
+}
+
+async fn run_export(state: &AppState, export: ClaimedExport) -> anyhow::Result<()> {
+    let format = BulkFormat::parse(&export.format);
+    let (body, task_count) = build(&state.pool, &export).await?;
+    let size = body.len() as i64;
+    let key = format!("{}{}.{}", user_prefix(&export.user_id), export.id, format.extension());
+    state.storage.put_object(&key, body, format.content_type()).await?;
+
+    sqlx::query(
+        r#"UPDATE exports SET status = 'completed', task_count = $2, size_bytes = $3, storage_key = $4,
+               error = NULL, completed_at = now()
+           WHERE id = $1"#,
+    )
+    .bind(&export.id)
+    .bind(task_count as i32)
+    .bind(size)
+    .bind(&key)
+    .execute(&state.pool)
+    .await?;
+
+    let base = std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let message = format!(
+        "Your export of {} task{} is ready: {}/exports/{}/download",
+        task_count,
+        if task_count == 1 { "" } else { "s" },
+        base.trim_end_matches('/'),
+        export.id
+    );
+    let _ = notify(&state.pool, &export.user_id, NotificationEvent::ExportReady, "Export ready", &message).await;
+    info!("📦 [Exports] Export {} ready: {} tasks, {} bytes", export.id, task_count, size);
+    Ok(())
+}
+
+/// Build queued exports one at a time, for as long as the worker runs
+pub async fn start_export_runner(state: Arc<AppState>) {
+    loop {
+        let export = match claim_export(&state.pool).await {
+            Ok(Some(export)) => export,
+            Ok(None) => {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+            Err(e) => {
+                warn!("⚠️ [Exports] Failed to claim an export: {}", e);
+                tokio::time::sleep(Duration::from_secs(15)).await;
+                continue;
+            }
+        };
+
+        let export_id = export.id.clone();
+        let user_id = export.user_id.clone();
+        info!("📦 [Exports] Building export {} for {}", export_id, user_id);
+        if let Err(e) = run_export(&state, export).await {
+            error!("❌ [Exports] Export {} failed: {:#}", export_id, e);
+            let _ = sqlx::query("UPDATE exports SET status = 'failed', error = $2, completed_at = now() WHERE id = $1")
+                .bind(&export_id)
+                .bind(format!("{:#}", e))
+                .execute(&state.pool)
+                .await;
+        }
+    }
+}
+
+// ============================================================================
+// API
+// ============================================================================
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Exports] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+/// Queue a bulk export of your (and your teammates') completed tasks
+#[utoipa::path(
+    post,
+    path = "/exports",
+    tag = "crawler",
+    request_body = CreateExportRequest,
+    responses(
+        (status = 202, description = "Export queued; poll it or wait for the export_ready notification", body = BulkExport),
+        (status = 400, description = "Invalid date range")
+    )
+)]
+pub async fn create_export(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<CreateExportRequest>,
+) -> Result<(StatusCode, Json<BulkExport>), ApiError> {
+    if let (Some(from), Some(to)) = (req.filters.from, req.filters.to) {
+        if from >= to {
+            return Err((StatusCode::BAD_REQUEST, "from must be before to".to_string()));
+        }
+    }
+    let format = req.format.unwrap_or_default();
+    let export: BulkExport = sqlx::query_as(&format!(
+        "INSERT INTO exports (id, user_id, all_users, format, filters) VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+        EXPORT_COLUMNS
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(&user.id)
+    .bind(user.is_admin())
+    .bind(format.as_str())
+    .bind(sqlx::types::Json(&req.filters))
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    info!("📦 [Exports] {} queued export {} ({})", user.id, export.id, format.as_str());
+    Ok((StatusCode::ACCEPTED, Json(export)))
+}
+
+/// Your bulk exports, newest first
+#[utoipa::path(
+    get,
+    path = "/exports",
+    tag = "crawler",
+    responses(
+        (status = 200, description = "Your latest exports", body = Vec<BulkExport>)
+    )
+)]
+pub async fn list_exports(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<Vec<BulkExport>>, ApiError> {
+    let exports = sqlx::query_as(&format!(
+        "SELECT {} FROM exports WHERE user_id = $1 ORDER BY created_at DESC LIMIT 50",
+        EXPORT_COLUMNS
+    ))
+    .bind(&user.id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(exports))
+}
+
+/// Status of one of your bulk exports
+#[utoipa::path(
+    get,
+    path = "/exports/{id}",
+    tag = "crawler",
+    params(
+        ("id" = String, Path, description = "Export ID")
+    ),
+    responses(
+        (status = 200, description = "Export", body = BulkExport),
+        (status = 404, description = "Export not found")
+    )
+)]
+pub async fn get_export(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<BulkExport>, ApiError> {
+    let export: Option<BulkExport> =
+        sqlx::query_as(&format!("SELECT {} FROM exports WHERE id = $1 AND user_id = $2", EXPORT_COLUMNS))
+            .bind(&id)
+            .bind(&user.id)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_error)?;
+    export.map(Json).ok_or((StatusCode::NOT_FOUND, "Export not found".to_string()))
+}
+
+/// Download a completed bulk export
+#[utoipa::path(
+    get,
+    path = "/exports/{id}/download",
+    tag = "crawler",
+    params(
+        ("id" = String, Path, description = "Export ID")
+    ),
+    responses(
+        (status = 200, description = "The export file (application/x-ndjson or application/zip)"),
+        (status = 404, description = "Export not found"),
+        (status = 409, description = "Export isn't ready")
+    )
+)]
+pub async fn download_export(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Response, ApiError> {
+    let export: Option<(String, String, Option<String>)> =
+        sqlx::query_as("SELECT status, format, storage_key FROM exports WHERE id = $1 AND user_id = $2")
+            .bind(&id)
+            .bind(&user.id)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_error)?;
+    let (status, format, key) = export.ok_or((StatusCode::NOT_FOUND, "Export not found".to_string()))?;
+    let key = match key {
+        Some(key) if status == "completed" => key,
+        _ => return Err((StatusCode::CONFLICT, format!("Export is {}", status))),
+    };
+
+    let object = state
+        .storage
+        .get_object(&key)
+        .await
+        .map_err(|e| {
+            error!("❌ [Exports] Failed to read {}: {}", key, e);
+            (StatusCode::BAD_GATEWAY, "Failed to read the export file".to_string())
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Export file no longer exists".to_string()))?;
+
+    let format = BulkFormat::parse(&format);
+    let disposition = format!("attachment; filename=\"export-{}.{}\"", id, format.extension());
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_LENGTH, object.body.len().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        object.body,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str) -> ExportSource {
+        ExportSource {
+            id: id.to_string(),
+            keyword: "rust jobs".to_string(),
+            engine: "bing".to_string(),
+            status: "completed".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ndjson_artifact() {
+        let mut artifact = Artifact::new(BulkFormat::Ndjson);
+        artifact.add(&task("t1")).unwrap();
+        artifact.add(&task("t2")).unwrap();
+        let text = String::from_utf8(artifact.finish().unwrap()).unwrap();
+        let lines: Vec<serde_json::Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["task_id"], "t2");
+    }
+
+    #[test]
+    fn test_csv_zip_artifact() {
+        let mut artifact = Artifact::new(BulkFormat::CsvZip);
+        // Same keyword and short ID: the second entry falls back to the full ID
+        artifact.add(&task("abcdefgh-1")).unwrap();
+        artifact.add(&task("abcdefgh-2")).unwrap();
+        let zip = zip::ZipArchive::new(std::io::Cursor::new(artifact.finish().unwrap())).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort();
+        assert_eq!(names, ["abcdefgh-2.csv", "rust-jobs-abcdefgh.csv"]);
+
+        let request: CreateExportRequest = serde_json::from_str(r#"{"format": "csv_zip", "engine": "google"}"#).unwrap();
+        assert_eq!(request.format, Some(BulkFormat::CsvZip));
+        assert_eq!(request.filters.engine.as_deref(), Some("google"));
+    }
+}
@@ -0,0 +1,389 @@
+//! GraphQL API.
+//!
+//! `POST /graphql` serves read-only queries over tasks (with their SERP results
+//! and deep-extracted page data), schedules and, for admins, proxies, so dashboards
+//! can fetch exactly the nested fields they need in one round trip. Task visibility
+//! matches `GET /tasks`. `GET /graphql` serves GraphiQL for exploring the schema.
+//!
+//! Heavy task columns are only read when the query asks for `serp` or `deepData`.
+
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{ComplexObject, Context, EmptyMutation, EmptySubscription, Error, Object, Schema, SimpleObject};
+use axum::{extract::State, response::Html, Json};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use once_cell::sync::Lazy;
+use sqlx::FromRow;
+use std::sync::Arc;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::crawler::{MarketingData, SerpData};
+use tracing::error;
+
+/// Most tasks a single `tasks` query returns
+const MAX_TASKS: i32 = 100;
+
+pub type CrawlerSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+static SCHEMA: Lazy<CrawlerSchema> = Lazy::new(|| {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(8)
+        .limit_complexity(2000)
+        .finish()
+});
+
+/// The schema in SDL, e.g. for client code generation
+pub fn sdl() -> String {
+    SCHEMA.sdl()
+}
+
+fn db_error(e: sqlx::Error) -> Error {
+    error!("❌ [GraphQL] Database error: {}", e);
+    Error::new("Database error")
+}
+
+/// A crawl task
+#[derive(Debug, SimpleObject, FromRow)]
+#[graphql(complex)]
+pub struct Task {
+    pub id: String,
+    pub keyword: String,
+    /// google, bing or generic
+    pub engine: String,
+    /// pending, running, retrying, completed, partial or failed
+    pub status: String,
+    pub stage: Option<String>,
+    pub progress: Option<i32>,
+    /// Who submitted the task
+    pub user_id: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub completed_at: Option<NaiveDateTime>,
+    pub attempts: Option<i32>,
+    pub last_error: Option<String>,
+    #[graphql(skip)]
+    results_json: Option<String>,
+    #[graphql(skip)]
+    #[sqlx(flatten)]
+    deep: DeepColumns,
+}
+
+/// Columns behind `Task.deepData`
+#[derive(Debug, Default, FromRow)]
+struct DeepColumns {
+    meta_description: Option<String>,
+    meta_author: Option<String>,
+    meta_date: Option<String>,
+    sentiment: Option<String>,
+    category: Option<String>,
+    emails: Option<serde_json::Value>,
+    phone_numbers: Option<serde_json::Value>,
+    outbound_links: Option<serde_json::Value>,
+    entities: Option<serde_json::Value>,
+    marketing_data: Option<serde_json::Value>,
+}
+
+const TASK_COLUMNS: &str = "id, keyword, engine, status, stage, progress, user_id, created_at, completed_at, attempts, last_error";
+
+/// Typed placeholders for the deep columns a query doesn't ask for
+const DEEP_COLUMNS: &[(&str, &str)] = &[
+    ("meta_description", "text"),
+    ("meta_author", "text"),
+    ("meta_date", "text"),
+    ("sentiment", "text"),
+    ("category", "text"),
+    ("emails", "jsonb"),
+    ("phone_numbers", "jsonb"),
+    ("outbound_links", "jsonb"),
+    ("entities", "jsonb"),
+    ("marketing_data", "jsonb"),
+];
+
+/// Columns to select for a `Task` field, reading heavy ones only when the query uses them
+fn task_select(ctx: &Context<'_>) -> String {
+    let lookahead = ctx.look_ahead();
+    let mut columns = vec![TASK_COLUMNS.to_string()];
+    columns.push(if lookahead.field("serp").exists() { "results_json".to_string() } else { "NULL::text AS results_json".to_string() });
+    let deep = lookahead.field("deepData").exists();
+    for (column, sql_type) in DEEP_COLUMNS {
+        columns.push(if deep { column.to_string() } else { format!("NULL::{} AS {}", sql_type, column) });
+    }
+    columns.join(", ")
+}
+
+fn strings(value: &Option<serde_json::Value>) -> Vec<String> {
+    value.as_ref().and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or_default()
+}
+
+#[ComplexObject]
+impl Task {
+    /// Search results; null until the task completes
+    async fn serp(&self) -> Option<Serp> {
+        let data: SerpData = serde_json::from_str(self.results_json.as_deref()?).ok()?;
+        Some(Serp {
+            total_results: data.total_results,
+            results: data
+                .results
+                .into_iter()
+                .enumerate()
+                .map(|(i, r)| SerpResult { position: i as i32 + 1, title: r.title, link: r.link, snippet: r.snippet })
+                .collect(),
+            featured_snippet: data.featured_snippet.map(|s| FeaturedSnippet {
+                content: s.content,
+                source_url: s.source_url,
+                source_title: s.source_title,
+            }),
+            people_also_ask: data.people_also_ask,
+            related_searches: data.related_searches,
+        })
+    }
+
+    /// Data extracted from the first result's page; null until the task completes
+    async fn deep_data(&self) -> Option<DeepData> {
+        if self.status != "completed" {
+            return None;
+        }
+        let deep = &self.deep;
+        let marketing: MarketingData =
+            deep.marketing_data.as_ref().and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or_default();
+        Some(DeepData {
+            meta_description: deep.meta_description.clone(),
+            meta_author: deep.meta_author.clone(),
+            meta_date: deep.meta_date.clone(),
+            sentiment: deep.sentiment.clone(),
+            category: deep.category.clone(),
+            emails: strings(&deep.emails),
+            phone_numbers: strings(&deep.phone_numbers),
+            outbound_links: strings(&deep.outbound_links),
+            entities: deep
+                .entities
+                .as_ref()
+                .and_then(|v| serde_json::from_value::<Vec<crate::ml::Entity>>(v.clone()).ok())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| Entity { text: e.text, label: e.label })
+                .collect(),
+            headlines: marketing.headlines,
+            key_benefits: marketing.key_benefits,
+            ctas: marketing.ctas,
+        })
+    }
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct Serp {
+    pub total_results: Option<String>,
+    pub results: Vec<SerpResult>,
+    pub featured_snippet: Option<FeaturedSnippet>,
+    pub people_also_ask: Vec<String>,
+    pub related_searches: Vec<String>,
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct SerpResult {
+    /// 1-based rank
+    pub position: i32,
+    pub title: String,
+    pub link: String,
+    pub snippet: String,
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct FeaturedSnippet {
+    pub content: String,
+    pub source_url: Option<String>,
+    pub source_title: Option<String>,
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct DeepData {
+    pub meta_description: Option<String>,
+    pub meta_author: Option<String>,
+    pub meta_date: Option<String>,
+    pub sentiment: Option<String>,
+    pub category: Option<String>,
+    pub emails: Vec<String>,
+    pub phone_numbers: Vec<String>,
+    pub outbound_links: Vec<String>,
+    pub entities: Vec<Entity>,
+    pub headlines: Vec<String>,
+    pub key_benefits: Vec<String>,
+    pub ctas: Vec<String>,
+}
+
+/// A named entity found in the page text
+#[derive(Debug, SimpleObject)]
+pub struct Entity {
+    pub text: String,
+    /// e.g. ORG, PERSON, GPE
+    pub label: String,
+}
+
+/// A recurring crawl
+#[derive(Debug, SimpleObject, FromRow)]
+pub struct Schedule {
+    pub id: String,
+    pub owner: String,
+    pub cron: String,
+    pub timezone: String,
+    pub keyword: String,
+    pub engine: String,
+    pub enabled: bool,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+}
+
+/// A proxy in the pool (admins only)
+#[derive(Debug, SimpleObject)]
+pub struct Proxy {
+    pub id: String,
+    pub host: String,
+    pub port: i32,
+    /// http, https or socks5
+    pub protocol: String,
+    pub healthy: bool,
+    pub fail_count: i32,
+    pub success_rate: f64,
+    pub latency_ms: Option<i64>,
+    pub country: Option<String>,
+    /// Provider that manages the proxy
+    pub source: Option<String>,
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Tasks you (or your organization) submitted, newest first
+    async fn tasks(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<String>,
+        engine: Option<String>,
+        #[graphql(desc = "Keyword contains this text (case-insensitive)")] keyword: Option<String>,
+        #[graphql(default = 20)] limit: i32,
+        #[graphql(default = 0)] offset: i32,
+    ) -> async_graphql::Result<Vec<Task>> {
+        let state = ctx.data::<Arc<AppState>>()?;
+        let user = ctx.data::<AuthUser>()?;
+        let sql = format!(
+            "SELECT {} FROM tasks WHERE {} ORDER BY created_at DESC NULLS LAST, id LIMIT $8 OFFSET $9",
+            task_select(ctx),
+            crate::api::task_filters_sql()
+        );
+        sqlx::query_as(&sql)
+            .bind(&user.id)
+            .bind(user.is_admin())
+            .bind(status)
+            .bind(engine)
+            .bind(None::<NaiveDateTime>)
+            .bind(None::<NaiveDateTime>)
+            .bind(keyword.filter(|k| !k.is_empty()).map(|k| crate::api::contains_pattern(&k)))
+            .bind(limit.clamp(1, MAX_TASKS) as i64)
+            .bind(offset.max(0) as i64)
+            .fetch_all(&state.pool)
+            .await
+            .map_err(db_error)
+    }
+
+    /// One task by ID; null if it doesn't exist or isn't visible to you
+    async fn task(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<Option<Task>> {
+        let state = ctx.data::<Arc<AppState>>()?;
+        let user = ctx.data::<AuthUser>()?;
+        let sql = format!(
+            "SELECT {} FROM tasks WHERE id = $3 AND deleted_at IS NULL AND (user_id = $1 OR $2 OR {})",
+            task_select(ctx),
+            crate::organizations::teammates_filter("$1")
+        );
+        sqlx::query_as(&sql)
+            .bind(&user.id)
+            .bind(user.is_admin())
+            .bind(id)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_error)
+    }
+
+    /// Your schedules (everyone's for admins)
+    async fn schedules(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Schedule>> {
+        let state = ctx.data::<Arc<AppState>>()?;
+        let user = ctx.data::<AuthUser>()?;
+        sqlx::query_as(
+            r#"SELECT id, owner, cron, timezone, keyword, engine, enabled, next_run_at, last_run_at
+               FROM schedules WHERE owner = $1 OR $2 ORDER BY created_at"#,
+        )
+        .bind(&user.id)
+        .bind(user.is_admin())
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)
+    }
+
+    /// The proxy pool; admins only
+    async fn proxies(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Proxy>> {
+        if !ctx.data::<AuthUser>()?.is_admin() {
+            return Err(Error::new("Admin access required"));
+        }
+        Ok(crate::proxy::PROXY_MANAGER
+            .list_proxies()
+            .into_iter()
+            .map(|p| Proxy {
+                id: p.id,
+                host: p.host,
+                port: p.port as i32,
+                protocol: p.protocol.as_str().to_string(),
+                healthy: p.healthy,
+                fail_count: p.fail_count as i32,
+                success_rate: p.success_rate,
+                latency_ms: p.latency_ms.map(|ms| ms as i64),
+                country: p.country,
+                source: p.source,
+            })
+            .collect())
+    }
+}
+
+/// Run a GraphQL query
+#[utoipa::path(
+    post,
+    path = "/graphql",
+    tag = "crawler",
+    request_body(content = Object, description = "GraphQL request: query, variables, operationName"),
+    responses(
+        (status = 200, description = "GraphQL response with data and/or errors", body = Object)
+    )
+)]
+pub async fn graphql_handler(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(SCHEMA.execute(request.data(state).data(user)).await)
+}
+
+/// GraphiQL, an in-browser query editor for `POST /graphql`
+pub async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema() {
+        let sdl = sdl();
+        assert!(sdl.contains("serp: Serp"));
+        assert!(sdl.contains("deepData: DeepData"));
+        assert!(sdl.contains("limit: Int! = 20"));
+        assert!(sdl.contains("proxies: [Proxy!]!"));
+    }
+
+    #[tokio::test]
+    async fn test_queries_need_a_user() {
+        // Without a caller in the context, resolvers refuse instead of leaking tasks
+        let response = SCHEMA.execute("{ proxies { id } }").await;
+        assert_eq!(response.errors.len(), 1);
+
+        let response = SCHEMA.execute("{ tasks { id serp { results { title } } } }").await;
+        assert!(!response.errors.is_empty());
+    }
+}
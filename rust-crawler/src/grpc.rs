@@ -0,0 +1,320 @@
+//! gRPC service for internal callers (`--features grpc`).
+//!
+//! Implements `proto/crawler.proto` on `GRPC_PORT` (default 50051): `SubmitCrawl`
+//! goes through the same plan, quota and credit checks as `POST /crawl`, `GetTask`
+//! mirrors `GET /crawl/:task_id`, and `StreamTaskEvents` streams the caller's job
+//! events like `/ws`. Callers authenticate with the same `authorization` (JWT) or
+//! `x-api-key` metadata as the REST API, and API key scopes apply per method.
+
+use axum::extract::FromRequestParts;
+use axum::http::{Method, StatusCode};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+use crate::api::{AppState, CrawlRequest};
+use crate::auth::AuthUser;
+use crate::crawler::SerpData;
+use proto::*;
+use tracing::{error, info};
+
+/// Messages of `proto/crawler.proto`, kept in sync by hand (tags must match)
+pub mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SubmitCrawlRequest {
+        #[prost(string, tag = "1")]
+        pub keyword: String,
+        #[prost(string, tag = "2")]
+        pub engine: String,
+        #[prost(string, tag = "3")]
+        pub idempotency_key: String,
+        #[prost(string, tag = "4")]
+        pub priority: String,
+        #[prost(uint32, optional, tag = "5")]
+        pub max_retries: Option<u32>,
+        #[prost(string, tag = "6")]
+        pub options_json: String,
+        #[prost(string, tag = "7")]
+        pub callback_url: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SubmitCrawlResponse {
+        #[prost(string, tag = "1")]
+        pub task_id: String,
+        #[prost(string, tag = "2")]
+        pub message: String,
+        #[prost(bool, tag = "3")]
+        pub duplicate: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetTaskRequest {
+        #[prost(string, tag = "1")]
+        pub task_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Task {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(string, tag = "2")]
+        pub keyword: String,
+        #[prost(string, tag = "3")]
+        pub engine: String,
+        #[prost(string, tag = "4")]
+        pub status: String,
+        #[prost(string, tag = "5")]
+        pub stage: String,
+        #[prost(int32, tag = "6")]
+        pub progress: i32,
+        #[prost(int32, tag = "7")]
+        pub attempts: i32,
+        #[prost(string, tag = "8")]
+        pub last_error: String,
+        #[prost(message, repeated, tag = "9")]
+        pub results: Vec<SearchResult>,
+        #[prost(string, tag = "10")]
+        pub results_json: String,
+        #[prost(string, tag = "11")]
+        pub meta_description: String,
+        #[prost(string, tag = "12")]
+        pub category: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SearchResult {
+        #[prost(uint32, tag = "1")]
+        pub position: u32,
+        #[prost(string, tag = "2")]
+        pub title: String,
+        #[prost(string, tag = "3")]
+        pub link: String,
+        #[prost(string, tag = "4")]
+        pub snippet: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StreamTaskEventsRequest {
+        #[prost(string, tag = "1")]
+        pub task_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TaskEvent {
+        #[prost(string, tag = "1")]
+        pub event: String,
+        #[prost(string, tag = "2")]
+        pub task_id: String,
+        #[prost(string, tag = "3")]
+        pub keyword: String,
+        #[prost(string, tag = "4")]
+        pub engine: String,
+        #[prost(string, tag = "5")]
+        pub message: String,
+        #[prost(int64, tag = "6")]
+        pub timestamp: i64,
+    }
+}
+
+// Server stubs generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/crawler.v1.Crawler.rs"));
+
+use crawler_server::{Crawler, CrawlerServer};
+
+pub struct CrawlerService {
+    state: Arc<AppState>,
+}
+
+/// Authenticate like the REST extractor; `route` is the REST equivalent, so API key scopes carry over
+async fn authenticate<T>(state: &Arc<AppState>, request: &Request<T>, method: Method, route: &str) -> Result<AuthUser, Status> {
+    let mut http = axum::http::Request::builder()
+        .method(method)
+        .uri(route)
+        .body(())
+        .map_err(|e| Status::internal(e.to_string()))?;
+    *http.headers_mut() = request.metadata().clone().into_headers();
+    let (mut parts, ()) = http.into_parts();
+    AuthUser::from_request_parts(&mut parts, state).await.map_err(|(code, body)| match code {
+        StatusCode::UNAUTHORIZED => Status::unauthenticated(body.0.message),
+        StatusCode::FORBIDDEN => Status::permission_denied(body.0.message),
+        StatusCode::SERVICE_UNAVAILABLE => Status::unavailable(body.0.message),
+        _ => Status::internal(body.0.message),
+    })
+}
+
+/// gRPC status of a REST error response
+async fn status_from(response: axum::response::Response) -> Status {
+    let code = response.status();
+    let body = axum::body::to_bytes(response.into_body(), 64 * 1024).await.unwrap_or_default();
+    let message = String::from_utf8_lossy(&body).into_owned();
+    match code {
+        StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Status::invalid_argument(message),
+        StatusCode::PAYMENT_REQUIRED | StatusCode::TOO_MANY_REQUESTS => Status::resource_exhausted(message),
+        StatusCode::FORBIDDEN => Status::permission_denied(message),
+        StatusCode::NOT_FOUND => Status::not_found(message),
+        _ => Status::internal(message),
+    }
+}
+
+/// The `POST /crawl` body a `SubmitCrawlRequest` stands for
+fn crawl_request(req: &SubmitCrawlRequest) -> Result<CrawlRequest, String> {
+    let mut body = match req.options_json.trim() {
+        "" => serde_json::json!({}),
+        json => serde_json::from_str(json).map_err(|e| format!("options_json: {}", e))?,
+    };
+    let Some(fields) = body.as_object_mut() else {
+        return Err("options_json must be a JSON object".to_string());
+    };
+    fields.insert("keyword".to_string(), req.keyword.clone().into());
+    let optional = [("engine", &req.engine), ("priority", &req.priority), ("callback_url", &req.callback_url)];
+    for (field, value) in optional {
+        if !value.is_empty() {
+            fields.insert(field.to_string(), value.clone().into());
+        }
+    }
+    if let Some(max_retries) = req.max_retries {
+        fields.insert("max_retries".to_string(), max_retries.into());
+    }
+    serde_json::from_value(body).map_err(|e| e.to_string())
+}
+
+#[tonic::async_trait]
+impl Crawler for CrawlerService {
+    async fn submit_crawl(&self, request: Request<SubmitCrawlRequest>) -> Result<Response<SubmitCrawlResponse>, Status> {
+        let user = authenticate(&self.state, &request, Method::POST, "/crawl").await?;
+        let req = request.into_inner();
+        if req.keyword.trim().is_empty() {
+            return Err(Status::invalid_argument("keyword is required"));
+        }
+        let payload = crawl_request(&req).map_err(Status::invalid_argument)?;
+        let idempotency_key = Some(req.idempotency_key.trim()).filter(|k| !k.is_empty());
+        match crate::api::submit_crawl(&self.state, &user, payload, idempotency_key).await {
+            Ok((_, response)) => Ok(Response::new(SubmitCrawlResponse {
+                task_id: response.task_id,
+                message: response.message,
+                duplicate: response.duplicate,
+            })),
+            Err(response) => Err(status_from(response).await),
+        }
+    }
+
+    async fn get_task(&self, request: Request<GetTaskRequest>) -> Result<Response<Task>, Status> {
+        let user = authenticate(&self.state, &request, Method::GET, "/crawl/:task_id").await?;
+        let task_id = request.into_inner().task_id;
+        let db_error = |e: sqlx::Error| {
+            error!("❌ [gRPC] Database error: {}", e);
+            Status::internal("Database error")
+        };
+        let visible: Option<bool> = sqlx::query_scalar(&format!(
+            "SELECT user_id = $2 OR $3 OR {} FROM tasks WHERE id = $1 AND deleted_at IS NULL",
+            crate::organizations::teammates_filter("$2")
+        ))
+        .bind(&task_id)
+        .bind(&user.id)
+        .bind(user.is_admin())
+        .fetch_optional(&self.state.pool)
+        .await
+        .map_err(db_error)?;
+        if visible != Some(true) {
+            return Err(Status::not_found("Task not found"));
+        }
+        let task = crate::api::load_task(&self.state.pool, &task_id)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| Status::not_found("Task not found"))?;
+
+        let serp: SerpData = task.results_json.as_deref().and_then(|j| serde_json::from_str(j).ok()).unwrap_or_default();
+        Ok(Response::new(Task {
+            id: task.id,
+            keyword: task.keyword,
+            engine: task.engine,
+            status: task.status,
+            stage: task.stage.unwrap_or_default(),
+            progress: task.progress.unwrap_or_default(),
+            attempts: task.attempts.unwrap_or_default(),
+            last_error: task.last_error.unwrap_or_default(),
+            results: serp
+                .results
+                .into_iter()
+                .enumerate()
+                .map(|(i, r)| SearchResult { position: i as u32 + 1, title: r.title, link: r.link, snippet: r.snippet })
+                .collect(),
+            results_json: task.results_json.unwrap_or_default(),
+            meta_description: task.meta_description.unwrap_or_default(),
+            category: task.category.unwrap_or_default(),
+        }))
+    }
+
+    type StreamTaskEventsStream = Pin<Box<dyn Stream<Item = Result<TaskEvent, Status>> + Send + 'static>>;
+
+    async fn stream_task_events(
+        &self,
+        request: Request<StreamTaskEventsRequest>,
+    ) -> Result<Response<Self::StreamTaskEventsStream>, Status> {
+        let user = authenticate(&self.state, &request, Method::GET, "/crawl/:task_id").await?;
+        let task_id = request.into_inner().task_id;
+        info!("📡 [gRPC] Event stream opened by {}", user.id);
+
+        // Lagging subscribers skip events, as on /ws
+        let events = BroadcastStream::new(crate::events::EVENTS.subscribe()).filter_map(move |event| {
+            let event = event.ok()?;
+            if event.user_id != user.id || (!task_id.is_empty() && event.task_id != task_id) {
+                return None;
+            }
+            let kind = serde_json::to_value(event.event).ok()?.as_str()?.to_string();
+            Some(Ok(TaskEvent {
+                event: kind,
+                task_id: event.task_id,
+                keyword: event.keyword,
+                engine: event.engine,
+                message: event.message.unwrap_or_default(),
+                timestamp: event.timestamp,
+            }))
+        });
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+/// Serve the gRPC API on `GRPC_PORT` until the process exits
+pub async fn serve(state: Arc<AppState>) -> anyhow::Result<()> {
+    let port: u16 = std::env::var("GRPC_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(50051);
+    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
+    info!("📡 gRPC server listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(CrawlerServer::new(CrawlerService { state }))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_submit_request_maps_to_crawl_body() {
+        let req = SubmitCrawlRequest {
+            keyword: "rust jobs".to_string(),
+            engine: "google".to_string(),
+            priority: "high".to_string(),
+            max_retries: Some(0),
+            options_json: r#"{"max_pages": 2}"#.to_string(),
+            ..Default::default()
+        };
+        // Survives the wire
+        let req = SubmitCrawlRequest::decode(req.encode_to_vec().as_slice()).unwrap();
+        let crawl = crawl_request(&req).unwrap();
+        assert_eq!(crawl.keyword, "rust jobs");
+        assert_eq!(crawl.engine.as_deref(), Some("google"));
+        assert_eq!(crawl.priority, Some(crate::queue::Priority::High));
+        assert_eq!(crawl.max_retries, Some(0));
+        assert_eq!(crawl.options.max_pages, Some(2));
+        assert!(crawl.callback_url.is_none());
+
+        let bad = SubmitCrawlRequest { options_json: "[1]".to_string(), ..req };
+        assert!(crawl_request(&bad).is_err());
+    }
+}
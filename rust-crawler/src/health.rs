@@ -0,0 +1,202 @@
+//! Health checks and Kubernetes probes.
+//!
+//! `GET /health` probes every dependency (Postgres, the job queue, MinIO and the
+//! Chrome binary) and reports each one's status and latency. `GET /live` only says
+//! the process is serving, and `GET /ready` checks what the API can't work without
+//! (Postgres and the queue), so a replica whose database is unreachable is taken
+//! out of the load balancer instead of restarted.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+use crate::api::AppState;
+
+/// A dependency that doesn't answer within this is reported down
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Up,
+    Down,
+}
+
+/// Overall health: `degraded` when only optional dependencies are down
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DependencyCheck {
+    #[schema(example = "postgres")]
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// Whether the API stops being ready without this dependency
+    pub critical: bool,
+    pub latency_ms: u64,
+    /// Backend in use or binary found, e.g. `redis` or `/usr/bin/google-chrome-stable`
+    pub detail: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: HealthStatus,
+    #[schema(example = "0.1.0")]
+    pub version: &'static str,
+    pub checks: Vec<DependencyCheck>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LivenessResponse {
+    #[schema(example = "ok")]
+    pub status: &'static str,
+}
+
+/// Run one probe with a timeout, timing it
+async fn probe<F>(name: &'static str, critical: bool, check: F) -> DependencyCheck
+where
+    F: Future<Output = anyhow::Result<Option<String>>>,
+{
+    let started = Instant::now();
+    let result = tokio::time::timeout(PROBE_TIMEOUT, check).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let (status, detail, error) = match result {
+        Ok(Ok(detail)) => (CheckStatus::Up, detail, None),
+        Ok(Err(e)) => (CheckStatus::Down, None, Some(e.to_string())),
+        Err(_) => (CheckStatus::Down, None, Some(format!("no answer within {}s", PROBE_TIMEOUT.as_secs()))),
+    };
+    DependencyCheck { name, status, critical, latency_ms, detail, error }
+}
+
+async fn check_postgres(state: &AppState) -> DependencyCheck {
+    probe("postgres", true, async {
+        sqlx::query("SELECT 1").execute(&state.pool).await?;
+        Ok(None)
+    })
+    .await
+}
+
+async fn check_queue(state: &AppState) -> DependencyCheck {
+    probe("queue", true, async {
+        // A round trip to the backend; reports whether workers are paused too
+        let paused = state.queue.is_paused().await?;
+        let name = state.queue.backend_name();
+        Ok(Some(if paused { format!("{} (paused)", name) } else { name.to_string() }))
+    })
+    .await
+}
+
+async fn check_storage(state: &AppState) -> DependencyCheck {
+    probe("storage", false, async {
+        state.storage.ping().await?;
+        Ok(Some(state.storage.backend_name().to_string()))
+    })
+    .await
+}
+
+/// Looks the binary up (`CHROME` or the usual install paths) without launching it
+async fn check_chrome() -> DependencyCheck {
+    probe("chrome", false, async {
+        let path = tokio::task::spawn_blocking(headless_chrome::browser::default_executable)
+            .await?
+            .map_err(anyhow::Error::msg)?;
+        Ok(Some(path.display().to_string()))
+    })
+    .await
+}
+
+/// `down` if a critical dependency is down, `degraded` if only optional ones are
+pub fn overall_status(checks: &[DependencyCheck]) -> HealthStatus {
+    let down = checks.iter().filter(|c| c.status == CheckStatus::Down);
+    let mut status = HealthStatus::Ok;
+    for check in down {
+        if check.critical {
+            return HealthStatus::Down;
+        }
+        status = HealthStatus::Degraded;
+    }
+    status
+}
+
+fn respond(checks: Vec<DependencyCheck>) -> (StatusCode, Json<HealthResponse>) {
+    let status = overall_status(&checks);
+    let code = if status == HealthStatus::Down { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+    (code, Json(HealthResponse { status, version: env!("CARGO_PKG_VERSION"), checks }))
+}
+
+/// Status and latency of every dependency
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "Healthy, or degraded (MinIO or Chrome unavailable)", body = HealthResponse),
+        (status = 503, description = "Postgres or the queue is unavailable", body = HealthResponse)
+    )
+)]
+pub async fn health(State(state): State<Arc<AppState>>) -> (StatusCode, Json<HealthResponse>) {
+    let (postgres, queue, storage, chrome) =
+        tokio::join!(check_postgres(&state), check_queue(&state), check_storage(&state), check_chrome());
+    respond(vec![postgres, queue, storage, chrome])
+}
+
+/// Liveness probe: the process is up and serving requests
+#[utoipa::path(
+    get,
+    path = "/live",
+    tag = "health",
+    responses(
+        (status = 200, description = "Alive", body = LivenessResponse)
+    )
+)]
+pub async fn live() -> Json<LivenessResponse> {
+    Json(LivenessResponse { status: "ok" })
+}
+
+/// Readiness probe: Postgres and the queue are reachable
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready to take traffic", body = HealthResponse),
+        (status = 503, description = "Postgres or the queue is unavailable", body = HealthResponse)
+    )
+)]
+pub async fn ready(State(state): State<Arc<AppState>>) -> (StatusCode, Json<HealthResponse>) {
+    let (postgres, queue) = tokio::join!(check_postgres(&state), check_queue(&state));
+    respond(vec![postgres, queue])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(name: &'static str, critical: bool, status: CheckStatus) -> DependencyCheck {
+        DependencyCheck { name, status, critical, latency_ms: 1, detail: None, error: None }
+    }
+
+    #[tokio::test]
+    async fn test_overall_status_by_criticality() {
+        let down = probe("chrome", false, async { anyhow::bail!("not found") }).await;
+        assert_eq!((down.status, down.error.as_deref()), (CheckStatus::Down, Some("not found")));
+
+        let up = || check("postgres", true, CheckStatus::Up);
+        assert_eq!(overall_status(&[up(), check("storage", false, CheckStatus::Up)]), HealthStatus::Ok);
+        assert_eq!(overall_status(&[up(), check("chrome", false, CheckStatus::Down)]), HealthStatus::Degraded);
+        assert_eq!(
+            overall_status(&[check("chrome", false, CheckStatus::Down), check("queue", true, CheckStatus::Down)]),
+            HealthStatus::Down
+        );
+    }
+}
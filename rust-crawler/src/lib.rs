@@ -1,14 +1,63 @@
+pub mod admin;
+pub mod alerts;
+pub mod analytics;
 pub mod api;
+pub mod api_keys;
+pub mod artifacts;
 pub mod auth;
+pub mod config;
 pub mod crawler;
+pub mod credits;
 pub mod db;
+pub mod email;
+pub mod event_bus;
+pub mod events;
+pub mod export;
+pub mod exports;
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod health;
+pub mod login_secrets;
+pub mod metrics;
 pub mod ml;
+pub mod monitors;
 pub mod notifications;
+pub mod organizations;
+pub mod payment_providers;
 pub mod payments;
 pub mod profiles;
 pub mod proxy;
+pub mod proxy_forwarder;
+pub mod proxy_providers;
 pub mod queue;
+pub mod queue_memory;
+pub mod queue_postgres;
+pub mod queue_redis;
+pub mod quotas;
+pub mod rankings;
+pub mod rate_limit;
+pub mod recipes;
+pub mod retention;
+pub mod revocation;
 pub mod scheduler;
+pub mod search_index;
+pub mod schedules;
+pub mod serp_diff;
 pub mod stealth;
 pub mod storage;
+pub mod storage_fs;
+pub mod storage_memory;
+pub mod storage_s3;
+pub mod subscriptions;
+pub mod task_logs;
+pub mod telemetry;
+pub mod usage;
+pub mod validation;
+pub mod webhooks;
 pub mod worker;
+
+/// `APP_MODE=dev`: run without Redis and MinIO, using in-process queue and storage
+pub fn dev_mode() -> bool {
+    std::env::var("APP_MODE").map(|m| m.eq_ignore_ascii_case("dev")).unwrap_or(false)
+}
@@ -0,0 +1,141 @@
+//! Passwords of scripted logins, kept out of queued jobs.
+//!
+//! Queued jobs are JSON in Redis (or the Postgres queue) and travel through
+//! retries, dead-letter lists and logs, so a job's `login.password` is moved into
+//! the `login_secrets` table when it is queued and the job carries only the row's
+//! ID as `login.password_ref`. The worker puts the password back into its own
+//! copy of the options right before crawling, and the row is deleted once the
+//! task finishes or fails for good. A reference only resolves for the user it
+//! was stored for.
+//!
+//! Schedules and monitors keep their password the same way, under
+//! `schedule:<id>` / `monitor:<id>` ([`stored_key`]), so their stored options and
+//! API responses never contain it. Each run copies it to a row of its own task.
+
+use anyhow::Context;
+use sqlx::PgPool;
+use crate::crawler::CrawlOptions;
+
+/// Key of the password stored for a schedule or monitor (`kind` is `schedule` or `monitor`)
+pub fn stored_key(kind: &str, id: &str) -> String {
+    format!("{}:{}", kind, id)
+}
+
+/// Take the login password out of `options`, leaving a reference to `key`.
+/// Client-sent references are dropped; only the server sets them.
+pub fn take_password(key: &str, options: &mut CrawlOptions) -> Option<String> {
+    let login = options.login.as_mut()?;
+    login.password_ref = None;
+    if login.password.is_empty() {
+        return None;
+    }
+    login.password_ref = Some(key.to_string());
+    Some(std::mem::take(&mut login.password))
+}
+
+async fn store(pool: &PgPool, key: &str, user_id: &str, password: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO login_secrets (id, user_id, password) VALUES ($1, $2, $3)
+           ON CONFLICT (id) DO UPDATE SET user_id = EXCLUDED.user_id, password = EXCLUDED.password"#,
+    )
+    .bind(key)
+    .bind(user_id)
+    .bind(password)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Move the login password of task `task_id` into `login_secrets`, leaving a reference
+pub async fn seal(pool: &PgPool, task_id: &str, user_id: &str, options: &mut CrawlOptions) -> Result<(), sqlx::Error> {
+    let Some(password) = take_password(task_id, options) else { return Ok(()) };
+    if let Err(e) = store(pool, task_id, user_id, &password).await {
+        // Leave the options as they were so the caller doesn't queue a dangling reference
+        if let Some(login) = options.login.as_mut() {
+            login.password = password;
+            login.password_ref = None;
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Seal the login password of a schedule or monitor being saved under `key`.
+/// Options sent without a password keep the one stored before; options without
+/// a login drop it.
+pub async fn seal_stored(pool: &PgPool, key: &str, owner: &str, options: &mut CrawlOptions) -> Result<(), sqlx::Error> {
+    if options.login.is_none() {
+        return discard(pool, key).await;
+    }
+    if let Some(password) = take_password(key, options) {
+        return store(pool, key, owner, &password).await;
+    }
+    // Follow an owner change, and keep the reference only if there is something to refer to
+    let kept = sqlx::query("UPDATE login_secrets SET user_id = $2 WHERE id = $1")
+        .bind(key)
+        .bind(owner)
+        .execute(pool)
+        .await?
+        .rows_affected()
+        > 0;
+    if kept {
+        if let Some(login) = options.login.as_mut() {
+            login.password_ref = Some(key.to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Seal the password of one run of a stored schedule or monitor under its task's ID
+pub async fn seal_run(pool: &PgPool, task_id: &str, owner: &str, options: &mut CrawlOptions) -> anyhow::Result<()> {
+    unseal(pool, owner, options).await?;
+    seal(pool, task_id, owner, options).await?;
+    Ok(())
+}
+
+/// Put a sealed login password back into `options`
+pub async fn unseal(pool: &PgPool, user_id: &str, options: &mut CrawlOptions) -> anyhow::Result<()> {
+    let Some(login) = options.login.as_mut() else { return Ok(()) };
+    let Some(reference) = login.password_ref.take() else { return Ok(()) };
+    let password: Option<String> = sqlx::query_scalar("SELECT password FROM login_secrets WHERE id = $1 AND user_id = $2")
+        .bind(&reference)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .context("Failed to load the login password")?;
+    login.password = password.ok_or_else(|| anyhow::anyhow!("Login password {} is gone", reference))?;
+    Ok(())
+}
+
+/// Delete the login password stored under `key` (a task ID or [`stored_key`]), if any
+pub async fn discard(pool: &PgPool, key: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM login_secrets WHERE id = $1").bind(key).execute(pool).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crawler::LoginFlow;
+
+    #[test]
+    fn test_password_stays_out_of_logs_and_sealed_jobs() {
+        let login: LoginFlow = serde_json::from_value(serde_json::json!({
+            "username": "alice", "password": "hunter2",
+            "username_selector": "#u", "password_selector": "#p", "submit_selector": "button"
+        }))
+        .unwrap();
+        assert!(!format!("{:?}", login).contains("hunter2"));
+
+        // What `seal` leaves in the queued job
+        let mut options = crate::crawler::CrawlOptions { login: Some(login), ..Default::default() };
+        assert_eq!(super::take_password("task-1", &mut options).as_deref(), Some("hunter2"));
+        let queued = serde_json::to_value(&options.login).unwrap();
+        assert!(queued.get("password").is_none());
+        assert_eq!(queued["password_ref"], "task-1");
+
+        // A reference sent by a client is never trusted
+        options.login.as_mut().unwrap().password_ref = Some("someone-elses-task".to_string());
+        assert_eq!(super::take_password("task-2", &mut options), None);
+        assert_eq!(options.login.unwrap().password_ref, None);
+    }
+}
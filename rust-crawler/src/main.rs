@@ -1,51 +1,266 @@
 
-use rust_crawler::{api, auth, crawler, db, ml, notifications, payments, profiles, proxy, queue, scheduler, stealth, storage, worker};
+use rust_crawler::{admin, alerts, analytics, api, api_keys, artifacts, auth, config, crawler, credits, db, event_bus, events, export, exports, graphql, health, metrics, monitors, notifications, organizations, payments, profiles, proxy, proxy_providers, queue, rankings, rate_limit, recipes, revocation, scheduler, schedules, search_index, serp_diff, storage, subscriptions, task_logs, telemetry, usage, validation, webhooks, worker};
 use axum::{
-    routing::{get, post, delete},
+    routing::{get, post},
     Router,
 };
-use sqlx::postgres::{PgPoolOptions, PgConnectOptions};
+use sqlx::postgres::PgPoolOptions;
 use sqlx::ConnectOptions;
-use std::net::SocketAddr;
 use std::sync::Arc;
 use dotenv::dotenv;
 use std::env;
-use tokio::time::Duration;
-use tower_http::cors::{CorsLayer, Any};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
+use tracing::{error, info, warn};
 
 use tower_http::services::ServeDir;
+use tower_http::trace::TraceLayer;
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         api::trigger_crawl,
+        api::batch_crawl,
         api::get_crawl_status,
         api::list_tasks,
+        api::search_tasks,
+        api::delete_task,
+        api::restore_task,
+        analytics::summary,
+        api::get_task_html,
+        task_logs::get_task_logs,
+        artifacts::list_artifacts,
+        artifacts::get_artifact,
+        export::export_task,
+        exports::create_export,
+        exports::list_exports,
+        exports::get_export,
+        exports::download_export,
+        graphql::graphql_handler,
+        serp_diff::keyword_diff,
+        health::health,
+        health::live,
+        health::ready,
+        api::queue_stats,
+        api::pause_workers,
+        api::resume_workers,
+        admin::admin_status,
+        admin::schema_version,
         api::list_proxies,
         api::add_proxy,
         api::remove_proxy,
         api::enable_proxy,
-        api::proxy_stats
+        api::test_proxy,
+        api::proxy_stats,
+        api::set_proxy_strategy,
+        recipes::list_recipes,
+        recipes::get_recipe,
+        recipes::create_recipe,
+        recipes::update_recipe,
+        recipes::delete_recipe,
+        schedules::list_schedules,
+        schedules::get_schedule,
+        schedules::create_schedule,
+        schedules::update_schedule,
+        schedules::delete_schedule,
+        schedules::run_schedule_now,
+        rankings::list_tracked_domains,
+        rankings::track_domain,
+        rankings::untrack_domain,
+        rankings::ranking_history,
+        rankings::competitor_report,
+        monitors::list_monitors,
+        monitors::get_monitor,
+        monitors::create_monitor,
+        monitors::update_monitor,
+        monitors::delete_monitor,
+        alerts::list_alert_rules,
+        alerts::get_alert_rule,
+        alerts::create_alert_rule,
+        alerts::update_alert_rule,
+        alerts::delete_alert_rule,
+        webhooks::get_webhook_secret,
+        webhooks::rotate_webhook_secret,
+        webhooks::list_deliveries,
+        webhooks::redeliver,
+        revocation::revoke,
+        profiles::upsert_my_profile,
+        profiles::delete_profile,
+        api_keys::create_api_key,
+        api_keys::list_api_keys,
+        api_keys::revoke_api_key,
+        organizations::create_organization,
+        organizations::get_organization,
+        organizations::create_invite,
+        organizations::list_invites,
+        organizations::accept_invite,
+        organizations::update_member,
+        organizations::remove_member,
+        organizations::update_organization,
+        subscriptions::list_plans,
+        subscriptions::get_subscription,
+        subscriptions::create_subscription_checkout,
+        credits::get_balance,
+        credits::get_ledger,
+        usage::get_usage,
+        notifications::get_notifications,
+        notifications::list_channels,
+        notifications::create_channel,
+        notifications::delete_channel,
+        notifications::test_channel,
+        notifications::get_preferences,
+        notifications::update_preferences
     ),
     components(
         schemas(
             api::CrawlRequest, 
             api::CrawlResponse, 
+            api::BatchCrawlResponse,
+            api::BatchRowResult,
+            crate::queue::Priority,
+            crate::queue::QueueSnapshot,
+            crate::queue::LaneDepth,
+            health::HealthResponse,
+            health::HealthStatus,
+            health::DependencyCheck,
+            health::CheckStatus,
+            health::LivenessResponse,
+            validation::ValidationErrors,
+            validation::FieldError,
+            crate::worker::LaneConcurrency,
+            api::QueueStatsResponse,
+            admin::AdminStatusResponse,
+            admin::WorkerStatus,
+            admin::ScheduledRun,
+            admin::ErrorRate,
+            db::SchemaVersion,
+            db::MigrationStatus,
+            db::MigrationState,
+            crate::serp_diff::SerpDiffResponse,
+            crate::serp_diff::SerpDiff,
+            crate::serp_diff::SerpChanges,
+            crate::serp_diff::SerpEntry,
+            crate::serp_diff::RankMovement,
+            api::WorkerStateResponse,
+            crate::crawler::CrawlOptions,
+            crate::crawler::LoginFlow,
+            crate::crawler::InteractionStep,
+            crate::crawler::InfiniteScroll,
+            crate::crawler::WaitFor,
+            crate::recipes::Recipe,
+            crate::recipes::RecipeField,
+            crate::recipes::FieldType,
+            crate::recipes::PostProcess,
+            crate::recipes::CreateRecipeRequest,
+            crate::recipes::UpdateRecipeRequest,
+            crate::recipes::RecipeResponse,
+            crate::schedules::Schedule,
+            crate::schedules::CatchUpPolicy,
+            crate::schedules::CreateScheduleRequest,
+            crate::schedules::UpdateScheduleRequest,
+            crate::schedules::ScheduleResponse,
+            crate::schedules::ScheduleRunResponse,
+            crate::rankings::TrackedDomain,
+            crate::rankings::TrackDomainRequest,
+            crate::rankings::TrackedDomainResponse,
+            crate::rankings::RankingPoint,
+            crate::rankings::KeywordPosition,
+            crate::rankings::DomainVisibility,
+            crate::monitors::PageMonitor,
+            crate::monitors::PageChange,
+            crate::monitors::CreateMonitorRequest,
+            crate::monitors::UpdateMonitorRequest,
+            crate::monitors::MonitorResponse,
+            crate::alerts::AlertRule,
+            crate::alerts::AlertCondition,
+            crate::alerts::CreateAlertRuleRequest,
+            crate::alerts::UpdateAlertRuleRequest,
+            crate::alerts::AlertRuleResponse,
+            crate::webhooks::WebhookDelivery,
+            crate::webhooks::WebhookDeliveryResponse,
+            crate::webhooks::WebhookSecretResponse,
+            crate::revocation::RevokeRequest,
+            crate::profiles::Profile,
+            crate::profiles::ProfileResponse,
+            crate::profiles::UpsertMyProfileRequest,
+            crate::profiles::DeletionReport,
+            crate::revocation::RevokeResponse,
+            crate::api_keys::ApiKey,
+            crate::api_keys::CreateApiKeyRequest,
+            crate::api_keys::CreateApiKeyResponse,
+            crate::organizations::OrgRole,
+            crate::organizations::Organization,
+            crate::organizations::OrgMember,
+            crate::organizations::OrgInvite,
+            crate::organizations::OrganizationResponse,
+            crate::organizations::CreateOrganizationRequest,
+            crate::organizations::InviteRequest,
+            crate::organizations::UpdateMemberRequest,
+            crate::organizations::UpdateOrganizationRequest,
+            crate::subscriptions::Plan,
+            crate::subscriptions::Subscription,
+            crate::subscriptions::SubscriptionResponse,
+            crate::subscriptions::SubscribeRequest,
+            crate::subscriptions::SubscribeResponse,
+            crate::credits::CreditBalanceResponse,
+            crate::credits::CreditPack,
+            crate::credits::LedgerEntry,
+            crate::usage::UsagePeriod,
+            crate::usage::UsageReport,
+            crate::notifications::Notification,
+            crate::notifications::NotificationPage,
+            crate::notifications::ChannelKind,
+            crate::notifications::NotificationChannelInfo,
+            crate::notifications::CreateChannelRequest,
+            crate::notifications::ChannelResponse,
+            crate::notifications::NotificationEvent,
+            crate::notifications::Destination,
+            crate::notifications::EventPreference,
+            crate::notifications::UpdatePreferencesRequest,
+            crate::notifications::PreferencesResponse,
             api::TaskResult, 
             api::TaskSummary,
+            api::TaskPage,
+            api::TaskSearchHit,
+            api::TaskDeletion,
+            analytics::CrawlSummary,
+            analytics::GroupCount,
+            analytics::DailyCount,
+            analytics::KeywordCount,
+            task_logs::TaskLogLine,
+            task_logs::TaskLogsResponse,
+            artifacts::TaskArtifact,
+            artifacts::TaskArtifactsResponse,
+            export::TaskExport,
+            export::ExportRow,
+            exports::BulkFormat,
+            exports::ExportFilters,
+            exports::CreateExportRequest,
+            exports::BulkExport,
             api::AddProxyRequest,
             api::AddProxyResponse,
             api::RemoveProxyResponse,
             crate::proxy::ProxyInfo,
             crate::proxy::ProxyStats,
+            crate::proxy::RotationStrategy,
+            api::SetStrategyRequest,
+            api::SetStrategyResponse,
+            crate::proxy::ProxyTestResult,
             crate::proxy::ProxyProtocol
         )
     ),
     tags(
         (name = "crawler", description = "Crawler Management API"),
+        (name = "health", description = "Health Checks and Kubernetes Probes"),
         (name = "proxy", description = "Proxy Management API"),
+        (name = "recipes", description = "Extraction Recipes API"),
+        (name = "schedules", description = "Recurring Crawl Schedules API"),
+        (name = "rankings", description = "Keyword Rank Tracking API"),
+        (name = "monitors", description = "Page Change Monitoring API"),
+        (name = "alerts", description = "Alert Rules API"),
+        (name = "webhooks", description = "Completion Webhooks API"),
+        (name = "auth", description = "API Keys and Token Revocation API"),
+        (name = "organizations", description = "Organizations (Teams) API"),
         (name = "profiles", description = "User Profiles API"),
         (name = "payments", description = "Payment Processing API"),
         (name = "notifications", description = "Notifications API")
@@ -56,18 +271,19 @@ struct ApiDoc;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
-    tracing_subscriber::fmt::init();
+    let _telemetry = telemetry::init();
+    let config = config::init(config::Config::load().expect("Invalid configuration"));
 
     let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     
     // Robust Connection Retry Loop
     // Robust Connection Retry Loop
-    println!("🔌 Connecting to Database...");
+    info!("🔌 Connecting to Database...");
     let pool = {
         let mut attempts = 0;
         loop {
             // Fix for Supabase Transaction Pooler: Disable Prepared Statements
-            let mut opts = sqlx::postgres::PgConnectOptions::from_url(&db_url.parse().unwrap())
+            let opts = sqlx::postgres::PgConnectOptions::from_url(&db_url.parse().unwrap())
                 .expect("Invalid DATABASE_URL")
                 .statement_cache_capacity(0);
             
@@ -81,31 +297,53 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .await 
             {
                 Ok(p) => {
-                    println!("✅ Database Connected!");
+                    info!("✅ Database Connected!");
                     break p;
                 },
                 Err(e) => {
                     attempts += 1;
                     if attempts >= 15 {
-                        eprintln!("🔥 CRITICAL: Failed to connect to DB after 15 attempts.");
+                        error!("🔥 CRITICAL: Failed to connect to DB after 15 attempts.");
                         return Err(e.into());
                     }
-                    println!("⚠️ DB Connect failed ({}), retrying in 2s... (Attempt {}/15)", e, attempts);
+                    warn!("⚠️ DB Connect failed ({}), retrying in 2s... (Attempt {}/15)", e, attempts);
                     tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
                 }
             }
         }
     };
 
-    let _ = profiles::init_profiles_table(&pool).await;
-    let _ = payments::init_payments_table(&pool).await;
-    let _ = notifications::init_notifications_table(&pool).await;
-    println!("✅ All database tables initialized!");
+    if let Err(e) = db::run_migrations(&pool).await {
+        error!("🔥 CRITICAL: Database migrations failed: {}", e);
+        return Err(e.into());
+    }
+    if let Err(e) = subscriptions::sync_stripe_prices(&pool).await {
+        warn!("⚠️ Failed to update plan prices: {}", e);
+    }
+    info!("✅ Database schema up to date!");
 
-    let storage = storage::StorageManager::new().await.expect("Failed to init MinIO");
-    let queue = queue::QueueManager::new().await.expect("Failed to init Redis");
+    if let Err(e) = proxy::PROXY_MANAGER.attach_db(pool.clone()).await {
+        warn!("⚠️ Failed to load persisted proxies: {}", e);
+    }
+    if let Err(e) = organizations::load_reserved_proxies(&pool).await {
+        warn!("⚠️ Failed to load organization proxy pools: {}", e);
+    }
+    if let Err(e) = event_bus::connect().await {
+        warn!("⚠️ Event bus unavailable, task events won't be published: {}", e);
+    }
+    if let Some(index) = search_index::SEARCH_INDEX.as_ref() {
+        match index.ensure_index().await {
+            Ok(()) => info!("🔎 Indexing completed tasks into Elasticsearch index {}", index.index_name()),
+            Err(e) => warn!("⚠️ Failed to prepare Elasticsearch index {}: {}", index.index_name(), e),
+        }
+    }
+
+    let storage = storage::StorageManager::new(&config.storage).await.expect("Failed to init object storage");
+    let queue = queue::QueueManager::new(&pool, &config.queue).await.expect("Failed to init job queue");
 
-    let state = Arc::new(api::AppState { pool, storage, queue });
+    let denylist = revocation::Denylist::connect().await;
+
+    let state = Arc::new(api::AppState { pool, storage, queue, denylist, config });
 
     // Start Background Worker
     let worker_state = state.clone();
@@ -113,11 +351,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         worker::start_worker(worker_state).await;
     });
 
+    #[cfg(feature = "grpc")]
+    {
+        let grpc_state = state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = rust_crawler::grpc::serve(grpc_state).await {
+                error!("🔥 gRPC server error: {}", e);
+            }
+        });
+    }
+
+    // Recover jobs left behind by crashed workers
+    tokio::spawn(worker::start_janitor(state.clone()));
+
+    // Start Proxy Health Checker
+    tokio::spawn(proxy::start_health_checker());
+    tokio::spawn(proxy::start_cooldown_recovery());
+
+    // Start Proxy Provider Refresh (no-op unless a provider is configured)
+    tokio::spawn(proxy_providers::start_provider_refresh());
+
     // Start Central Scheduler (Rust)
     let scheduler_state = state.clone();
     tokio::spawn(async move {
         if let Err(e) = scheduler::start_scheduler(scheduler_state).await {
-            eprintln!("🔥 Scheduler Error: {}", e);
+            error!("🔥 Scheduler Error: {}", e);
         }
     });
 
@@ -125,38 +383,139 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .merge(SwaggerUi::new("/rust-crawler-swagger").url("/api-docs/openapi.json", ApiDoc::openapi()))
         // Crawler endpoints
         .route("/crawl", post(api::trigger_crawl))
+        .route("/crawl/batch", post(api::batch_crawl))
         .route("/crawl/:task_id", get(api::get_crawl_status))
         .route("/tasks", get(api::list_tasks))
+        .route("/tasks/search", get(api::search_tasks))
+        .route("/tasks/:task_id", axum::routing::delete(api::delete_task))
+        .route("/tasks/:task_id/restore", post(api::restore_task))
+        .route("/tasks/:task_id/html", get(api::get_task_html))
+        .route("/tasks/:task_id/logs", get(task_logs::get_task_logs))
+        .route("/tasks/:task_id/artifacts", get(artifacts::list_artifacts))
+        .route("/tasks/:task_id/artifacts/*name", get(artifacts::get_artifact))
+        .route("/tasks/:task_id/export", get(export::export_task))
+        .route("/analytics/summary", get(analytics::summary))
+        .route("/exports", get(exports::list_exports))
+        .route("/exports", post(exports::create_export))
+        .route("/exports/:id", get(exports::get_export))
+        .route("/exports/:id/download", get(exports::download_export))
+        .route("/graphql", get(graphql::graphiql))
+        .route("/graphql", post(graphql::graphql_handler))
+        .route("/keywords/:keyword/diff", get(serp_diff::keyword_diff))
+        .route("/queue/stats", get(api::queue_stats))
+        .route("/metrics", get(metrics::metrics_handler))
+        .route("/health", get(health::health))
+        .route("/live", get(health::live))
+        .route("/ready", get(health::ready))
+        .route("/worker/pause", post(api::pause_workers))
+        .route("/worker/resume", post(api::resume_workers))
+        .route("/admin/status", get(admin::admin_status))
+        .route("/admin/schema", get(admin::schema_version))
+        // Live job events
+        .route("/ws", get(events::ws_handler))
         // Proxy management endpoints
         .route("/proxies", get(api::list_proxies))
         .route("/proxies", post(api::add_proxy))
         .route("/proxies/:proxy_id", axum::routing::delete(api::remove_proxy))
         .route("/proxies/:proxy_id/enable", post(api::enable_proxy))
+        .route("/proxies/:proxy_id/test", post(api::test_proxy))
         .route("/proxies/stats", get(api::proxy_stats))
+        .route("/proxies/strategy", axum::routing::put(api::set_proxy_strategy))
+        // Recipe endpoints
+        .route("/recipes", get(recipes::list_recipes))
+        .route("/recipes", post(recipes::create_recipe))
+        .route("/recipes/:id", get(recipes::get_recipe))
+        .route("/recipes/:id", axum::routing::patch(recipes::update_recipe))
+        .route("/recipes/:id", axum::routing::delete(recipes::delete_recipe))
+        // Schedule endpoints
+        .route("/schedules", get(schedules::list_schedules))
+        .route("/schedules", post(schedules::create_schedule))
+        .route("/schedules/:id", get(schedules::get_schedule))
+        .route("/schedules/:id", axum::routing::patch(schedules::update_schedule))
+        .route("/schedules/:id", axum::routing::delete(schedules::delete_schedule))
+        .route("/schedules/:id/run-now", post(schedules::run_schedule_now))
+        // Rank tracking endpoints
+        .route("/rankings/domains", get(rankings::list_tracked_domains))
+        .route("/rankings/domains", post(rankings::track_domain))
+        .route("/rankings/domains/:id", axum::routing::delete(rankings::untrack_domain))
+        .route("/rankings/history", get(rankings::ranking_history))
+        .route("/rankings/competitors/report", get(rankings::competitor_report))
+        // Page monitor endpoints
+        .route("/monitors", get(monitors::list_monitors))
+        .route("/monitors", post(monitors::create_monitor))
+        .route("/monitors/:id", get(monitors::get_monitor))
+        .route("/monitors/:id", axum::routing::patch(monitors::update_monitor))
+        .route("/monitors/:id", axum::routing::delete(monitors::delete_monitor))
+        // Alert rule endpoints
+        .route("/alerts", get(alerts::list_alert_rules))
+        .route("/alerts", post(alerts::create_alert_rule))
+        .route("/alerts/:id", get(alerts::get_alert_rule))
+        .route("/alerts/:id", axum::routing::patch(alerts::update_alert_rule))
+        .route("/alerts/:id", axum::routing::delete(alerts::delete_alert_rule))
+        // Webhook endpoints
+        .route("/webhooks/secret", get(webhooks::get_webhook_secret))
+        .route("/webhooks/secret/rotate", post(webhooks::rotate_webhook_secret))
+        .route("/webhooks/deliveries", get(webhooks::list_deliveries))
+        .route("/webhooks/deliveries/:id/redeliver", post(webhooks::redeliver))
         // Auth endpoints
         .route("/auth/status", get(auth::auth_status))
+        .route("/auth/revoke", post(revocation::revoke))
+        .route("/api-keys", get(api_keys::list_api_keys))
+        .route("/api-keys", post(api_keys::create_api_key))
+        .route("/api-keys/:id", axum::routing::delete(api_keys::revoke_api_key))
+        // Organization endpoints
+        .route("/organizations", post(organizations::create_organization))
+        .route("/organizations/:id", axum::routing::patch(organizations::update_organization))
+        .route("/organization", get(organizations::get_organization))
+        .route("/organization/invites", get(organizations::list_invites))
+        .route("/organization/invites", post(organizations::create_invite))
+        .route("/organization/invites/:token/accept", post(organizations::accept_invite))
+        .route("/organization/members/:user_id", axum::routing::patch(organizations::update_member))
+        .route("/organization/members/:user_id", axum::routing::delete(organizations::remove_member))
         // Profile endpoints
         .route("/profiles", get(profiles::list_profiles))
         .route("/profiles", post(profiles::create_profile))
+        .route("/profiles/me", post(profiles::upsert_my_profile))
         .route("/profiles/:id", get(profiles::get_profile))
         .route("/profiles/:id", axum::routing::patch(profiles::update_profile))
+        .route("/profiles/:id", axum::routing::delete(profiles::delete_profile))
         // Payment endpoints
         .route("/payments/checkout", post(payments::create_checkout))
         .route("/payments/webhook", post(payments::handle_webhook))
         .route("/payments/history/:user_id", get(payments::get_payment_history))
+        .route("/payments/:payment_id/refund", post(payments::refund_payment))
+        .route("/plans", get(subscriptions::list_plans))
+        .route("/credits", get(credits::get_balance))
+        .route("/credits/ledger", get(credits::get_ledger))
+        .route("/usage", get(usage::get_usage))
+        .route("/subscription", get(subscriptions::get_subscription))
+        .route("/subscription/checkout", post(subscriptions::create_subscription_checkout))
         // Notification endpoints
         .route("/notifications/send", post(notifications::send_notification))
         .route("/notifications", get(notifications::get_notifications))
         .route("/notifications/:id/read", axum::routing::patch(notifications::mark_as_read))
+        .route("/notifications/unread-count", get(notifications::unread_count))
+        .route("/notifications/read-all", post(notifications::mark_all_read))
+        .route("/notifications/preferences", get(notifications::get_preferences))
+        .route("/notifications/preferences", axum::routing::put(notifications::update_preferences))
+        .route("/notifications/channels", get(notifications::list_channels))
+        .route("/notifications/channels", post(notifications::create_channel))
+        .route("/notifications/channels/:id", axum::routing::delete(notifications::delete_channel))
+        .route("/notifications/channels/:id/test", post(notifications::test_channel))
         // Static files
         .nest_service("/", ServeDir::new("static"))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), rate_limit::limit))
+        .layer(TraceLayer::new_for_http().make_span_with(telemetry::request_span))
         .with_state(state);
 
     let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
     let addr = format!("0.0.0.0:{}", port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    println!("Listening on {}", listener.local_addr()?);
-    axum::serve(listener, app).await?;
+    if auth::auth_disabled() {
+        warn!("⚠️ AUTH_DISABLED=true: requests without credentials run as an admin. Local development only!");
+    }
+    info!("Listening on {}", listener.local_addr()?);
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await?;
 
     Ok(())
 }
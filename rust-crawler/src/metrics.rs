@@ -0,0 +1,146 @@
+//! Prometheus metrics.
+//!
+//! `GET /metrics` serves crawl health in the text exposition format: crawls by
+//! engine and outcome, job durations, challenge pages, proxy failures, Chrome
+//! launches and the queue backlog. Counters are per process, so scrape every API
+//! and worker replica. With `METRICS_TOKEN` set the scraper must send it as a
+//! bearer token; otherwise the endpoint is open (it exposes no user data).
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram_vec_with_registry, register_int_counter_vec_with_registry, register_int_gauge_vec_with_registry,
+    register_int_gauge_with_registry, Encoder, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Registry, TextEncoder,
+};
+use std::sync::Arc;
+use crate::api::AppState;
+use tracing::warn;
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+/// Finished crawl attempts, by engine and outcome (completed, partial, retrying, failed)
+pub static CRAWLS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec_with_registry!("crawler_crawls_total", "Crawl attempts by engine and outcome", &["engine", "status"], REGISTRY)
+        .expect("valid metric")
+});
+
+/// Wall time of one crawl attempt, from claim to outcome
+pub static JOB_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec_with_registry!(
+        "crawler_job_duration_seconds",
+        "Duration of crawl attempts",
+        &["engine", "status"],
+        vec![1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 45.0, 60.0, 90.0, 120.0, 300.0],
+        REGISTRY
+    )
+    .expect("valid metric")
+});
+
+/// Challenge or captcha pages served instead of results
+pub static CHALLENGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec_with_registry!("crawler_challenges_total", "Challenge/captcha pages detected", &["engine"], REGISTRY)
+        .expect("valid metric")
+});
+
+/// Crawls that failed because of the exit node, by proxy source (`manual` or the provider)
+pub static PROXY_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec_with_registry!("crawler_proxy_failures_total", "Proxy failures", &["source"], REGISTRY)
+        .expect("valid metric")
+});
+
+/// Chrome launches, by what was crawled and whether the browser started
+pub static CHROME_LAUNCHES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec_with_registry!("crawler_chrome_launches_total", "Chrome launches", &["kind", "result"], REGISTRY)
+        .expect("valid metric")
+});
+
+static QUEUE_READY: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec_with_registry!("crawler_queue_ready_jobs", "Jobs waiting to be claimed", &["lane"], REGISTRY)
+        .expect("valid metric")
+});
+
+static QUEUE_IN_FLIGHT: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec_with_registry!("crawler_queue_in_flight_jobs", "Jobs claimed but not yet acknowledged", &["lane"], REGISTRY)
+        .expect("valid metric")
+});
+
+static QUEUE_DELAYED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge_with_registry!("crawler_queue_delayed_jobs", "Retries and scheduled jobs not yet due", REGISTRY)
+        .expect("valid metric")
+});
+
+static QUEUE_OLDEST_AGE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge_with_registry!("crawler_queue_oldest_job_age_seconds", "Age of the oldest ready or in-flight job", REGISTRY)
+        .expect("valid metric")
+});
+
+/// Record the outcome and duration of one crawl attempt
+pub fn observe_crawl(engine: &str, status: &str, duration: std::time::Duration) {
+    CRAWLS.with_label_values(&[engine, status]).inc();
+    JOB_DURATION.with_label_values(&[engine, status]).observe(duration.as_secs_f64());
+}
+
+/// Refresh the queue gauges from the backend (done per scrape)
+async fn refresh_queue_gauges(state: &AppState) {
+    match state.queue.stats().await {
+        Ok(snapshot) => {
+            for lane in &snapshot.lanes {
+                QUEUE_READY.with_label_values(&[&lane.lane]).set(lane.ready as i64);
+                QUEUE_IN_FLIGHT.with_label_values(&[&lane.lane]).set(lane.in_flight as i64);
+            }
+            QUEUE_DELAYED.set(snapshot.delayed as i64);
+            QUEUE_OLDEST_AGE.set(snapshot.oldest_job_age_secs.unwrap_or(0));
+        }
+        Err(e) => warn!("⚠️ [Metrics] Failed to read queue stats: {}", e),
+    }
+}
+
+/// All metrics in the Prometheus text format
+pub fn render() -> String {
+    // Touch every family so it's exported (with no samples) before its first event
+    Lazy::force(&CRAWLS);
+    Lazy::force(&JOB_DURATION);
+    Lazy::force(&CHALLENGES);
+    Lazy::force(&PROXY_FAILURES);
+    Lazy::force(&CHROME_LAUNCHES);
+    Lazy::force(&QUEUE_DELAYED);
+    Lazy::force(&QUEUE_OLDEST_AGE);
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        warn!("⚠️ [Metrics] Failed to encode metrics: {}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Prometheus scrape endpoint
+pub async fn metrics_handler(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    if let Ok(token) = std::env::var("METRICS_TOKEN") {
+        let presented = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if !token.is_empty() && presented != Some(token.as_str()) {
+            return (StatusCode::UNAUTHORIZED, "Invalid metrics token").into_response();
+        }
+    }
+    refresh_queue_gauges(&state).await;
+    ([(header::CONTENT_TYPE, TextEncoder::new().format_type().to_string())], render()).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_exposes_crawl_metrics() {
+        observe_crawl("bing", "completed", std::time::Duration::from_millis(4200));
+        CHALLENGES.with_label_values(&["google"]).inc();
+        let text = render();
+        assert!(text.contains("crawler_crawls_total{engine=\"bing\",status=\"completed\"}"));
+        assert!(text.contains("crawler_job_duration_seconds_bucket{engine=\"bing\",status=\"completed\",le=\"5\"}"));
+        assert!(text.contains("crawler_challenges_total{engine=\"google\"} 1"));
+        assert!(text.contains("# TYPE crawler_queue_delayed_jobs gauge"));
+    }
+}
@@ -7,6 +7,7 @@
 
 use once_cell::sync::Lazy;
 use std::collections::HashSet;
+use tracing::{info, warn};
 
 // Common positive words for sentiment detection
 static POSITIVE_WORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
@@ -82,7 +83,7 @@ pub fn analyze_sentiment(text: &str) -> Option<String> {
         ("Neutral", 0.5 + (positive_ratio - 0.5).abs())
     };
 
-    println!(
+    info!(
         "🧠 Sentiment Analysis: {} words analyzed, {} positive, {} negative",
         words.len(),
         positive_count,
@@ -137,6 +138,7 @@ struct NERResponse {
 #[derive(Debug, Deserialize)]
 struct ClassificationResponse {
     category: String,
+    #[allow(dead_code)]
     confidence: f32,
 }
 
@@ -154,17 +156,17 @@ pub async fn extract_entities_remote(text: &str) -> Option<Vec<Entity>> {
                 match response.json::<NERResponse>().await {
                     Ok(data) => Some(data.entities),
                     Err(e) => {
-                        eprintln!("⚠️ [ML] NER parse error: {}", e);
+                        warn!("⚠️ [ML] NER parse error: {}", e);
                         None
                     }
                 }
             } else {
-                eprintln!("⚠️ [ML] NER request failed: {}", response.status());
+                warn!("⚠️ [ML] NER request failed: {}", response.status());
                 None
             }
         },
         Err(e) => {
-             eprintln!("⚠️ [ML] NER connection failed: {}. Is python-crawler running?", e);
+             warn!("⚠️ [ML] NER connection failed: {}. Is python-crawler running?", e);
              None
         }
     }
@@ -184,7 +186,7 @@ pub async fn classify_content_remote(text: &str) -> Option<String> {
                 match response.json::<ClassificationResponse>().await {
                     Ok(data) => Some(data.category),
                     Err(e) => {
-                        eprintln!("⚠️ [ML] Classify parse error: {}", e);
+                        warn!("⚠️ [ML] Classify parse error: {}", e);
                         None
                     }
                 }
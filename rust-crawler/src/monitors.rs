@@ -0,0 +1,687 @@
+//! Page change monitoring.
+//!
+//! A monitor re-crawls a URL on a cron schedule (as a generic crawl through the
+//! normal queue). After each crawl the worker hashes the extracted text and, when
+//! it differs from the previous version, diffs the two line by line. Changes at
+//! or above the monitor's threshold notify the owner.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{types::Json as SqlJson, FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::crawler::CrawlOptions;
+use crate::notifications::{NotificationDetails, NotificationEvent};
+use crate::quotas;
+use crate::schedules::next_run;
+use tracing::{error, info, warn};
+
+/// Share of changed lines that triggers a notification unless the monitor sets its own
+pub const DEFAULT_CHANGE_THRESHOLD: f64 = 0.05;
+/// Added/removed lines quoted in change records and notifications
+const SAMPLE_LINES: usize = 5;
+
+#[derive(Debug, Serialize, Clone, ToSchema, FromRow)]
+pub struct PageMonitor {
+    pub id: String,
+    pub owner: String,
+    #[schema(example = "https://example.com/pricing")]
+    pub url: String,
+    /// Cron expression (see `/schedules`)
+    #[schema(example = "0 */6 * * *")]
+    pub cron: String,
+    pub timezone: String,
+    /// Share of changed lines (0-1) needed to notify
+    #[schema(example = 0.05)]
+    pub threshold: f64,
+    /// Crawl options for the page (wait_for, login, headers, ...)
+    #[schema(value_type = CrawlOptions)]
+    pub options: SqlJson<CrawlOptions>,
+    pub enabled: bool,
+    #[schema(value_type = Option<String>)]
+    pub next_run_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>)]
+    pub last_checked_at: Option<DateTime<Utc>>,
+    /// Last time a change at or above the threshold was detected
+    #[schema(value_type = Option<String>)]
+    pub last_changed_at: Option<DateTime<Utc>>,
+    /// SHA-256 of the normalized text seen last
+    pub content_hash: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// A detected difference between two versions of a page
+#[derive(Debug, Serialize, Clone, ToSchema, FromRow)]
+pub struct PageChange {
+    pub id: i64,
+    pub task_id: String,
+    /// Changed lines over total lines of both versions (0-1)
+    pub change_ratio: f64,
+    pub added_lines: i32,
+    pub removed_lines: i32,
+    /// A few added (`+`) and removed (`-`) lines
+    pub summary: String,
+    /// Whether the change met the threshold and the owner was notified
+    pub notified: bool,
+    #[schema(value_type = String)]
+    pub detected_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateMonitorRequest {
+    #[schema(example = "https://example.com/pricing")]
+    pub url: String,
+    #[schema(example = "0 */6 * * *")]
+    pub cron: String,
+    /// IANA timezone; defaults to UTC
+    pub timezone: Option<String>,
+    /// Defaults to 0.05
+    pub threshold: Option<f64>,
+    #[serde(default)]
+    pub options: CrawlOptions,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateMonitorRequest {
+    pub cron: Option<String>,
+    pub timezone: Option<String>,
+    pub threshold: Option<f64>,
+    pub options: Option<CrawlOptions>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MonitorResponse {
+    pub success: bool,
+    pub monitor: Option<PageMonitor>,
+    /// Most recent changes, newest first
+    pub changes: Vec<PageChange>,
+    pub message: Option<String>,
+}
+
+type ApiError = (StatusCode, String);
+
+// ============================================================================
+// Change detection
+// ============================================================================
+
+/// Non-empty lines with whitespace collapsed, so reflowed markup doesn't count as a change
+fn normalized_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+pub fn content_hash(text: &str) -> String {
+    let digest = Sha256::digest(normalized_lines(text).join("\n").as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChange {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// (added + removed) / (old lines + new lines)
+    pub ratio: f64,
+}
+
+impl TextChange {
+    fn summary(&self) -> String {
+        let added = self.added.iter().take(SAMPLE_LINES).map(|l| format!("+ {}", l));
+        let removed = self.removed.iter().take(SAMPLE_LINES).map(|l| format!("- {}", l));
+        added.chain(removed).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Lines added and removed between two versions (order-insensitive, duplicates counted)
+pub fn compare_text(old: &str, new: &str) -> TextChange {
+    let old_lines = normalized_lines(old);
+    let new_lines = normalized_lines(new);
+
+    let mut remaining: HashMap<&str, usize> = HashMap::new();
+    for line in &old_lines {
+        *remaining.entry(line.as_str()).or_default() += 1;
+    }
+    let mut added = Vec::new();
+    for line in &new_lines {
+        match remaining.get_mut(line.as_str()) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => added.push(line.clone()),
+        }
+    }
+    let removed: Vec<String> = old_lines
+        .iter()
+        .filter(|line| {
+            let count = remaining.get_mut(line.as_str()).expect("every old line was counted");
+            let unmatched = *count > 0;
+            if unmatched {
+                *count -= 1;
+            }
+            unmatched
+        })
+        .cloned()
+        .collect();
+
+    let total = old_lines.len() + new_lines.len();
+    let ratio = if total == 0 { 0.0 } else { (added.len() + removed.len()) as f64 / total as f64 };
+    TextChange { added, removed, ratio }
+}
+
+#[derive(FromRow)]
+struct LastVersion {
+    owner: String,
+    url: String,
+    threshold: f64,
+    content_hash: Option<String>,
+    content: Option<String>,
+}
+
+/// Compare a monitor crawl's text with the previous version, record the change
+/// and notify the owner if it meets the threshold
+pub async fn record_check(pool: &PgPool, monitor_id: &str, task_id: &str, text: &str) -> Result<(), sqlx::Error> {
+    let monitor: Option<LastVersion> =
+        sqlx::query_as("SELECT owner, url, threshold, content_hash, content FROM page_monitors WHERE id = $1")
+            .bind(monitor_id)
+            .fetch_optional(pool)
+            .await?;
+    let Some(LastVersion { owner, url, threshold, content_hash: previous_hash, content: previous_text }) = monitor else {
+        return Ok(());
+    };
+
+    if text.trim().is_empty() {
+        warn!("⚠️ [Monitor] {} returned no text; keeping the previous version", url);
+        return Ok(());
+    }
+    let hash = content_hash(text);
+    let unchanged = previous_hash.as_deref() == Some(hash.as_str());
+
+    let mut changed_at_threshold = false;
+    if let (false, Some(previous_hash), Some(previous_text)) = (unchanged, previous_hash, previous_text) {
+        let change = compare_text(&previous_text, text);
+        changed_at_threshold = change.ratio >= threshold;
+        info!(
+            "🔎 [Monitor] {} changed: {:.1}% of lines ({} -> {})",
+            url,
+            change.ratio * 100.0,
+            &previous_hash[..8],
+            &hash[..8]
+        );
+
+        sqlx::query(
+            r#"INSERT INTO page_monitor_changes (monitor_id, task_id, change_ratio, added_lines, removed_lines, summary, notified)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        )
+        .bind(monitor_id)
+        .bind(task_id)
+        .bind(change.ratio)
+        .bind(change.added.len() as i32)
+        .bind(change.removed.len() as i32)
+        .bind(change.summary())
+        .bind(changed_at_threshold)
+        .execute(pool)
+        .await?;
+
+        if changed_at_threshold {
+            let message = format!(
+                "{} changed ({:.1}% of lines: {} added, {} removed)\n{}",
+                url,
+                change.ratio * 100.0,
+                change.added.len(),
+                change.removed.len(),
+                change.summary()
+            );
+            let details = NotificationDetails { task_id: Some(task_id.to_string()), ..Default::default() };
+            crate::notifications::notify_with(pool, &owner, NotificationEvent::PageChanged, "Page changed", &message, details).await?;
+        }
+    }
+
+    sqlx::query(
+        r#"UPDATE page_monitors SET content_hash = $2, content = $3, last_checked_at = now(),
+           last_changed_at = CASE WHEN $4 THEN now() ELSE last_changed_at END
+           WHERE id = $1"#,
+    )
+    .bind(monitor_id)
+    .bind(&hash)
+    .bind(text)
+    .bind(changed_at_threshold)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ============================================================================
+// Scheduler hook
+// ============================================================================
+
+/// Queue a check crawl for every enabled monitor that is due, then advance it.
+/// Returns the number of crawls queued.
+pub async fn enqueue_due(state: &AppState) -> anyhow::Result<usize> {
+    let due: Vec<PageMonitor> = sqlx::query_as(&format!(
+        "SELECT {} FROM page_monitors WHERE enabled AND next_run_at <= now() ORDER BY next_run_at LIMIT 500",
+        MONITOR_COLUMNS
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut queued = 0;
+    for monitor in due {
+        let now = Utc::now();
+        let next_run_at = next_run(&monitor.cron, &monitor.timezone, now)
+            .map_err(|e| warn!("⚠️ [Monitor] Disabling {}: {}", monitor.id, e))
+            .ok();
+
+        // Compare-and-set on next_run_at, so only one replica queues each check
+        let claimed = sqlx::query(
+            "UPDATE page_monitors SET next_run_at = $3, enabled = $3 IS NOT NULL WHERE id = $1 AND next_run_at = $2",
+        )
+        .bind(&monitor.id)
+        .bind(monitor.next_run_at)
+        .bind(next_run_at)
+        .execute(&state.pool)
+        .await?;
+        if claimed.rows_affected() == 0 {
+            continue;
+        }
+
+        // The plan may have changed since the monitor was saved
+        let mut options = monitor.options.0.clone();
+        if let Err((_, reason)) = crate::subscriptions::enforce_plan(&state.pool, &monitor.owner, &mut options).await {
+            info!("⏭️ [Monitor] Skipping {} this run: {}", monitor.id, reason);
+            continue;
+        }
+
+        match quotas::reserve(&state.pool, &monitor.owner, 1).await {
+            Ok(_) => {}
+            Err(quotas::QuotaError::Exceeded(_)) => {
+                info!("⏭️ [Monitor] Skipping {} this run: {} is over quota", monitor.id, monitor.owner);
+                continue;
+            }
+            Err(quotas::QuotaError::Database(e)) => {
+                warn!("⚠️ [Monitor] Quota check failed for {}: {}", monitor.id, e);
+                continue;
+            }
+        }
+
+        // Checks are charged like any generic crawl, against the task's ID
+        let task_id = Uuid::new_v4().to_string();
+        let kind = crate::credits::CrawlKind::of("generic", &options);
+        match crate::credits::debit(&state.pool, &monitor.owner, &task_id, kind).await {
+            Ok(_) => {}
+            Err(e) => {
+                match e {
+                    crate::credits::CreditError::Insufficient { balance, cost } => info!(
+                        "⏭️ [Monitor] Skipping {} this run: {} has {} credits, the check costs {}",
+                        monitor.id, monitor.owner, balance, cost
+                    ),
+                    crate::credits::CreditError::Database(e) => warn!("⚠️ [Monitor] Credit debit failed for {}: {}", monitor.id, e),
+                }
+                release_quota(state, &monitor.owner).await;
+                continue;
+            }
+        }
+        if let Err(e) = crate::login_secrets::seal_run(&state.pool, &task_id, &monitor.owner, &mut options).await {
+            warn!("⚠️ [Monitor] Failed to store the login password for {}: {}", monitor.id, e);
+            if let Err(e) = crate::credits::refund(&state.pool, &task_id).await {
+                warn!("⚠️ [Monitor] Failed to refund credits for {}: {}", task_id, e);
+            }
+            release_quota(state, &monitor.owner).await;
+            continue;
+        }
+
+        let job = crate::queue::CrawlJob {
+            id: task_id.clone(),
+            user_id: monitor.owner.clone(),
+            keyword: monitor.url.clone(),
+            engine: "generic".to_string(),
+            options,
+            max_retries: crate::queue::DEFAULT_MAX_RETRIES,
+            backoff_secs: crate::queue::DEFAULT_BACKOFF_SECS,
+            attempt: 0,
+            priority: crate::queue::Priority::Low,
+            run_at: None,
+            monitor_id: Some(monitor.id.clone()),
+            callback_url: None,
+            receipt: None,
+        };
+        let queued_event = crate::events::JobEvent::new(crate::events::JobEventKind::Queued, &job);
+        match state.queue.push_job(job).await {
+            Ok(()) => {
+                crate::events::publish(queued_event);
+                queued += 1;
+            }
+            Err(e) => {
+                error!("❌ [Monitor] Failed to queue check of {}: {}", monitor.url, e);
+                let _ = crate::login_secrets::discard(&state.pool, &task_id).await;
+                if let Err(e) = crate::credits::refund(&state.pool, &task_id).await {
+                    warn!("⚠️ [Monitor] Failed to refund credits for {}: {}", task_id, e);
+                }
+                release_quota(state, &monitor.owner).await;
+            }
+        }
+    }
+    Ok(queued)
+}
+
+/// Give back the quota reserved for a check that wasn't queued
+async fn release_quota(state: &AppState, owner: &str) {
+    if let Err(e) = quotas::release(&state.pool, owner, 1).await {
+        warn!("⚠️ [Monitor] Failed to release quota for {}: {}", owner, e);
+    }
+}
+
+// ============================================================================
+// CRUD API
+// ============================================================================
+
+const MONITOR_COLUMNS: &str = "id, owner, url, cron, timezone, threshold, options, enabled, next_run_at, \
+     last_checked_at, last_changed_at, content_hash, created_at";
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Monitor] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+fn validate_threshold(threshold: f64) -> Result<f64, ApiError> {
+    if (0.0..=1.0).contains(&threshold) {
+        Ok(threshold)
+    } else {
+        Err((StatusCode::BAD_REQUEST, "Threshold must be between 0 and 1".to_string()))
+    }
+}
+
+/// Load a monitor the caller owns (admins may load any)
+async fn owned_monitor(pool: &PgPool, id: &str, user: &AuthUser) -> Result<PageMonitor, ApiError> {
+    let monitor: Option<PageMonitor> = sqlx::query_as(&format!("SELECT {} FROM page_monitors WHERE id = $1", MONITOR_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?;
+    match monitor {
+        Some(monitor) if monitor.owner == user.id || user.is_admin() => Ok(monitor),
+        _ => Err((StatusCode::NOT_FOUND, "Monitor not found".to_string())),
+    }
+}
+
+/// List the caller's page monitors
+#[utoipa::path(
+    get,
+    path = "/monitors",
+    tag = "monitors",
+    responses(
+        (status = 200, description = "Page monitors", body = Vec<PageMonitor>)
+    )
+)]
+pub async fn list_monitors(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<Vec<PageMonitor>>, ApiError> {
+    let monitors: Vec<PageMonitor> = sqlx::query_as(&format!(
+        "SELECT {} FROM page_monitors WHERE owner = $1 ORDER BY created_at",
+        MONITOR_COLUMNS
+    ))
+    .bind(&user.id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(monitors))
+}
+
+/// Get a monitor with its recent changes
+#[utoipa::path(
+    get,
+    path = "/monitors/{id}",
+    tag = "monitors",
+    params(
+        ("id" = String, Path, description = "Monitor ID")
+    ),
+    responses(
+        (status = 200, description = "Monitor and recent changes", body = MonitorResponse),
+        (status = 404, description = "Monitor not found")
+    )
+)]
+pub async fn get_monitor(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<MonitorResponse>, ApiError> {
+    let monitor = owned_monitor(&state.pool, &id, &user).await?;
+    let changes: Vec<PageChange> = sqlx::query_as(
+        r#"SELECT id, task_id, change_ratio, added_lines, removed_lines, summary, notified, detected_at
+           FROM page_monitor_changes WHERE monitor_id = $1 ORDER BY detected_at DESC LIMIT 20"#,
+    )
+    .bind(&id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(MonitorResponse {
+        success: true,
+        monitor: Some(monitor),
+        changes,
+        message: None,
+    }))
+}
+
+/// Start monitoring a page for changes
+#[utoipa::path(
+    post,
+    path = "/monitors",
+    tag = "monitors",
+    request_body = CreateMonitorRequest,
+    responses(
+        (status = 200, description = "Monitor created", body = MonitorResponse),
+        (status = 400, description = "Invalid URL, cron expression or threshold"),
+        (status = 403, description = "Your plan doesn't allow the crawl options")
+    )
+)]
+pub async fn create_monitor(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<CreateMonitorRequest>,
+) -> Result<Json<MonitorResponse>, ApiError> {
+    let url = reqwest::Url::parse(req.url.trim())
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+        .ok_or((StatusCode::BAD_REQUEST, format!("Invalid URL '{}'", req.url)))?;
+    crate::webhooks::check_public_url(&url).await.map_err(|e| (StatusCode::BAD_REQUEST, format!("url: {}", e)))?;
+    let timezone = req.timezone.as_deref().map(str::trim).unwrap_or("UTC").to_string();
+    let threshold = validate_threshold(req.threshold.unwrap_or(DEFAULT_CHANGE_THRESHOLD))?;
+    let mut options = req.options;
+    crate::subscriptions::enforce_plan(&state.pool, &user.id, &mut options).await?;
+    crate::organizations::check_pinned_proxy(&state.pool, &user.id, options.proxy_id.as_deref()).await?;
+    let enabled = req.enabled.unwrap_or(true);
+    // The first check records the baseline, so run it right away
+    next_run(&req.cron, &timezone, Utc::now()).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let next_run_at = enabled.then(Utc::now);
+    let id = Uuid::new_v4().to_string();
+    let secret_key = crate::login_secrets::stored_key("monitor", &id);
+    crate::login_secrets::seal_stored(&state.pool, &secret_key, &user.id, &mut options).await.map_err(db_error)?;
+
+    let inserted = sqlx::query_as(&format!(
+        r#"INSERT INTO page_monitors (id, owner, url, cron, timezone, threshold, options, enabled, next_run_at)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+           RETURNING {}"#,
+        MONITOR_COLUMNS
+    ))
+    .bind(&id)
+    .bind(&user.id)
+    .bind(url.as_str())
+    .bind(req.cron.trim())
+    .bind(&timezone)
+    .bind(threshold)
+    .bind(SqlJson(&options))
+    .bind(enabled)
+    .bind(next_run_at)
+    .fetch_one(&state.pool)
+    .await;
+    let monitor: PageMonitor = match inserted {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            let _ = crate::login_secrets::discard(&state.pool, &secret_key).await;
+            return Err(db_error(e));
+        }
+    };
+
+    info!("👀 [Monitor] Watching {} ({})", monitor.url, monitor.cron);
+    Ok(Json(MonitorResponse {
+        success: true,
+        monitor: Some(monitor),
+        changes: Vec::new(),
+        message: Some("Monitor created".to_string()),
+    }))
+}
+
+/// Update a monitor's schedule, threshold or crawl options
+#[utoipa::path(
+    patch,
+    path = "/monitors/{id}",
+    tag = "monitors",
+    params(
+        ("id" = String, Path, description = "Monitor ID")
+    ),
+    request_body = UpdateMonitorRequest,
+    responses(
+        (status = 200, description = "Monitor updated", body = MonitorResponse),
+        (status = 400, description = "Invalid cron expression or threshold"),
+        (status = 403, description = "The owner's plan doesn't allow the crawl options"),
+        (status = 404, description = "Monitor not found")
+    )
+)]
+pub async fn update_monitor(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateMonitorRequest>,
+) -> Result<Json<MonitorResponse>, ApiError> {
+    let mut monitor = owned_monitor(&state.pool, &id, &user).await?;
+    let reschedule = req.cron.is_some() || req.timezone.is_some() || (req.enabled == Some(true) && !monitor.enabled);
+
+    if let Some(cron) = req.cron {
+        monitor.cron = cron.trim().to_string();
+    }
+    if let Some(timezone) = req.timezone {
+        monitor.timezone = timezone.trim().to_string();
+    }
+    if let Some(threshold) = req.threshold {
+        monitor.threshold = validate_threshold(threshold)?;
+    }
+    if let Some(mut options) = req.options {
+        crate::subscriptions::enforce_plan(&state.pool, &monitor.owner, &mut options).await?;
+        crate::organizations::check_pinned_proxy(&state.pool, &monitor.owner, options.proxy_id.as_deref()).await?;
+        let secret_key = crate::login_secrets::stored_key("monitor", &id);
+        crate::login_secrets::seal_stored(&state.pool, &secret_key, &monitor.owner, &mut options)
+            .await
+            .map_err(db_error)?;
+        monitor.options = SqlJson(options);
+    }
+    if let Some(enabled) = req.enabled {
+        monitor.enabled = enabled;
+    }
+    if reschedule {
+        let next = next_run(&monitor.cron, &monitor.timezone, Utc::now()).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        monitor.next_run_at = monitor.enabled.then_some(next);
+    } else if !monitor.enabled {
+        monitor.next_run_at = None;
+    }
+
+    let monitor: PageMonitor = sqlx::query_as(&format!(
+        r#"UPDATE page_monitors SET cron = $2, timezone = $3, threshold = $4, options = $5, enabled = $6, next_run_at = $7
+           WHERE id = $1
+           RETURNING {}"#,
+        MONITOR_COLUMNS
+    ))
+    .bind(&id)
+    .bind(&monitor.cron)
+    .bind(&monitor.timezone)
+    .bind(monitor.threshold)
+    .bind(&monitor.options)
+    .bind(monitor.enabled)
+    .bind(monitor.next_run_at)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Monitor not found".to_string()))?;
+
+    Ok(Json(MonitorResponse {
+        success: true,
+        monitor: Some(monitor),
+        changes: Vec::new(),
+        message: Some("Monitor updated".to_string()),
+    }))
+}
+
+/// Stop monitoring a page and drop its change history
+#[utoipa::path(
+    delete,
+    path = "/monitors/{id}",
+    tag = "monitors",
+    params(
+        ("id" = String, Path, description = "Monitor ID")
+    ),
+    responses(
+        (status = 200, description = "Monitor deleted", body = MonitorResponse),
+        (status = 404, description = "Monitor not found")
+    )
+)]
+pub async fn delete_monitor(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<MonitorResponse>, ApiError> {
+    owned_monitor(&state.pool, &id, &user).await?;
+    sqlx::query("DELETE FROM page_monitor_changes WHERE monitor_id = $1")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    sqlx::query("DELETE FROM page_monitors WHERE id = $1")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    let secret_key = crate::login_secrets::stored_key("monitor", &id);
+    if let Err(e) = crate::login_secrets::discard(&state.pool, &secret_key).await {
+        warn!("⚠️ [Monitor] Failed to delete the login password of {}: {}", id, e);
+    }
+
+    Ok(Json(MonitorResponse {
+        success: true,
+        monitor: None,
+        changes: Vec::new(),
+        message: Some("Monitor deleted".to_string()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_text_counts_line_changes() {
+        let old = "Pricing\n  Basic   $10\nPro $20\n\nContact us";
+        let new = "Pricing\nBasic $10\nPro $25\nContact us";
+        assert_eq!(content_hash(old), content_hash("Pricing\nBasic $10\nPro $20\nContact us"));
+
+        let change = compare_text(old, new);
+        assert_eq!(change.added, vec!["Pro $25"]);
+        assert_eq!(change.removed, vec!["Pro $20"]);
+        assert_eq!(change.ratio, 0.25);
+        assert_eq!(change.summary(), "+ Pro $25\n- Pro $20");
+
+        let unchanged = compare_text(old, old);
+        assert_eq!(unchanged.ratio, 0.0);
+    }
+}
@@ -1,16 +1,24 @@
 //! Notifications module using Resend (FREE - 3K emails/month).
+//!
+//! `notify` is the single dispatch point for system events: it stores the
+//! in-app notification and fans out to the user's registered channels (email,
+//! Slack, Discord, Telegram), as far as the user's per-event preferences allow.
 
 use axum::{
-    extract::{Path, State},
+    async_trait,
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, FromRow};
 use uuid::Uuid;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use std::sync::Arc;
 use crate::api::AppState;
+use tracing::{error, info, warn};
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema, FromRow)]
 pub struct Notification {
@@ -21,6 +29,65 @@ pub struct Notification {
     pub message: String,
     pub read: bool,
     pub created_at: Option<String>,
+    /// Email delivery of this notification: pending, retrying, sent, failed or skipped
+    pub email_status: Option<String>,
+    pub email_attempts: Option<i32>,
+    /// Resend's message ID once accepted
+    pub email_id: Option<String>,
+    pub email_error: Option<String>,
+}
+
+/// Row of the notification list, with the raw timestamp the cursor is built from
+#[derive(FromRow)]
+struct NotificationRow {
+    #[sqlx(flatten)]
+    notification: Notification,
+    created_ts: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct NotificationListQuery {
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Page size (default 50, max 200)
+    pub limit: Option<i64>,
+    /// Only read (`true`) or unread (`false`) notifications
+    pub read: Option<bool>,
+    /// Only this notification type, e.g. `crawl_completed` or `email`
+    #[serde(rename = "type")]
+    #[param(rename = "type")]
+    pub notification_type: Option<String>,
+    /// Created at or after this time (RFC 3339)
+    pub since: Option<DateTime<Utc>>,
+    /// Created before this time (RFC 3339)
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NotificationPage {
+    pub notifications: Vec<Notification>,
+    /// Pass as `cursor` to get the next page; absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Position after a notification in newest-first order: its creation time and ID
+#[derive(Debug, PartialEq)]
+struct NotificationCursor {
+    created_at: NaiveDateTime,
+    id: String,
+}
+
+impl NotificationCursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}|{}", self.created_at.and_utc().timestamp_micros(), self.id))
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        let raw = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+        let (micros, id) = raw.split_once('|')?;
+        let created_at = DateTime::from_timestamp_micros(micros.parse().ok()?)?.naive_utc();
+        Some(Self { created_at, id: id.to_string() })
+    }
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
@@ -38,34 +105,586 @@ pub struct NotificationResponse {
     pub message: String,
 }
 
-pub async fn init_notifications_table(pool: &PgPool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"CREATE TABLE IF NOT EXISTS notifications (
-            id VARCHAR PRIMARY KEY,
-            user_id VARCHAR NOT NULL,
-            notification_type VARCHAR(20) DEFAULT 'email',
-            subject VARCHAR(255),
-            message TEXT NOT NULL,
-            read BOOLEAN DEFAULT FALSE,
-            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-        );"#,
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UnreadCountResponse {
+    pub unread: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MarkAllReadResponse {
+    pub success: bool,
+    /// Notifications that were unread before the call
+    pub updated: u64,
+}
+
+/// Where a channel delivers
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    /// Email through Resend; target is the address
+    Email,
+    /// Slack incoming webhook; target is the webhook URL
+    Slack,
+    /// Discord incoming webhook; target is the webhook URL
+    Discord,
+    /// Telegram chat via the bot in `TELEGRAM_BOT_TOKEN`; target is the chat ID or @channel
+    Telegram,
+}
+
+impl ChannelKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelKind::Email => "email",
+            ChannelKind::Slack => "slack",
+            ChannelKind::Discord => "discord",
+            ChannelKind::Telegram => "telegram",
+        }
+    }
+
+    /// The kind stored as `s`; `None` for names this version doesn't know
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "email" => Some(ChannelKind::Email),
+            "slack" => Some(ChannelKind::Slack),
+            "discord" => Some(ChannelKind::Discord),
+            "telegram" => Some(ChannelKind::Telegram),
+            _ => None,
+        }
+    }
+
+    /// Check (and normalize) a target for this kind of channel
+    pub fn validate_target(&self, target: &str) -> Result<String, String> {
+        let target = target.trim();
+        match self {
+            ChannelKind::Email => {
+                let valid = target.split_once('@').is_some_and(|(user, host)| !user.is_empty() && host.contains('.'));
+                valid.then(|| target.to_string()).ok_or(format!("Invalid email address '{}'", target))
+            }
+            ChannelKind::Slack => {
+                let url = reqwest::Url::parse(target).map_err(|_| "Invalid Slack webhook URL".to_string())?;
+                if url.scheme() == "https" && url.host_str() == Some("hooks.slack.com") && url.path().starts_with("/services/") {
+                    Ok(url.to_string())
+                } else {
+                    Err("Slack target must be a https://hooks.slack.com/services/... URL".to_string())
+                }
+            }
+            ChannelKind::Discord => {
+                let url = reqwest::Url::parse(target).map_err(|_| "Invalid Discord webhook URL".to_string())?;
+                let host_ok = matches!(url.host_str(), Some("discord.com" | "discordapp.com" | "ptb.discord.com" | "canary.discord.com"));
+                if url.scheme() == "https" && host_ok && url.path().starts_with("/api/webhooks/") {
+                    Ok(url.to_string())
+                } else {
+                    Err("Discord target must be a https://discord.com/api/webhooks/... URL".to_string())
+                }
+            }
+            ChannelKind::Telegram => {
+                let numeric = target.strip_prefix('-').unwrap_or(target);
+                let valid = (!numeric.is_empty() && numeric.chars().all(|c| c.is_ascii_digit()))
+                    || (target.len() > 1 && target.starts_with('@'));
+                valid.then(|| target.to_string()).ok_or("Telegram target must be a chat ID or @channel".to_string())
+            }
+        }
+    }
+
+    /// Sender for a target of this kind (email records its delivery status through `pool`)
+    pub fn channel(&self, target: &str, pool: &PgPool) -> Box<dyn NotificationChannel> {
+        match self {
+            ChannelKind::Email => Box::new(EmailChannel { to: target.to_string(), pool: pool.clone() }),
+            ChannelKind::Slack => Box::new(SlackChannel { webhook_url: target.to_string() }),
+            ChannelKind::Discord => Box::new(DiscordChannel { webhook_url: target.to_string() }),
+            ChannelKind::Telegram => Box::new(TelegramChannel { chat_id: target.to_string() }),
+        }
+    }
+}
+
+impl TryFrom<String> for ChannelKind {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        ChannelKind::parse(&s).ok_or_else(|| format!("unknown channel kind '{}'", s))
+    }
+}
+
+/// System events a user can route to destinations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    CrawlCompleted,
+    CrawlFailed,
+    /// Alert rules firing (rank drops, sentiment changes)
+    RankAlert,
+    /// Page monitor changes
+    PageChanged,
+    /// Payments and plan changes
+    Billing,
+    /// Bulk exports ready to download
+    ExportReady,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 6] = [
+        NotificationEvent::CrawlCompleted,
+        NotificationEvent::CrawlFailed,
+        NotificationEvent::RankAlert,
+        NotificationEvent::PageChanged,
+        NotificationEvent::Billing,
+        NotificationEvent::ExportReady,
+    ];
+
+    /// Also stored as the in-app `notification_type`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationEvent::CrawlCompleted => "crawl_completed",
+            NotificationEvent::CrawlFailed => "crawl_failed",
+            NotificationEvent::RankAlert => "rank_alert",
+            NotificationEvent::PageChanged => "page_changed",
+            NotificationEvent::Billing => "billing",
+            NotificationEvent::ExportReady => "export_ready",
+        }
+    }
+
+    fn from_str_opt(s: &str) -> Option<Self> {
+        NotificationEvent::ALL.into_iter().find(|e| e.as_str() == s)
+    }
+}
+
+/// Where an event may be delivered: in-app, or the user's channels of one kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Destination {
+    InApp,
+    Email,
+    Slack,
+    Discord,
+    Telegram,
+}
+
+impl Destination {
+    pub const ALL: [Destination; 5] = [
+        Destination::InApp,
+        Destination::Email,
+        Destination::Slack,
+        Destination::Discord,
+        Destination::Telegram,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Destination::InApp => "in_app",
+            Destination::Email => "email",
+            Destination::Slack => "slack",
+            Destination::Discord => "discord",
+            Destination::Telegram => "telegram",
+        }
+    }
+
+    fn from_str_opt(s: &str) -> Option<Self> {
+        Destination::ALL.into_iter().find(|d| d.as_str() == s)
+    }
+
+    fn channel_kind(&self) -> Option<ChannelKind> {
+        match self {
+            Destination::InApp => None,
+            Destination::Email => Some(ChannelKind::Email),
+            Destination::Slack => Some(ChannelKind::Slack),
+            Destination::Discord => Some(ChannelKind::Discord),
+            Destination::Telegram => Some(ChannelKind::Telegram),
+        }
+    }
+}
+
+/// Destinations of one event; events without a stored preference go everywhere
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct EventPreference {
+    pub event: NotificationEvent,
+    /// Empty to mute the event entirely
+    pub destinations: Vec<Destination>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdatePreferencesRequest {
+    /// Events to change; others keep their current routing
+    pub preferences: Vec<EventPreference>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PreferencesResponse {
+    pub success: bool,
+    /// Effective routing of every event
+    pub preferences: Vec<EventPreference>,
+    pub message: Option<String>,
+}
+
+/// A registered delivery target of a user
+#[derive(Debug, Serialize, Clone, ToSchema, FromRow)]
+pub struct NotificationChannelInfo {
+    pub id: String,
+    #[sqlx(try_from = "String")]
+    pub kind: ChannelKind,
+    #[schema(example = "https://discord.com/api/webhooks/123/abc")]
+    pub target: String,
+    pub enabled: bool,
+    #[schema(value_type = Option<String>)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// A `notification_channels` row as stored, before its kind is checked
+#[derive(FromRow)]
+struct ChannelRow {
+    id: String,
+    kind: String,
+    target: String,
+    enabled: bool,
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl ChannelRow {
+    /// The channel, or `None` for a kind this version doesn't know: such rows are
+    /// logged and skipped rather than sent through some other channel
+    fn known(self) -> Option<NotificationChannelInfo> {
+        let Some(kind) = ChannelKind::parse(&self.kind) else {
+            warn!("⚠️ [Notify] Skipping channel {} of unknown kind '{}'", self.id, self.kind);
+            return None;
+        };
+        Some(NotificationChannelInfo { id: self.id, kind, target: self.target, enabled: self.enabled, created_at: self.created_at })
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateChannelRequest {
+    pub kind: ChannelKind,
+    #[schema(example = "https://discord.com/api/webhooks/123/abc")]
+    pub target: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChannelResponse {
+    pub success: bool,
+    pub channel: Option<NotificationChannelInfo>,
+    pub message: Option<String>,
+}
+
+type ApiError = (StatusCode, String);
+
+// ============================================================================
+// Delivery channels
+// ============================================================================
+
+/// Longest message Discord accepts in `content`
+const DISCORD_MAX_CHARS: usize = 2000;
+/// Longest message Telegram accepts in `text`
+const TELEGRAM_MAX_CHARS: usize = 4096;
+
+/// Structured facts about a notification, used by templates and deep links
+#[derive(Debug, Clone, Default)]
+pub struct NotificationDetails {
+    pub keyword: Option<String>,
+    pub task_id: Option<String>,
+    pub result_count: Option<usize>,
+    pub category: Option<String>,
+    pub error: Option<String>,
+    /// Stage that failed in a partial crawl, which saved its search results only
+    pub failed_stage: Option<String>,
+}
+
+/// A notification as handed to channels
+#[derive(Debug, Clone)]
+pub struct OutgoingMessage {
+    /// In-app record that tracks the email delivery status, if one was stored
+    pub notification_id: Option<String>,
+    /// `None` for messages outside the event catalog (e.g. channel tests)
+    pub event: Option<NotificationEvent>,
+    pub subject: String,
+    /// Plain-text body
+    pub message: String,
+    pub details: NotificationDetails,
+}
+
+impl OutgoingMessage {
+    /// Link to the task's status (`PUBLIC_BASE_URL`, default http://localhost:3000)
+    pub fn task_url(&self) -> Option<String> {
+        let task_id = self.details.task_id.as_deref()?;
+        let base = std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+        Some(format!("{}/crawl/{}", base.trim_end_matches('/'), task_id))
+    }
+
+    /// Plain-text body with the task link appended
+    pub fn text(&self) -> String {
+        match self.task_url() {
+            Some(url) => format!("{}\n\n{}", self.message, url),
+            None => self.message.clone(),
+        }
+    }
+}
+
+/// An external destination for notifications
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    async fn send(&self, msg: &OutgoingMessage) -> Result<(), String>;
+}
+
+pub struct EmailChannel {
+    pub to: String,
+    pub pool: PgPool,
+}
+
+pub struct SlackChannel {
+    pub webhook_url: String,
+}
+
+pub struct DiscordChannel {
+    pub webhook_url: String,
+}
+
+pub struct TelegramChannel {
+    pub chat_id: String,
+}
+
+/// Cut `text` to at most `max` characters, marking the cut with an ellipsis
+fn truncate_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max - 1).collect();
+    cut.push('…');
+    cut
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    async fn send(&self, msg: &OutgoingMessage) -> Result<(), String> {
+        // A broken template shouldn't lose the notification; fall back to text only
+        let html = crate::email::render_html(msg)
+            .map_err(|e| warn!("⚠️ [Notify] Failed to render email template: {}", e))
+            .ok();
+        let email = Email { to: &self.to, subject: &msg.subject, text: &msg.text(), html: html.as_deref() };
+        deliver_email(&self.pool, msg.notification_id.as_deref(), &email)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for SlackChannel {
+    async fn send(&self, msg: &OutgoingMessage) -> Result<(), String> {
+        let response = reqwest::Client::new()
+            .post(&self.webhook_url)
+            .json(&serde_json::json!({ "text": format!("*{}*\n{}", msg.subject, msg.text()) }))
+            .send()
+            .await
+            .map_err(|e| format!("Slack error: {}", e))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("Slack failed: HTTP {}", response.status()))
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for DiscordChannel {
+    async fn send(&self, msg: &OutgoingMessage) -> Result<(), String> {
+        let content = truncate_chars(&format!("**{}**\n{}", msg.subject, msg.text()), DISCORD_MAX_CHARS);
+        let response = reqwest::Client::new()
+            .post(&self.webhook_url)
+            .json(&serde_json::json!({ "content": content }))
+            .send()
+            .await
+            .map_err(|e| format!("Discord error: {}", e))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("Discord failed: HTTP {}", response.status()))
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for TelegramChannel {
+    async fn send(&self, msg: &OutgoingMessage) -> Result<(), String> {
+        let token = std::env::var("TELEGRAM_BOT_TOKEN").map_err(|_| "TELEGRAM_BOT_TOKEN not set".to_string())?;
+        let text = truncate_chars(&format!("{}\n\n{}", msg.subject, msg.text()), TELEGRAM_MAX_CHARS);
+        let response = reqwest::Client::new()
+            .post(format!("https://api.telegram.org/bot{}/sendMessage", token))
+            .json(&serde_json::json!({ "chat_id": self.chat_id, "text": text }))
+            .send()
+            .await
+            .map_err(|e| format!("Telegram error: {}", e))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("Telegram failed: HTTP {}", response.status()))
+        }
+    }
+}
+
+/// Parse stored destinations, ignoring unknown names
+fn parse_destinations(stored: &[String]) -> Vec<Destination> {
+    stored.iter().filter_map(|s| Destination::from_str_opt(s)).collect()
+}
+
+/// Where the user wants `event` delivered (everywhere unless they said otherwise)
+async fn destinations_for(pool: &PgPool, user_id: &str, event: NotificationEvent) -> Result<Vec<Destination>, sqlx::Error> {
+    let stored: Option<Vec<String>> =
+        sqlx::query_scalar("SELECT destinations FROM notification_preferences WHERE user_id = $1 AND event = $2")
+            .bind(user_id)
+            .bind(event.as_str())
+            .fetch_optional(pool)
+            .await?;
+    Ok(stored.map(|s| parse_destinations(&s)).unwrap_or_else(|| Destination::ALL.to_vec()))
+}
+
+/// Send to the user's enabled channels of the given kinds in the background
+async fn fan_out(pool: &PgPool, user_id: &str, kinds: &[ChannelKind], msg: &OutgoingMessage) -> Result<(), sqlx::Error> {
+    if kinds.is_empty() {
+        return Ok(());
+    }
+    let channels: Vec<ChannelRow> = sqlx::query_as(
+        "SELECT id, kind, target, enabled, created_at FROM notification_channels WHERE user_id = $1 AND enabled",
     )
-    .execute(pool)
+    .bind(user_id)
+    .fetch_all(pool)
     .await?;
+
+    for info in channels.into_iter().filter_map(ChannelRow::known).filter(|c| kinds.contains(&c.kind)) {
+        let channel = info.kind.channel(&info.target, pool);
+        let msg = msg.clone();
+        tokio::spawn(async move {
+            if let Err(e) = channel.send(&msg).await {
+                warn!("⚠️ [Notify] {} channel {} failed: {}", info.kind.as_str(), info.id, e);
+            }
+        });
+    }
     Ok(())
 }
 
-async fn send_email_via_resend(to: &str, subject: &str, body: &str) -> Result<String, String> {
-    let api_key = std::env::var("RESEND_API_KEY")
-        .map_err(|_| "RESEND_API_KEY not set - email simulated")?;
+/// Deliver a system event to `user_id`: in-app and/or through their channels,
+/// as their preferences for the event allow. Returns the in-app notification ID
+/// if one was stored.
+pub async fn notify(pool: &PgPool, user_id: &str, event: NotificationEvent, subject: &str, message: &str) -> Result<Option<String>, sqlx::Error> {
+    notify_with(pool, user_id, event, subject, message, NotificationDetails::default()).await
+}
+
+/// `notify` with details for the email template and task link
+pub async fn notify_with(
+    pool: &PgPool,
+    user_id: &str,
+    event: NotificationEvent,
+    subject: &str,
+    message: &str,
+    details: NotificationDetails,
+) -> Result<Option<String>, sqlx::Error> {
+    let destinations = destinations_for(pool, user_id, event).await?;
+
+    let mut notification_id = None;
+    if destinations.contains(&Destination::InApp) {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO notifications (id, user_id, notification_type, subject, message) VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(&id)
+        .bind(user_id)
+        .bind(event.as_str())
+        .bind(subject)
+        .bind(message)
+        .execute(pool)
+        .await?;
+        notification_id = Some(id);
+    }
+
+    let kinds: Vec<ChannelKind> = destinations.iter().filter_map(Destination::channel_kind).collect();
+    let msg = OutgoingMessage {
+        notification_id: notification_id.clone(),
+        event: Some(event),
+        subject: subject.to_string(),
+        message: message.to_string(),
+        details,
+    };
+    if let Err(e) = fan_out(pool, user_id, &kinds, &msg).await {
+        warn!("⚠️ [Notify] Failed to load channels for {}: {}", user_id, e);
+    }
+    Ok(notification_id)
+}
+
+// ============================================================================
+// Email delivery
+// ============================================================================
+
+/// Attempts per email, including the first
+const EMAIL_MAX_ATTEMPTS: u32 = 3;
+/// Delay before the first retry, doubled on each further one
+const EMAIL_RETRY_BASE_SECS: u64 = 2;
+
+pub struct Email<'a> {
+    pub to: &'a str,
+    pub subject: &'a str,
+    pub text: &'a str,
+    pub html: Option<&'a str>,
+}
+
+/// Why Resend didn't take an email
+#[derive(Debug, PartialEq)]
+pub enum EmailError {
+    /// `RESEND_API_KEY` isn't set; nothing was sent
+    NotConfigured,
+    /// Network errors, rate limiting and 5xx: worth retrying
+    Transient(String),
+    /// Rejected (bad address, invalid key, ...): retrying won't help
+    Permanent(String),
+}
+
+impl std::fmt::Display for EmailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmailError::NotConfigured => write!(f, "RESEND_API_KEY not set - email simulated"),
+            EmailError::Transient(e) | EmailError::Permanent(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ResendSent {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct ResendErrorBody {
+    name: Option<String>,
+    message: Option<String>,
+}
+
+/// Classify a non-2xx Resend response
+fn resend_error(status: u16, body: &str) -> EmailError {
+    let detail = serde_json::from_str::<ResendErrorBody>(body)
+        .ok()
+        .and_then(|b| match (b.name, b.message) {
+            (Some(name), Some(message)) => Some(format!("{}: {}", name, message)),
+            (name, message) => name.or(message),
+        })
+        .unwrap_or_else(|| body.chars().take(200).collect());
+    let error = format!("Resend HTTP {}: {}", status, detail);
+    if status == 429 || status >= 500 {
+        EmailError::Transient(error)
+    } else {
+        EmailError::Permanent(error)
+    }
+}
+
+/// Send one email through Resend, returning its message ID
+async fn send_email_via_resend(email: &Email<'_>) -> Result<String, EmailError> {
+    let api_key = std::env::var("RESEND_API_KEY").map_err(|_| EmailError::NotConfigured)?;
 
     let client = reqwest::Client::new();
-    let payload = serde_json::json!({
+    let mut payload = serde_json::json!({
         "from": "Crawler <notifications@resend.dev>",
-        "to": [to],
-        "subject": subject,
-        "text": body
+        "to": [email.to],
+        "subject": email.subject,
+        "text": email.text
     });
+    if let Some(html) = email.html {
+        payload["html"] = serde_json::Value::from(html);
+    }
 
     let response = client
         .post("https://api.resend.com/emails")
@@ -73,31 +692,79 @@ async fn send_email_via_resend(to: &str, subject: &str, body: &str) -> Result<St
         .json(&payload)
         .send()
         .await
-        .map_err(|e| format!("Resend error: {}", e))?;
+        .map_err(|e| EmailError::Transient(format!("Resend error: {}", e)))?;
 
-    if response.status().is_success() {
-        Ok("Email sent".to_string())
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    if status.is_success() {
+        Ok(serde_json::from_str::<ResendSent>(&body).map(|sent| sent.id).unwrap_or_default())
     } else {
-        Err("Resend failed".to_string())
+        Err(resend_error(status.as_u16(), &body))
     }
 }
 
-use crate::auth::AuthUser;
+/// Record an email's delivery state on its notification
+async fn record_email_status(
+    pool: &PgPool,
+    notification_id: &str,
+    status: &str,
+    attempts: u32,
+    email_id: Option<&str>,
+    error: Option<&str>,
+) {
+    let result = sqlx::query(
+        "UPDATE notifications SET email_status = $2, email_attempts = $3, email_id = $4, email_error = $5 WHERE id = $1",
+    )
+    .bind(notification_id)
+    .bind(status)
+    .bind(attempts as i32)
+    .bind(email_id)
+    .bind(error)
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        warn!("⚠️ [Notify] Failed to record email status of {}: {}", notification_id, e);
+    }
+}
 
+/// Send an email, retrying transient failures with backoff, and keep the
+/// notification's email status up to date. Returns Resend's message ID.
+pub async fn deliver_email(pool: &PgPool, notification_id: Option<&str>, email: &Email<'_>) -> Result<String, EmailError> {
+    for attempt in 1..=EMAIL_MAX_ATTEMPTS {
+        let result = send_email_via_resend(email).await;
+        let (status, error) = match &result {
+            Ok(_) => ("sent", None),
+            Err(EmailError::NotConfigured) => ("skipped", Some(result.as_ref().unwrap_err().to_string())),
+            Err(EmailError::Transient(e)) if attempt < EMAIL_MAX_ATTEMPTS => ("retrying", Some(e.clone())),
+            Err(e) => ("failed", Some(e.to_string())),
+        };
+        if let Some(id) = notification_id {
+            let email_id = result.as_ref().ok().map(String::as_str);
+            record_email_status(pool, id, status, attempt, email_id, error.as_deref()).await;
+        }
+        if status != "retrying" {
+            return result;
+        }
+
+        let delay = EMAIL_RETRY_BASE_SECS << (attempt - 1);
+        info!("🔁 [Notify] Email to {} failed ({}); retrying in {}s", email.to, error.unwrap_or_default(), delay);
+        tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+    }
+    unreachable!("the last attempt never retries")
+}
+
+use crate::auth::{AdminUser, AuthUser};
+
+/// Send a notification to any user (admins only)
 pub async fn send_notification(
     State(state): State<Arc<AppState>>,
-    _user: AuthUser, // Require auth, but currently anyone can send to anyone (or we could enforce admin role)
+    _admin: AdminUser,
     Json(req): Json<SendNotificationRequest>,
 ) -> Result<Json<NotificationResponse>, StatusCode> {
     let notification_id = Uuid::new_v4().to_string();
-    
-    let message = match send_email_via_resend(&req.to_email, &req.subject, &req.message).await {
-        Ok(msg) => msg,
-        Err(e) => format!("Stored (email skipped: {})", e),
-    };
 
     sqlx::query(
-        "INSERT INTO notifications (id, user_id, notification_type, subject, message) VALUES ($1, $2, 'email', $3, $4)"
+        "INSERT INTO notifications (id, user_id, notification_type, subject, message, email_status, email_attempts) VALUES ($1, $2, 'email', $3, $4, 'pending', 0)"
     )
     .bind(&notification_id)
     .bind(&req.user_id)
@@ -107,37 +774,101 @@ pub async fn send_notification(
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // Retries can take a while; the outcome shows up as the notification's email_status
+    let outgoing = OutgoingMessage {
+        notification_id: Some(notification_id.clone()),
+        event: None,
+        subject: req.subject.clone(),
+        message: req.message.clone(),
+        details: NotificationDetails::default(),
+    };
+    let pool = state.pool.clone();
+    let to = req.to_email.clone();
+    tokio::spawn(async move {
+        let html = crate::email::render_html(&outgoing).ok();
+        let email = Email { to: &to, subject: &outgoing.subject, text: &outgoing.message, html: html.as_deref() };
+        if let Err(e) = deliver_email(&pool, outgoing.notification_id.as_deref(), &email).await {
+            warn!("⚠️ [Notify] Email to {} not delivered: {}", to, e);
+        }
+    });
+
     Ok(Json(NotificationResponse {
         success: true,
         notification_id: Some(notification_id),
-        message,
+        message: "Stored; email delivery pending".to_string(),
     }))
 }
 
+/// The caller's notifications, newest first, a page at a time
+#[utoipa::path(
+    get,
+    path = "/notifications",
+    tag = "notifications",
+    params(NotificationListQuery),
+    responses(
+        (status = 200, description = "One page of notifications", body = NotificationPage),
+        (status = 400, description = "Invalid cursor")
+    )
+)]
 pub async fn get_notifications(
     State(state): State<Arc<AppState>>,
     user: AuthUser,
-) -> Result<Json<Vec<Notification>>, StatusCode> {
-    
+    Query(query): Query<NotificationListQuery>,
+) -> Result<Json<NotificationPage>, ApiError> {
+    let cursor = match query.cursor.as_deref() {
+        Some(raw) => Some(
+            NotificationCursor::decode(raw).ok_or((StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))?,
+        ),
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+
     // Workaround: Acquire connection and clean it
-    let mut conn = state.pool.acquire().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut conn = state.pool.acquire().await.map_err(db_error)?;
     use sqlx::Executor; // trait import
     conn.execute("DEALLOCATE ALL").await.ok();
 
-    let notifications: Vec<Notification> = sqlx::query_as(
+    // One row past the page tells whether there is another page
+    let mut rows: Vec<NotificationRow> = sqlx::query_as(
         r#"SELECT id, user_id, notification_type, subject, message, read,
-           to_char(created_at, 'YYYY-MM-DD HH24:MI:SS') as created_at
-           FROM notifications WHERE user_id = $1 ORDER BY created_at DESC LIMIT 50"#
+           to_char(created_at, 'YYYY-MM-DD HH24:MI:SS') as created_at,
+           email_status, email_attempts, email_id, email_error,
+           created_at AS created_ts
+           FROM notifications
+           WHERE user_id = $1
+             AND ($2::timestamp IS NULL OR (created_at, id) < ($2, $3))
+             AND ($4::boolean IS NULL OR read = $4)
+             AND ($5::varchar IS NULL OR notification_type = $5)
+             AND ($6::timestamp IS NULL OR created_at >= $6)
+             AND ($7::timestamp IS NULL OR created_at < $7)
+           ORDER BY created_at DESC, id DESC
+           LIMIT $8"#
     )
     .bind(&user.id)
+    .bind(cursor.as_ref().map(|c| c.created_at))
+    .bind(cursor.as_ref().map(|c| c.id.as_str()))
+    .bind(query.read)
+    .bind(query.notification_type.as_deref())
+    .bind(query.since.map(|t| t.naive_utc()))
+    .bind(query.until.map(|t| t.naive_utc()))
+    .bind(limit + 1)
     .fetch_all(&mut *conn)
     .await
-    .map_err(|e| {
-        println!("🔥 DB Error: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    .map_err(db_error)?;
 
-    Ok(Json(notifications))
+    let next_cursor = if rows.len() as i64 > limit {
+        rows.truncate(limit as usize);
+        rows.last().and_then(|row| {
+            Some(NotificationCursor { created_at: row.created_ts?, id: row.notification.id.clone() }.encode())
+        })
+    } else {
+        None
+    };
+
+    Ok(Json(NotificationPage {
+        notifications: rows.into_iter().map(|row| row.notification).collect(),
+        next_cursor,
+    }))
 }
 
 pub async fn mark_as_read(
@@ -163,3 +894,344 @@ pub async fn mark_as_read(
         message: "Marked as read".to_string(),
     }))
 }
+
+/// Number of unread notifications, for the dashboard badge
+pub async fn unread_count(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<UnreadCountResponse>, StatusCode> {
+    let unread: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read = FALSE")
+        .bind(&user.id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(UnreadCountResponse { unread }))
+}
+
+/// Mark all of the caller's notifications as read
+pub async fn mark_all_read(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<MarkAllReadResponse>, StatusCode> {
+    let result = sqlx::query("UPDATE notifications SET read = TRUE WHERE user_id = $1 AND read = FALSE")
+        .bind(&user.id)
+        .execute(&state.pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(MarkAllReadResponse { success: true, updated: result.rows_affected() }))
+}
+
+// ============================================================================
+// Channel API
+// ============================================================================
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Notify] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+const CHANNEL_COLUMNS: &str = "id, kind, target, enabled, created_at";
+
+async fn owned_channel(pool: &PgPool, id: &str, user: &AuthUser) -> Result<NotificationChannelInfo, ApiError> {
+    sqlx::query_as::<_, ChannelRow>(&format!(
+        "SELECT {} FROM notification_channels WHERE id = $1 AND user_id = $2",
+        CHANNEL_COLUMNS
+    ))
+    .bind(id)
+    .bind(&user.id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error)?
+    .and_then(ChannelRow::known)
+    .ok_or((StatusCode::NOT_FOUND, "Channel not found".to_string()))
+}
+
+/// List the caller's notification channels
+#[utoipa::path(
+    get,
+    path = "/notifications/channels",
+    tag = "notifications",
+    responses(
+        (status = 200, description = "Notification channels", body = Vec<NotificationChannelInfo>)
+    )
+)]
+pub async fn list_channels(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<Vec<NotificationChannelInfo>>, ApiError> {
+    let channels: Vec<ChannelRow> = sqlx::query_as(&format!(
+        "SELECT {} FROM notification_channels WHERE user_id = $1 ORDER BY created_at",
+        CHANNEL_COLUMNS
+    ))
+    .bind(&user.id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(channels.into_iter().filter_map(ChannelRow::known).collect()))
+}
+
+/// Register an email address, Slack or Discord webhook, or Telegram chat to receive the caller's notifications
+#[utoipa::path(
+    post,
+    path = "/notifications/channels",
+    tag = "notifications",
+    request_body = CreateChannelRequest,
+    responses(
+        (status = 200, description = "Channel added", body = ChannelResponse),
+        (status = 400, description = "Invalid target for the channel kind")
+    )
+)]
+pub async fn create_channel(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<CreateChannelRequest>,
+) -> Result<Json<ChannelResponse>, ApiError> {
+    let target = req.kind.validate_target(&req.target).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let channel: NotificationChannelInfo = sqlx::query_as(&format!(
+        "INSERT INTO notification_channels (id, user_id, kind, target) VALUES ($1, $2, $3, $4) RETURNING {}",
+        CHANNEL_COLUMNS
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(&user.id)
+    .bind(req.kind.as_str())
+    .bind(&target)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(ChannelResponse {
+        success: true,
+        channel: Some(channel),
+        message: Some("Channel added".to_string()),
+    }))
+}
+
+/// Remove a notification channel
+#[utoipa::path(
+    delete,
+    path = "/notifications/channels/{id}",
+    tag = "notifications",
+    params(
+        ("id" = String, Path, description = "Channel ID")
+    ),
+    responses(
+        (status = 200, description = "Channel removed", body = ChannelResponse),
+        (status = 404, description = "Channel not found")
+    )
+)]
+pub async fn delete_channel(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<ChannelResponse>, ApiError> {
+    let result = sqlx::query("DELETE FROM notification_channels WHERE id = $1 AND user_id = $2")
+        .bind(&id)
+        .bind(&user.id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Channel not found".to_string()));
+    }
+
+    Ok(Json(ChannelResponse {
+        success: true,
+        channel: None,
+        message: Some("Channel removed".to_string()),
+    }))
+}
+
+/// Send a test message through a channel and report the result
+#[utoipa::path(
+    post,
+    path = "/notifications/channels/{id}/test",
+    tag = "notifications",
+    params(
+        ("id" = String, Path, description = "Channel ID")
+    ),
+    responses(
+        (status = 200, description = "Test result; `success` is false if the platform rejected it", body = ChannelResponse),
+        (status = 404, description = "Channel not found")
+    )
+)]
+pub async fn test_channel(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<ChannelResponse>, ApiError> {
+    let info = owned_channel(&state.pool, &id, &user).await?;
+    let result = info
+        .kind
+        .channel(&info.target, &state.pool)
+        .send(&OutgoingMessage {
+            notification_id: None,
+            event: None,
+            subject: "Test notification".to_string(),
+            message: "Notifications from the crawler will arrive here.".to_string(),
+            details: NotificationDetails::default(),
+        })
+        .await;
+
+    Ok(Json(ChannelResponse {
+        success: result.is_ok(),
+        channel: Some(info),
+        message: Some(result.map(|_| "Test message sent".to_string()).unwrap_or_else(|e| e)),
+    }))
+}
+
+/// Effective routing of every event for `user_id`
+async fn load_preferences(pool: &PgPool, user_id: &str) -> Result<Vec<EventPreference>, sqlx::Error> {
+    let stored: Vec<(String, Vec<String>)> =
+        sqlx::query_as("SELECT event, destinations FROM notification_preferences WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await?;
+    Ok(NotificationEvent::ALL
+        .into_iter()
+        .map(|event| {
+            let destinations = stored
+                .iter()
+                .find(|(e, _)| NotificationEvent::from_str_opt(e) == Some(event))
+                .map(|(_, d)| parse_destinations(d))
+                .unwrap_or_else(|| Destination::ALL.to_vec());
+            EventPreference { event, destinations }
+        })
+        .collect())
+}
+
+/// Which destinations each event goes to
+#[utoipa::path(
+    get,
+    path = "/notifications/preferences",
+    tag = "notifications",
+    responses(
+        (status = 200, description = "Routing of every event", body = PreferencesResponse)
+    )
+)]
+pub async fn get_preferences(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<PreferencesResponse>, ApiError> {
+    let preferences = load_preferences(&state.pool, &user.id).await.map_err(db_error)?;
+    Ok(Json(PreferencesResponse {
+        success: true,
+        preferences,
+        message: None,
+    }))
+}
+
+/// Route events to destinations (`in_app`, `email`, `slack`, `discord`, `telegram`)
+#[utoipa::path(
+    put,
+    path = "/notifications/preferences",
+    tag = "notifications",
+    request_body = UpdatePreferencesRequest,
+    responses(
+        (status = 200, description = "Updated routing of every event", body = PreferencesResponse)
+    )
+)]
+pub async fn update_preferences(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<UpdatePreferencesRequest>,
+) -> Result<Json<PreferencesResponse>, ApiError> {
+    for preference in &req.preferences {
+        let mut destinations: Vec<&str> = preference.destinations.iter().map(Destination::as_str).collect();
+        destinations.sort_unstable();
+        destinations.dedup();
+        sqlx::query(
+            r#"INSERT INTO notification_preferences (user_id, event, destinations) VALUES ($1, $2, $3)
+               ON CONFLICT (user_id, event) DO UPDATE SET destinations = EXCLUDED.destinations, updated_at = now()"#,
+        )
+        .bind(&user.id)
+        .bind(preference.event.as_str())
+        .bind(&destinations)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    }
+
+    let preferences = load_preferences(&state.pool, &user.id).await.map_err(db_error)?;
+    Ok(Json(PreferencesResponse {
+        success: true,
+        preferences,
+        message: Some("Preferences updated".to_string()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_channel_kinds_are_skipped() {
+        assert_eq!(ChannelKind::parse("discord"), Some(ChannelKind::Discord));
+        assert_eq!(ChannelKind::parse("pagerduty"), None);
+        assert!(ChannelKind::try_from("pagerduty".to_string()).is_err());
+        let row = |kind: &str| ChannelRow {
+            id: "c1".into(),
+            kind: kind.into(),
+            target: "https://discord.com/api/webhooks/1/abc".into(),
+            enabled: true,
+            created_at: None,
+        };
+        assert_eq!(row("discord").known().map(|c| c.kind), Some(ChannelKind::Discord));
+        assert!(row("pagerduty").known().is_none());
+    }
+
+    #[test]
+    fn test_validate_channel_targets() {
+        assert!(ChannelKind::Discord.validate_target("https://discord.com/api/webhooks/1/abc").is_ok());
+        assert!(ChannelKind::Discord.validate_target("https://example.com/api/webhooks/1/abc").is_err());
+        assert!(ChannelKind::Discord.validate_target("http://discord.com/api/webhooks/1/abc").is_err());
+        assert!(ChannelKind::Slack.validate_target("https://hooks.slack.com/services/T0/B0/x").is_ok());
+        assert!(ChannelKind::Slack.validate_target("https://discord.com/api/webhooks/1/abc").is_err());
+        assert_eq!(ChannelKind::Telegram.validate_target(" -100123 ").unwrap(), "-100123");
+        assert!(ChannelKind::Telegram.validate_target("@crawler_alerts").is_ok());
+        assert!(ChannelKind::Telegram.validate_target("12ab").is_err());
+        assert!(ChannelKind::Email.validate_target("ops@example.com").is_ok());
+        assert!(ChannelKind::Email.validate_target("ops@localhost").is_err());
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("short", 10), "short");
+        assert_eq!(truncate_chars("ñandú ñandú", 6), "ñandú…");
+    }
+
+    #[test]
+    fn test_event_and_destination_names_round_trip() {
+        for event in NotificationEvent::ALL {
+            assert_eq!(NotificationEvent::from_str_opt(event.as_str()), Some(event));
+            assert!(event.as_str().len() <= 20, "notification_type is VARCHAR(20)");
+        }
+        let stored = vec!["in_app".to_string(), "pager".to_string(), "slack".to_string()];
+        assert_eq!(parse_destinations(&stored), vec![Destination::InApp, Destination::Slack]);
+        assert_eq!(Destination::Slack.channel_kind(), Some(ChannelKind::Slack));
+        assert_eq!(Destination::InApp.channel_kind(), None);
+    }
+
+    #[test]
+    fn test_notification_cursor_round_trip() {
+        let cursor = NotificationCursor {
+            created_at: DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap().naive_utc(),
+            id: "5f0c|odd-id".to_string(),
+        };
+        assert_eq!(NotificationCursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(NotificationCursor::decode("not a cursor"), None);
+        assert_eq!(NotificationCursor::decode(&URL_SAFE_NO_PAD.encode("abc|id")), None);
+    }
+
+    #[test]
+    fn test_resend_errors_are_classified() {
+        let body = r#"{"statusCode":422,"name":"validation_error","message":"Invalid `to` field."}"#;
+        assert_eq!(
+            resend_error(422, body),
+            EmailError::Permanent("Resend HTTP 422: validation_error: Invalid `to` field.".to_string())
+        );
+        assert!(matches!(resend_error(429, "{}"), EmailError::Transient(_)));
+        assert!(matches!(resend_error(503, "upstream down"), EmailError::Transient(e) if e.ends_with("upstream down")));
+    }
+}
@@ -0,0 +1,601 @@
+//! Organizations (teams).
+//!
+//! A user belongs to at most one organization. Members share:
+//! - crawl quotas: usage of all members counts against one daily and monthly
+//!   limit, the organization's own if an admin set one, otherwise the owner's plan;
+//! - tasks: `GET /tasks`, `GET /crawl/:task_id` and keyword diffs cover teammates' crawls;
+//! - a proxy pool: proxies an admin dedicates to the organization serve only its
+//!   members' jobs and leave the general rotation.
+//!
+//! Owners and org admins invite people by email; the invitee accepts with the
+//! returned token while signed in with that email.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashSet;
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::api::AppState;
+use crate::auth::{AdminUser, AuthUser};
+use crate::proxy::PROXY_MANAGER;
+use tracing::{error, info};
+
+const INVITE_TTL_DAYS: i64 = 7;
+
+/// SQL condition on a `user_id` column matching the teammates of the user bound at `param` (e.g. `$1`)
+pub fn teammates_filter(param: &str) -> String {
+    format!(
+        "user_id IN (SELECT m.user_id FROM organization_members m \
+         JOIN organization_members me ON me.org_id = m.org_id WHERE me.user_id = {})",
+        param
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrgRole {
+    /// Created the organization; can't leave or be removed
+    Owner,
+    /// Invites, removes and changes the role of members
+    Admin,
+    Member,
+}
+
+impl OrgRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrgRole::Owner => "owner",
+            OrgRole::Admin => "admin",
+            OrgRole::Member => "member",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "owner" => OrgRole::Owner,
+            "admin" => OrgRole::Admin,
+            _ => OrgRole::Member,
+        }
+    }
+
+    pub fn can_manage(&self) -> bool {
+        matches!(self, OrgRole::Owner | OrgRole::Admin)
+    }
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+    /// Shared daily crawl limit (default `QUOTA_DAILY_DEFAULT`)
+    pub daily_crawl_quota: Option<i32>,
+    /// Shared monthly crawl limit (default: the owner's plan)
+    pub monthly_crawl_quota: Option<i32>,
+    /// Proxies dedicated to the organization
+    pub proxy_ids: Vec<String>,
+    #[schema(value_type = String)]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct OrgMember {
+    pub user_id: String,
+    pub email: Option<String>,
+    /// owner, admin or member
+    pub role: String,
+    #[schema(value_type = String)]
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+pub struct OrgInvite {
+    pub id: String,
+    pub email: String,
+    /// admin or member
+    pub role: String,
+    /// Give this to the invitee; they accept with `POST /organization/invites/{token}/accept`
+    pub token: String,
+    pub invited_by: String,
+    #[schema(value_type = String)]
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrganizationResponse {
+    pub organization: Organization,
+    /// The caller's role
+    pub role: OrgRole,
+    pub members: Vec<OrgMember>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateOrganizationRequest {
+    #[schema(example = "Acme SEO")]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct InviteRequest {
+    #[schema(example = "colleague@example.com")]
+    pub email: String,
+    /// `admin` or `member` (default)
+    pub role: Option<OrgRole>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateMemberRequest {
+    /// `admin` or `member`
+    pub role: OrgRole,
+}
+
+/// Platform-admin settings of an organization
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateOrganizationRequest {
+    pub daily_crawl_quota: Option<i32>,
+    pub monthly_crawl_quota: Option<i32>,
+    /// Replaces the dedicated proxy pool (see GET /proxies); `[]` releases all
+    pub proxy_ids: Option<Vec<String>>,
+}
+
+type ApiError = (StatusCode, String);
+
+/// Mark every proxy dedicated to some organization as reserved in the proxy manager
+pub async fn load_reserved_proxies(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    let ids: Vec<String> = sqlx::query_scalar("SELECT DISTINCT unnest(proxy_ids) FROM organizations")
+        .fetch_all(pool)
+        .await?;
+    let count = ids.len();
+    PROXY_MANAGER.set_reserved(ids.into_iter().collect::<HashSet<_>>());
+    Ok(count)
+}
+
+/// The user's organization and role, if any
+pub async fn membership(pool: &PgPool, user_id: &str) -> Result<Option<(String, OrgRole)>, sqlx::Error> {
+    let row: Option<(String, String)> = sqlx::query_as("SELECT org_id, role FROM organization_members WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|(org_id, role)| (org_id, OrgRole::parse(&role))))
+}
+
+/// Quota settings shared by an organization's members
+pub struct SharedQuota {
+    pub org_id: String,
+    pub owner_id: Option<String>,
+    pub member_ids: Vec<String>,
+    pub daily_crawl_quota: Option<i32>,
+    pub monthly_crawl_quota: Option<i32>,
+}
+
+/// The shared quota of the user's organization; `None` for users without one
+pub async fn shared_quota(pool: &PgPool, user_id: &str) -> Result<Option<SharedQuota>, sqlx::Error> {
+    let Some((org_id, _)) = membership(pool, user_id).await? else {
+        return Ok(None);
+    };
+    let (daily_crawl_quota, monthly_crawl_quota): (Option<i32>, Option<i32>) =
+        sqlx::query_as("SELECT daily_crawl_quota, monthly_crawl_quota FROM organizations WHERE id = $1")
+            .bind(&org_id)
+            .fetch_one(pool)
+            .await?;
+    let members: Vec<(String, String)> = sqlx::query_as("SELECT user_id, role FROM organization_members WHERE org_id = $1")
+        .bind(&org_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(Some(SharedQuota {
+        org_id,
+        owner_id: members.iter().find(|(_, role)| role == "owner").map(|(id, _)| id.clone()),
+        member_ids: members.into_iter().map(|(id, _)| id).collect(),
+        daily_crawl_quota,
+        monthly_crawl_quota,
+    }))
+}
+
+/// The dedicated proxy pool of the user's organization, if it has one
+pub async fn proxy_pool(pool: &PgPool, user_id: &str) -> Result<Option<Vec<String>>, sqlx::Error> {
+    let ids: Option<Vec<String>> = sqlx::query_scalar(
+        "SELECT o.proxy_ids FROM organizations o JOIN organization_members m ON m.org_id = o.id WHERE m.user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(ids.filter(|ids| !ids.is_empty()))
+}
+
+/// Refuse a pinned `proxy_id` outside the user's pool, or reserved for another organization
+pub async fn check_pinned_proxy(pool: &PgPool, user_id: &str, proxy_id: Option<&str>) -> Result<(), ApiError> {
+    let Some(id) = proxy_id else { return Ok(()) };
+    let ids = proxy_pool(pool, user_id).await.map_err(db_error)?;
+    if PROXY_MANAGER.allows(id, ids.as_deref()) {
+        Ok(())
+    } else {
+        Err((StatusCode::UNPROCESSABLE_ENTITY, format!("proxy_id: proxy {} isn't available to your account", id)))
+    }
+}
+
+fn generate_invite_token() -> String {
+    let bytes: [u8; 24] = rand::random();
+    format!("inv_{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+// ============================================================================
+// API
+// ============================================================================
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Orgs] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+/// The caller's organization and role, or 404
+async fn require_membership(pool: &PgPool, user: &AuthUser) -> Result<(String, OrgRole), ApiError> {
+    membership(pool, &user.id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "You are not in an organization".to_string()))
+}
+
+/// The caller's organization if they may manage it, otherwise 403
+async fn require_manager(pool: &PgPool, user: &AuthUser) -> Result<String, ApiError> {
+    match require_membership(pool, user).await? {
+        (org_id, role) if role.can_manage() => Ok(org_id),
+        _ => Err((StatusCode::FORBIDDEN, "Only the organization's owner and admins can do that".to_string())),
+    }
+}
+
+async fn load_organization(pool: &PgPool, org_id: &str, role: OrgRole) -> Result<OrganizationResponse, sqlx::Error> {
+    let organization: Organization = sqlx::query_as(
+        "SELECT id, name, daily_crawl_quota, monthly_crawl_quota, proxy_ids, created_at FROM organizations WHERE id = $1",
+    )
+    .bind(org_id)
+    .fetch_one(pool)
+    .await?;
+    let members = sqlx::query_as(
+        "SELECT user_id, email, role, joined_at FROM organization_members WHERE org_id = $1 ORDER BY joined_at",
+    )
+    .bind(org_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(OrganizationResponse { organization, role, members })
+}
+
+/// Create an organization with the caller as owner
+#[utoipa::path(
+    post,
+    path = "/organizations",
+    tag = "organizations",
+    request_body = CreateOrganizationRequest,
+    responses(
+        (status = 200, description = "Organization created", body = OrganizationResponse),
+        (status = 409, description = "Caller already belongs to an organization")
+    )
+)]
+pub async fn create_organization(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<CreateOrganizationRequest>,
+) -> Result<Json<OrganizationResponse>, ApiError> {
+    let name = req.name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return Err((StatusCode::BAD_REQUEST, "name must be 1-100 characters".to_string()));
+    }
+    if membership(&state.pool, &user.id).await.map_err(db_error)?.is_some() {
+        return Err((StatusCode::CONFLICT, "Leave your current organization first".to_string()));
+    }
+
+    let org_id = Uuid::new_v4().to_string();
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+    sqlx::query("INSERT INTO organizations (id, name) VALUES ($1, $2)")
+        .bind(&org_id)
+        .bind(name)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    sqlx::query("INSERT INTO organization_members (user_id, org_id, email, role) VALUES ($1, $2, $3, 'owner')")
+        .bind(&user.id)
+        .bind(&org_id)
+        .bind(&user.email)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    info!("🏢 [Orgs] {} created organization {} ({})", user.id, name, org_id);
+    Ok(Json(load_organization(&state.pool, &org_id, OrgRole::Owner).await.map_err(db_error)?))
+}
+
+/// The caller's organization with its members
+#[utoipa::path(
+    get,
+    path = "/organization",
+    tag = "organizations",
+    responses(
+        (status = 200, description = "Organization", body = OrganizationResponse),
+        (status = 404, description = "Caller is not in an organization")
+    )
+)]
+pub async fn get_organization(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<OrganizationResponse>, ApiError> {
+    let (org_id, role) = require_membership(&state.pool, &user).await?;
+    Ok(Json(load_organization(&state.pool, &org_id, role).await.map_err(db_error)?))
+}
+
+/// Invite someone by email (owner or org admin)
+#[utoipa::path(
+    post,
+    path = "/organization/invites",
+    tag = "organizations",
+    request_body = InviteRequest,
+    responses(
+        (status = 200, description = "Invite with its token", body = OrgInvite),
+        (status = 403, description = "Caller can't manage the organization")
+    )
+)]
+pub async fn create_invite(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<InviteRequest>,
+) -> Result<Json<OrgInvite>, ApiError> {
+    let org_id = require_manager(&state.pool, &user).await?;
+    let email = req.email.trim().to_lowercase();
+    if !email.contains('@') {
+        return Err((StatusCode::BAD_REQUEST, "Invalid email".to_string()));
+    }
+    let role = match req.role.unwrap_or(OrgRole::Member) {
+        OrgRole::Owner => return Err((StatusCode::BAD_REQUEST, "Invites are for admins or members".to_string())),
+        role => role,
+    };
+
+    let invite = sqlx::query_as(
+        r#"INSERT INTO organization_invites (id, org_id, email, role, token, invited_by, expires_at)
+           VALUES ($1, $2, $3, $4, $5, $6, $7)
+           RETURNING id, email, role, token, invited_by, expires_at"#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&org_id)
+    .bind(&email)
+    .bind(role.as_str())
+    .bind(generate_invite_token())
+    .bind(&user.id)
+    .bind(Utc::now() + Duration::days(INVITE_TTL_DAYS))
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    info!("✉️ [Orgs] {} invited {} to {}", user.id, email, org_id);
+    Ok(Json(invite))
+}
+
+/// Pending invites of the caller's organization (owner or org admin)
+#[utoipa::path(
+    get,
+    path = "/organization/invites",
+    tag = "organizations",
+    responses(
+        (status = 200, description = "Pending invites", body = Vec<OrgInvite>)
+    )
+)]
+pub async fn list_invites(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<Vec<OrgInvite>>, ApiError> {
+    let org_id = require_manager(&state.pool, &user).await?;
+    let invites = sqlx::query_as(
+        r#"SELECT id, email, role, token, invited_by, expires_at FROM organization_invites
+           WHERE org_id = $1 AND accepted_at IS NULL AND expires_at > now()
+           ORDER BY created_at DESC"#,
+    )
+    .bind(&org_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(invites))
+}
+
+/// Join an organization with an invite sent to the caller's email
+#[utoipa::path(
+    post,
+    path = "/organization/invites/{token}/accept",
+    tag = "organizations",
+    params(
+        ("token" = String, Path, description = "Invite token")
+    ),
+    responses(
+        (status = 200, description = "Joined", body = OrganizationResponse),
+        (status = 403, description = "Invite is for another email"),
+        (status = 404, description = "Unknown, used or expired invite"),
+        (status = 409, description = "Caller already belongs to an organization")
+    )
+)]
+pub async fn accept_invite(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(token): Path<String>,
+) -> Result<Json<OrganizationResponse>, ApiError> {
+    let invite: Option<(String, String, String, String)> = sqlx::query_as(
+        "SELECT id, org_id, email, role FROM organization_invites WHERE token = $1 AND accepted_at IS NULL AND expires_at > now()",
+    )
+    .bind(&token)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?;
+    let (invite_id, org_id, email, role) = invite.ok_or((StatusCode::NOT_FOUND, "Invite not found or expired".to_string()))?;
+
+    if !user.email.as_deref().is_some_and(|own| own.eq_ignore_ascii_case(&email)) {
+        return Err((StatusCode::FORBIDDEN, "This invite was sent to another email address".to_string()));
+    }
+    if membership(&state.pool, &user.id).await.map_err(db_error)?.is_some() {
+        return Err((StatusCode::CONFLICT, "Leave your current organization first".to_string()));
+    }
+
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+    sqlx::query("UPDATE organization_invites SET accepted_at = now() WHERE id = $1")
+        .bind(&invite_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    sqlx::query("INSERT INTO organization_members (user_id, org_id, email, role) VALUES ($1, $2, $3, $4)")
+        .bind(&user.id)
+        .bind(&org_id)
+        .bind(&user.email)
+        .bind(&role)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    info!("🏢 [Orgs] {} joined {} as {}", user.id, org_id, role);
+    Ok(Json(load_organization(&state.pool, &org_id, OrgRole::parse(&role)).await.map_err(db_error)?))
+}
+
+/// Change a member's role between admin and member (owner or org admin)
+#[utoipa::path(
+    patch,
+    path = "/organization/members/{user_id}",
+    tag = "organizations",
+    params(
+        ("user_id" = String, Path, description = "Member's user ID")
+    ),
+    request_body = UpdateMemberRequest,
+    responses(
+        (status = 200, description = "Role updated", body = OrganizationResponse),
+        (status = 403, description = "Caller can't manage the organization, or the member is its owner"),
+        (status = 404, description = "Not a member")
+    )
+)]
+pub async fn update_member(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(member_id): Path<String>,
+    Json(req): Json<UpdateMemberRequest>,
+) -> Result<Json<OrganizationResponse>, ApiError> {
+    let org_id = require_manager(&state.pool, &user).await?;
+    if req.role == OrgRole::Owner {
+        return Err((StatusCode::BAD_REQUEST, "Ownership can't be transferred".to_string()));
+    }
+    let updated = sqlx::query("UPDATE organization_members SET role = $3 WHERE user_id = $1 AND org_id = $2 AND role <> 'owner'")
+        .bind(&member_id)
+        .bind(&org_id)
+        .bind(req.role.as_str())
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    if updated.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "No such member (the owner's role can't change)".to_string()));
+    }
+
+    let (_, role) = require_membership(&state.pool, &user).await?;
+    Ok(Json(load_organization(&state.pool, &org_id, role).await.map_err(db_error)?))
+}
+
+/// Remove a member (owner or org admin), or leave the organization (any member but the owner)
+#[utoipa::path(
+    delete,
+    path = "/organization/members/{user_id}",
+    tag = "organizations",
+    params(
+        ("user_id" = String, Path, description = "Member's user ID")
+    ),
+    responses(
+        (status = 200, description = "Member removed"),
+        (status = 403, description = "Caller can't remove that member"),
+        (status = 404, description = "Not a member")
+    )
+)]
+pub async fn remove_member(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(member_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let (org_id, role) = require_membership(&state.pool, &user).await?;
+    if member_id != user.id && !role.can_manage() {
+        return Err((StatusCode::FORBIDDEN, "Only the organization's owner and admins can remove members".to_string()));
+    }
+    let removed = sqlx::query("DELETE FROM organization_members WHERE user_id = $1 AND org_id = $2 AND role <> 'owner'")
+        .bind(&member_id)
+        .bind(&org_id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    if removed.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "No such member (the owner can't leave)".to_string()));
+    }
+    info!("🏢 [Orgs] {} removed {} from {}", user.id, member_id, org_id);
+    Ok(StatusCode::OK)
+}
+
+/// Set an organization's shared quotas and dedicated proxies (platform admins only)
+#[utoipa::path(
+    patch,
+    path = "/organizations/{id}",
+    tag = "organizations",
+    params(
+        ("id" = String, Path, description = "Organization ID")
+    ),
+    request_body = UpdateOrganizationRequest,
+    responses(
+        (status = 200, description = "Organization updated", body = Organization),
+        (status = 400, description = "Unknown proxy ID"),
+        (status = 403, description = "Caller is not an admin"),
+        (status = 404, description = "Organization not found")
+    )
+)]
+pub async fn update_organization(
+    State(state): State<Arc<AppState>>,
+    AdminUser(admin): AdminUser,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateOrganizationRequest>,
+) -> Result<Json<Organization>, ApiError> {
+    if let Some(ref ids) = req.proxy_ids {
+        if let Some(unknown) = ids.iter().find(|id| PROXY_MANAGER.find_proxy(id).is_none()) {
+            return Err((StatusCode::BAD_REQUEST, format!("Unknown proxy {}", unknown)));
+        }
+    }
+    let organization: Option<Organization> = sqlx::query_as(
+        r#"UPDATE organizations SET
+               daily_crawl_quota = COALESCE($2, daily_crawl_quota),
+               monthly_crawl_quota = COALESCE($3, monthly_crawl_quota),
+               proxy_ids = COALESCE($4, proxy_ids)
+           WHERE id = $1
+           RETURNING id, name, daily_crawl_quota, monthly_crawl_quota, proxy_ids, created_at"#,
+    )
+    .bind(&id)
+    .bind(req.daily_crawl_quota)
+    .bind(req.monthly_crawl_quota)
+    .bind(&req.proxy_ids)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?;
+    let organization = organization.ok_or((StatusCode::NOT_FOUND, "Organization not found".to_string()))?;
+
+    if req.proxy_ids.is_some() {
+        load_reserved_proxies(&state.pool).await.map_err(db_error)?;
+    }
+    info!("🏢 [Orgs] {} updated organization {}", admin.id, id);
+    Ok(Json(organization))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_org_roles() {
+        for role in [OrgRole::Owner, OrgRole::Admin, OrgRole::Member] {
+            assert_eq!(OrgRole::parse(role.as_str()), role);
+        }
+        assert_eq!(OrgRole::parse("unknown"), OrgRole::Member);
+        assert!(OrgRole::Admin.can_manage());
+        assert!(!OrgRole::Member.can_manage());
+        assert!(generate_invite_token().starts_with("inv_"));
+        assert!(teammates_filter("$3").ends_with("WHERE me.user_id = $3)"));
+    }
+}
@@ -0,0 +1,512 @@
+//! Payment provider adapters.
+//!
+//! `/payments/checkout` and `/payments/webhook` talk to whichever provider
+//! `PAYMENT_PROVIDER` selects: `stripe` (default) or `paypal`, for regions
+//! where Stripe isn't available. A provider starts a checkout for a payment
+//! and turns its webhook calls into a `WebhookEvent`, and refunds completed
+//! payments; payments.rs does the bookkeeping either way. Without credentials
+//! both run in demo mode; with credentials, webhooks are only acted on once
+//! their signature checks out.
+
+use axum::{async_trait, http::HeaderMap};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::info;
+
+const STRIPE_API: &str = "https://api.stripe.com";
+const PAYPAL_LIVE_API: &str = "https://api-m.paypal.com";
+const PAYPAL_SANDBOX_API: &str = "https://api-m.sandbox.paypal.com";
+const REQUEST_TIMEOUT_SECS: u64 = 15;
+/// How far a Stripe signature's timestamp may be from now, against replays
+const STRIPE_SIGNATURE_TOLERANCE_SECS: i64 = 300;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A payment to collect; `amount` is in the currency's minor unit (cents)
+#[derive(Debug)]
+pub struct CheckoutRequest<'a> {
+    pub payment_id: &'a str,
+    pub amount: i32,
+    pub currency: &'a str,
+}
+
+/// A started checkout
+#[derive(Debug)]
+pub struct Checkout {
+    /// `pending` for a real checkout, `demo` without provider credentials
+    pub status: &'static str,
+    pub checkout_url: String,
+    /// The provider's ID for the checkout (e.g. the PayPal order)
+    pub provider_ref: Option<String>,
+    pub message: String,
+}
+
+/// What a webhook call means for us
+#[derive(Debug, PartialEq)]
+pub enum WebhookEvent {
+    /// The payer paid; `payment_id` is ours, `provider_ref` what the provider
+    /// refunds against (Stripe payment intent, PayPal capture). `verified` is
+    /// false for unsigned demo-mode calls, which anyone could send.
+    PaymentCompleted { payment_id: String, provider_ref: Option<String>, verified: bool },
+    /// A Stripe `customer.subscription.*` event
+    Subscription { event_type: String, object: serde_json::Value },
+    /// Anything else, by event type
+    Ignored(String),
+}
+
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    /// Stored with each payment
+    fn name(&self) -> &'static str;
+
+    async fn create_checkout(&self, req: &CheckoutRequest<'_>) -> Result<Checkout, String>;
+
+    /// Verify and interpret a webhook call from the provider
+    async fn handle_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<WebhookEvent, String>;
+
+    /// Refund a completed payment in full, returning the provider's refund ID
+    /// (`None` in demo mode, where there is nothing to refund)
+    async fn refund(&self, provider_ref: Option<&str>) -> Result<Option<String>, String>;
+}
+
+/// The provider selected by `PAYMENT_PROVIDER`
+pub fn from_env() -> Box<dyn PaymentProvider> {
+    let name = std::env::var("PAYMENT_PROVIDER").unwrap_or_default();
+    by_name(&name)
+}
+
+/// The provider called `name` (stripe for anything unknown), e.g. the one a stored payment went through
+pub fn by_name(name: &str) -> Box<dyn PaymentProvider> {
+    let var = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+    match name.trim().to_lowercase().as_str() {
+        "paypal" => Box::new(PayPalProvider {
+            credentials: var("PAYPAL_CLIENT_ID").zip(var("PAYPAL_CLIENT_SECRET")),
+            webhook_id: var("PAYPAL_WEBHOOK_ID"),
+            api_base: if var("PAYPAL_MODE").is_some_and(|m| m.eq_ignore_ascii_case("live")) {
+                PAYPAL_LIVE_API
+            } else {
+                PAYPAL_SANDBOX_API
+            },
+        }),
+        _ => Box::new(StripeProvider { secret_key: var("STRIPE_SECRET_KEY"), webhook_secret: var("STRIPE_WEBHOOK_SECRET") }),
+    }
+}
+
+/// Demo checkout served by this API when the provider isn't configured
+fn demo_checkout(payment_id: &str, hint: &str) -> Checkout {
+    Checkout {
+        status: "demo",
+        checkout_url: format!("http://localhost:3000/payments/demo/{}", payment_id),
+        provider_ref: None,
+        message: format!("Demo mode: Set {} for real payments", hint),
+    }
+}
+
+// ============================================================================
+// Stripe
+// ============================================================================
+
+pub struct StripeProvider {
+    secret_key: Option<String>,
+    /// Endpoint signing secret (`whsec_...`); required once `secret_key` is set
+    webhook_secret: Option<String>,
+}
+
+/// Check a `Stripe-Signature` header, `t=<unix time>,v1=<hex HMAC-SHA256 of "<t>.<body>">`
+/// (several `v1` entries while a secret is being rolled)
+fn verify_stripe_signature(secret: &str, header: &str, body: &[u8], now: i64) -> Result<(), String> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", t)) => timestamp = Some(t),
+            Some(("v1", signature)) => signatures.push(signature),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or("Stripe-Signature has no timestamp")?;
+    let sent_at: i64 = timestamp.parse().map_err(|_| "Invalid Stripe-Signature timestamp")?;
+    if (now - sent_at).abs() > STRIPE_SIGNATURE_TOLERANCE_SECS {
+        return Err("Stripe-Signature timestamp is outside the tolerance".to_string());
+    }
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    let valid = signatures.iter().filter_map(|s| decode_hex(s)).any(|s| mac.clone().verify_slice(&s).is_ok());
+    if valid { Ok(()) } else { Err("Stripe signature mismatch".to_string()) }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct StripeWebhookEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    data: serde_json::Value,
+}
+
+#[async_trait]
+impl PaymentProvider for StripeProvider {
+    fn name(&self) -> &'static str {
+        "stripe"
+    }
+
+    async fn create_checkout(&self, req: &CheckoutRequest<'_>) -> Result<Checkout, String> {
+        if self.secret_key.is_none() {
+            return Ok(demo_checkout(req.payment_id, "STRIPE_SECRET_KEY"));
+        }
+        Ok(Checkout {
+            status: "pending",
+            checkout_url: format!("https://checkout.stripe.com/demo/{}", req.payment_id),
+            provider_ref: None,
+            message: "Stripe checkout session created".to_string(),
+        })
+    }
+
+    async fn handle_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<WebhookEvent, String> {
+        let verified = match (&self.webhook_secret, &self.secret_key) {
+            (Some(secret), _) => {
+                let header = headers.get("stripe-signature").and_then(|v| v.to_str().ok()).ok_or("Missing Stripe-Signature header")?;
+                verify_stripe_signature(secret, header, body, chrono::Utc::now().timestamp())?;
+                true
+            }
+            (None, Some(_)) => return Err("STRIPE_WEBHOOK_SECRET is not set; refusing unverified webhook".to_string()),
+            // Demo mode: nothing real to pay for or refund
+            (None, None) => false,
+        };
+        let event: StripeWebhookEvent = serde_json::from_slice(body).map_err(|e| format!("Invalid Stripe event: {}", e))?;
+        let object = event.data.get("object").cloned().unwrap_or_default();
+
+        if event.event_type.starts_with("customer.subscription.") {
+            // A subscription event grants a plan, so anyone could forge one without a signature
+            if !verified {
+                return Err("STRIPE_WEBHOOK_SECRET is not set; refusing unverified subscription event".to_string());
+            }
+            return Ok(WebhookEvent::Subscription { event_type: event.event_type, object });
+        }
+        match object.get("client_reference_id").and_then(|v| v.as_str()) {
+            Some(payment_id) if event.event_type == "checkout.session.completed" => Ok(WebhookEvent::PaymentCompleted {
+                payment_id: payment_id.to_string(),
+                provider_ref: object.get("payment_intent").and_then(|v| v.as_str()).map(str::to_string),
+                verified,
+            }),
+            _ => Ok(WebhookEvent::Ignored(event.event_type)),
+        }
+    }
+
+    async fn refund(&self, provider_ref: Option<&str>) -> Result<Option<String>, String> {
+        let Some(ref secret_key) = self.secret_key else {
+            return Ok(None);
+        };
+        let payment_intent = provider_ref.ok_or("Payment has no Stripe payment intent")?;
+        let response = reqwest::Client::new()
+            .post(format!("{}/v1/refunds", STRIPE_API))
+            .bearer_auth(secret_key)
+            .header("Idempotency-Key", format!("refund-{}", payment_intent))
+            .form(&[("payment_intent", payment_intent)])
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .send()
+            .await
+            .map_err(|e| format!("Stripe refund error: {}", e))?;
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            let message = body["error"]["message"].as_str().unwrap_or("unknown error");
+            return Err(format!("Stripe refund failed: HTTP {}: {}", status, message));
+        }
+        Ok(body["id"].as_str().map(str::to_string))
+    }
+}
+
+// ============================================================================
+// PayPal
+// ============================================================================
+
+/// PayPal Orders v2: checkout creates an order, the `CHECKOUT.ORDER.APPROVED`
+/// webhook captures it and `PAYMENT.CAPTURE.COMPLETED` completes the payment.
+/// Our payment ID travels as the purchase unit's `custom_id`.
+pub struct PayPalProvider {
+    /// Client ID and secret
+    credentials: Option<(String, String)>,
+    /// Webhook ID from the PayPal dashboard, to verify webhooks against; required with `credentials`
+    webhook_id: Option<String>,
+    api_base: &'static str,
+}
+
+#[derive(Deserialize)]
+struct PayPalToken {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct PayPalLink {
+    href: String,
+    rel: String,
+}
+
+#[derive(Deserialize)]
+struct PayPalOrder {
+    id: String,
+    #[serde(default)]
+    links: Vec<PayPalLink>,
+}
+
+#[derive(Deserialize)]
+struct PayPalWebhookEvent {
+    event_type: String,
+    #[serde(default)]
+    resource: serde_json::Value,
+}
+
+/// "12.34" from 1234 minor units
+fn paypal_amount(amount: i32) -> String {
+    format!("{}.{:02}", amount / 100, (amount % 100).abs())
+}
+
+impl PayPalProvider {
+    fn client() -> reqwest::Client {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default()
+    }
+
+    async fn access_token(&self, client: &reqwest::Client) -> Result<String, String> {
+        let (client_id, secret) = self.credentials.as_ref().ok_or("PayPal credentials not set")?;
+        let response = client
+            .post(format!("{}/v1/oauth2/token", self.api_base))
+            .basic_auth(client_id, Some(secret))
+            .form(&[("grant_type", "client_credentials")])
+            .send()
+            .await
+            .map_err(|e| format!("PayPal auth error: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("PayPal auth failed: HTTP {}", response.status()));
+        }
+        let token: PayPalToken = response.json().await.map_err(|e| format!("PayPal auth error: {}", e))?;
+        Ok(token.access_token)
+    }
+
+    /// Ask PayPal whether a webhook call is genuine
+    async fn verify_webhook(&self, client: &reqwest::Client, token: &str, webhook_id: &str, headers: &HeaderMap, body: &[u8]) -> Result<(), String> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+        let event: serde_json::Value = serde_json::from_slice(body).map_err(|e| format!("Invalid PayPal event: {}", e))?;
+        let payload = serde_json::json!({
+            "auth_algo": header("paypal-auth-algo"),
+            "cert_url": header("paypal-cert-url"),
+            "transmission_id": header("paypal-transmission-id"),
+            "transmission_sig": header("paypal-transmission-sig"),
+            "transmission_time": header("paypal-transmission-time"),
+            "webhook_id": webhook_id,
+            "webhook_event": event,
+        });
+        let verdict: serde_json::Value = client
+            .post(format!("{}/v1/notifications/verify-webhook-signature", self.api_base))
+            .bearer_auth(token)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("PayPal verification error: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("PayPal verification error: {}", e))?;
+        match verdict["verification_status"].as_str() {
+            Some("SUCCESS") => Ok(()),
+            other => Err(format!("PayPal webhook verification {}", other.unwrap_or("failed"))),
+        }
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for PayPalProvider {
+    fn name(&self) -> &'static str {
+        "paypal"
+    }
+
+    async fn create_checkout(&self, req: &CheckoutRequest<'_>) -> Result<Checkout, String> {
+        if self.credentials.is_none() {
+            return Ok(demo_checkout(req.payment_id, "PAYPAL_CLIENT_ID and PAYPAL_CLIENT_SECRET"));
+        }
+        let client = Self::client();
+        let token = self.access_token(&client).await?;
+        let order = serde_json::json!({
+            "intent": "CAPTURE",
+            "purchase_units": [{
+                "reference_id": req.payment_id,
+                "custom_id": req.payment_id,
+                "amount": {"currency_code": req.currency.to_uppercase(), "value": paypal_amount(req.amount)}
+            }]
+        });
+        let response = client
+            .post(format!("{}/v2/checkout/orders", self.api_base))
+            .bearer_auth(&token)
+            .header("PayPal-Request-Id", req.payment_id)
+            .json(&order)
+            .send()
+            .await
+            .map_err(|e| format!("PayPal order error: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("PayPal order failed: HTTP {}: {}", status, body.chars().take(200).collect::<String>()));
+        }
+        let order: PayPalOrder = response.json().await.map_err(|e| format!("PayPal order error: {}", e))?;
+        let approve = order
+            .links
+            .iter()
+            .find(|l| l.rel == "approve" || l.rel == "payer-action")
+            .ok_or("PayPal order has no approval link")?;
+
+        Ok(Checkout {
+            status: "pending",
+            checkout_url: approve.href.clone(),
+            provider_ref: Some(order.id),
+            message: "PayPal order created".to_string(),
+        })
+    }
+
+    async fn handle_webhook(&self, headers: &HeaderMap, body: &[u8]) -> Result<WebhookEvent, String> {
+        let event: PayPalWebhookEvent = serde_json::from_slice(body).map_err(|e| format!("Invalid PayPal event: {}", e))?;
+        if self.credentials.is_none() {
+            // Demo mode: nothing is verified, so completions are marked unverified and buy no credits
+            if self.webhook_id.is_some() {
+                return Err("PAYPAL_WEBHOOK_ID is set without PayPal credentials to verify against".to_string());
+            }
+            return Ok(paypal_event(event, false));
+        }
+        let webhook_id = self
+            .webhook_id
+            .as_ref()
+            .ok_or("PAYPAL_WEBHOOK_ID is not set; refusing unverified webhook")?;
+
+        let client = Self::client();
+        let token = self.access_token(&client).await?;
+        self.verify_webhook(&client, &token, webhook_id, headers, body).await?;
+        if event.event_type == "CHECKOUT.ORDER.APPROVED" {
+            // Capturing the order moves the money; completion arrives as PAYMENT.CAPTURE.COMPLETED
+            let order_id = event.resource["id"].as_str().ok_or("Approved order without ID")?;
+            client
+                .post(format!("{}/v2/checkout/orders/{}/capture", self.api_base, order_id))
+                .bearer_auth(&token)
+                .header("Content-Type", "application/json")
+                .header("PayPal-Request-Id", format!("capture-{}", order_id))
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| format!("PayPal capture failed: {}", e))?;
+            info!("💳 PayPal order {} captured", order_id);
+        }
+        Ok(paypal_event(event, true))
+    }
+
+    async fn refund(&self, provider_ref: Option<&str>) -> Result<Option<String>, String> {
+        if self.credentials.is_none() {
+            return Ok(None);
+        }
+        let capture_id = provider_ref.ok_or("Payment has no PayPal capture")?;
+        let client = Self::client();
+        let token = self.access_token(&client).await?;
+        let response = client
+            .post(format!("{}/v2/payments/captures/{}/refund", self.api_base, capture_id))
+            .bearer_auth(&token)
+            .header("PayPal-Request-Id", format!("refund-{}", capture_id))
+            .json(&serde_json::json!({}))
+            .send()
+            .await
+            .map_err(|e| format!("PayPal refund error: {}", e))?;
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            let message = body["message"].as_str().unwrap_or("unknown error");
+            return Err(format!("PayPal refund failed: HTTP {}: {}", status, message));
+        }
+        Ok(body["id"].as_str().map(str::to_string))
+    }
+}
+
+fn paypal_event(event: PayPalWebhookEvent, verified: bool) -> WebhookEvent {
+    match event.resource["custom_id"].as_str() {
+        Some(payment_id) if event.event_type == "PAYMENT.CAPTURE.COMPLETED" => WebhookEvent::PaymentCompleted {
+            payment_id: payment_id.to_string(),
+            provider_ref: event.resource["id"].as_str().map(str::to_string),
+            verified,
+        },
+        _ => WebhookEvent::Ignored(event.event_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_webhook_events() {
+        let stripe = StripeProvider { secret_key: None, webhook_secret: None };
+        let body = br#"{"type": "checkout.session.completed", "data": {"object": {"client_reference_id": "pay-1", "payment_intent": "pi_1"}}}"#;
+        assert_eq!(
+            stripe.handle_webhook(&HeaderMap::new(), body).await,
+            Ok(WebhookEvent::PaymentCompleted { payment_id: "pay-1".to_string(), provider_ref: Some("pi_1".to_string()), verified: false })
+        );
+
+        let paypal = PayPalProvider { credentials: None, webhook_id: None, api_base: PAYPAL_SANDBOX_API };
+        let body = br#"{"event_type": "PAYMENT.CAPTURE.COMPLETED", "resource": {"id": "cap-9", "custom_id": "pay-2"}}"#;
+        assert_eq!(
+            paypal.handle_webhook(&HeaderMap::new(), body).await,
+            Ok(WebhookEvent::PaymentCompleted { payment_id: "pay-2".to_string(), provider_ref: Some("cap-9".to_string()), verified: false })
+        );
+        let body = br#"{"event_type": "CHECKOUT.ORDER.APPROVED", "resource": {"id": "order-1"}}"#;
+        assert_eq!(
+            paypal.handle_webhook(&HeaderMap::new(), body).await,
+            Ok(WebhookEvent::Ignored("CHECKOUT.ORDER.APPROVED".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stripe_signature() {
+        let body = br#"{"type": "customer.subscription.updated", "data": {"object": {}}}"#;
+        let now = 1_714_564_800;
+        let mut mac = HmacSha256::new_from_slice(b"whsec_test").unwrap();
+        mac.update(format!("{}.", now).as_bytes());
+        mac.update(body);
+        let signature: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        let header = format!("t={},v1=deadbeef,v1={}", now, signature);
+
+        assert!(verify_stripe_signature("whsec_test", &header, body, now + 10).is_ok());
+        assert!(verify_stripe_signature("whsec_other", &header, body, now).is_err());
+        assert!(verify_stripe_signature("whsec_test", &header, b"{}", now).is_err());
+        // Replayed long after it was signed
+        assert!(verify_stripe_signature("whsec_test", &header, body, now + 3600).is_err());
+
+        // A configured account never acts on unsigned events
+        let paypal = PayPalProvider {
+            credentials: Some(("client".to_string(), "secret".to_string())),
+            webhook_id: None,
+            api_base: PAYPAL_SANDBOX_API,
+        };
+        let capture = br#"{"event_type": "PAYMENT.CAPTURE.COMPLETED", "resource": {"id": "cap-9", "custom_id": "pay-2"}}"#;
+        assert!(paypal.handle_webhook(&HeaderMap::new(), capture).await.unwrap_err().contains("PAYPAL_WEBHOOK_ID"));
+        let demo = StripeProvider { secret_key: None, webhook_secret: None };
+        assert!(demo.handle_webhook(&HeaderMap::new(), body).await.unwrap_err().contains("subscription"));
+        let live = StripeProvider { secret_key: Some("sk_live".to_string()), webhook_secret: None };
+        assert!(live.handle_webhook(&HeaderMap::new(), body).await.is_err());
+        let signed = StripeProvider { secret_key: Some("sk_live".to_string()), webhook_secret: Some("whsec_test".to_string()) };
+        assert!(signed.handle_webhook(&HeaderMap::new(), body).await.is_err());
+    }
+
+    #[test]
+    fn test_paypal_amount() {
+        assert_eq!(paypal_amount(1234), "12.34");
+        assert_eq!(paypal_amount(5), "0.05");
+        assert_eq!(paypal_amount(2000), "20.00");
+    }
+}
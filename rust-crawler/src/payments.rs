@@ -1,8 +1,10 @@
-//! Payments module using Stripe (Test Mode - FREE).
+//! Payments module: one-off checkouts through the configured payment provider
+//! (Stripe or PayPal, see `payment_providers`).
 
 use axum::{
+    body::Bytes,
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Json,
 };
 use serde::{Deserialize, Serialize};
@@ -11,6 +13,10 @@ use uuid::Uuid;
 use utoipa::ToSchema;
 use std::sync::Arc;
 use crate::api::AppState;
+use crate::auth::{AdminUser, AuthUser};
+use crate::notifications::{notify, NotificationEvent};
+use crate::payment_providers::{self, CheckoutRequest, WebhookEvent};
+use tracing::{error, info, warn};
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema, FromRow)]
 pub struct Payment {
@@ -20,14 +26,47 @@ pub struct Payment {
     pub currency: String,
     pub status: String,
     pub stripe_id: Option<String>,
+    /// stripe or paypal
+    pub provider: Option<String>,
+    /// What the provider refunds against (Stripe payment intent, PayPal order/capture)
+    pub provider_ref: Option<String>,
+    /// The provider's refund ID once refunded
+    pub refund_ref: Option<String>,
+    /// Credits added to the balance once the payment completes
+    pub credits: Option<i32>,
     pub created_at: Option<String>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct CreatePaymentRequest {
     pub user_id: String,
-    pub amount: i32,
+    /// In the currency's minor unit (cents). Required without `credits`; with
+    /// `credits`, the pack's price is charged and a different amount is refused.
+    pub amount: Option<i32>,
     pub currency: Option<String>,
+    /// Crawl credits to buy; must be one of the packs listed by `GET /credits`
+    pub credits: Option<i32>,
+}
+
+/// Amount and currency to charge: a credit pack's price, or the client's amount
+/// for payments that buy no credits
+fn checkout_price(req: &CreatePaymentRequest) -> Result<(i32, String), (StatusCode, String)> {
+    let bad_request = |message: String| (StatusCode::BAD_REQUEST, message);
+    let Some(credits) = req.credits else {
+        let amount = req.amount.filter(|a| *a > 0).ok_or_else(|| bad_request("amount must be a positive number of cents".to_string()))?;
+        return Ok((amount, req.currency.clone().unwrap_or_else(|| "USD".to_string())));
+    };
+    let pack = crate::credits::credit_pack(credits).ok_or_else(|| {
+        let sizes: Vec<String> = crate::credits::CREDIT_PACKS.iter().map(|p| p.credits.to_string()).collect();
+        bad_request(format!("credits must be one of the packs: {}", sizes.join(", ")))
+    })?;
+    if req.amount.is_some_and(|a| a != pack.price_cents) {
+        return Err(bad_request(format!("{} credits cost {} cents", credits, pack.price_cents)));
+    }
+    if req.currency.as_deref().is_some_and(|c| !c.eq_ignore_ascii_case(crate::credits::CREDIT_PACK_CURRENCY)) {
+        return Err(bad_request(format!("Credit packs are priced in {}", crate::credits::CREDIT_PACK_CURRENCY)));
+    }
+    Ok((pack.price_cents, crate::credits::CREDIT_PACK_CURRENCY.to_string()))
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -38,84 +77,108 @@ pub struct PaymentResponse {
     pub message: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct StripeWebhookEvent {
-    #[serde(rename = "type")]
-    pub event_type: String,
-    pub data: serde_json::Value,
-}
-
-pub async fn init_payments_table(pool: &PgPool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"CREATE TABLE IF NOT EXISTS payments (
-            id VARCHAR PRIMARY KEY,
-            user_id VARCHAR NOT NULL,
-            amount INTEGER NOT NULL,
-            currency VARCHAR(3) DEFAULT 'USD',
-            status VARCHAR(20) DEFAULT 'pending',
-            stripe_id VARCHAR(100),
-            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-        );"#,
-    )
-    .execute(pool)
-    .await?;
-    Ok(())
-}
-
 pub async fn create_checkout(
     State(state): State<Arc<AppState>>,
+    user: AuthUser,
     Json(req): Json<CreatePaymentRequest>,
-) -> Result<Json<PaymentResponse>, StatusCode> {
+) -> Result<Json<PaymentResponse>, (StatusCode, String)> {
+    // Admins may start a checkout on someone else's behalf
+    if req.user_id != user.id && !user.is_admin() {
+        return Err((StatusCode::FORBIDDEN, "Checkouts are for your own account".to_string()));
+    }
+    let (amount, currency) = checkout_price(&req)?;
     let payment_id = Uuid::new_v4().to_string();
-    let currency = req.currency.unwrap_or_else(|| "USD".to_string());
-    
-    let stripe_key = std::env::var("STRIPE_SECRET_KEY").ok();
-    
-    let (status, checkout_url, message) = if stripe_key.is_some() {
-        ("pending".to_string(), 
-         Some(format!("https://checkout.stripe.com/demo/{}", payment_id)),
-         "Stripe checkout session created".to_string())
-    } else {
-        ("demo".to_string(),
-         Some(format!("http://localhost:3000/payments/demo/{}", payment_id)),
-         "Demo mode: Set STRIPE_SECRET_KEY for real payments".to_string())
-    };
+
+    let provider = payment_providers::from_env();
+    let checkout = provider
+        .create_checkout(&CheckoutRequest { payment_id: &payment_id, amount, currency: &currency })
+        .await
+        .map_err(|e| {
+            error!("❌ {} checkout failed: {}", provider.name(), e);
+            (StatusCode::BAD_GATEWAY, "Payment provider error".to_string())
+        })?;
 
     sqlx::query(
-        "INSERT INTO payments (id, user_id, amount, currency, status) VALUES ($1, $2, $3, $4, $5)"
+        "INSERT INTO payments (id, user_id, amount, currency, status, credits, provider, provider_ref) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
     )
     .bind(&payment_id)
     .bind(&req.user_id)
-    .bind(req.amount)
+    .bind(amount)
     .bind(&currency)
-    .bind(&status)
+    .bind(checkout.status)
+    .bind(req.credits)
+    .bind(provider.name())
+    .bind(&checkout.provider_ref)
     .execute(&state.pool)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|e| {
+        error!("❌ Failed to record payment {}: {}", payment_id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+    })?;
 
     Ok(Json(PaymentResponse {
         success: true,
         payment_id: Some(payment_id),
-        checkout_url,
-        message,
+        checkout_url: Some(checkout.checkout_url),
+        message: checkout.message,
     }))
 }
 
+/// Mark a payment completed (once) and deliver what it bought. Credits are only
+/// added for `verified` completions; an unsigned demo-mode call could come from anyone.
+async fn complete_payment(pool: &PgPool, payment_id: &str, provider_ref: Option<&str>, verified: bool) -> Result<(), sqlx::Error> {
+    let paid: Option<(String, i32, Option<String>, Option<i32>)> = sqlx::query_as(
+        r#"UPDATE payments SET status = 'completed', provider_ref = COALESCE($2, provider_ref)
+           WHERE id = $1 AND status NOT IN ('completed', 'refunded')
+           RETURNING user_id, amount, currency, credits"#,
+    )
+    .bind(payment_id)
+    .bind(provider_ref)
+    .fetch_optional(pool)
+    .await?;
+    let Some((user_id, amount, currency, credits)) = paid else {
+        return Ok(());
+    };
+
+    match credits {
+        Some(credits) if verified => crate::credits::top_up(pool, &user_id, payment_id, credits.into()).await?,
+        Some(credits) => warn!("⚠️ Payment {} completed by an unverified webhook; not adding its {} credits", payment_id, credits),
+        None => {}
+    }
+    let message = format!(
+        "Payment {} of {} {} completed.",
+        payment_id,
+        amount,
+        currency.as_deref().unwrap_or("USD")
+    );
+    let _ = notify(pool, &user_id, NotificationEvent::Billing, "Payment received", &message).await;
+    Ok(())
+}
+
 pub async fn handle_webhook(
     State(state): State<Arc<AppState>>,
-    Json(event): Json<StripeWebhookEvent>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> Result<Json<PaymentResponse>, StatusCode> {
-    println!("📦 Received Stripe webhook: {}", event.event_type);
-    
-    if event.event_type == "checkout.session.completed" {
-        if let Some(session) = event.data.get("object") {
-            if let Some(payment_id) = session.get("client_reference_id").and_then(|v| v.as_str()) {
-                let _ = sqlx::query("UPDATE payments SET status = 'completed' WHERE id = $1")
-                    .bind(payment_id)
-                    .execute(&state.pool)
-                    .await;
-            }
+    let provider = payment_providers::from_env();
+    let event = provider.handle_webhook(&headers, &body).await.map_err(|e| {
+        error!("❌ Rejected {} webhook: {}", provider.name(), e);
+        StatusCode::BAD_REQUEST
+    })?;
+    info!("📦 Received {} webhook: {:?}", provider.name(), event);
+
+    let result = match event {
+        WebhookEvent::PaymentCompleted { payment_id, provider_ref, verified } => {
+            complete_payment(&state.pool, &payment_id, provider_ref.as_deref(), verified).await
         }
+        WebhookEvent::Subscription { event_type, object } => {
+            crate::subscriptions::sync_from_stripe(&state.pool, &event_type, &object).await
+        }
+        WebhookEvent::Ignored(_) => Ok(()),
+    };
+    if let Err(e) = result {
+        error!("❌ Failed to process {} webhook: {}", provider.name(), e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
     }
 
     Ok(Json(PaymentResponse {
@@ -126,12 +189,83 @@ pub async fn handle_webhook(
     }))
 }
 
+type ApiError = (StatusCode, String);
+
+/// Refund a completed payment through its provider (admins only) and take back
+/// the credits it bought
+pub async fn refund_payment(
+    State(state): State<Arc<AppState>>,
+    AdminUser(user): AdminUser,
+    Path(payment_id): Path<String>,
+) -> Result<Json<PaymentResponse>, ApiError> {
+    let db_error = |e: sqlx::Error| {
+        error!("❌ Refund of {} failed: {}", payment_id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+    };
+
+    let payment: Option<(String, Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT status, provider, provider_ref FROM payments WHERE id = $1")
+            .bind(&payment_id)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(db_error)?;
+    let (status, provider, provider_ref) = payment.ok_or((StatusCode::NOT_FOUND, "Payment not found".to_string()))?;
+    if status != "completed" {
+        return Err((StatusCode::CONFLICT, format!("Payment is {}, only completed payments can be refunded", status)));
+    }
+
+    let provider = payment_providers::by_name(provider.as_deref().unwrap_or("stripe"));
+    let refund_ref = provider.refund(provider_ref.as_deref()).await.map_err(|e| {
+        error!("❌ {} refund of {} failed: {}", provider.name(), payment_id, e);
+        (StatusCode::BAD_GATEWAY, e)
+    })?;
+
+    let refunded: Option<(String, i32, Option<String>)> = sqlx::query_as(
+        r#"UPDATE payments SET status = 'refunded', refund_ref = $2
+           WHERE id = $1 AND status = 'completed'
+           RETURNING user_id, amount, currency"#,
+    )
+    .bind(&payment_id)
+    .bind(&refund_ref)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?;
+    let Some((user_id, amount, currency)) = refunded else {
+        return Err((StatusCode::CONFLICT, "Payment was refunded concurrently".to_string()));
+    };
+
+    let reversed = crate::credits::reverse_purchase(&state.pool, &payment_id).await.map_err(db_error)?;
+    let message = format!(
+        "Payment {} of {} {} was refunded.",
+        payment_id,
+        amount,
+        currency.as_deref().unwrap_or("USD")
+    );
+    let _ = notify(&state.pool, &user_id, NotificationEvent::Billing, "Payment refunded", &message).await;
+    info!("💸 {} refunded payment {} ({} credits reversed)", user.id, payment_id, reversed);
+
+    Ok(Json(PaymentResponse {
+        success: true,
+        payment_id: Some(payment_id),
+        checkout_url: None,
+        message: match refund_ref {
+            Some(id) => format!("Refunded ({}); {} credits reversed", id, reversed),
+            None => format!("Refunded (demo mode, no provider refund); {} credits reversed", reversed),
+        },
+    }))
+}
+
+/// A user's payments; admins can read anyone's
 pub async fn get_payment_history(
     State(state): State<Arc<AppState>>,
+    user: AuthUser,
     Path(user_id): Path<String>,
 ) -> Result<Json<Vec<Payment>>, StatusCode> {
+    if user_id != user.id && !user.is_admin() {
+        return Err(StatusCode::FORBIDDEN);
+    }
     let payments: Vec<Payment> = sqlx::query_as(
-        r#"SELECT id, user_id, amount, currency, status, stripe_id,
+        r#"SELECT id, user_id, amount, currency, status, stripe_id, provider, provider_ref, refund_ref, credits,
            to_char(created_at, 'YYYY-MM-DD HH24:MI:SS') as created_at
            FROM payments WHERE user_id = $1 ORDER BY created_at DESC"#
     )
@@ -142,3 +276,20 @@ pub async fn get_payment_history(
 
     Ok(Json(payments))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkout_price_comes_from_the_pack() {
+        let request = |amount: Option<i32>, credits: Option<i32>| CreatePaymentRequest { user_id: "u1".to_string(), amount, currency: None, credits };
+        assert_eq!(checkout_price(&request(None, Some(500))).unwrap(), (500, "USD".to_string()));
+        assert_eq!(checkout_price(&request(Some(2_000), Some(2_500))).unwrap().0, 2_000);
+        // A 1-cent checkout can't carry a pack, nor can credits outside the packs be bought
+        assert_eq!(checkout_price(&request(Some(1), Some(10_000))).unwrap_err().0, StatusCode::BAD_REQUEST);
+        assert!(checkout_price(&request(Some(1), Some(1_000_000))).is_err());
+        assert_eq!(checkout_price(&request(Some(1999), None)).unwrap().0, 1999);
+        assert!(checkout_price(&request(None, None)).is_err());
+    }
+}
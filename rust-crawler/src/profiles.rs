@@ -1,20 +1,40 @@
 //! User Profiles module.
+//!
+//! Profiles are keyed by email: callers can create, read and update the profile
+//! matching their JWT email. Admins can manage and list everyone's.
+//!
+//! A user's own profile has the account's ID (the JWT `sub`) as its ID, so it
+//! joins up with tasks, payments and notifications. `POST /profiles/me` creates
+//! it on first sign-in, and adopts a profile created for the same email before
+//! profiles were linked to accounts.
+//!
+//! `DELETE /profiles/:id` erases the account behind a profile (GDPR): the user's
+//! tasks and their stored HTML, notifications, schedules, monitors, alerts,
+//! rankings, API keys and other per-user rows are deleted, and the financial
+//! records that must be kept (payments, credit ledger) are detached from the
+//! user under an anonymous ID. `?dry_run=true` reports the same counts without
+//! changing anything.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, FromRow};
+use sqlx::{FromRow, Row};
 use uuid::Uuid;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use crate::api::AppState;
+use crate::auth::{AdminUser, AuthUser};
+use tracing::{error, info, warn};
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema, FromRow)]
 pub struct Profile {
     pub id: String,
+    /// Account (JWT `sub`) the profile belongs to
+    pub user_id: Option<String>,
     pub email: String,
     pub name: Option<String>,
     pub avatar_url: Option<String>,
@@ -28,6 +48,13 @@ pub struct CreateProfileRequest {
     pub name: Option<String>,
 }
 
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct UpsertMyProfileRequest {
+    /// Kept as is when omitted
+    pub name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateProfileRequest {
     pub name: Option<String>,
@@ -42,28 +69,18 @@ pub struct ProfileResponse {
     pub message: Option<String>,
 }
 
-pub async fn init_profiles_table(pool: &PgPool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"CREATE TABLE IF NOT EXISTS profiles (
-            id VARCHAR PRIMARY KEY,
-            email VARCHAR NOT NULL UNIQUE,
-            name VARCHAR,
-            avatar_url TEXT,
-            bio TEXT,
-            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-        );"#,
-    )
-    .execute(pool)
-    .await?;
-    Ok(())
+/// Whether `user` may act on the profile registered for `email`
+fn owns_profile(user: &AuthUser, email: &str) -> bool {
+    user.is_admin() || user.email.as_deref().is_some_and(|own| own.eq_ignore_ascii_case(email))
 }
 
 pub async fn get_profile(
     State(state): State<Arc<AppState>>,
+    user: AuthUser,
     Path(id): Path<String>,
 ) -> Result<Json<ProfileResponse>, StatusCode> {
     let row: Option<Profile> = sqlx::query_as(
-        r#"SELECT id, email, name, avatar_url, bio, 
+        r#"SELECT id, user_id, email, name, avatar_url, bio,
            to_char(created_at, 'YYYY-MM-DD HH24:MI:SS') as created_at
            FROM profiles WHERE id = $1"#
     )
@@ -73,6 +90,7 @@ pub async fn get_profile(
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     match row {
+        Some(profile) if !owns_profile(&user, &profile.email) => Err(StatusCode::FORBIDDEN),
         Some(profile) => Ok(Json(ProfileResponse {
             success: true,
             profile: Some(profile),
@@ -84,14 +102,21 @@ pub async fn get_profile(
 
 pub async fn create_profile(
     State(state): State<Arc<AppState>>,
+    user: AuthUser,
     Json(req): Json<CreateProfileRequest>,
 ) -> Result<Json<ProfileResponse>, StatusCode> {
-    let id = Uuid::new_v4().to_string();
-    
-    sqlx::query("INSERT INTO profiles (id, email, name) VALUES ($1, $2, $3)")
+    if !owns_profile(&user, &req.email) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    // Admins creating someone else's profile don't know their account ID
+    let owner = user.email.as_deref().is_some_and(|own| own.eq_ignore_ascii_case(&req.email)).then_some(&user.id);
+    let id = owner.cloned().unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    sqlx::query("INSERT INTO profiles (id, email, name, user_id) VALUES ($1, $2, $3, $4)")
         .bind(&id)
         .bind(&req.email)
         .bind(&req.name)
+        .bind(owner)
         .execute(&state.pool)
         .await
         .map_err(|_| StatusCode::BAD_REQUEST)?;
@@ -100,6 +125,7 @@ pub async fn create_profile(
         success: true,
         profile: Some(Profile {
             id,
+            user_id: owner.cloned(),
             email: req.email,
             name: req.name,
             avatar_url: None,
@@ -112,9 +138,21 @@ pub async fn create_profile(
 
 pub async fn update_profile(
     State(state): State<Arc<AppState>>,
+    user: AuthUser,
     Path(id): Path<String>,
     Json(req): Json<UpdateProfileRequest>,
 ) -> Result<Json<ProfileResponse>, StatusCode> {
+    let email: Option<String> = sqlx::query_scalar("SELECT email FROM profiles WHERE id = $1")
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    match email {
+        None => return Err(StatusCode::NOT_FOUND),
+        Some(email) if !owns_profile(&user, &email) => return Err(StatusCode::FORBIDDEN),
+        Some(_) => {}
+    }
+
     let result = sqlx::query(
         r#"UPDATE profiles SET 
            name = COALESCE($2, name),
@@ -141,11 +179,118 @@ pub async fn update_profile(
     }))
 }
 
+/// What to do with the profile already registered for a user's email
+#[derive(Debug, PartialEq)]
+enum Linkage {
+    /// None yet, or it is already the user's own
+    Upsert,
+    /// A profile from before linkage (random ID); re-key it to the account ID
+    Adopt(String),
+    /// Linked to another account
+    Conflict,
+}
+
+fn linkage(existing: Option<(String, Option<String>)>, user_id: &str) -> Linkage {
+    match existing {
+        None => Linkage::Upsert,
+        Some((_, Some(owner))) if owner != user_id => Linkage::Conflict,
+        Some((id, _)) if id == user_id => Linkage::Upsert,
+        Some((id, _)) => Linkage::Adopt(id),
+    }
+}
+
+/// Create or update the caller's own profile, keyed by their account ID
+#[utoipa::path(
+    post,
+    path = "/profiles/me",
+    tag = "profiles",
+    request_body = UpsertMyProfileRequest,
+    responses(
+        (status = 200, description = "Profile created, linked or updated", body = ProfileResponse),
+        (status = 400, description = "Token has no email"),
+        (status = 409, description = "Email already used by another account's profile")
+    )
+)]
+pub async fn upsert_my_profile(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    body: Option<Json<UpsertMyProfileRequest>>,
+) -> Result<Json<ProfileResponse>, ApiError> {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let email = user.email.clone().ok_or((StatusCode::BAD_REQUEST, "Token has no email claim".to_string()))?;
+    let conflict = || (StatusCode::CONFLICT, "Another account's profile uses this email".to_string());
+
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+    let existing: Option<(String, Option<String>)> =
+        sqlx::query_as("SELECT id, user_id FROM profiles WHERE lower(email) = lower($1) FOR UPDATE")
+            .bind(&email)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?;
+    let adopted = match linkage(existing, &user.id) {
+        Linkage::Conflict => return Err(conflict()),
+        Linkage::Adopt(old_id) => {
+            let rekeyed = sqlx::query(
+                "UPDATE profiles SET id = $2, user_id = $2 WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM profiles WHERE id = $2)",
+            )
+            .bind(&old_id)
+            .bind(&user.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            if rekeyed.rows_affected() == 0 {
+                return Err(conflict());
+            }
+            true
+        }
+        Linkage::Upsert => false,
+    };
+
+    let row = sqlx::query(
+        r#"INSERT INTO profiles (id, user_id, email, name, avatar_url) VALUES ($1, $1, $2, $3, $4)
+           ON CONFLICT (id) DO UPDATE SET
+               user_id = EXCLUDED.user_id,
+               email = EXCLUDED.email,
+               name = COALESCE(EXCLUDED.name, profiles.name),
+               avatar_url = COALESCE(EXCLUDED.avatar_url, profiles.avatar_url)
+           RETURNING id, user_id, email, name, avatar_url, bio,
+               to_char(created_at, 'YYYY-MM-DD HH24:MI:SS') as created_at, (xmax = 0) AS inserted"#,
+    )
+    .bind(&user.id)
+    .bind(&email)
+    .bind(&req.name)
+    .bind(&req.avatar_url)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        // The JWT email changed to one another profile already uses
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => conflict(),
+        e => db_error(e),
+    })?;
+    let profile = Profile::from_row(&row).map_err(db_error)?;
+    let inserted: bool = row.try_get("inserted").map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    let message = match (adopted, inserted) {
+        (true, _) => "Profile linked to your account",
+        (false, true) => "Profile created",
+        (false, false) => "Profile updated",
+    };
+    info!("👤 [Profiles] {}: {}", user.id, message);
+    Ok(Json(ProfileResponse {
+        success: true,
+        profile: Some(profile),
+        message: Some(message.to_string()),
+    }))
+}
+
+/// Latest profiles of all users (admins only)
 pub async fn list_profiles(
     State(state): State<Arc<AppState>>,
+    _admin: AdminUser,
 ) -> Result<Json<Vec<Profile>>, StatusCode> {
     let profiles: Vec<Profile> = sqlx::query_as(
-        r#"SELECT id, email, name, avatar_url, bio,
+        r#"SELECT id, user_id, email, name, avatar_url, bio,
            to_char(created_at, 'YYYY-MM-DD HH24:MI:SS') as created_at
            FROM profiles ORDER BY created_at DESC LIMIT 50"#
     )
@@ -155,3 +300,277 @@ pub async fn list_profiles(
 
     Ok(Json(profiles))
 }
+
+// ============================================================================
+// Account deletion
+// ============================================================================
+
+/// Rows removed when an account is erased, in order; `$1` is the user ID
+const ERASE_STEPS: &[(&str, &str)] = &[
+    ("tasks", "DELETE FROM tasks WHERE user_id = $1"),
+    ("task_logs", "DELETE FROM task_logs WHERE user_id = $1"),
+    ("notifications", "DELETE FROM notifications WHERE user_id = $1"),
+    ("notification_preferences", "DELETE FROM notification_preferences WHERE user_id = $1"),
+    ("notification_channels", "DELETE FROM notification_channels WHERE user_id = $1"),
+    ("schedules", "DELETE FROM schedules WHERE owner = $1"),
+    ("page_monitor_changes", "DELETE FROM page_monitor_changes WHERE monitor_id IN (SELECT id FROM page_monitors WHERE owner = $1)"),
+    ("page_monitors", "DELETE FROM page_monitors WHERE owner = $1"),
+    ("alert_rule_states", "DELETE FROM alert_rule_states WHERE rule_id IN (SELECT id FROM alert_rules WHERE owner = $1)"),
+    ("alert_rules", "DELETE FROM alert_rules WHERE owner = $1"),
+    ("rankings", "DELETE FROM rankings WHERE user_id = $1"),
+    ("tracked_domains", "DELETE FROM tracked_domains WHERE user_id = $1"),
+    ("webhook_deliveries", "DELETE FROM webhook_deliveries WHERE user_id = $1"),
+    ("webhook_secrets", "DELETE FROM webhook_secrets WHERE user_id = $1"),
+    ("exports", "DELETE FROM exports WHERE user_id = $1"),
+    ("api_keys", "DELETE FROM api_keys WHERE user_id = $1"),
+    ("login_secrets", "DELETE FROM login_secrets WHERE user_id = $1"),
+    // Only organizations the user is alone in get here (see `delete_profile`); members and invites cascade
+    (
+        "organizations",
+        "DELETE FROM organizations WHERE id IN (SELECT org_id FROM organization_members WHERE user_id = $1 AND role = 'owner')",
+    ),
+    ("organization_members", "DELETE FROM organization_members WHERE user_id = $1"),
+    ("usage_events", "DELETE FROM usage_events WHERE user_id = $1"),
+    ("crawl_usage", "DELETE FROM crawl_usage WHERE user_id = $1"),
+    ("credit_balances", "DELETE FROM credit_balances WHERE user_id = $1"),
+    ("subscriptions", "DELETE FROM subscriptions WHERE user_id = $1"),
+];
+
+/// Records kept for accounting but detached from the user; `$1` is the user ID,
+/// `$2` the anonymous ID that replaces it
+const ANONYMIZE_STEPS: &[(&str, &str)] = &[
+    ("payments", "UPDATE payments SET user_id = $2, stripe_id = NULL, provider_ref = NULL, refund_ref = NULL WHERE user_id = $1"),
+    ("credit_ledger", "UPDATE credit_ledger SET user_id = $2 WHERE user_id = $1"),
+];
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteProfileQuery {
+    /// Report what would be deleted without deleting anything
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeletionReport {
+    pub dry_run: bool,
+    pub user_id: String,
+    /// Rows deleted (or that would be) per table
+    #[schema(example = json!({"tasks": 120, "notifications": 45}))]
+    pub deleted: BTreeMap<String, u64>,
+    /// Rows kept for accounting but moved to `anonymous_id`, per table
+    pub anonymized: BTreeMap<String, u64>,
+    /// Replaces the user ID in anonymized rows (absent on dry runs)
+    pub anonymous_id: Option<String>,
+    /// Tasks whose stored objects (HTML, debug artifacts, archives) were deleted (or
+    /// would be); the user's export files under `exports/<user_id>/` go too
+    pub stored_objects: u64,
+    /// Key prefixes of stored objects that couldn't be deleted and need cleaning up by hand
+    pub stored_objects_failed: Vec<String>,
+}
+
+type ApiError = (StatusCode, String);
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Profiles] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+/// Delete a profile and erase the account behind it (self or admin)
+#[utoipa::path(
+    delete,
+    path = "/profiles/{id}",
+    tag = "profiles",
+    params(
+        ("id" = String, Path, description = "Profile ID"),
+        DeleteProfileQuery
+    ),
+    responses(
+        (status = 200, description = "Account erased, or what would be on a dry run", body = DeletionReport),
+        (status = 403, description = "Neither the profile's owner nor an admin"),
+        (status = 404, description = "Profile not found"),
+        (status = 409, description = "Profile not linked to an account, a paid subscription is still active, or the account owns an organization with other members")
+    )
+)]
+pub async fn delete_profile(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Query(query): Query<DeleteProfileQuery>,
+) -> Result<Json<DeletionReport>, ApiError> {
+    let dry_run = query.dry_run.unwrap_or(false);
+    let linked_user: Option<Option<String>> = sqlx::query_scalar("SELECT user_id FROM profiles WHERE id = $1")
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?;
+    let linked_user = linked_user.ok_or((StatusCode::NOT_FOUND, "Profile not found".to_string()))?;
+
+    // Ownership is the linked account only: a profile's email is whatever its creator typed
+    let is_self = linked_user.as_deref() == Some(user.id.as_str());
+    if !is_self && !user.is_admin() {
+        return Err((StatusCode::FORBIDDEN, "Only the profile's owner or an admin can delete it".to_string()));
+    }
+    let user_id = linked_user.ok_or((StatusCode::CONFLICT, "Profile isn't linked to an account".to_string()))?;
+
+    let active_plan: Option<String> = sqlx::query_scalar(
+        "SELECT plan_id FROM subscriptions WHERE user_id = $1 AND status IN ('active', 'trialing', 'past_due') AND plan_id <> 'free'",
+    )
+    .bind(&user_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?;
+    if let Some(plan) = active_plan {
+        return Err((StatusCode::CONFLICT, format!("Cancel the active {} subscription before deleting the account", plan)));
+    }
+    // Ownership can't be transferred, so an owner's organization goes with them, but only once it has no one else in it
+    let teammates: Option<i64> = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FILTER (WHERE m.user_id <> $1) FROM organization_members o
+           JOIN organization_members m ON m.org_id = o.org_id
+           WHERE o.user_id = $1 AND o.role = 'owner'
+           GROUP BY o.org_id"#,
+    )
+    .bind(&user_id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?;
+    if let Some(n) = teammates.filter(|&n| n > 0) {
+        return Err((StatusCode::CONFLICT, format!("Remove the organization's {} other member(s) before deleting its owner", n)));
+    }
+
+    // HTML objects are keyed by task, so list them before the task rows go
+    let stored: Vec<(String, String)> = sqlx::query_as("SELECT engine, id FROM tasks WHERE user_id = $1")
+        .bind(&user_id)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+
+    // Dry runs run the same statements and roll back, so the counts are exact
+    let anonymous_id = format!("deleted-{}", Uuid::new_v4());
+    let mut tx = state.pool.begin().await.map_err(db_error)?;
+    let mut deleted = BTreeMap::new();
+    for (table, statement) in ERASE_STEPS {
+        let rows = sqlx::query(statement).bind(&user_id).execute(&mut *tx).await.map_err(db_error)?.rows_affected();
+        deleted.insert(table.to_string(), rows);
+    }
+    let mut anonymized = BTreeMap::new();
+    for (table, statement) in ANONYMIZE_STEPS {
+        let rows = sqlx::query(statement)
+            .bind(&user_id)
+            .bind(&anonymous_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .rows_affected();
+        anonymized.insert(table.to_string(), rows);
+    }
+    let profiles = sqlx::query("DELETE FROM profiles WHERE id = $1 OR user_id = $2")
+        .bind(&id)
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+    deleted.insert("profiles".to_string(), profiles);
+
+    if dry_run {
+        tx.rollback().await.map_err(db_error)?;
+        return Ok(Json(DeletionReport {
+            dry_run,
+            user_id,
+            deleted,
+            anonymized,
+            anonymous_id: None,
+            stored_objects: stored.len() as u64,
+            stored_objects_failed: Vec::new(),
+        }));
+    }
+    tx.commit().await.map_err(db_error)?;
+
+    let mut stored_objects_failed = Vec::new();
+    for (engine, task_id) in &stored {
+        let prefix = crate::storage::task_prefix(engine, task_id);
+        if let Err(e) = state.storage.delete_prefix(&prefix).await {
+            warn!("⚠️ [Profiles] Failed to delete {}*: {}", prefix, e);
+            stored_objects_failed.push(prefix);
+        }
+    }
+    let stored_objects = (stored.len() - stored_objects_failed.len()) as u64;
+    // Export files are copies of the user's tasks
+    let exports_prefix = crate::exports::user_prefix(&user_id);
+    if let Err(e) = state.storage.delete_prefix(&exports_prefix).await {
+        warn!("⚠️ [Profiles] Failed to delete {}*: {}", exports_prefix, e);
+        stored_objects_failed.push(exports_prefix);
+    }
+    if let Some(index) = crate::search_index::SEARCH_INDEX.as_ref() {
+        if let Err(e) = index.delete_user(&user_id).await {
+            warn!("⚠️ [Profiles] Failed to remove tasks of {} from Elasticsearch: {}", user_id, e);
+        }
+    }
+    // A deleted organization's dedicated proxies return to the general rotation
+    if deleted.get("organizations").is_some_and(|&n| n > 0) {
+        if let Err(e) = crate::organizations::load_reserved_proxies(&state.pool).await {
+            warn!("⚠️ [Profiles] Failed to reload reserved proxies: {}", e);
+        }
+    }
+    // Tokens already issued would otherwise keep working until they expire
+    if let Err(e) = state.denylist.revoke_user(&user_id).await {
+        warn!("⚠️ [Profiles] Failed to revoke tokens of {}: {}", user_id, e);
+    }
+
+    info!(
+        "🗑️ [Profiles] {} erased account {} ({} rows deleted, {} anonymized as {}, {} objects)",
+        user.id,
+        user_id,
+        deleted.values().sum::<u64>(),
+        anonymized.values().sum::<u64>(),
+        anonymous_id,
+        stored_objects
+    );
+    Ok(Json(DeletionReport {
+        dry_run,
+        user_id,
+        deleted,
+        anonymized,
+        anonymous_id: Some(anonymous_id),
+        stored_objects,
+        stored_objects_failed,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_ownership() {
+        let user = |email: Option<&str>, role: &str| AuthUser {
+            id: "u1".to_string(),
+            email: email.map(str::to_string),
+            role: role.to_string(),
+        };
+        assert!(owns_profile(&user(Some("Ann@example.com"), "authenticated"), "ann@example.com"));
+        assert!(!owns_profile(&user(Some("bob@example.com"), "authenticated"), "ann@example.com"));
+        assert!(!owns_profile(&user(None, "authenticated"), "ann@example.com"));
+        assert!(owns_profile(&user(None, "admin"), "ann@example.com"));
+    }
+
+    #[test]
+    fn test_profile_linkage() {
+        assert_eq!(linkage(None, "u1"), Linkage::Upsert);
+        assert_eq!(linkage(Some(("u1".to_string(), Some("u1".to_string()))), "u1"), Linkage::Upsert);
+        assert_eq!(linkage(Some(("p1".to_string(), None)), "u1"), Linkage::Adopt("p1".to_string()));
+        assert_eq!(linkage(Some(("p1".to_string(), Some("u1".to_string()))), "u1"), Linkage::Adopt("p1".to_string()));
+        assert_eq!(linkage(Some(("p1".to_string(), Some("u2".to_string()))), "u1"), Linkage::Conflict);
+    }
+
+    #[test]
+    fn test_erasure_statement_parameters() {
+        // Postgres rejects binds a statement doesn't use, so each list must match what it binds
+        for (table, statement) in ERASE_STEPS {
+            assert!(statement.contains("$1") && !statement.contains("$2"), "{}", table);
+        }
+        for (table, statement) in ANONYMIZE_STEPS {
+            assert!(statement.contains("$1") && statement.contains("$2"), "{}", table);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Proxy provider adapters.
+//!
+//! Providers periodically fetch their current proxy list and reconcile it into
+//! `PROXY_MANAGER`, so rotating residential endpoints don't need manual
+//! `/proxies` calls. Each provider only adds/removes the proxies it owns.
+
+use serde::Deserialize;
+use std::time::Duration;
+use crate::proxy::{Proxy, PROXY_MANAGER};
+use tracing::{info, warn};
+
+const WEBSHARE_LIST_URL: &str = "https://proxy.webshare.io/api/v2/proxy/list/?mode=direct&page_size=100";
+const BRIGHTDATA_DEFAULT_HOST: &str = "brd.superproxy.io:22225";
+
+/// A source of proxies that can be polled for its current list
+#[derive(Debug, Clone)]
+pub enum ProxyProvider {
+    /// Webshare proxy list API (`WEBSHARE_API_KEY`)
+    Webshare { api_key: String },
+    /// Bright Data zone behind the super proxy (rotates exit IPs per request)
+    Brightdata {
+        customer_id: String,
+        zone: String,
+        password: String,
+        host: String,
+    },
+    /// Any URL returning a JSON array or a newline/comma separated list of proxy strings
+    Url { url: String },
+}
+
+#[derive(Deserialize)]
+struct WebsharePage {
+    next: Option<String>,
+    results: Vec<WebshareProxy>,
+}
+
+#[derive(Deserialize)]
+struct WebshareProxy {
+    proxy_address: String,
+    port: u16,
+    username: String,
+    password: String,
+    #[serde(default = "default_true")]
+    valid: bool,
+    country_code: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ProxyProvider {
+    /// Source tag stored on every proxy this provider manages
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProxyProvider::Webshare { .. } => "webshare",
+            ProxyProvider::Brightdata { .. } => "brightdata",
+            ProxyProvider::Url { .. } => "url",
+        }
+    }
+
+    /// Build the providers configured through environment variables
+    pub fn from_env() -> Vec<ProxyProvider> {
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+        let mut providers = Vec::new();
+
+        if let Some(api_key) = var("WEBSHARE_API_KEY") {
+            providers.push(ProxyProvider::Webshare { api_key });
+        }
+        if let (Some(customer_id), Some(zone), Some(password)) = (
+            var("BRIGHTDATA_CUSTOMER_ID"),
+            var("BRIGHTDATA_ZONE"),
+            var("BRIGHTDATA_PASSWORD"),
+        ) {
+            let host = var("BRIGHTDATA_HOST").unwrap_or_else(|| BRIGHTDATA_DEFAULT_HOST.to_string());
+            providers.push(ProxyProvider::Brightdata { customer_id, zone, password, host });
+        }
+        if let Some(url) = var("PROXY_PROVIDER_URL") {
+            providers.push(ProxyProvider::Url { url });
+        }
+        providers
+    }
+
+    /// Fetch the provider's current proxy list
+    pub async fn fetch(&self, client: &reqwest::Client) -> Result<Vec<Proxy>, String> {
+        match self {
+            ProxyProvider::Webshare { api_key } => {
+                let mut proxies = Vec::new();
+                let mut next = Some(WEBSHARE_LIST_URL.to_string());
+                while let Some(url) = next {
+                    let page: WebsharePage = client
+                        .get(&url)
+                        .header("Authorization", format!("Token {}", api_key))
+                        .send()
+                        .await
+                        .and_then(|r| r.error_for_status())
+                        .map_err(|e| e.to_string())?
+                        .json()
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    proxies.extend(
+                        page.results
+                            .iter()
+                            .filter(|p| p.valid)
+                            .filter_map(|p| {
+                                let proxy = Proxy::parse(&format!("http://{}:{}@{}:{}", p.username, p.password, p.proxy_address, p.port)).ok()?;
+                                proxy.set_country(p.country_code.as_deref());
+                                Some(proxy)
+                            }),
+                    );
+                    next = page.next;
+                }
+                Ok(proxies)
+            }
+            ProxyProvider::Brightdata { customer_id, zone, password, host } => {
+                let proxy = format!("http://brd-customer-{}-zone-{}:{}@{}", customer_id, zone, password, host);
+                Ok(vec![Proxy::parse(&proxy)?])
+            }
+            ProxyProvider::Url { url } => {
+                let body = client
+                    .get(url)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(|e| e.to_string())?
+                    .text()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(parse_proxy_list(&body))
+            }
+        }
+    }
+}
+
+/// Parse a JSON array of proxy strings, or a newline/comma separated list.
+/// Invalid entries are skipped.
+pub fn parse_proxy_list(body: &str) -> Vec<Proxy> {
+    let entries: Vec<String> = serde_json::from_str(body).unwrap_or_else(|_| {
+        body.split([',', '\n'])
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty() && !s.starts_with('#'))
+            .collect()
+    });
+    entries.iter().filter_map(|s| Proxy::parse(s).ok()).collect()
+}
+
+/// Periodically refresh every configured provider into the global pool
+pub async fn start_provider_refresh() {
+    let providers = ProxyProvider::from_env();
+    if providers.is_empty() {
+        return;
+    }
+    let refresh_secs: u64 = std::env::var("PROXY_PROVIDER_REFRESH_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(600)
+        .max(30);
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .unwrap_or_default();
+
+    info!(
+        "🔄 Proxy providers: {} (refresh every {}s)",
+        providers.iter().map(|p| p.name()).collect::<Vec<_>>().join(", "),
+        refresh_secs
+    );
+    let mut ticker = tokio::time::interval(Duration::from_secs(refresh_secs));
+    loop {
+        ticker.tick().await;
+        for provider in &providers {
+            match provider.fetch(&client).await {
+                // An empty list is more likely an outage than a real answer; keep the old pool
+                Ok(proxies) if proxies.is_empty() => {
+                    warn!("⚠️ Proxy provider {} returned no proxies, keeping current pool", provider.name());
+                }
+                Ok(proxies) => {
+                    let (added, removed) = PROXY_MANAGER.reconcile_source(provider.name(), proxies);
+                    info!("🔄 Proxy provider {}: +{} / -{}", provider.name(), added, removed);
+                }
+                Err(e) => warn!("⚠️ Proxy provider {} refresh failed: {}", provider.name(), e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proxy_list_formats() {
+        let json = parse_proxy_list(r#"["10.0.0.1:8080", "user:pass@10.0.0.2:3128", "bogus"]"#);
+        assert_eq!(json.len(), 2);
+
+        let text = parse_proxy_list("# pool\n10.0.0.1:8080\nsocks5://10.0.0.3:1080, 10.0.0.4:80\n");
+        let ids: Vec<_> = text.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["10.0.0.1:8080", "10.0.0.3:1080", "10.0.0.4:80"]);
+    }
+}
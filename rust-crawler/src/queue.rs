@@ -1,14 +1,86 @@
-use redis::{Client, AsyncCommands};
+//! Crawl job queue.
+//!
+//! `QueueManager` fronts a pluggable `QueueBackend`: Redis Streams by default,
+//! Postgres (`QUEUE_BACKEND=postgres`) for deployments without Redis, or an
+//! in-process queue (`QUEUE_BACKEND=memory`, the default in dev mode).
+
 use anyhow::Result;
-use std::env;
+use axum::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use std::sync::Arc;
+use crate::config::{QueueBackendKind, QueueConfig};
+use crate::crawler::CrawlOptions;
+use tracing::info;
 
-#[derive(Clone)]
-pub struct QueueManager {
-    client: Client,
+/// Retries after the first failed attempt, unless the request says otherwise
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+/// First retry delay; doubles on every further attempt
+pub const DEFAULT_BACKOFF_SECS: u64 = 30;
+/// Upper bound for a single retry delay
+const MAX_BACKOFF_SECS: u64 = 3600;
+
+/// Queue priority: interactive crawls use `high`, bulk/scheduled work `low`
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
 }
 
-use serde::{Deserialize, Serialize};
-use crate::api::CrawlRequest;
+impl Priority {
+    /// Highest first, the order lanes are drained in
+    pub const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    /// Sort key; lower runs first
+    pub fn rank(self) -> i16 {
+        match self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Normal => "normal",
+            Priority::Low => "low",
+        }
+    }
+}
+
+/// Independent queue per job type, so slow generic crawls can't starve SERP lookups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    Google,
+    Bing,
+    Generic,
+}
+
+impl Lane {
+    pub const ALL: [Lane; 3] = [Lane::Google, Lane::Bing, Lane::Generic];
+
+    /// Unknown engines run the Bing path in the worker, so they queue there too
+    pub fn from_engine(engine: &str) -> Self {
+        match engine {
+            "google" => Lane::Google,
+            "generic" => Lane::Generic,
+            _ => Lane::Bing,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Lane::Google => "google",
+            Lane::Bing => "bing",
+            Lane::Generic => "generic",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CrawlJob {
@@ -16,39 +88,245 @@ pub struct CrawlJob {
     pub user_id: String, // Added user_id
     pub keyword: String,
     pub engine: String,
-    pub selectors: Option<std::collections::HashMap<String, String>>,
+    #[serde(flatten)]
+    pub options: CrawlOptions,
+    /// Retries allowed after the first failure
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Base delay for exponential backoff between attempts
+    #[serde(default = "default_backoff_secs")]
+    pub backoff_secs: u64,
+    /// Failed attempts so far (0 on first run)
+    #[serde(default)]
+    pub attempt: u32,
+    #[serde(default)]
+    pub priority: Priority,
+    /// Don't start before this time; the job stays delayed until then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Page monitor this crawl checks; the worker compares the extracted text afterwards
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor_id: Option<String>,
+    /// URL the final task record is POSTed to when the job completes or fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    /// Backend handle for the claim (stream entry id, row id); handed back to `ack_job` when done
+    #[serde(skip)]
+    pub receipt: Option<String>,
+}
+
+fn default_max_retries() -> u32 {
+    DEFAULT_MAX_RETRIES
+}
+
+fn default_backoff_secs() -> u64 {
+    DEFAULT_BACKOFF_SECS
+}
+
+impl CrawlJob {
+    pub fn lane(&self) -> Lane {
+        Lane::from_engine(&self.engine)
+    }
+
+    /// Delay before the next attempt: backoff_secs * 2^(attempt - 1), capped at an hour
+    pub fn retry_delay(&self) -> u64 {
+        let exp = self.attempt.saturating_sub(1).min(16);
+        self.backoff_secs.saturating_mul(1 << exp).min(MAX_BACKOFF_SECS)
+    }
+
+    /// Whether another attempt is allowed after `attempt` failures
+    pub fn can_retry(&self) -> bool {
+        self.attempt <= self.max_retries
+    }
+}
+
+/// Storage and delivery of crawl jobs. Implementations must hand each ready job
+/// to one worker at a time and keep it recoverable until `ack`.
+#[async_trait]
+pub trait QueueBackend: Send + Sync {
+    /// Short name for logs
+    fn name(&self) -> &'static str;
+
+    /// Make a job available to workers now
+    async fn push(&self, job: &CrawlJob) -> Result<()>;
+
+    /// Make a job available once unix time `due` has passed
+    async fn schedule(&self, job: &CrawlJob, due: i64) -> Result<()>;
+
+    /// Claim the next ready job of `lane` for `worker_id`, highest priority first
+    async fn pop(&self, worker_id: &str, lane: Lane) -> Result<Option<CrawlJob>>;
+
+    /// Acknowledge a claimed job once it has completed, failed or been rescheduled
+    async fn ack(&self, job: &CrawlJob) -> Result<()>;
+
+    /// Reserve `key` for `task_id` for `window_secs`. Returns the task id already
+    /// holding the key if this is a duplicate submission.
+    async fn claim_dedup_key(&self, key: &str, task_id: &str, window_secs: u64) -> Result<Option<String>>;
+
+    /// Drop a dedup reservation, e.g. when the submission was refused after all
+    async fn release_dedup_key(&self, key: &str) -> Result<()>;
+
+    /// Refresh `worker_id`'s heartbeat; it counts as dead once `ttl_secs` pass without one
+    async fn heartbeat(&self, worker_id: &str, ttl_secs: u64) -> Result<()>;
+
+    /// Take over (as `janitor_id`, a live worker) jobs claimed by workers whose
+    /// heartbeat has expired, returning each with its dead owner. The caller
+    /// decides whether each is retried or failed.
+    async fn take_orphaned_jobs(&self, janitor_id: &str) -> Result<Vec<(CrawlJob, String)>>;
+
+    /// Count a finished job towards the last-hour throughput stats
+    async fn record_outcome(&self, succeeded: bool) -> Result<()>;
+
+    /// Current backlog and last-hour throughput
+    async fn snapshot(&self) -> Result<QueueSnapshot>;
+
+    /// Set the shared pause flag every worker checks before claiming a job
+    async fn set_paused(&self, paused: bool) -> Result<()>;
+
+    async fn is_paused(&self) -> Result<bool>;
+}
+
+/// Backlog of one lane
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct LaneDepth {
+    #[schema(example = "bing")]
+    pub lane: String,
+    /// Jobs waiting to be claimed
+    pub ready: u64,
+    /// Jobs claimed by a worker but not yet acknowledged
+    pub in_flight: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct QueueSnapshot {
+    pub lanes: Vec<LaneDepth>,
+    /// Retries and `run_at` jobs that aren't due yet
+    pub delayed: u64,
+    /// Seconds since the oldest ready or in-flight job became ready
+    pub oldest_job_age_secs: Option<i64>,
+    /// Jobs completed in the last hour
+    pub processed_last_hour: u64,
+    /// Jobs that failed for good (retries exhausted) in the last hour
+    pub failed_last_hour: u64,
+}
+
+#[derive(Clone)]
+pub struct QueueManager {
+    backend: Arc<dyn QueueBackend>,
 }
 
 impl QueueManager {
-    pub async fn new() -> Result<Self> {
-        let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
-        let client = Client::open(redis_url)?;
-        
-        // Test connection
-        let mut conn = client.get_async_connection().await?;
-        let _: String = redis::cmd("PING").query_async(&mut conn).await?;
-        println!("✅ Redis Connected successfully");
+    /// Connect the backend selected by `[queue] backend` / `QUEUE_BACKEND` (`redis`,
+    /// `postgres` or `memory`; defaults to `memory` in dev mode and `redis` otherwise)
+    pub async fn new(pool: &PgPool, config: &QueueConfig) -> Result<Self> {
+        let backend: Arc<dyn QueueBackend> = match config.backend() {
+            QueueBackendKind::Postgres => Arc::new(crate::queue_postgres::PostgresQueue::new(pool.clone())),
+            QueueBackendKind::Redis => Arc::new(crate::queue_redis::RedisQueue::connect().await?),
+            QueueBackendKind::Memory => Arc::new(crate::queue_memory::MemoryQueue::default()),
+        };
+        info!("📬 Queue backend: {}", backend.name());
+        Ok(Self { backend })
+    }
 
-        Ok(Self { client })
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
     }
 
+    /// Enqueue a job, or hold it back if its `run_at` is in the future
+    #[tracing::instrument(name = "queue.push", skip_all, fields(task_id = %job.id, lane = job.lane().as_str()))]
     pub async fn push_job(&self, job: CrawlJob) -> Result<()> {
-        let mut conn = self.client.get_async_connection().await?;
-        let job_json = serde_json::to_string(&job)?;
-        conn.lpush::<_, _, ()>("crawl_queue", job_json).await?;
-        Ok(())
-    }
-
-    pub async fn pop_job(&self) -> Result<Option<CrawlJob>> {
-        let mut conn = self.client.get_async_connection().await?;
-        let result: Option<String> = conn.rpop("crawl_queue", None).await?;
-        
-        match result {
-            Some(json) => {
-                let job: CrawlJob = serde_json::from_str(&json)?;
-                Ok(Some(job))
+        if let Some(run_at) = job.run_at {
+            if run_at > chrono::Utc::now() {
+                return self.backend.schedule(&job, run_at.timestamp()).await;
             }
-            None => Ok(None)
         }
+        self.backend.push(&job).await
+    }
+
+    /// Hold a job back until `delay_secs` from now; `pop_job` picks it up once due
+    #[tracing::instrument(name = "queue.push_delayed", skip_all, fields(task_id = %job.id, delay_secs))]
+    pub async fn push_job_delayed(&self, job: CrawlJob, delay_secs: u64) -> Result<()> {
+        let due = chrono::Utc::now().timestamp() + delay_secs as i64;
+        self.backend.schedule(&job, due).await
+    }
+
+    /// Claim the next job from `lane` for `worker_id`. It stays claimed until
+    /// `ack_job`, so a worker that dies mid-crawl doesn't lose it. Workers poll
+    /// this every second, so its span is debug level.
+    #[tracing::instrument(name = "queue.pop", level = "debug", skip(self, lane), fields(lane = lane.as_str(), task_id = tracing::field::Empty))]
+    pub async fn pop_job(&self, worker_id: &str, lane: Lane) -> Result<Option<CrawlJob>> {
+        let job = self.backend.pop(worker_id, lane).await?;
+        if let Some(ref job) = job {
+            tracing::Span::current().record("task_id", job.id.as_str());
+        }
+        Ok(job)
+    }
+
+    #[tracing::instrument(name = "queue.ack", skip_all, fields(task_id = %job.id))]
+    pub async fn ack_job(&self, job: &CrawlJob) -> Result<()> {
+        self.backend.ack(job).await
+    }
+
+    pub async fn claim_dedup_key(&self, key: &str, task_id: &str, window_secs: u64) -> Result<Option<String>> {
+        self.backend.claim_dedup_key(key, task_id, window_secs).await
+    }
+
+    pub async fn release_dedup_key(&self, key: &str) -> Result<()> {
+        self.backend.release_dedup_key(key).await
+    }
+
+    pub async fn heartbeat(&self, worker_id: &str, ttl_secs: u64) -> Result<()> {
+        self.backend.heartbeat(worker_id, ttl_secs).await
+    }
+
+    pub async fn take_orphaned_jobs(&self, janitor_id: &str) -> Result<Vec<(CrawlJob, String)>> {
+        self.backend.take_orphaned_jobs(janitor_id).await
+    }
+
+    pub async fn record_outcome(&self, succeeded: bool) -> Result<()> {
+        self.backend.record_outcome(succeeded).await
+    }
+
+    pub async fn stats(&self) -> Result<QueueSnapshot> {
+        self.backend.snapshot().await
+    }
+
+    /// Pause or resume job claiming on all workers; running jobs finish either way
+    pub async fn set_paused(&self, paused: bool) -> Result<()> {
+        self.backend.set_paused(paused).await
+    }
+
+    pub async fn is_paused(&self) -> Result<bool> {
+        self.backend.is_paused().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_backoff_is_exponential_and_capped() {
+        let mut job: CrawlJob = serde_json::from_value(serde_json::json!({
+            "id": "t1", "user_id": "u1", "keyword": "rust", "engine": "bing"
+        }))
+        .unwrap();
+        assert_eq!(job.max_retries, DEFAULT_MAX_RETRIES);
+
+        job.attempt = 1;
+        assert_eq!(job.retry_delay(), 30);
+        assert!(job.can_retry());
+        job.attempt = 3;
+        assert_eq!(job.retry_delay(), 120);
+        job.attempt = 30;
+        assert_eq!(job.retry_delay(), MAX_BACKOFF_SECS);
+        assert!(!job.can_retry());
+    }
+
+    #[test]
+    fn test_lane_from_engine() {
+        assert_eq!(Lane::from_engine("google"), Lane::Google);
+        assert_eq!(Lane::from_engine("generic"), Lane::Generic);
+        assert_eq!(Lane::from_engine("yahoo"), Lane::Bing);
     }
 }
@@ -0,0 +1,231 @@
+//! In-process queue backend (`QUEUE_BACKEND=memory`, the default with `APP_MODE=dev`).
+//!
+//! Lets the API and worker run locally without Redis. Jobs live only as long as
+//! the process, so this is for development, not deployments.
+
+use anyhow::Result;
+use axum::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::queue::{CrawlJob, Lane, LaneDepth, QueueBackend, QueueSnapshot};
+
+#[derive(Default)]
+pub struct MemoryQueue {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    next_id: u64,
+    entries: Vec<Entry>,
+    /// Dedup key -> (task id, expiry)
+    dedup: HashMap<String, (String, Instant)>,
+    /// Worker id -> heartbeat expiry
+    heartbeats: HashMap<String, Instant>,
+    /// Finish time and success of recent jobs, oldest first
+    outcomes: VecDeque<(Instant, bool)>,
+    paused: bool,
+}
+
+struct Entry {
+    id: u64,
+    job: CrawlJob,
+    /// Unix timestamp the job becomes (or became) ready at
+    due: i64,
+    claimed_by: Option<String>,
+}
+
+impl MemoryQueue {
+    fn insert(&self, job: &CrawlJob, due: i64) {
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let id = state.next_id;
+        let mut job = job.clone();
+        job.receipt = None;
+        state.entries.push(Entry { id, job, due, claimed_by: None });
+    }
+}
+
+#[async_trait]
+impl QueueBackend for MemoryQueue {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn push(&self, job: &CrawlJob) -> Result<()> {
+        self.insert(job, chrono::Utc::now().timestamp());
+        Ok(())
+    }
+
+    async fn schedule(&self, job: &CrawlJob, due: i64) -> Result<()> {
+        self.insert(job, due);
+        Ok(())
+    }
+
+    async fn pop(&self, worker_id: &str, lane: Lane) -> Result<Option<CrawlJob>> {
+        let now = chrono::Utc::now().timestamp();
+        let mut state = self.state.lock().unwrap();
+        let next = state
+            .entries
+            .iter_mut()
+            .filter(|e| e.claimed_by.is_none() && e.due <= now && e.job.lane() == lane)
+            .min_by_key(|e| (e.job.priority.rank(), e.due, e.id));
+
+        Ok(next.map(|entry| {
+            entry.claimed_by = Some(worker_id.to_string());
+            let mut job = entry.job.clone();
+            job.receipt = Some(entry.id.to_string());
+            job
+        }))
+    }
+
+    async fn ack(&self, job: &CrawlJob) -> Result<()> {
+        let Some(id) = job.receipt.as_deref().and_then(|r| r.parse::<u64>().ok()) else { return Ok(()) };
+        self.state.lock().unwrap().entries.retain(|e| e.id != id);
+        Ok(())
+    }
+
+    async fn claim_dedup_key(&self, key: &str, task_id: &str, window_secs: u64) -> Result<Option<String>> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        match state.dedup.get(key) {
+            Some((existing, expires)) if *expires > now => Ok(Some(existing.clone())),
+            _ => {
+                state.dedup.insert(key.to_string(), (task_id.to_string(), now + Duration::from_secs(window_secs)));
+                Ok(None)
+            }
+        }
+    }
+
+    async fn release_dedup_key(&self, key: &str) -> Result<()> {
+        self.state.lock().unwrap().dedup.remove(key);
+        Ok(())
+    }
+
+    async fn heartbeat(&self, worker_id: &str, ttl_secs: u64) -> Result<()> {
+        let expires = Instant::now() + Duration::from_secs(ttl_secs);
+        self.state.lock().unwrap().heartbeats.insert(worker_id.to_string(), expires);
+        Ok(())
+    }
+
+    async fn take_orphaned_jobs(&self, _janitor_id: &str) -> Result<Vec<(CrawlJob, String)>> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        state.dedup.retain(|_, (_, expires)| *expires > now);
+
+        let MemoryState { entries, heartbeats, .. } = &mut *state;
+        let alive = |worker: &str| heartbeats.get(worker).is_some_and(|expires| *expires > now);
+        let (orphaned, kept): (Vec<Entry>, Vec<Entry>) = entries
+            .drain(..)
+            .partition(|e| e.claimed_by.as_deref().is_some_and(|owner| !alive(owner)));
+        *entries = kept;
+
+        Ok(orphaned
+            .into_iter()
+            .map(|e| (e.job, e.claimed_by.unwrap_or_default()))
+            .collect())
+    }
+
+    async fn record_outcome(&self, succeeded: bool) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.outcomes.push_back((Instant::now(), succeeded));
+        while state.outcomes.front().is_some_and(|(at, _)| at.elapsed() > Duration::from_secs(3600)) {
+            state.outcomes.pop_front();
+        }
+        Ok(())
+    }
+
+    async fn snapshot(&self) -> Result<QueueSnapshot> {
+        let now = chrono::Utc::now().timestamp();
+        let state = self.state.lock().unwrap();
+        let lanes = Lane::ALL
+            .into_iter()
+            .map(|lane| {
+                let entries = state.entries.iter().filter(|e| e.job.lane() == lane);
+                let (in_flight, ready): (Vec<&Entry>, Vec<&Entry>) =
+                    entries.filter(|e| e.claimed_by.is_some() || e.due <= now).partition(|e| e.claimed_by.is_some());
+                LaneDepth { lane: lane.as_str().to_string(), ready: ready.len() as u64, in_flight: in_flight.len() as u64 }
+            })
+            .collect();
+        let recent = state.outcomes.iter().filter(|(at, _)| at.elapsed() <= Duration::from_secs(3600));
+
+        Ok(QueueSnapshot {
+            lanes,
+            delayed: state.entries.iter().filter(|e| e.claimed_by.is_none() && e.due > now).count() as u64,
+            oldest_job_age_secs: state.entries.iter().map(|e| e.due).filter(|due| *due <= now).min().map(|due| now - due),
+            processed_last_hour: recent.clone().filter(|(_, ok)| *ok).count() as u64,
+            failed_last_hour: recent.filter(|(_, ok)| !*ok).count() as u64,
+        })
+    }
+
+    async fn set_paused(&self, paused: bool) -> Result<()> {
+        self.state.lock().unwrap().paused = paused;
+        Ok(())
+    }
+
+    async fn is_paused(&self) -> Result<bool> {
+        Ok(self.state.lock().unwrap().paused)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::Priority;
+
+    fn job(id: &str, engine: &str, priority: Priority) -> CrawlJob {
+        let mut job: CrawlJob = serde_json::from_value(serde_json::json!({
+            "id": id, "user_id": "u1", "keyword": "rust", "engine": engine
+        }))
+        .unwrap();
+        job.priority = priority;
+        job
+    }
+
+    #[tokio::test]
+    async fn test_memory_queue_claims_by_lane_and_priority() {
+        let queue = MemoryQueue::default();
+        queue.push(&job("low", "bing", Priority::Low)).await.unwrap();
+        queue.push(&job("high", "bing", Priority::High)).await.unwrap();
+        queue.push(&job("google", "google", Priority::Normal)).await.unwrap();
+        queue.schedule(&job("later", "bing", Priority::High), chrono::Utc::now().timestamp() + 3600).await.unwrap();
+
+        let first = queue.pop("w1", Lane::Bing).await.unwrap().unwrap();
+        assert_eq!(first.id, "high");
+        let second = queue.pop("w1", Lane::Bing).await.unwrap().unwrap();
+        assert_eq!(second.id, "low");
+        assert!(queue.pop("w1", Lane::Bing).await.unwrap().is_none());
+
+        queue.ack(&first).await.unwrap();
+        // w1 never heartbeat, so its remaining claim is orphaned
+        let orphaned = queue.take_orphaned_jobs("w2").await.unwrap();
+        assert_eq!(orphaned.len(), 1);
+        assert_eq!((orphaned[0].0.id.as_str(), orphaned[0].1.as_str()), ("low", "w1"));
+
+        queue.heartbeat("w2", 30).await.unwrap();
+        let google = queue.pop("w2", Lane::Google).await.unwrap().unwrap();
+        assert!(queue.take_orphaned_jobs("w2").await.unwrap().is_empty());
+
+        let stats = queue.snapshot().await.unwrap();
+        let google_depth = stats.lanes.iter().find(|l| l.lane == "google").unwrap();
+        assert_eq!((google_depth.ready, google_depth.in_flight), (0, 1));
+        assert_eq!(stats.delayed, 1);
+
+        queue.ack(&google).await.unwrap();
+        queue.record_outcome(true).await.unwrap();
+        queue.record_outcome(false).await.unwrap();
+        let stats = queue.snapshot().await.unwrap();
+        assert_eq!((stats.processed_last_hour, stats.failed_last_hour), (1, 1));
+        assert_eq!(stats.oldest_job_age_secs, None);
+    }
+
+    #[tokio::test]
+    async fn test_memory_queue_dedup_window() {
+        let queue = MemoryQueue::default();
+        assert_eq!(queue.claim_dedup_key("k", "t1", 60).await.unwrap(), None);
+        assert_eq!(queue.claim_dedup_key("k", "t2", 60).await.unwrap(), Some("t1".to_string()));
+        queue.release_dedup_key("k").await.unwrap();
+        assert_eq!(queue.claim_dedup_key("k", "t3", 60).await.unwrap(), None);
+    }
+}
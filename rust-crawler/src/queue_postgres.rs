@@ -0,0 +1,236 @@
+//! Postgres queue backend (`QUEUE_BACKEND=postgres`).
+//!
+//! Jobs are rows in `crawl_job_queue`; workers claim them with
+//! `SELECT ... FOR UPDATE SKIP LOCKED`, so concurrent workers never block on or
+//! double-claim the same row. Acknowledged jobs are deleted.
+
+use anyhow::Result;
+use axum::async_trait;
+use sqlx::PgPool;
+use crate::queue::{CrawlJob, Lane, LaneDepth, QueueBackend, QueueSnapshot};
+use tracing::warn;
+
+#[derive(Clone)]
+pub struct PostgresQueue {
+    pool: PgPool,
+}
+
+impl PostgresQueue {
+    /// The tables come from the `job_queue` migration
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn insert(&self, job: &CrawlJob, due: Option<i64>) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO crawl_job_queue (lane, priority, payload, run_at) VALUES ($1, $2, $3, COALESCE(to_timestamp($4), now()))",
+        )
+        .bind(job.lane().as_str())
+        .bind(job.priority.rank())
+        .bind(serde_json::to_string(job)?)
+        .bind(due.map(|d| d as f64))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl QueueBackend for PostgresQueue {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn push(&self, job: &CrawlJob) -> Result<()> {
+        self.insert(job, None).await
+    }
+
+    async fn schedule(&self, job: &CrawlJob, due: i64) -> Result<()> {
+        self.insert(job, Some(due)).await
+    }
+
+    async fn pop(&self, worker_id: &str, lane: Lane) -> Result<Option<CrawlJob>> {
+        let claimed: Option<(i64, String)> = sqlx::query_as(
+            r#"UPDATE crawl_job_queue SET claimed_by = $1, claimed_at = now()
+               WHERE id = (
+                   SELECT id FROM crawl_job_queue
+                   WHERE lane = $2 AND claimed_by IS NULL AND run_at <= now()
+                   ORDER BY priority, run_at, id
+                   FOR UPDATE SKIP LOCKED
+                   LIMIT 1
+               )
+               RETURNING id, payload"#,
+        )
+        .bind(worker_id)
+        .bind(lane.as_str())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some((id, payload)) = claimed else { return Ok(None) };
+        match serde_json::from_str::<CrawlJob>(&payload) {
+            Ok(mut job) => {
+                job.receipt = Some(id.to_string());
+                Ok(Some(job))
+            }
+            Err(e) => {
+                // Unparseable payloads would be recovered forever; drop them here
+                sqlx::query("DELETE FROM crawl_job_queue WHERE id = $1").bind(id).execute(&self.pool).await?;
+                Err(e.into())
+            }
+        }
+    }
+
+    async fn ack(&self, job: &CrawlJob) -> Result<()> {
+        let Some(id) = job.receipt.as_deref().and_then(|r| r.parse::<i64>().ok()) else { return Ok(()) };
+        sqlx::query("DELETE FROM crawl_job_queue WHERE id = $1").bind(id).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn claim_dedup_key(&self, key: &str, task_id: &str, window_secs: u64) -> Result<Option<String>> {
+        // Takes the key if it's free or expired; otherwise nothing is returned
+        let claimed: Option<(String,)> = sqlx::query_as(
+            r#"INSERT INTO crawl_dedup (key, task_id, expires_at) VALUES ($1, $2, now() + make_interval(secs => $3))
+               ON CONFLICT (key) DO UPDATE SET task_id = EXCLUDED.task_id, expires_at = EXCLUDED.expires_at
+               WHERE crawl_dedup.expires_at <= now()
+               RETURNING task_id"#,
+        )
+        .bind(key)
+        .bind(task_id)
+        .bind(window_secs as f64)
+        .fetch_optional(&self.pool)
+        .await?;
+        if claimed.is_some() {
+            return Ok(None);
+        }
+
+        let existing: Option<(String,)> = sqlx::query_as("SELECT task_id FROM crawl_dedup WHERE key = $1")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(existing.map(|(task_id,)| task_id))
+    }
+
+    async fn release_dedup_key(&self, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM crawl_dedup WHERE key = $1").bind(key).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn heartbeat(&self, worker_id: &str, ttl_secs: u64) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO crawl_worker_heartbeats (worker_id, expires_at) VALUES ($1, now() + make_interval(secs => $2))
+               ON CONFLICT (worker_id) DO UPDATE SET expires_at = EXCLUDED.expires_at"#,
+        )
+        .bind(worker_id)
+        .bind(ttl_secs as f64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn take_orphaned_jobs(&self, _janitor_id: &str) -> Result<Vec<(CrawlJob, String)>> {
+        // Row locks make the DELETE the claim: concurrent janitors never return the same job
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            r#"DELETE FROM crawl_job_queue q
+               WHERE q.claimed_by IS NOT NULL
+                 AND NOT EXISTS (
+                     SELECT 1 FROM crawl_worker_heartbeats h
+                     WHERE h.worker_id = q.claimed_by AND h.expires_at > now()
+                 )
+               RETURNING q.payload, q.claimed_by"#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Housekeeping: forget long-dead workers and expired dedup keys
+        let _ = sqlx::query("DELETE FROM crawl_worker_heartbeats WHERE expires_at < now() - interval '1 day'").execute(&self.pool).await;
+        let _ = sqlx::query("DELETE FROM crawl_dedup WHERE expires_at < now()").execute(&self.pool).await;
+        let _ = sqlx::query("DELETE FROM crawl_job_outcomes WHERE minute < now() - interval '2 hours'").execute(&self.pool).await;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(payload, owner)| match serde_json::from_str::<CrawlJob>(&payload) {
+                Ok(job) => Some((job, owner)),
+                Err(e) => {
+                    warn!("⚠️ Dropping unparseable orphaned job: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    async fn record_outcome(&self, succeeded: bool) -> Result<()> {
+        let (processed, failed) = if succeeded { (1, 0) } else { (0, 1) };
+        sqlx::query(
+            r#"INSERT INTO crawl_job_outcomes (minute, processed, failed) VALUES (date_trunc('minute', now()), $1, $2)
+               ON CONFLICT (minute) DO UPDATE SET
+                   processed = crawl_job_outcomes.processed + EXCLUDED.processed,
+                   failed = crawl_job_outcomes.failed + EXCLUDED.failed"#,
+        )
+        .bind(processed)
+        .bind(failed)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn snapshot(&self) -> Result<QueueSnapshot> {
+        let rows: Vec<(String, i64, i64)> = sqlx::query_as(
+            r#"SELECT lane,
+                   COUNT(*) FILTER (WHERE claimed_by IS NULL AND run_at <= now()),
+                   COUNT(*) FILTER (WHERE claimed_by IS NOT NULL)
+               FROM crawl_job_queue GROUP BY lane"#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let lanes = Lane::ALL
+            .into_iter()
+            .map(|lane| {
+                let (ready, in_flight) = rows
+                    .iter()
+                    .find(|(name, _, _)| name == lane.as_str())
+                    .map(|(_, ready, in_flight)| (*ready as u64, *in_flight as u64))
+                    .unwrap_or_default();
+                LaneDepth { lane: lane.as_str().to_string(), ready, in_flight }
+            })
+            .collect();
+
+        let (delayed, oldest_job_age_secs): (i64, Option<i64>) = sqlx::query_as(
+            r#"SELECT COUNT(*) FILTER (WHERE claimed_by IS NULL AND run_at > now()),
+                   EXTRACT(EPOCH FROM now() - MIN(run_at) FILTER (WHERE run_at <= now()))::BIGINT
+               FROM crawl_job_queue"#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let (processed, failed): (i64, i64) = sqlx::query_as(
+            "SELECT COALESCE(SUM(processed), 0)::BIGINT, COALESCE(SUM(failed), 0)::BIGINT FROM crawl_job_outcomes WHERE minute > now() - interval '1 hour'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(QueueSnapshot {
+            lanes,
+            delayed: delayed as u64,
+            oldest_job_age_secs,
+            processed_last_hour: processed as u64,
+            failed_last_hour: failed as u64,
+        })
+    }
+
+    async fn set_paused(&self, paused: bool) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO crawl_queue_flags (name, enabled) VALUES ('paused', $1) ON CONFLICT (name) DO UPDATE SET enabled = EXCLUDED.enabled",
+        )
+        .bind(paused)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn is_paused(&self) -> Result<bool> {
+        let paused: Option<(bool,)> = sqlx::query_as("SELECT enabled FROM crawl_queue_flags WHERE name = 'paused'")
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(paused.is_some_and(|(enabled,)| enabled))
+    }
+}
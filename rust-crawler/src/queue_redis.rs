@@ -0,0 +1,447 @@
+//! Redis queue backend.
+//!
+//! Ready jobs live in one stream per lane and priority, consumed through a
+//! shared consumer group so entries stay pending until acknowledged. Delayed
+//! jobs wait in a sorted set, and workers prove liveness with expiring keys.
+//!
+//! A worker restarted under the same `WORKER_ID` comes back alive, so the
+//! janitor won't reclaim the entries it held when it stopped; instead its first
+//! poll of each stream re-reads its own pending entries (`XREADGROUP` from `0`)
+//! and hands those out before any new ones.
+
+use anyhow::Result;
+use axum::async_trait;
+use redis::{Client, AsyncCommands, RedisResult};
+use redis::streams::{
+    StreamClaimReply, StreamId, StreamInfoConsumersReply, StreamPendingCountReply, StreamPendingReply, StreamRangeReply,
+    StreamReadOptions, StreamReadReply,
+};
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use crate::queue::{CrawlJob, Lane, LaneDepth, Priority, QueueBackend, QueueSnapshot};
+use tracing::{info, warn};
+
+/// Redis streams holding jobs ready to run, one per lane and priority
+const STREAM_KEY_PREFIX: &str = "crawl_stream";
+/// Consumer group shared by all workers; each worker consumes under its worker id
+const CONSUMER_GROUP: &str = "crawl_workers";
+/// Stream entry field carrying the job payload
+const JOB_FIELD: &str = "job";
+/// Pre-streams list queues (bing lane used plain `crawl_queue`), drained into streams on startup
+const LEGACY_QUEUE_KEY_PREFIX: &str = "crawl_queue";
+const LEGACY_PROCESSING_KEY: &str = "crawl_processing";
+const LEGACY_CLAIM_KEYS: [&str; 2] = ["crawl_claims", "crawl_claim_owners"];
+/// Set once the list queues have been drained, so later startups skip it
+const LEGACY_MIGRATED_KEY: &str = "crawl_queue:migrated_to_streams";
+/// Redis sorted set of delayed jobs (retries, `run_at`), scored by due unix timestamp
+const DELAYED_KEY: &str = "crawl_delayed";
+/// Prefix of per-worker heartbeat keys; a key expiring means the worker is gone
+const HEARTBEAT_KEY_PREFIX: &str = "crawl_worker:";
+/// Prefix of dedup keys mapping a submission fingerprint to its task id
+const DEDUP_KEY_PREFIX: &str = "crawl_dedup:";
+/// Set while workers are paused (`POST /worker/pause`)
+const PAUSED_KEY: &str = "crawl_workers:paused";
+/// Prefix of per-minute outcome counters (`crawl_stats:<processed|failed>:<unix minute>`)
+const STATS_KEY_PREFIX: &str = "crawl_stats:";
+
+/// Entries a consumer held before it restarted, keyed by (consumer, stream)
+type RecoveredEntries = HashMap<(String, String), VecDeque<CrawlJob>>;
+
+#[derive(Clone)]
+pub struct RedisQueue {
+    client: Client,
+    /// Filled on the first poll of each stream, then drained before reading new entries
+    recovered: Arc<Mutex<RecoveredEntries>>,
+}
+
+fn stream_key(lane: Lane, priority: Priority) -> String {
+    format!("{}:{}:{}", STREAM_KEY_PREFIX, lane.as_str(), priority.as_str())
+}
+
+fn stream_key_for(job: &CrawlJob) -> String {
+    stream_key(job.lane(), job.priority)
+}
+
+fn stats_key(kind: &str, minute: i64) -> String {
+    format!("{}{}:{}", STATS_KEY_PREFIX, kind, minute)
+}
+
+fn legacy_list_key(lane: Lane, priority: Priority) -> String {
+    let suffix = match priority {
+        Priority::High => ":high",
+        Priority::Normal => "",
+        Priority::Low => ":low",
+    };
+    match lane {
+        Lane::Bing => format!("{}{}", LEGACY_QUEUE_KEY_PREFIX, suffix),
+        lane => format!("{}:{}{}", LEGACY_QUEUE_KEY_PREFIX, lane.as_str(), suffix),
+    }
+}
+
+impl RedisQueue {
+    pub async fn connect() -> Result<Self> {
+        let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
+        let client = Client::open(redis_url)?;
+        
+        // Test connection
+        let mut conn = client.get_async_connection().await?;
+        let _: String = redis::cmd("PING").query_async(&mut conn).await?;
+        info!("✅ Redis Connected successfully");
+
+        ensure_groups(&mut conn).await?;
+        if !conn.exists::<_, bool>(LEGACY_MIGRATED_KEY).await? {
+            let migrated = migrate_list_queues(&mut conn).await?;
+            if migrated > 0 {
+                info!("📦 Moved {} queued job(s) from list queues to streams", migrated);
+            }
+            conn.set::<_, _, ()>(LEGACY_MIGRATED_KEY, chrono::Utc::now().timestamp()).await?;
+        }
+
+        Ok(Self { client, recovered: Arc::default() })
+    }
+
+    /// Next entry `worker_id` held on `key` before this process started. The
+    /// first call per stream reads the consumer's whole pending list; entries
+    /// claimed by this process afterwards are never re-read.
+    async fn next_recovered(&self, conn: &mut redis::aio::Connection, worker_id: &str, key: &str) -> Result<Option<CrawlJob>> {
+        let mut recovered = self.recovered.lock().await;
+        let slot = (worker_id.to_string(), key.to_string());
+        if !recovered.contains_key(&slot) {
+            let options = StreamReadOptions::default().group(CONSUMER_GROUP, worker_id).count(RECOVER_BATCH);
+            let reply: RedisResult<Option<StreamReadReply>> = conn.xread_options(&[key], &["0"], &options).await;
+            let entries = match reply {
+                Ok(reply) => reply.into_iter().flat_map(|r| r.keys).flat_map(|k| k.ids).collect(),
+                Err(e) if e.code() == Some("NOGROUP") => Vec::new(),
+                Err(e) => return Err(e.into()),
+            };
+            let mut jobs = VecDeque::new();
+            for entry in entries {
+                match parse_entry(&entry) {
+                    Some(mut job) => {
+                        job.receipt = Some(entry.id);
+                        jobs.push_back(job);
+                    }
+                    None => {
+                        warn!("⚠️ Dropping unparseable stream entry {} from {}", entry.id, key);
+                        release_entry(conn, key, &entry.id).await?;
+                    }
+                }
+            }
+            if !jobs.is_empty() {
+                info!("♻️ Resuming {} job(s) {} held on {} before restarting", jobs.len(), worker_id, key);
+            }
+            recovered.insert(slot.clone(), jobs);
+        }
+        Ok(recovered.get_mut(&slot).and_then(VecDeque::pop_front))
+    }
+
+    /// Move delayed jobs whose time has come onto their stream
+    async fn promote_due_jobs(&self, conn: &mut redis::aio::Connection) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let due: Vec<String> = conn.zrangebyscore_limit(DELAYED_KEY, "-inf", now, 0, 100).await?;
+        for job_json in due {
+            // Only the worker that actually removes the entry enqueues it
+            let removed: i64 = conn.zrem(DELAYED_KEY, &job_json).await?;
+            if removed == 1 {
+                add_to_stream(conn, &ready_stream(&job_json), &job_json).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl QueueBackend for RedisQueue {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn push(&self, job: &CrawlJob) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let job_json = serde_json::to_string(job)?;
+        add_to_stream(&mut conn, &stream_key_for(job), &job_json).await
+    }
+
+    /// Delayed jobs wait in a sorted set; `pop` moves them onto their stream once due
+    async fn schedule(&self, job: &CrawlJob, due: i64) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let job_json = serde_json::to_string(job)?;
+        conn.zadd::<_, _, _, ()>(DELAYED_KEY, job_json, due).await?;
+        Ok(())
+    }
+
+    /// Entries stay pending in the consumer group until `ack`
+    async fn pop(&self, worker_id: &str, lane: Lane) -> Result<Option<CrawlJob>> {
+        let mut conn = self.client.get_async_connection().await?;
+        self.promote_due_jobs(&mut conn).await?;
+
+        // Drain higher priorities first
+        for priority in Priority::ALL {
+            let key = stream_key(lane, priority);
+            if let Some(job) = self.next_recovered(&mut conn, worker_id, &key).await? {
+                return Ok(Some(job));
+            }
+            let options = StreamReadOptions::default().group(CONSUMER_GROUP, worker_id).count(1);
+            let reply: RedisResult<Option<StreamReadReply>> = conn.xread_options(&[&key], &[">"], &options).await;
+            let reply = match reply {
+                Ok(reply) => reply,
+                // Stream or group vanished (e.g. Redis was flushed); recreate and retry next poll
+                Err(e) if e.code() == Some("NOGROUP") => {
+                    ensure_groups(&mut conn).await?;
+                    return Ok(None);
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let Some(entry) = reply.and_then(|r| r.keys.into_iter().next()).and_then(|k| k.ids.into_iter().next()) else {
+                continue;
+            };
+            match parse_entry(&entry) {
+                Some(mut job) => {
+                    job.receipt = Some(entry.id);
+                    return Ok(Some(job));
+                }
+                None => {
+                    // Unparseable payloads would be redelivered forever; drop them here
+                    warn!("⚠️ Dropping unparseable stream entry {} from {}", entry.id, key);
+                    release_entry(&mut conn, &key, &entry.id).await?;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    async fn ack(&self, job: &CrawlJob) -> Result<()> {
+        let Some(receipt) = &job.receipt else { return Ok(()) };
+        let mut conn = self.client.get_async_connection().await?;
+        release_entry(&mut conn, &stream_key_for(job), receipt).await?;
+        Ok(())
+    }
+
+    async fn claim_dedup_key(&self, key: &str, task_id: &str, window_secs: u64) -> Result<Option<String>> {
+        let mut conn = self.client.get_async_connection().await?;
+        let key = format!("{}{}", DEDUP_KEY_PREFIX, key);
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(task_id)
+            .arg("NX")
+            .arg("EX")
+            .arg(window_secs)
+            .query_async(&mut conn)
+            .await?;
+        if claimed.is_some() {
+            return Ok(None);
+        }
+        Ok(conn.get(&key).await?)
+    }
+
+    async fn release_dedup_key(&self, key: &str) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        conn.del::<_, ()>(format!("{}{}", DEDUP_KEY_PREFIX, key)).await?;
+        Ok(())
+    }
+
+    async fn heartbeat(&self, worker_id: &str, ttl_secs: u64) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let key = format!("{}{}", HEARTBEAT_KEY_PREFIX, worker_id);
+        conn.set_ex::<_, _, ()>(key, chrono::Utc::now().timestamp(), ttl_secs).await?;
+        Ok(())
+    }
+
+    /// Dead consumers with nothing left pending are also removed from the group
+    async fn take_orphaned_jobs(&self, janitor_id: &str) -> Result<Vec<(CrawlJob, String)>> {
+        let mut conn = self.client.get_async_connection().await?;
+        let mut orphaned = Vec::new();
+
+        for key in all_stream_keys() {
+            let consumers: StreamInfoConsumersReply = match conn.xinfo_consumers(&key, CONSUMER_GROUP).await {
+                Ok(consumers) => consumers,
+                Err(e) if e.code() == Some("NOGROUP") => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for consumer in consumers.consumers {
+                if worker_alive(&mut conn, &consumer.name).await? {
+                    continue;
+                }
+                if consumer.pending == 0 {
+                    conn.xgroup_delconsumer::<_, _, _, ()>(&key, CONSUMER_GROUP, &consumer.name).await?;
+                    continue;
+                }
+
+                let pending: StreamPendingCountReply = conn
+                    .xpending_consumer_count(&key, CONSUMER_GROUP, "-", "+", 100, &consumer.name)
+                    .await?;
+                let ids: Vec<String> = pending.ids.into_iter().map(|p| p.id).collect();
+                // XCLAIM resets the idle time, so a concurrent janitor's claim comes back empty
+                let claimed: StreamClaimReply = conn
+                    .xclaim(&key, CONSUMER_GROUP, janitor_id, ORPHAN_MIN_IDLE_MS, &ids)
+                    .await?;
+                for entry in claimed.ids {
+                    release_entry(&mut conn, &key, &entry.id).await?;
+                    match parse_entry(&entry) {
+                        Some(job) => orphaned.push((job, consumer.name.clone())),
+                        None => warn!("⚠️ Dropping unparseable orphaned entry {} from {}", entry.id, key),
+                    }
+                }
+            }
+        }
+        Ok(orphaned)
+    }
+
+    async fn record_outcome(&self, succeeded: bool) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        let minute = chrono::Utc::now().timestamp() / 60;
+        let key = stats_key(if succeeded { "processed" } else { "failed" }, minute);
+        conn.incr::<_, _, ()>(&key, 1).await?;
+        conn.expire::<_, ()>(&key, 2 * 3600).await?;
+        Ok(())
+    }
+
+    async fn snapshot(&self) -> Result<QueueSnapshot> {
+        let mut conn = self.client.get_async_connection().await?;
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut snapshot = QueueSnapshot::default();
+        let mut oldest_ms: Option<i64> = None;
+
+        for lane in Lane::ALL {
+            let mut depth = LaneDepth { lane: lane.as_str().to_string(), ..Default::default() };
+            for priority in Priority::ALL {
+                let key = stream_key(lane, priority);
+                let len: u64 = conn.xlen(&key).await?;
+                let pending: u64 = match conn.xpending::<_, _, StreamPendingReply>(&key, CONSUMER_GROUP).await {
+                    Ok(reply) => reply.count() as u64,
+                    Err(e) if e.code() == Some("NOGROUP") => 0,
+                    Err(e) => return Err(e.into()),
+                };
+                depth.in_flight += pending;
+                depth.ready += len.saturating_sub(pending);
+
+                // Acked entries are deleted, so the first entry is the oldest unfinished job
+                let first: StreamRangeReply = conn.xrange_count(&key, "-", "+", 1).await?;
+                if let Some(ms) = first.ids.first().and_then(|e| e.id.split('-').next()).and_then(|ms| ms.parse().ok()) {
+                    oldest_ms = Some(oldest_ms.map_or(ms, |o: i64| o.min(ms)));
+                }
+            }
+            snapshot.lanes.push(depth);
+        }
+        snapshot.delayed = conn.zcard(DELAYED_KEY).await?;
+        snapshot.oldest_job_age_secs = oldest_ms.map(|ms| (now_ms - ms).max(0) / 1000);
+
+        let minute = chrono::Utc::now().timestamp() / 60;
+        for (kind, total) in [("processed", &mut snapshot.processed_last_hour), ("failed", &mut snapshot.failed_last_hour)] {
+            let keys: Vec<String> = (0..60).map(|i| stats_key(kind, minute - i)).collect();
+            let counts: Vec<Option<u64>> = redis::cmd("MGET").arg(&keys).query_async(&mut conn).await?;
+            *total = counts.into_iter().flatten().sum();
+        }
+        Ok(snapshot)
+    }
+
+    async fn set_paused(&self, paused: bool) -> Result<()> {
+        let mut conn = self.client.get_async_connection().await?;
+        if paused {
+            conn.set::<_, _, ()>(PAUSED_KEY, chrono::Utc::now().timestamp()).await?;
+        } else {
+            conn.del::<_, ()>(PAUSED_KEY).await?;
+        }
+        Ok(())
+    }
+
+    async fn is_paused(&self) -> Result<bool> {
+        let mut conn = self.client.get_async_connection().await?;
+        Ok(conn.exists(PAUSED_KEY).await?)
+    }
+}
+
+/// Pending entries must be idle at least this long before a janitor takes them over
+const ORPHAN_MIN_IDLE_MS: usize = 1000;
+/// Most entries re-read from a restarted consumer's pending list per stream
+const RECOVER_BATCH: usize = 1000;
+
+fn all_stream_keys() -> impl Iterator<Item = String> {
+    Lane::ALL
+        .into_iter()
+        .flat_map(|lane| Priority::ALL.into_iter().map(move |priority| stream_key(lane, priority)))
+}
+
+/// Create the consumer group on every stream (and the streams themselves) if missing
+async fn ensure_groups(conn: &mut redis::aio::Connection) -> Result<()> {
+    for key in all_stream_keys() {
+        let created: RedisResult<()> = conn.xgroup_create_mkstream(&key, CONSUMER_GROUP, "0").await;
+        match created {
+            Ok(()) => {}
+            Err(e) if e.code() == Some("BUSYGROUP") => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Move jobs left in the pre-streams list queues onto their streams (once per
+/// Redis, see `LEGACY_MIGRATED_KEY`)
+async fn migrate_list_queues(conn: &mut redis::aio::Connection) -> Result<usize> {
+    let mut legacy_keys: Vec<String> = Lane::ALL
+        .into_iter()
+        .flat_map(|lane| Priority::ALL.into_iter().map(move |priority| legacy_list_key(lane, priority)))
+        .collect();
+    legacy_keys.push(LEGACY_PROCESSING_KEY.to_string());
+
+    let mut moved = 0;
+    for key in legacy_keys {
+        let key_type: String = redis::cmd("TYPE").arg(&key).query_async(conn).await?;
+        if key_type != "list" {
+            continue;
+        }
+        while let Some(job_json) = conn.rpop::<_, Option<String>>(&key, None).await? {
+            add_to_stream(conn, &ready_stream(&job_json), &job_json).await?;
+            moved += 1;
+        }
+    }
+    conn.del::<_, ()>(&LEGACY_CLAIM_KEYS[..]).await?;
+    Ok(moved)
+}
+
+async fn add_to_stream(conn: &mut redis::aio::Connection, key: &str, job_json: &str) -> Result<()> {
+    conn.xadd::<_, _, _, _, ()>(key, "*", &[(JOB_FIELD, job_json)]).await?;
+    Ok(())
+}
+
+/// Acknowledge and delete an entry, so stream length stays equal to the backlog
+async fn release_entry(conn: &mut redis::aio::Connection, key: &str, id: &str) -> Result<()> {
+    conn.xack::<_, _, _, ()>(key, CONSUMER_GROUP, &[id]).await?;
+    conn.xdel::<_, _, ()>(key, &[id]).await?;
+    Ok(())
+}
+
+fn parse_entry(entry: &StreamId) -> Option<CrawlJob> {
+    let job_json: String = entry.get(JOB_FIELD)?;
+    serde_json::from_str(&job_json).ok()
+}
+
+/// Stream for a raw job payload (the default stream if it doesn't parse)
+fn ready_stream(job_json: &str) -> String {
+    serde_json::from_str::<CrawlJob>(job_json)
+        .map(|job| stream_key_for(&job))
+        .unwrap_or_else(|_| stream_key(Lane::Bing, Priority::Normal))
+}
+
+async fn worker_alive(conn: &mut redis::aio::Connection, worker_id: &str) -> Result<bool> {
+    Ok(conn.exists(format!("{}{}", HEARTBEAT_KEY_PREFIX, worker_id)).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lane_queue_keys() {
+        assert_eq!(stream_key(Lane::Bing, Priority::Normal), "crawl_stream:bing:normal");
+        assert_eq!(stream_key(Lane::Google, Priority::Low), "crawl_stream:google:low");
+        assert_eq!(legacy_list_key(Lane::Bing, Priority::Normal), "crawl_queue");
+        assert_eq!(legacy_list_key(Lane::Bing, Priority::High), "crawl_queue:high");
+        assert_eq!(legacy_list_key(Lane::Google, Priority::Low), "crawl_queue:google:low");
+        assert_eq!(legacy_list_key(Lane::Generic, Priority::Normal), "crawl_queue:generic");
+        assert_eq!(all_stream_keys().count(), 9);
+    }
+}
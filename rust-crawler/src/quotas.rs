@@ -0,0 +1,288 @@
+//! Per-user crawl quotas.
+//!
+//! Every submitted crawl counts against the user's daily and monthly quota.
+//! Limits come from the user's profile (`daily_crawl_quota`, `monthly_crawl_quota`)
+//! when set there. Otherwise the monthly limit is the one of the user's
+//! subscription plan and the daily limit falls back to `QUOTA_DAILY_DEFAULT`.
+//!
+//! Members of an organization share one quota: their usage is summed, and the
+//! limits are the organization's overrides, falling back to the owner's as above.
+//!
+//! Submissions `reserve` their crawls up front: under a per-quota advisory lock,
+//! one conditional upsert adds them to today's usage only if they still fit, so
+//! concurrent requests can't overshoot the limit. Crawls that end up not being
+//! queued are `release`d again.
+
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{PgExecutor, PgPool};
+use tracing::{error, warn};
+
+pub const DEFAULT_DAILY_QUOTA: i64 = 1000;
+
+fn default_limit(var: &str, default: i64) -> i64 {
+    std::env::var(var).ok().and_then(|s| s.parse().ok()).unwrap_or(default)
+}
+
+/// A user's limits and usage for the current UTC day and month
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quota {
+    pub daily_limit: i64,
+    pub daily_used: i64,
+    pub monthly_limit: i64,
+    pub monthly_used: i64,
+}
+
+/// Whose usage counts against a user's quota, and its limits
+struct QuotaScope {
+    /// The user, or their organization; reservations against one scope are serialized
+    key: String,
+    usage_users: Vec<String>,
+    daily_limit: i64,
+    monthly_limit: i64,
+}
+
+impl QuotaScope {
+    async fn load(pool: &PgPool, user_id: &str) -> Result<Self, sqlx::Error> {
+        let shared = crate::organizations::shared_quota(pool, user_id).await?;
+        let (key, limits_user, usage_users, org_daily, org_monthly) = match shared {
+            Some(org) => (
+                format!("org:{}", org.org_id),
+                org.owner_id.unwrap_or_else(|| user_id.to_string()),
+                org.member_ids,
+                org.daily_crawl_quota,
+                org.monthly_crawl_quota,
+            ),
+            None => (format!("user:{}", user_id), user_id.to_string(), vec![user_id.to_string()], None, None),
+        };
+
+        let limits: Option<(Option<i32>, Option<i32>)> =
+            sqlx::query_as("SELECT daily_crawl_quota, monthly_crawl_quota FROM profiles WHERE id = $1")
+                .bind(&limits_user)
+                .fetch_optional(pool)
+                .await?;
+        let (daily, monthly) = limits.unwrap_or((None, None));
+        let daily = org_daily.or(daily);
+        let monthly = match org_monthly.or(monthly) {
+            Some(limit) => limit,
+            None => crate::subscriptions::current_plan(pool, &limits_user).await?.monthly_crawl_quota,
+        };
+
+        Ok(Self {
+            key,
+            usage_users,
+            daily_limit: daily.map(i64::from).unwrap_or_else(|| default_limit("QUOTA_DAILY_DEFAULT", DEFAULT_DAILY_QUOTA)),
+            monthly_limit: i64::from(monthly),
+        })
+    }
+
+    /// The scope's limits with its usage for today and this month
+    async fn quota<'e>(&self, executor: impl PgExecutor<'e>) -> Result<Quota, sqlx::Error> {
+        let today = Utc::now().date_naive();
+        let (daily_used, monthly_used): (i64, i64) = sqlx::query_as(
+            r#"SELECT
+                COALESCE(SUM(count) FILTER (WHERE day = $2), 0)::BIGINT,
+                COALESCE(SUM(count), 0)::BIGINT
+               FROM crawl_usage WHERE user_id = ANY($1) AND day >= $3"#,
+        )
+        .bind(&self.usage_users)
+        .bind(today)
+        .bind(month_start(today))
+        .fetch_one(executor)
+        .await?;
+        Ok(Quota { daily_limit: self.daily_limit, daily_used, monthly_limit: self.monthly_limit, monthly_used })
+    }
+}
+
+fn month_start(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).unwrap_or(day)
+}
+
+impl Quota {
+    pub async fn load(pool: &PgPool, user_id: &str) -> Result<Self, sqlx::Error> {
+        QuotaScope::load(pool, user_id).await?.quota(pool).await
+    }
+
+    pub fn daily_remaining(&self) -> i64 {
+        (self.daily_limit - self.daily_used).max(0)
+    }
+
+    pub fn monthly_remaining(&self) -> i64 {
+        (self.monthly_limit - self.monthly_used).max(0)
+    }
+
+    /// Whether `jobs` more crawls fit in both quotas
+    pub fn allows(&self, jobs: i64) -> bool {
+        jobs <= self.daily_remaining() && jobs <= self.monthly_remaining()
+    }
+
+    /// Account for `jobs` accepted crawls without reloading
+    pub fn consume(&mut self, jobs: i64) {
+        self.daily_used += jobs;
+        self.monthly_used += jobs;
+    }
+
+    /// Account for `jobs` released crawls without reloading
+    pub fn unreserve(&mut self, jobs: i64) {
+        self.daily_used = (self.daily_used - jobs).max(0);
+        self.monthly_used = (self.monthly_used - jobs).max(0);
+    }
+
+    /// Seconds until the exhausted quota resets (next UTC midnight or month start)
+    pub fn retry_after_secs(&self, now: chrono::DateTime<Utc>) -> i64 {
+        let today = now.date_naive();
+        let reset = if self.monthly_remaining() == 0 {
+            let (year, month) = if today.month() == 12 { (today.year() + 1, 1) } else { (today.year(), today.month() + 1) };
+            NaiveDate::from_ymd_opt(year, month, 1)
+        } else {
+            today.succ_opt()
+        };
+        reset
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|t| (t.and_utc() - now).num_seconds().max(1))
+            .unwrap_or(86400)
+    }
+
+    /// `X-Quota-*` headers describing the current state
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in [
+            ("x-quota-limit-day", self.daily_limit),
+            ("x-quota-remaining-day", self.daily_remaining()),
+            ("x-quota-limit-month", self.monthly_limit),
+            ("x-quota-remaining-month", self.monthly_remaining()),
+        ] {
+            headers.insert(name, HeaderValue::from(value));
+        }
+        headers
+    }
+}
+
+/// Take `jobs` reserved crawls back off today's usage, for crawls that weren't queued after all
+pub async fn release(pool: &PgPool, user_id: &str, jobs: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE crawl_usage SET count = GREATEST(count - $3, 0) WHERE user_id = $1 AND day = $2")
+        .bind(user_id)
+        .bind(Utc::now().date_naive())
+        .bind(jobs as i32)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct QuotaErrorBody {
+    success: bool,
+    error: String,
+}
+
+/// Why a submission was refused: over quota (429) or the quota couldn't be checked (500)
+pub enum QuotaError {
+    Exceeded(Quota),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for QuotaError {
+    fn from(e: sqlx::Error) -> Self {
+        QuotaError::Database(e)
+    }
+}
+
+impl IntoResponse for QuotaError {
+    fn into_response(self) -> Response {
+        match self {
+            QuotaError::Exceeded(quota) => {
+                let mut headers = quota.headers();
+                headers.insert("retry-after", HeaderValue::from(quota.retry_after_secs(Utc::now())));
+                let period = if quota.monthly_remaining() == 0 { "Monthly" } else { "Daily" };
+                let body = QuotaErrorBody {
+                    success: false,
+                    error: format!("{} crawl quota exceeded", period),
+                };
+                (StatusCode::TOO_MANY_REQUESTS, headers, Json(body)).into_response()
+            }
+            QuotaError::Database(e) => {
+                error!("❌ Quota check failed: {}", e);
+                let body = QuotaErrorBody {
+                    success: false,
+                    error: "Failed to check crawl quota".to_string(),
+                };
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+            }
+        }
+    }
+}
+
+/// Count `jobs` more crawls against the user's quota if they fit, returning the
+/// quota with them included. Fails without recording anything when they don't.
+pub async fn reserve(pool: &PgPool, user_id: &str, jobs: i64) -> Result<Quota, QuotaError> {
+    let scope = QuotaScope::load(pool, user_id).await?;
+    let today = Utc::now().date_naive();
+
+    let mut tx = pool.begin().await?;
+    // Members of an organization add to their own rows, so the sums are only
+    // stable while other reservations against the same quota wait
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(format!("crawl_quota:{}", scope.key))
+        .execute(&mut *tx)
+        .await?;
+    let reserved: Option<i32> = sqlx::query_scalar(
+        r#"WITH used AS (
+               SELECT COALESCE(SUM(count) FILTER (WHERE day = $2), 0) AS daily, COALESCE(SUM(count), 0) AS monthly
+               FROM crawl_usage WHERE user_id = ANY($4) AND day >= $5
+           )
+           INSERT INTO crawl_usage (user_id, day, count)
+           SELECT $1, $2, $3 FROM used WHERE used.daily + $3 <= $6 AND used.monthly + $3 <= $7
+           ON CONFLICT (user_id, day) DO UPDATE SET count = crawl_usage.count + EXCLUDED.count
+           RETURNING count"#,
+    )
+    .bind(user_id)
+    .bind(today)
+    .bind(jobs as i32)
+    .bind(&scope.usage_users)
+    .bind(month_start(today))
+    .bind(scope.daily_limit)
+    .bind(scope.monthly_limit)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let quota = scope.quota(&mut *tx).await?;
+    tx.commit().await?;
+
+    if reserved.is_some() {
+        Ok(quota)
+    } else {
+        warn!("🚫 Quota exceeded for {} ({} requested)", user_id, jobs);
+        Err(QuotaError::Exceeded(quota))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_quota_remaining_and_reset() {
+        let mut quota = Quota { daily_limit: 10, daily_used: 9, monthly_limit: 100, monthly_used: 50 };
+        assert!(quota.allows(1));
+        assert!(!quota.allows(2));
+
+        let now = Utc.with_ymd_and_hms(2026, 12, 31, 23, 0, 0).unwrap();
+        quota.consume(1);
+        assert_eq!(quota.daily_remaining(), 0);
+        assert_eq!(quota.retry_after_secs(now), 3600);
+
+        quota.monthly_used = 100;
+        assert_eq!(quota.headers()["x-quota-remaining-month"], "0");
+        assert_eq!(quota.retry_after_secs(now), 3600);
+        let mid_month = Utc.with_ymd_and_hms(2026, 11, 30, 0, 0, 0).unwrap();
+        assert_eq!(quota.retry_after_secs(mid_month), 86400);
+
+        quota.unreserve(5);
+        assert_eq!((quota.daily_used, quota.monthly_used), (5, 95));
+    }
+}
@@ -0,0 +1,469 @@
+//! Keyword rank tracking.
+//!
+//! Users register the domains they care about. After every Google/Bing crawl the
+//! worker records where each of the job owner's tracked domains ranked (or that
+//! it didn't appear), building a position history per keyword, engine and country.
+//!
+//! Domains can be flagged as competitors; the visibility report aggregates the
+//! latest position of each tracked domain across every keyword the user crawls.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::crawler::SearchResult;
+use crate::queue::CrawlJob;
+use tracing::error;
+
+/// Longest history returned by `GET /rankings/history`
+const MAX_HISTORY_DAYS: i64 = 365;
+/// Approximate click-through rate of organic positions 1-10, used to weight visibility
+const POSITION_CTR: [f64; 10] = [0.28, 0.15, 0.11, 0.08, 0.07, 0.05, 0.04, 0.03, 0.03, 0.02];
+
+#[derive(Debug, Serialize, Clone, ToSchema, FromRow)]
+pub struct TrackedDomain {
+    pub id: String,
+    #[schema(example = "example.com")]
+    pub domain: String,
+    /// A competitor's domain rather than one of the user's own
+    pub competitor: bool,
+    #[schema(value_type = Option<String>)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TrackDomainRequest {
+    /// Domain or URL; subdomains of it count as matches
+    #[schema(example = "example.com")]
+    pub domain: String,
+    /// Register as a competitor (included in `GET /rankings/competitors/report`)
+    #[serde(default)]
+    pub competitor: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrackedDomainResponse {
+    pub success: bool,
+    pub domain: Option<TrackedDomain>,
+    pub message: Option<String>,
+}
+
+/// One observation of a domain's position
+#[derive(Debug, Serialize, Clone, ToSchema, FromRow)]
+pub struct RankingPoint {
+    pub task_id: String,
+    pub keyword: String,
+    pub engine: String,
+    /// Market the crawl targeted (`gl`), if any
+    pub country: Option<String>,
+    pub domain: String,
+    /// 1-based organic position; null when the domain wasn't in the results
+    pub position: Option<i32>,
+    /// Best-ranked URL of the domain
+    pub url: Option<String>,
+    #[schema(value_type = String)]
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Latest known position of a domain for one keyword
+#[derive(Debug, Serialize, Clone, PartialEq, ToSchema, FromRow)]
+pub struct KeywordPosition {
+    pub keyword: String,
+    pub engine: String,
+    pub country: Option<String>,
+    pub position: Option<i32>,
+    pub url: Option<String>,
+}
+
+/// How visible a domain is across the user's keywords
+#[derive(Debug, Serialize, Clone, PartialEq, ToSchema)]
+pub struct DomainVisibility {
+    pub domain: String,
+    pub competitor: bool,
+    /// Keyword/engine/country combinations with a recent observation
+    pub keywords_tracked: usize,
+    /// Of those, how many the domain ranks for at all
+    pub keywords_ranked: usize,
+    pub top3: usize,
+    pub top10: usize,
+    /// Mean position over ranked keywords
+    pub average_position: Option<f64>,
+    /// Expected share of clicks (0-100) if every keyword had equal volume
+    pub visibility: f64,
+    pub keywords: Vec<KeywordPosition>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VisibilityReportQuery {
+    /// Only report this domain
+    pub domain: Option<String>,
+    /// Ignore observations older than this many days (default 30, max 365)
+    pub days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RankingHistoryQuery {
+    /// Tracked domain
+    pub domain: String,
+    pub keyword: Option<String>,
+    pub engine: Option<String>,
+    pub country: Option<String>,
+    /// How far back to look (default 30, max 365)
+    pub days: Option<i64>,
+}
+
+type ApiError = (StatusCode, String);
+
+/// Lowercased host without `www.`, from a bare domain or a URL
+pub fn normalize_domain(input: &str) -> Option<String> {
+    let input = input.trim().to_lowercase();
+    let host = if input.contains("://") {
+        reqwest::Url::parse(&input).ok()?.host_str()?.to_string()
+    } else {
+        input.split(['/', '?', '#']).next()?.split(':').next()?.to_string()
+    };
+    let host = host.trim_start_matches("www.").trim_end_matches('.');
+    (host.contains('.') && !host.contains(char::is_whitespace)).then(|| host.to_string())
+}
+
+/// Whether `link` is on `domain` or one of its subdomains
+fn link_matches(link: &str, domain: &str) -> bool {
+    match normalize_domain(link) {
+        Some(host) => host == domain || host.ends_with(&format!(".{}", domain)),
+        None => false,
+    }
+}
+
+/// Best position (1-based) and URL of each domain in `results`
+pub fn find_positions(results: &[SearchResult], domains: &[String]) -> Vec<(String, Option<(i32, String)>)> {
+    domains
+        .iter()
+        .map(|domain| {
+            let hit = results
+                .iter()
+                .position(|r| link_matches(&r.link, domain))
+                .map(|i| ((i + 1) as i32, results[i].link.clone()));
+            (domain.clone(), hit)
+        })
+        .collect()
+}
+
+/// Aggregate a domain's latest positions into its visibility summary
+pub fn summarize_visibility(domain: &str, competitor: bool, keywords: Vec<KeywordPosition>) -> DomainVisibility {
+    let ranked: Vec<i32> = keywords.iter().filter_map(|k| k.position).collect();
+    let ctr_sum: f64 = ranked
+        .iter()
+        .filter_map(|p| POSITION_CTR.get((*p as usize).saturating_sub(1)))
+        .sum();
+    let visibility = if keywords.is_empty() {
+        0.0
+    } else {
+        (ctr_sum / (keywords.len() as f64 * POSITION_CTR[0]) * 1000.0).round() / 10.0
+    };
+
+    DomainVisibility {
+        domain: domain.to_string(),
+        competitor,
+        keywords_tracked: keywords.len(),
+        keywords_ranked: ranked.len(),
+        top3: ranked.iter().filter(|p| **p <= 3).count(),
+        top10: ranked.iter().filter(|p| **p <= 10).count(),
+        average_position: (!ranked.is_empty()).then(|| ranked.iter().sum::<i32>() as f64 / ranked.len() as f64),
+        visibility,
+        keywords,
+    }
+}
+
+/// Record the positions of the job owner's tracked domains for a finished SERP crawl
+pub async fn record_rankings(pool: &PgPool, job: &CrawlJob, results: &[SearchResult]) -> Result<usize, sqlx::Error> {
+    if job.engine == "generic" {
+        return Ok(0);
+    }
+    let domains: Vec<(String,)> = sqlx::query_as("SELECT domain FROM tracked_domains WHERE user_id = $1")
+        .bind(&job.user_id)
+        .fetch_all(pool)
+        .await?;
+    if domains.is_empty() {
+        return Ok(0);
+    }
+    let domains: Vec<String> = domains.into_iter().map(|(d,)| d).collect();
+
+    let positions = find_positions(results, &domains);
+    for (domain, hit) in &positions {
+        sqlx::query(
+            r#"INSERT INTO rankings (task_id, user_id, keyword, engine, country, domain, position, url)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+        )
+        .bind(&job.id)
+        .bind(&job.user_id)
+        .bind(&job.keyword)
+        .bind(&job.engine)
+        .bind(job.options.gl.as_deref().map(str::to_lowercase))
+        .bind(domain)
+        .bind(hit.as_ref().map(|(position, _)| *position))
+        .bind(hit.as_ref().map(|(_, url)| url))
+        .execute(pool)
+        .await?;
+    }
+    Ok(positions.len())
+}
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Rankings] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+// ============================================================================
+// API
+// ============================================================================
+
+/// List the caller's tracked domains
+#[utoipa::path(
+    get,
+    path = "/rankings/domains",
+    tag = "rankings",
+    responses(
+        (status = 200, description = "Tracked domains", body = Vec<TrackedDomain>)
+    )
+)]
+pub async fn list_tracked_domains(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<Vec<TrackedDomain>>, ApiError> {
+    let domains: Vec<TrackedDomain> =
+        sqlx::query_as("SELECT id, domain, competitor, created_at FROM tracked_domains WHERE user_id = $1 ORDER BY domain")
+            .bind(&user.id)
+            .fetch_all(&state.pool)
+            .await
+            .map_err(db_error)?;
+    Ok(Json(domains))
+}
+
+/// Start tracking a domain's position in the caller's crawls
+#[utoipa::path(
+    post,
+    path = "/rankings/domains",
+    tag = "rankings",
+    request_body = TrackDomainRequest,
+    responses(
+        (status = 200, description = "Domain tracked", body = TrackedDomainResponse),
+        (status = 400, description = "Invalid domain")
+    )
+)]
+pub async fn track_domain(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<TrackDomainRequest>,
+) -> Result<Json<TrackedDomainResponse>, ApiError> {
+    let domain = normalize_domain(&req.domain)
+        .ok_or((StatusCode::BAD_REQUEST, format!("Invalid domain '{}'", req.domain)))?;
+
+    // Re-registering returns the existing entry (with the new competitor flag)
+    let tracked: TrackedDomain = sqlx::query_as(
+        r#"INSERT INTO tracked_domains (id, user_id, domain, competitor) VALUES ($1, $2, $3, $4)
+           ON CONFLICT (user_id, domain) DO UPDATE SET competitor = EXCLUDED.competitor
+           RETURNING id, domain, competitor, created_at"#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&user.id)
+    .bind(&domain)
+    .bind(req.competitor)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(TrackedDomainResponse {
+        success: true,
+        domain: Some(tracked),
+        message: Some("Domain tracked".to_string()),
+    }))
+}
+
+/// Stop tracking a domain (its history is kept)
+#[utoipa::path(
+    delete,
+    path = "/rankings/domains/{id}",
+    tag = "rankings",
+    params(
+        ("id" = String, Path, description = "Tracked domain ID")
+    ),
+    responses(
+        (status = 200, description = "Domain no longer tracked", body = TrackedDomainResponse),
+        (status = 404, description = "Tracked domain not found")
+    )
+)]
+pub async fn untrack_domain(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<TrackedDomainResponse>, ApiError> {
+    let result = sqlx::query("DELETE FROM tracked_domains WHERE id = $1 AND user_id = $2")
+        .bind(&id)
+        .bind(&user.id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Tracked domain not found".to_string()));
+    }
+
+    Ok(Json(TrackedDomainResponse {
+        success: true,
+        domain: None,
+        message: Some("Domain no longer tracked".to_string()),
+    }))
+}
+
+/// Position history of a tracked domain, oldest first
+#[utoipa::path(
+    get,
+    path = "/rankings/history",
+    tag = "rankings",
+    params(RankingHistoryQuery),
+    responses(
+        (status = 200, description = "Position time series", body = Vec<RankingPoint>),
+        (status = 400, description = "Invalid domain")
+    )
+)]
+pub async fn ranking_history(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Query(query): Query<RankingHistoryQuery>,
+) -> Result<Json<Vec<RankingPoint>>, ApiError> {
+    let domain = normalize_domain(&query.domain)
+        .ok_or((StatusCode::BAD_REQUEST, format!("Invalid domain '{}'", query.domain)))?;
+    let days = query.days.unwrap_or(30).clamp(1, MAX_HISTORY_DAYS);
+
+    let points: Vec<RankingPoint> = sqlx::query_as(
+        r#"SELECT task_id, keyword, engine, country, domain, position, url, checked_at
+           FROM rankings
+           WHERE user_id = $1 AND domain = $2
+             AND ($3::TEXT IS NULL OR keyword = $3)
+             AND ($4::TEXT IS NULL OR engine = $4)
+             AND ($5::TEXT IS NULL OR country = lower($5))
+             AND checked_at > now() - make_interval(days => $6)
+           ORDER BY checked_at"#,
+    )
+    .bind(&user.id)
+    .bind(&domain)
+    .bind(&query.keyword)
+    .bind(&query.engine)
+    .bind(&query.country)
+    .bind(days as i32)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(points))
+}
+
+/// Visibility of each competitor (and, for comparison, each of the caller's own
+/// domains) across the keywords they crawl, from the latest observation per
+/// keyword, engine and country
+#[utoipa::path(
+    get,
+    path = "/rankings/competitors/report",
+    tag = "rankings",
+    params(VisibilityReportQuery),
+    responses(
+        (status = 200, description = "Per-domain visibility, competitors first", body = Vec<DomainVisibility>)
+    )
+)]
+pub async fn competitor_report(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Query(query): Query<VisibilityReportQuery>,
+) -> Result<Json<Vec<DomainVisibility>>, ApiError> {
+    let days = query.days.unwrap_or(30).clamp(1, MAX_HISTORY_DAYS);
+    let only = query.domain.as_deref().and_then(normalize_domain);
+    let tracked: Vec<(String, bool)> = sqlx::query_as(
+        r#"SELECT domain, competitor FROM tracked_domains
+           WHERE user_id = $1 AND ($2::TEXT IS NULL OR domain = $2)
+           ORDER BY competitor DESC, domain"#,
+    )
+    .bind(&user.id)
+    .bind(&only)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    let mut report = Vec::with_capacity(tracked.len());
+    for (domain, competitor) in tracked {
+        let latest: Vec<KeywordPosition> = sqlx::query_as(
+            r#"SELECT DISTINCT ON (keyword, engine, country) keyword, engine, country, position, url
+               FROM rankings
+               WHERE user_id = $1 AND domain = $2 AND checked_at > now() - make_interval(days => $3)
+               ORDER BY keyword, engine, country, checked_at DESC"#,
+        )
+        .bind(&user.id)
+        .bind(&domain)
+        .bind(days as i32)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+        report.push(summarize_visibility(&domain, competitor, latest));
+    }
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(link: &str) -> SearchResult {
+        SearchResult { title: String::new(), link: link.to_string(), snippet: String::new() }
+    }
+
+    #[test]
+    fn test_normalize_domain() {
+        assert_eq!(normalize_domain("https://www.Example.com/page?q=1").as_deref(), Some("example.com"));
+        assert_eq!(normalize_domain("shop.example.co.uk/path").as_deref(), Some("shop.example.co.uk"));
+        assert_eq!(normalize_domain("localhost"), None);
+    }
+
+    #[test]
+    fn test_find_positions() {
+        let results = vec![
+            result("https://docs.rust-lang.org/book/"),
+            result("https://www.example.com/a"),
+            result("https://example.com/b"),
+            result("https://notexample.com/"),
+        ];
+        let domains = vec!["example.com".to_string(), "rust-lang.org".to_string(), "missing.org".to_string()];
+        let positions = find_positions(&results, &domains);
+        assert_eq!(positions[0].1, Some((2, "https://www.example.com/a".to_string())));
+        assert_eq!(positions[1].1.as_ref().map(|(p, _)| *p), Some(1));
+        assert_eq!(positions[2].1, None);
+    }
+
+    #[test]
+    fn test_summarize_visibility() {
+        let at = |keyword: &str, position: Option<i32>| KeywordPosition {
+            keyword: keyword.to_string(),
+            engine: "google".to_string(),
+            country: None,
+            position,
+            url: None,
+        };
+        let summary = summarize_visibility("rival.com", true, vec![at("a", Some(1)), at("b", Some(7)), at("c", None), at("d", Some(40))]);
+        assert_eq!((summary.keywords_tracked, summary.keywords_ranked), (4, 3));
+        assert_eq!((summary.top3, summary.top10), (1, 2));
+        assert_eq!(summary.average_position, Some(16.0));
+        // (0.28 + 0.04) / (4 * 0.28)
+        assert_eq!(summary.visibility, 28.6);
+
+        let empty = summarize_visibility("new.com", false, vec![]);
+        assert_eq!((empty.visibility, empty.average_position), (0.0, None));
+    }
+}
@@ -0,0 +1,223 @@
+//! Request rate limiting for the expensive endpoints.
+//!
+//! `/crawl` and `/proxies` requests are limited per client IP and, when sent with
+//! a valid `X-Api-Key`, per key, each with a token bucket refilled at the configured
+//! requests per minute (`[rate_limit]` / `RATE_LIMIT_*`). A key only gets a bucket
+//! once it authenticates and the IP bucket had room, so made-up keys can't fill
+//! the table or dodge the IP limit. Responses carry
+//! `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` for the tighter
+//! of the two; a request over the limit gets 429 with `Retry-After`. Buckets are
+//! per process, so with several replicas the effective limit is multiplied.
+//! Daily and monthly crawl quotas (`quotas`) are separate and still apply.
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::warn;
+use crate::api::AppState;
+use crate::config::RateLimitConfig;
+
+/// Buckets kept per limiter; past this, idle (full) buckets are dropped, then the least recently used
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+/// Size an eviction pass shrinks the table to, so passes don't run on every new client
+const EVICT_TO: usize = MAX_TRACKED_CLIENTS * 9 / 10;
+
+/// Outcome of taking one request from a bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again
+    pub reset_secs: u64,
+    /// Seconds until the next request is allowed (0 if this one was)
+    pub retry_after_secs: u64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets by client, each holding up to `per_minute` requests
+#[derive(Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn check(&self, client: &str, per_minute: u32, now: Instant) -> Decision {
+        let capacity = per_minute as f64;
+        let per_sec = capacity / 60.0;
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(client) {
+            evict(&mut buckets, now, per_sec, capacity);
+        }
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket { tokens: capacity, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let retry_after_secs = if allowed { 0 } else { ((1.0 - bucket.tokens) / per_sec).ceil() as u64 };
+        Decision {
+            allowed,
+            limit: per_minute,
+            remaining: bucket.tokens.floor() as u32,
+            reset_secs: ((capacity - bucket.tokens) / per_sec).ceil() as u64,
+            retry_after_secs,
+        }
+    }
+}
+
+/// Drop full buckets, then the least recently used ones down to `EVICT_TO`
+fn evict(buckets: &mut HashMap<String, Bucket>, now: Instant, per_sec: f64, capacity: f64) {
+    buckets.retain(|_, b| b.tokens + now.saturating_duration_since(b.updated).as_secs_f64() * per_sec < capacity);
+    if buckets.len() > EVICT_TO {
+        let mut updated: Vec<Instant> = buckets.values().map(|b| b.updated).collect();
+        let excess = buckets.len() - EVICT_TO;
+        let cutoff = *updated.select_nth_unstable(excess - 1).1;
+        buckets.retain(|_, b| b.updated > cutoff);
+    }
+}
+
+static BY_IP: Lazy<RateLimiter> = Lazy::new(RateLimiter::default);
+static BY_KEY: Lazy<RateLimiter> = Lazy::new(RateLimiter::default);
+
+fn limited_path(path: &str) -> bool {
+    ["/crawl", "/proxies"].iter().any(|prefix| path == *prefix || path.starts_with(&format!("{}/", prefix)))
+}
+
+/// The connecting address, or the last `X-Forwarded-For` hop when behind a trusted proxy
+fn client_ip(request: &Request, config: &RateLimitConfig) -> Option<String> {
+    if config.trust_forwarded_for {
+        let forwarded = request
+            .headers()
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit(',').next())
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty());
+        if let Some(ip) = forwarded {
+            return Some(ip.to_string());
+        }
+    }
+    request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip().to_string())
+}
+
+/// The stored ID of the request's API key, if it's valid; invalid keys are left to the handler's 401
+async fn authenticated_key_id(pool: &sqlx::PgPool, key: Option<String>) -> Option<String> {
+    match crate::api_keys::active_key_id(pool, key?.trim()).await {
+        Ok(id) => id,
+        Err(e) => {
+            warn!("⚠️ [RateLimit] API key lookup failed: {}", e);
+            None
+        }
+    }
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("ratelimit-reset", HeaderValue::from(decision.reset_secs));
+}
+
+/// Middleware limiting `/crawl` and `/proxies` per client IP and API key
+pub async fn limit(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if !limited_path(request.uri().path()) {
+        return next.run(request).await;
+    }
+    let config = &state.config.rate_limit;
+    let now = Instant::now();
+    let mut decisions = Vec::new();
+    let ip = client_ip(&request, config);
+    if let (Some(ip), true) = (&ip, config.ip_per_minute > 0) {
+        decisions.push(BY_IP.check(ip, config.ip_per_minute, now));
+    }
+    // A request the IP limit refused doesn't spend the key's tokens
+    if config.key_per_minute > 0 && decisions.iter().all(|d| d.allowed) {
+        let key = request.headers().get(crate::api_keys::API_KEY_HEADER).and_then(|v| v.to_str().ok()).map(str::to_string);
+        if let Some(key_id) = authenticated_key_id(&state.pool, key).await {
+            decisions.push(BY_KEY.check(&key_id, config.key_per_minute, now));
+        }
+    }
+
+    // Report the limit closest to running out, or the one that was hit
+    let Some(decision) = decisions.iter().min_by_key(|d| (d.allowed, d.remaining)).copied() else {
+        return next.run(request).await;
+    };
+    if !decision.allowed {
+        warn!("🚦 [RateLimit] {} {} from {} rejected", request.method(), request.uri().path(), ip.as_deref().unwrap_or("unknown"));
+        let retry_after = decisions.iter().map(|d| d.retry_after_secs).max().unwrap_or(1);
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Rate limit exceeded; retry in {}s", retry_after),
+        )
+            .into_response();
+        set_headers(response.headers_mut(), &decision);
+        response.headers_mut().insert("retry-after", HeaderValue::from(retry_after));
+        return response;
+    }
+    let mut response = next.run(request).await;
+    set_headers(response.headers_mut(), &decision);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_token_bucket_limits_and_refills() {
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        for remaining in (0..3).rev() {
+            let decision = limiter.check("10.0.0.1", 3, start);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+        }
+        let blocked = limiter.check("10.0.0.1", 3, start);
+        assert!(!blocked.allowed);
+        assert_eq!((blocked.retry_after_secs, blocked.reset_secs), (20, 60));
+        // Other clients have their own bucket
+        assert!(limiter.check("10.0.0.2", 3, start).allowed);
+
+        // 3 per minute refills one request every 20 seconds
+        let later = limiter.check("10.0.0.1", 3, start + Duration::from_secs(20));
+        assert!(later.allowed);
+        assert_eq!(later.remaining, 0);
+
+        assert!(limited_path("/crawl") && limited_path("/crawl/batch") && limited_path("/proxies/p1/test"));
+        assert!(!limited_path("/crawler") && !limited_path("/tasks"));
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used_past_the_cap() {
+        let limiter = RateLimiter::default();
+        let start = Instant::now();
+        // One request per minute leaves every bucket empty, so none is idle
+        for i in 0..MAX_TRACKED_CLIENTS {
+            limiter.check(&format!("c{}", i), 1, start + Duration::from_millis(i as u64));
+        }
+        let now = start + Duration::from_secs(10);
+        assert!(!limiter.check("c0", 1, now).allowed);
+        assert_eq!(limiter.buckets.lock().unwrap().len(), MAX_TRACKED_CLIENTS);
+
+        assert!(limiter.check("new", 1, now).allowed);
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), EVICT_TO + 1);
+        // c0 was just used, c1 is the oldest
+        assert!(buckets.contains_key("c0") && buckets.contains_key("new"));
+        assert!(!buckets.contains_key("c1"));
+    }
+}
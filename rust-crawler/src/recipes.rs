@@ -0,0 +1,433 @@
+//! Extraction recipe registry.
+//!
+//! Recipes are named, reusable selector sets stored in Postgres. A crawl request
+//! can reference a recipe by `recipe_id` instead of inlining selectors every time.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json as SqlJson, FromRow, PgPool};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use tracing::warn;
+
+/// How a field's value is read from the matched element(s)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    /// Text content of the first match
+    #[default]
+    Text,
+    /// Inner HTML of the first match
+    Html,
+    /// Attribute value of the first match (requires `attribute`)
+    Attribute,
+    /// First number found in the text of the first match
+    Number,
+    /// Text content of every match
+    List,
+}
+
+/// Post-processing applied to extracted string values, in order
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PostProcess {
+    Trim,
+    Lowercase,
+    Uppercase,
+    /// Collapse runs of whitespace into single spaces
+    CollapseWhitespace,
+    /// Keep the first capture group (or whole match) of a regex
+    Regex { pattern: String },
+    Replace { from: String, to: String },
+}
+
+/// Single named field of a recipe
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct RecipeField {
+    #[schema(example = "price")]
+    pub name: String,
+    #[schema(example = ".product-price")]
+    pub selector: String,
+    #[serde(default)]
+    pub field_type: FieldType,
+    /// Attribute name for `attribute` fields
+    #[schema(example = "href")]
+    pub attribute: Option<String>,
+    #[serde(default)]
+    pub post_process: Vec<PostProcess>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, FromRow)]
+pub struct Recipe {
+    pub id: String,
+    #[schema(example = "phpbb-thread")]
+    pub name: String,
+    pub description: Option<String>,
+    #[schema(value_type = Vec<RecipeField>)]
+    pub fields: SqlJson<Vec<RecipeField>>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateRecipeRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub fields: Vec<RecipeField>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateRecipeRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub fields: Option<Vec<RecipeField>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecipeResponse {
+    pub success: bool,
+    pub recipe: Option<Recipe>,
+    pub message: Option<String>,
+}
+
+/// Load a recipe by ID (used by the worker to resolve `recipe_id`)
+pub async fn fetch_recipe(pool: &PgPool, id: &str) -> Result<Option<Recipe>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT id, name, description, fields,
+           to_char(created_at, 'YYYY-MM-DD HH24:MI:SS') as created_at,
+           to_char(updated_at, 'YYYY-MM-DD HH24:MI:SS') as updated_at
+           FROM recipes WHERE id = $1"#
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+// ============================================================================
+// Extraction
+// ============================================================================
+
+fn apply_post_process(mut value: String, steps: &[PostProcess]) -> String {
+    for step in steps {
+        value = match step {
+            PostProcess::Trim => value.trim().to_string(),
+            PostProcess::Lowercase => value.to_lowercase(),
+            PostProcess::Uppercase => value.to_uppercase(),
+            PostProcess::CollapseWhitespace => value.split_whitespace().collect::<Vec<_>>().join(" "),
+            PostProcess::Regex { pattern } => match Regex::new(pattern) {
+                Ok(re) => re
+                    .captures(&value)
+                    .map(|c| c.get(1).or_else(|| c.get(0)).map(|m| m.as_str().to_string()).unwrap_or_default())
+                    .unwrap_or_default(),
+                Err(_) => value,
+            },
+            PostProcess::Replace { from, to } => value.replace(from.as_str(), to),
+        };
+    }
+    value
+}
+
+fn parse_number(text: &str) -> Option<f64> {
+    let re = Regex::new(r"-?\d[\d,]*(?:\.\d+)?").unwrap();
+    re.find(text).and_then(|m| m.as_str().replace(',', "").parse().ok())
+}
+
+/// Apply recipe fields to a parsed document, returning `{field_name: value}`
+pub fn apply_fields(document: &Html, fields: &[RecipeField]) -> serde_json::Value {
+    let mut out = serde_json::Map::new();
+
+    for field in fields {
+        let selector = match Selector::parse(&field.selector) {
+            Ok(s) => s,
+            Err(_) => {
+                warn!("⚠️ Invalid selector for field '{}': {}", field.name, field.selector);
+                out.insert(field.name.clone(), serde_json::Value::Null);
+                continue;
+            }
+        };
+
+        let value = match field.field_type {
+            FieldType::List => serde_json::Value::Array(
+                document
+                    .select(&selector)
+                    .map(|el| apply_post_process(el.text().collect::<String>(), &field.post_process))
+                    .map(serde_json::Value::String)
+                    .collect(),
+            ),
+            _ => match document.select(&selector).next() {
+                None => serde_json::Value::Null,
+                Some(el) => {
+                    let raw = match field.field_type {
+                        FieldType::Html => Some(el.inner_html()),
+                        FieldType::Attribute => field
+                            .attribute
+                            .as_deref()
+                            .and_then(|a| el.value().attr(a))
+                            .map(|s| s.to_string()),
+                        _ => Some(el.text().collect::<String>()),
+                    };
+                    match raw.map(|r| apply_post_process(r, &field.post_process)) {
+                        None => serde_json::Value::Null,
+                        Some(v) if field.field_type == FieldType::Number => parse_number(&v)
+                            .and_then(serde_json::Number::from_f64)
+                            .map(serde_json::Value::Number)
+                            .unwrap_or(serde_json::Value::Null),
+                        Some(v) => serde_json::Value::String(v),
+                    }
+                }
+            },
+        };
+        out.insert(field.name.clone(), value);
+    }
+
+    serde_json::Value::Object(out)
+}
+
+// ============================================================================
+// CRUD API
+// ============================================================================
+
+/// List stored recipes
+#[utoipa::path(
+    get,
+    path = "/recipes",
+    tag = "recipes",
+    responses(
+        (status = 200, description = "List recipes", body = Vec<Recipe>)
+    )
+)]
+pub async fn list_recipes(
+    State(state): State<Arc<AppState>>,
+    _user: AuthUser,
+) -> Result<Json<Vec<Recipe>>, StatusCode> {
+    let recipes: Vec<Recipe> = sqlx::query_as(
+        r#"SELECT id, name, description, fields,
+           to_char(created_at, 'YYYY-MM-DD HH24:MI:SS') as created_at,
+           to_char(updated_at, 'YYYY-MM-DD HH24:MI:SS') as updated_at
+           FROM recipes ORDER BY name"#
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(recipes))
+}
+
+/// Get a recipe by ID
+#[utoipa::path(
+    get,
+    path = "/recipes/{id}",
+    tag = "recipes",
+    params(
+        ("id" = String, Path, description = "Recipe ID")
+    ),
+    responses(
+        (status = 200, description = "Recipe", body = RecipeResponse),
+        (status = 404, description = "Recipe not found")
+    )
+)]
+pub async fn get_recipe(
+    State(state): State<Arc<AppState>>,
+    _user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<RecipeResponse>, StatusCode> {
+    match fetch_recipe(&state.pool, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+        Some(recipe) => Ok(Json(RecipeResponse {
+            success: true,
+            recipe: Some(recipe),
+            message: None,
+        })),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Create a new recipe
+#[utoipa::path(
+    post,
+    path = "/recipes",
+    tag = "recipes",
+    request_body = CreateRecipeRequest,
+    responses(
+        (status = 200, description = "Recipe created", body = RecipeResponse),
+        (status = 400, description = "Invalid recipe or duplicate name")
+    )
+)]
+pub async fn create_recipe(
+    State(state): State<Arc<AppState>>,
+    _user: AuthUser,
+    Json(req): Json<CreateRecipeRequest>,
+) -> Result<Json<RecipeResponse>, StatusCode> {
+    if req.fields.is_empty() || req.fields.iter().any(|f| Selector::parse(&f.selector).is_err()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let id = Uuid::new_v4().to_string();
+
+    sqlx::query("INSERT INTO recipes (id, name, description, fields) VALUES ($1, $2, $3, $4)")
+        .bind(&id)
+        .bind(&req.name)
+        .bind(&req.description)
+        .bind(SqlJson(&req.fields))
+        .execute(&state.pool)
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    Ok(Json(RecipeResponse {
+        success: true,
+        recipe: Some(Recipe {
+            id,
+            name: req.name,
+            description: req.description,
+            fields: SqlJson(req.fields),
+            created_at: None,
+            updated_at: None,
+        }),
+        message: Some("Recipe created".to_string()),
+    }))
+}
+
+/// Update an existing recipe
+#[utoipa::path(
+    patch,
+    path = "/recipes/{id}",
+    tag = "recipes",
+    params(
+        ("id" = String, Path, description = "Recipe ID")
+    ),
+    request_body = UpdateRecipeRequest,
+    responses(
+        (status = 200, description = "Recipe updated", body = RecipeResponse),
+        (status = 404, description = "Recipe not found")
+    )
+)]
+pub async fn update_recipe(
+    State(state): State<Arc<AppState>>,
+    _user: AuthUser,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateRecipeRequest>,
+) -> Result<Json<RecipeResponse>, StatusCode> {
+    if let Some(ref fields) = req.fields {
+        if fields.is_empty() || fields.iter().any(|f| Selector::parse(&f.selector).is_err()) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let result = sqlx::query(
+        r#"UPDATE recipes SET
+           name = COALESCE($2, name),
+           description = COALESCE($3, description),
+           fields = COALESCE($4, fields),
+           updated_at = CURRENT_TIMESTAMP
+           WHERE id = $1"#
+    )
+    .bind(&id)
+    .bind(&req.name)
+    .bind(&req.description)
+    .bind(req.fields.as_ref().map(SqlJson))
+    .execute(&state.pool)
+    .await
+    .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(RecipeResponse {
+        success: true,
+        recipe: None,
+        message: Some("Recipe updated".to_string()),
+    }))
+}
+
+/// Delete a recipe
+#[utoipa::path(
+    delete,
+    path = "/recipes/{id}",
+    tag = "recipes",
+    params(
+        ("id" = String, Path, description = "Recipe ID")
+    ),
+    responses(
+        (status = 200, description = "Recipe deleted", body = RecipeResponse),
+        (status = 404, description = "Recipe not found")
+    )
+)]
+pub async fn delete_recipe(
+    State(state): State<Arc<AppState>>,
+    _user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<RecipeResponse>, StatusCode> {
+    let result = sqlx::query("DELETE FROM recipes WHERE id = $1")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(RecipeResponse {
+        success: true,
+        recipe: None,
+        message: Some("Recipe deleted".to_string()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, selector: &str, field_type: FieldType) -> RecipeField {
+        RecipeField {
+            name: name.to_string(),
+            selector: selector.to_string(),
+            field_type,
+            attribute: None,
+            post_process: vec![],
+        }
+    }
+
+    #[test]
+    fn test_apply_text_and_list_fields() {
+        let doc = Html::parse_document(
+            "<h1>  Thread   Title </h1><ul><li>a</li><li>b</li></ul>",
+        );
+        let mut title = field("title", "h1", FieldType::Text);
+        title.post_process = vec![PostProcess::CollapseWhitespace];
+        let out = apply_fields(&doc, &[title, field("items", "li", FieldType::List)]);
+        assert_eq!(out["title"], "Thread Title");
+        assert_eq!(out["items"], serde_json::json!(["a", "b"]));
+    }
+
+    #[test]
+    fn test_apply_attribute_and_number_fields() {
+        let doc = Html::parse_document(
+            r#"<a class="next" href="/page/2">Next</a><span class="price">$1,299.50</span>"#,
+        );
+        let mut link = field("next", "a.next", FieldType::Attribute);
+        link.attribute = Some("href".to_string());
+        let out = apply_fields(&doc, &[link, field("price", ".price", FieldType::Number)]);
+        assert_eq!(out["next"], "/page/2");
+        assert_eq!(out["price"], 1299.5);
+    }
+
+    #[test]
+    fn test_regex_post_process_and_missing_field() {
+        let doc = Html::parse_document("<p class='meta'>Posted by alice on 2024-01-02</p>");
+        let mut author = field("author", ".meta", FieldType::Text);
+        author.post_process = vec![PostProcess::Regex { pattern: r"by (\w+)".to_string() }];
+        let out = apply_fields(&doc, &[author, field("missing", ".nope", FieldType::Text)]);
+        assert_eq!(out["author"], "alice");
+        assert!(out["missing"].is_null());
+    }
+}
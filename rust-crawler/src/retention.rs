@@ -0,0 +1,248 @@
+//! Task retention.
+//!
+//! A scheduler job (`[retention] cron`) archives finished tasks older than their
+//! owner's plan `retention_days`, or the global `[retention] days` for plans
+//! without one. Each task's full row is written to object storage as gzipped JSON
+//! next to its HTML (`<engine>/<task_id>.archive.json.gz`). Then either the page
+//! text and HTML are cleared from the row, keeping a slim summary (keyword,
+//! results, metadata) with `archived_at` set, or the row is deleted (`action =
+//! "delete"`). `GET /crawl/{task_id}` reads archived text back from storage.
+//!
+//! The same job purges tasks that have been in the trash (`DELETE /tasks/{id}`)
+//! longer than `[retention] deleted_grace_days`, with their stored objects.
+
+use chrono::NaiveDateTime;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+use sqlx::FromRow;
+use std::io::{Read, Write};
+use crate::api::{AppState, TaskResult};
+use crate::config::RetentionAction;
+use crate::storage::StorageManager;
+use tracing::{info, warn};
+
+/// Postgres advisory lock held while archiving, so only one replica's scheduler runs it
+const RETENTION_LOCK_ID: i64 = 0x7265_7465_6e74_0001;
+/// Tasks archived per query
+const BATCH_SIZE: i64 = 200;
+
+/// Object key of a task's archived row; under the task's prefix, so deleting or
+/// erasing the task removes it too
+pub fn archive_key(engine: &str, task_id: &str) -> String {
+    format!("{}/{}.archive.json.gz", engine, task_id)
+}
+
+/// A task due for archiving, with its row as JSON
+#[derive(Debug, FromRow)]
+struct ExpiredTask {
+    id: String,
+    engine: String,
+    created_at: NaiveDateTime,
+    row: String,
+}
+
+/// The columns taken out of archived rows
+#[derive(Debug, Default, Deserialize)]
+pub struct ArchivedColumns {
+    pub extracted_text: Option<String>,
+    pub first_page_html: Option<String>,
+}
+
+/// What one retention run did
+#[derive(Debug, Default)]
+pub struct RetentionSummary {
+    pub archived: usize,
+    pub deleted: usize,
+    /// Deleted tasks past their grace period, removed for good
+    pub purged: usize,
+    pub failed: usize,
+}
+
+fn compress(row: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(row.as_bytes())?;
+    encoder.finish()
+}
+
+fn decompress(body: &[u8]) -> std::io::Result<String> {
+    let mut row = String::new();
+    GzDecoder::new(body).read_to_string(&mut row)?;
+    Ok(row)
+}
+
+/// Archive expired tasks and purge the trash; `None` if another replica is already doing it
+pub async fn archive_expired(state: &AppState) -> anyhow::Result<Option<RetentionSummary>> {
+    // Session lock on a connection of its own, released below or when the connection drops
+    let mut lock = state.pool.acquire().await?;
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+        .bind(RETENTION_LOCK_ID)
+        .fetch_one(&mut *lock)
+        .await?;
+    if !locked {
+        info!("🗄️ [Retention] Another instance is archiving tasks, skipping");
+        return Ok(None);
+    }
+    let result = run(state).await;
+    let _ = sqlx::query("SELECT pg_advisory_unlock($1)").bind(RETENTION_LOCK_ID).execute(&mut *lock).await;
+    result.map(Some)
+}
+
+async fn run(state: &AppState) -> anyhow::Result<RetentionSummary> {
+    let config = &state.config.retention;
+    let mut summary = RetentionSummary::default();
+    // Keyset cursor, so tasks that fail to archive aren't retried within the run
+    let mut after: Option<(NaiveDateTime, String)> = None;
+    loop {
+        let batch: Vec<ExpiredTask> = sqlx::query_as(
+            r#"SELECT t.id, t.engine, t.created_at, (to_jsonb(t) - 'search_vector')::text AS row
+               FROM tasks t
+               LEFT JOIN subscriptions s ON s.user_id = t.user_id AND s.status = ANY($1)
+               LEFT JOIN plans p ON p.id = COALESCE(s.plan_id, $2)
+               WHERE t.archived_at IS NULL AND t.deleted_at IS NULL
+                 AND t.status IN ('completed', 'partial', 'failed')
+                 AND t.created_at < LOCALTIMESTAMP - make_interval(days => COALESCE(p.retention_days, $3))
+                 AND ($4::timestamp IS NULL OR (t.created_at, t.id) > ($4, $5))
+               ORDER BY t.created_at, t.id
+               LIMIT $6"#,
+        )
+        .bind(&crate::subscriptions::ACTIVE_STATUSES[..])
+        .bind(crate::subscriptions::FREE_PLAN)
+        .bind(config.days.map(|d| d as i32))
+        .bind(after.as_ref().map(|(created_at, _)| *created_at))
+        .bind(after.as_ref().map(|(_, id)| id.as_str()))
+        .bind(BATCH_SIZE)
+        .fetch_all(&state.pool)
+        .await?;
+        let Some(last) = batch.last() else { break };
+        after = Some((last.created_at, last.id.clone()));
+        let full = batch.len() as i64 == BATCH_SIZE;
+
+        for task in batch {
+            match archive_task(state, &task, config.action).await {
+                Ok(()) if config.action == RetentionAction::Delete => summary.deleted += 1,
+                Ok(()) => summary.archived += 1,
+                Err(e) => {
+                    warn!("⚠️ [Retention] Failed to archive task {}: {:#}", task.id, e);
+                    summary.failed += 1;
+                }
+            }
+        }
+        if !full {
+            break;
+        }
+    }
+    purge_deleted(state, &mut summary).await?;
+    Ok(summary)
+}
+
+/// Purge tasks deleted more than `deleted_grace_days` ago
+async fn purge_deleted(state: &AppState, summary: &mut RetentionSummary) -> anyhow::Result<()> {
+    let grace_days = state.config.retention.deleted_grace_days as i32;
+    let mut after: Option<String> = None;
+    loop {
+        let batch: Vec<(String, String)> = sqlx::query_as(
+            r#"SELECT id, engine FROM tasks
+               WHERE deleted_at < now() - make_interval(days => $1)
+                 AND ($2::text IS NULL OR id > $2)
+               ORDER BY id
+               LIMIT $3"#,
+        )
+        .bind(grace_days)
+        .bind(&after)
+        .bind(BATCH_SIZE)
+        .fetch_all(&state.pool)
+        .await?;
+        let Some((last, _)) = batch.last() else { break };
+        after = Some(last.clone());
+        let full = batch.len() as i64 == BATCH_SIZE;
+
+        for (id, engine) in batch {
+            match crate::api::purge_task(state, &id, &engine).await {
+                Ok(_) => summary.purged += 1,
+                Err(e) => {
+                    warn!("⚠️ [Retention] Failed to purge deleted task {}: {}", id, e);
+                    summary.failed += 1;
+                }
+            }
+        }
+        if !full {
+            break;
+        }
+    }
+    Ok(())
+}
+
+async fn archive_task(state: &AppState, task: &ExpiredTask, action: RetentionAction) -> anyhow::Result<()> {
+    let body = compress(&task.row)?;
+    state.storage.put_object(&archive_key(&task.engine, &task.id), body, "application/gzip").await?;
+    // Only after the archive is stored; the status check skips tasks re-run since they were read
+    match action {
+        RetentionAction::Archive => {
+            sqlx::query(
+                r#"UPDATE tasks SET extracted_text = NULL, first_page_html = NULL, archived_at = now()
+                   WHERE id = $1 AND archived_at IS NULL AND status IN ('completed', 'partial', 'failed')"#,
+            )
+            .bind(&task.id)
+            .execute(&state.pool)
+            .await?;
+        }
+        RetentionAction::Delete => {
+            sqlx::query("DELETE FROM tasks WHERE id = $1 AND status IN ('completed', 'partial', 'failed')")
+                .bind(&task.id)
+                .execute(&state.pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// The page text and HTML of an archived task, read back from storage
+pub async fn load_archived(storage: &StorageManager, engine: &str, task_id: &str) -> anyhow::Result<Option<ArchivedColumns>> {
+    let Some(object) = storage.get_object(&archive_key(engine, task_id)).await? else {
+        return Ok(None);
+    };
+    Ok(Some(serde_json::from_str(&decompress(&object.body)?)?))
+}
+
+/// Fill in an archived task's page text and HTML; left empty if the archive can't be read
+pub async fn restore(storage: &StorageManager, task: &mut TaskResult) {
+    if task.archived_at.is_none() {
+        return;
+    }
+    match load_archived(storage, &task.engine, &task.id).await {
+        Ok(Some(columns)) => {
+            task.extracted_text = columns.extracted_text;
+            task.first_page_html = columns.first_page_html;
+        }
+        Ok(None) => warn!("⚠️ [Retention] No archive stored for task {}", task.id),
+        Err(e) => warn!("⚠️ [Retention] Failed to read archive of task {}: {:#}", task.id, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_archive_round_trip() {
+        let storage = StorageManager::in_memory();
+        let row = serde_json::json!({
+            "id": "t1",
+            "keyword": "rust",
+            "extracted_text": "Page text",
+            "first_page_html": "<html></html>",
+            "results_json": "{}",
+        })
+        .to_string();
+        let key = archive_key("bing", "t1");
+        assert!(key.starts_with(&crate::storage::task_prefix("bing", "t1")));
+        storage.put_object(&key, compress(&row).unwrap(), "application/gzip").await.unwrap();
+
+        let columns = load_archived(&storage, "bing", "t1").await.unwrap().unwrap();
+        assert_eq!(columns.extracted_text.as_deref(), Some("Page text"));
+        assert_eq!(columns.first_page_html.as_deref(), Some("<html></html>"));
+        assert!(load_archived(&storage, "bing", "t2").await.unwrap().is_none());
+    }
+}
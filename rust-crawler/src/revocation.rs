@@ -0,0 +1,227 @@
+//! Token revocation (denylist).
+//!
+//! Lets admins lock out a leaked JWT (by its `jti`) or a whole account (every
+//! token issued up to now) before the tokens expire. Entries live in Redis so all
+//! API instances see them, and expire after `AUTH_REVOCATION_TTL_SECS` (default
+//! 7 days, the longest JWT lifetime Supabase allows), by which time every token
+//! they could match has expired anyway. Without Redis (`APP_MODE=dev`, or Redis
+//! unreachable at startup) the list is kept in process.
+//!
+//! Lookups that fail (Redis down) refuse the request with 503 rather than let a
+//! revoked token or API key through.
+
+use anyhow::Result;
+use axum::{extract::State, http::StatusCode, Json};
+use redis::{AsyncCommands, Client};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+use crate::api::AppState;
+use crate::auth::AdminUser;
+use tracing::{error, info, warn};
+
+/// `auth_revoked:jti:<jti>` -> 1
+const JTI_KEY_PREFIX: &str = "auth_revoked:jti:";
+/// `auth_revoked:user:<user id>` -> unix time of the revocation; tokens issued up to then are rejected
+const USER_KEY_PREFIX: &str = "auth_revoked:user:";
+const DEFAULT_TTL_SECS: u64 = 7 * 24 * 3600;
+
+fn jti_key(jti: &str) -> String {
+    format!("{}{}", JTI_KEY_PREFIX, jti)
+}
+
+fn user_key(user_id: &str) -> String {
+    format!("{}{}", USER_KEY_PREFIX, user_id)
+}
+
+fn revocation_ttl() -> u64 {
+    std::env::var("AUTH_REVOCATION_TTL_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(DEFAULT_TTL_SECS)
+}
+
+#[derive(Clone)]
+enum Backend {
+    Redis(Client),
+    /// Key -> (value, expiry)
+    Memory(Arc<Mutex<HashMap<String, (i64, Instant)>>>),
+}
+
+#[derive(Clone)]
+pub struct Denylist {
+    backend: Backend,
+}
+
+impl Denylist {
+    /// Redis at `REDIS_URL`, or an in-process list in dev mode or when Redis is unreachable
+    pub async fn connect() -> Self {
+        if crate::dev_mode() {
+            return Self::in_memory();
+        }
+        let redis_url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
+        let connected = async {
+            let client = Client::open(redis_url)?;
+            let mut conn = client.get_async_connection().await?;
+            let _: String = redis::cmd("PING").query_async(&mut conn).await?;
+            Ok::<_, redis::RedisError>(client)
+        };
+        match connected.await {
+            Ok(client) => Self { backend: Backend::Redis(client) },
+            Err(e) => {
+                warn!("⚠️ [Auth] Redis unavailable for the token denylist ({}); revocations stay local to this instance", e);
+                Self::in_memory()
+            }
+        }
+    }
+
+    pub fn in_memory() -> Self {
+        Self { backend: Backend::Memory(Arc::default()) }
+    }
+
+    async fn set(&self, key: String, value: i64, ttl_secs: u64) -> Result<()> {
+        match &self.backend {
+            Backend::Redis(client) => {
+                let mut conn = client.get_async_connection().await?;
+                let _: () = conn.set_ex(key, value, ttl_secs).await?;
+            }
+            Backend::Memory(entries) => {
+                let expiry = Instant::now() + Duration::from_secs(ttl_secs);
+                entries.lock().unwrap().insert(key, (value, expiry));
+            }
+        }
+        Ok(())
+    }
+
+    async fn get(&self, keys: &[String]) -> Result<Vec<Option<i64>>> {
+        match &self.backend {
+            Backend::Redis(client) => {
+                let mut conn = client.get_async_connection().await?;
+                // MGET with a single key returns a bare value rather than a list
+                let mut values = Vec::with_capacity(keys.len());
+                for key in keys {
+                    values.push(conn.get(key).await?);
+                }
+                Ok(values)
+            }
+            Backend::Memory(entries) => {
+                let mut entries = entries.lock().unwrap();
+                let now = Instant::now();
+                entries.retain(|_, (_, expiry)| *expiry > now);
+                Ok(keys.iter().map(|k| entries.get(k).map(|(v, _)| *v)).collect())
+            }
+        }
+    }
+
+    /// Reject the token with this `jti` from now on
+    pub async fn revoke_token(&self, jti: &str) -> Result<()> {
+        self.set(jti_key(jti), 1, revocation_ttl()).await
+    }
+
+    /// Reject every token issued to `user_id` up to now
+    pub async fn revoke_user(&self, user_id: &str) -> Result<()> {
+        self.set(user_key(user_id), chrono::Utc::now().timestamp(), revocation_ttl()).await
+    }
+
+    /// Whether a token (its subject, `jti` and issue time) has been revoked
+    pub async fn is_revoked(&self, user_id: &str, jti: Option<&str>, issued_at: i64) -> Result<bool> {
+        let mut keys = vec![user_key(user_id)];
+        keys.extend(jti.map(jti_key));
+        let values = self.get(&keys).await?;
+        let user_revoked = values[0].is_some_and(|revoked_at| issued_at <= revoked_at);
+        let token_revoked = values.get(1).is_some_and(Option::is_some);
+        Ok(user_revoked || token_revoked)
+    }
+}
+
+// ============================================================================
+// API
+// ============================================================================
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RevokeRequest {
+    /// Lock out every token issued to this user so far, and revoke their API keys
+    pub user_id: Option<String>,
+    /// Lock out the single token with this `jti` claim
+    pub jti: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RevokeResponse {
+    pub success: bool,
+    pub message: String,
+    /// API keys of the user that were revoked along with their tokens
+    pub api_keys_revoked: u64,
+}
+
+/// Revoke a user's tokens or a single token before they expire (admins only)
+#[utoipa::path(
+    post,
+    path = "/auth/revoke",
+    tag = "auth",
+    request_body = RevokeRequest,
+    responses(
+        (status = 200, description = "Revoked", body = RevokeResponse),
+        (status = 400, description = "Neither or both of user_id and jti given"),
+        (status = 403, description = "Caller is not an admin")
+    )
+)]
+pub async fn revoke(
+    State(state): State<Arc<AppState>>,
+    AdminUser(admin): AdminUser,
+    Json(req): Json<RevokeRequest>,
+) -> Result<Json<RevokeResponse>, (StatusCode, String)> {
+    let failed = |e: anyhow::Error| {
+        error!("❌ [Auth] Revocation failed: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to store revocation".to_string())
+    };
+
+    match (req.user_id.as_deref(), req.jti.as_deref()) {
+        (Some(user_id), None) if !user_id.is_empty() => {
+            state.denylist.revoke_user(user_id).await.map_err(failed)?;
+            let api_keys_revoked = sqlx::query("UPDATE api_keys SET revoked_at = now() WHERE user_id = $1 AND revoked_at IS NULL")
+                .bind(user_id)
+                .execute(&state.pool)
+                .await
+                .map_err(|e| failed(e.into()))?
+                .rows_affected();
+            info!("🔒 [Auth] {} revoked all tokens of {} ({} API keys)", admin.id, user_id, api_keys_revoked);
+            Ok(Json(RevokeResponse {
+                success: true,
+                message: format!("Tokens issued to {} so far are rejected", user_id),
+                api_keys_revoked,
+            }))
+        }
+        (None, Some(jti)) if !jti.is_empty() => {
+            state.denylist.revoke_token(jti).await.map_err(failed)?;
+            info!("🔒 [Auth] {} revoked token {}", admin.id, jti);
+            Ok(Json(RevokeResponse {
+                success: true,
+                message: format!("Token {} is rejected", jti),
+                api_keys_revoked: 0,
+            }))
+        }
+        _ => Err((StatusCode::BAD_REQUEST, "Give exactly one of user_id or jti".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_denylist() {
+        let denylist = Denylist::in_memory();
+        let before = chrono::Utc::now().timestamp() - 60;
+        assert!(!denylist.is_revoked("u1", Some("t1"), before).await.unwrap());
+
+        denylist.revoke_token("t1").await.unwrap();
+        assert!(denylist.is_revoked("u1", Some("t1"), before).await.unwrap());
+        assert!(!denylist.is_revoked("u1", Some("t2"), before).await.unwrap());
+        assert!(!denylist.is_revoked("u1", None, before).await.unwrap());
+
+        denylist.revoke_user("u2").await.unwrap();
+        assert!(denylist.is_revoked("u2", None, before).await.unwrap());
+        // Tokens issued after the revocation (a new login) are accepted again
+        assert!(!denylist.is_revoked("u2", None, before + 3600).await.unwrap());
+    }
+}
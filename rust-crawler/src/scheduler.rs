@@ -1,6 +1,7 @@
 use tokio_cron_scheduler::{Job, JobScheduler};
 use std::sync::Arc;
 use crate::api::AppState;
+use tracing::{error, info};
 
 pub async fn start_scheduler(state: Arc<AppState>) -> anyhow::Result<()> {
     let sched = JobScheduler::new().await?;
@@ -10,7 +11,7 @@ pub async fn start_scheduler(state: Arc<AppState>) -> anyhow::Result<()> {
     sched.add(
         Job::new_async("0 */5 * * * *", |_uuid, _l| {
             Box::pin(async move {
-                println!("⏰ [Scheduler] Heartbeat: Central Control System active.");
+                info!("⏰ [Scheduler] Heartbeat: Central Control System active.");
             })
         })?
     ).await?;
@@ -22,7 +23,7 @@ pub async fn start_scheduler(state: Arc<AppState>) -> anyhow::Result<()> {
         Job::new_async("0 0 0 * * *", move |_uuid, _l| {
             let state = state_clone.clone();
             Box::pin(async move {
-                println!("⏰ [Scheduler] Triggering Daily Crawl Batch...");
+                info!("⏰ [Scheduler] Triggering Daily Crawl Batch...");
                 
                 // Example: Trigger a crawl for "Rust Programming" daily
                 let job = crate::queue::CrawlJob {
@@ -30,12 +31,78 @@ pub async fn start_scheduler(state: Arc<AppState>) -> anyhow::Result<()> {
                     user_id: "system".to_string(), // Scheduler runs as system
                     keyword: "daily trend analysis".to_string(),
                     engine: "bing".to_string(),
-                    selectors: None,
+                    options: Default::default(),
+                    max_retries: crate::queue::DEFAULT_MAX_RETRIES,
+                    backoff_secs: crate::queue::DEFAULT_BACKOFF_SECS,
+                    attempt: 0,
+                    // Bulk work yields to interactive crawls
+                    priority: crate::queue::Priority::Low,
+                    run_at: None,
+                    monitor_id: None,
+                    callback_url: None,
+                    receipt: None,
                 };
 
                 match state.queue.push_job(job).await {
-                    Ok(_) => println!("✅ [Scheduler] Daily job queued successfully."),
-                    Err(e) => eprintln!("❌ [Scheduler] Failed to queue daily job: {}", e),
+                    Ok(_) => info!("✅ [Scheduler] Daily job queued successfully."),
+                    Err(e) => error!("❌ [Scheduler] Failed to queue daily job: {}", e),
+                }
+            })
+        })?
+    ).await?;
+
+    // 3. User schedules (`/schedules`) and page monitors (`/monitors`): queue whatever is due, every minute
+    let state_clone = state.clone();
+    sched.add(
+        Job::new_async("0 * * * * *", move |_uuid, _l| {
+            let state = state_clone.clone();
+            Box::pin(async move {
+                match crate::schedules::enqueue_due(&state).await {
+                    Ok(0) => {}
+                    Ok(n) => info!("⏰ [Scheduler] Queued {} scheduled crawl(s)", n),
+                    Err(e) => error!("❌ [Scheduler] Failed to process schedules: {}", e),
+                }
+                match crate::monitors::enqueue_due(&state).await {
+                    Ok(0) => {}
+                    Ok(n) => info!("⏰ [Scheduler] Queued {} page monitor check(s)", n),
+                    Err(e) => error!("❌ [Scheduler] Failed to process page monitors: {}", e),
+                }
+            })
+        })?
+    ).await?;
+
+    // 4. Parquet dumps of completed tasks for analytics (only with `PARQUET_EXPORT_CRON`)
+    if let Some(cron) = crate::analytics::dump_cron() {
+        let state_clone = state.clone();
+        sched.add(
+            Job::new_async(cron.as_str(), move |_uuid, _l| {
+                let state = state_clone.clone();
+                Box::pin(async move {
+                    match crate::analytics::dump_completed_tasks(&state).await {
+                        Ok(Some(dump)) => info!("📊 [Scheduler] Dumped {} completed task(s) to {} Parquet file(s)", dump.rows, dump.files.len()),
+                        Ok(None) => {}
+                        Err(e) => error!("❌ [Scheduler] Parquet dump failed: {:#}", e),
+                    }
+                })
+            })?
+        ).await?;
+        info!("📊 [Scheduler] Parquet dumps scheduled: {}", cron);
+    }
+
+    // 5. Archive finished tasks past their retention period, purge the trash
+    let state_clone = state.clone();
+    let retention_cron = state.config.retention.cron.clone();
+    sched.add(
+        Job::new_async(retention_cron.as_str(), move |_uuid, _l| {
+            let state = state_clone.clone();
+            Box::pin(async move {
+                match crate::retention::archive_expired(&state).await {
+                    Ok(Some(run)) if run.archived + run.deleted + run.purged + run.failed > 0 => info!(
+                        "🗄️ [Scheduler] Retention: archived {}, deleted {}, purged {}, failed {}",
+                        run.archived, run.deleted, run.purged, run.failed
+                    ),
+                    Ok(_) => {}
+                    Err(e) => error!("❌ [Scheduler] Retention run failed: {:#}", e),
                 }
             })
         })?
@@ -43,7 +110,7 @@ pub async fn start_scheduler(state: Arc<AppState>) -> anyhow::Result<()> {
 
     // Start the scheduler
     sched.start().await?;
-    println!("✅ Central Scheduler Started (Rust Native)");
+    info!("✅ Central Scheduler Started (Rust Native)");
 
     Ok(())
 }
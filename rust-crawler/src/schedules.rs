@@ -0,0 +1,871 @@
+//! Recurring crawl schedules.
+//!
+//! Users register a cron expression plus what to crawl; the central scheduler
+//! checks every minute for schedules whose `next_run_at` has passed, queues a
+//! crawl for each and advances them to their next occurrence.
+//!
+//! Cron expressions are evaluated in the schedule's IANA timezone. Around DST
+//! changes, a wall-clock time that occurs twice fires once (the first time), and
+//! one skipped by the spring-forward gap fires an hour later on the new clock.
+//!
+//! Runs that came due while the service was down are handled by each schedule's
+//! `catch_up` policy once the scheduler is back.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json as SqlJson, FromRow, PgPool};
+use std::str::FromStr;
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::crawler::CrawlOptions;
+use crate::credits::CreditError;
+use crate::quotas::{self, QuotaError};
+use tracing::{error, info, warn};
+
+/// Upcoming runs included in schedule responses
+const PREVIEW_RUNS: usize = 5;
+/// A due run older than this counts as missed rather than just picked up late
+const MISSED_RUN_GRACE_SECS: i64 = 120;
+/// Most missed runs `run_all_missed` queues for one schedule
+const MAX_CATCH_UP_RUNS: usize = 100;
+
+/// What the scheduler does with runs missed while the service was down
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Drop missed runs and wait for the next occurrence
+    Skip,
+    /// Queue a single crawl for any number of missed runs
+    #[default]
+    RunOnce,
+    /// Queue one crawl per missed run (up to 100)
+    RunAllMissed,
+}
+
+impl CatchUpPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CatchUpPolicy::Skip => "skip",
+            CatchUpPolicy::RunOnce => "run_once",
+            CatchUpPolicy::RunAllMissed => "run_all_missed",
+        }
+    }
+
+    fn from_str_lossy(s: &str) -> Self {
+        match s {
+            "skip" => CatchUpPolicy::Skip,
+            "run_all_missed" => CatchUpPolicy::RunAllMissed,
+            _ => CatchUpPolicy::RunOnce,
+        }
+    }
+
+    /// How many crawls to queue for the occurrences in `due` (oldest first, all <= `now`)
+    pub fn runs_to_queue(self, due: &[DateTime<Utc>], now: DateTime<Utc>) -> usize {
+        let on_time = due.iter().filter(|t| (now - **t).num_seconds() <= MISSED_RUN_GRACE_SECS).count();
+        match self {
+            CatchUpPolicy::Skip => on_time.min(1),
+            CatchUpPolicy::RunOnce => due.len().min(1),
+            CatchUpPolicy::RunAllMissed => (due.len() - on_time).min(MAX_CATCH_UP_RUNS) + on_time.min(1),
+        }
+    }
+}
+
+impl From<String> for CatchUpPolicy {
+    fn from(s: String) -> Self {
+        CatchUpPolicy::from_str_lossy(&s)
+    }
+}
+
+#[derive(Debug, Serialize, Clone, ToSchema, FromRow)]
+pub struct Schedule {
+    pub id: String,
+    /// User the crawls run as (and are billed to)
+    pub owner: String,
+    /// Cron expression: 5 fields (`min hour day month weekday`) or 6 with leading seconds
+    #[schema(example = "0 6 * * *")]
+    pub cron: String,
+    /// IANA timezone the cron expression is evaluated in
+    #[schema(example = "Europe/Berlin")]
+    pub timezone: String,
+    #[schema(example = "rust programming")]
+    pub keyword: String,
+    #[schema(example = "bing")]
+    pub engine: String,
+    #[schema(value_type = CrawlOptions)]
+    pub options: SqlJson<CrawlOptions>,
+    /// Disabled schedules keep their settings but never fire
+    pub enabled: bool,
+    #[sqlx(try_from = "String")]
+    pub catch_up: CatchUpPolicy,
+    /// Next time the scheduler queues this crawl; null while disabled
+    #[schema(value_type = Option<String>)]
+    pub next_run_at: Option<DateTime<Utc>>,
+    /// The next few occurrences after `next_run_at`, for previewing the cron expression
+    #[sqlx(skip)]
+    #[schema(value_type = Vec<String>)]
+    pub upcoming_runs: Vec<DateTime<Utc>>,
+    #[schema(value_type = Option<String>)]
+    pub last_run_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateScheduleRequest {
+    #[schema(example = "0 6 * * *")]
+    pub cron: String,
+    /// IANA timezone name; defaults to UTC
+    #[schema(example = "America/New_York", default = "UTC")]
+    pub timezone: Option<String>,
+    #[schema(example = "rust programming")]
+    pub keyword: String,
+    #[schema(example = "bing", default = "bing")]
+    pub engine: Option<String>,
+    #[serde(default)]
+    pub options: CrawlOptions,
+    /// Defaults to true
+    pub enabled: Option<bool>,
+    /// Handling of runs missed during downtime; defaults to `run_once`
+    pub catch_up: Option<CatchUpPolicy>,
+    /// Run as another user (admins only); defaults to the caller
+    pub owner: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateScheduleRequest {
+    pub cron: Option<String>,
+    pub timezone: Option<String>,
+    pub keyword: Option<String>,
+    pub engine: Option<String>,
+    /// Replaces the stored options
+    pub options: Option<CrawlOptions>,
+    pub enabled: Option<bool>,
+    pub catch_up: Option<CatchUpPolicy>,
+    /// Admins only
+    pub owner: Option<String>,
+}
+
+impl Schedule {
+    /// Fill in `upcoming_runs`
+    fn with_preview(mut self) -> Self {
+        self.upcoming_runs = match (self.next_run_at, parse_cron(&self.cron), parse_timezone(&self.timezone)) {
+            (Some(next), Ok(cron), Ok(tz)) => occurrences(&cron, tz, next).take(PREVIEW_RUNS).collect(),
+            _ => Vec::new(),
+        };
+        self
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScheduleRunResponse {
+    pub success: bool,
+    /// Task of the queued crawl
+    pub task_id: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScheduleResponse {
+    pub success: bool,
+    pub schedule: Option<Schedule>,
+    pub message: Option<String>,
+}
+
+type ApiError = (StatusCode, String);
+
+const WEEKDAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Standard cron numbers weekdays 0-7 from Sunday; the `cron` crate uses 1-7.
+/// Names mean the same in both, so numeric days are rewritten as names.
+fn weekday_names(field: &str) -> String {
+    field
+        .split(',')
+        .map(|part| {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (part, None),
+            };
+            let range = range
+                .split('-')
+                .map(|day| match day.parse::<usize>() {
+                    Ok(n) if n <= 7 => WEEKDAY_NAMES[n % 7].to_string(),
+                    _ => day.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("-");
+            match step {
+                Some(step) => format!("{}/{}", range, step),
+                None => range,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parse a cron expression. Standard 5-field expressions run at second 0.
+pub fn parse_cron(expr: &str) -> Result<cron::Schedule, String> {
+    let expr = expr.trim();
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    let normalized = match fields.as_slice() {
+        [minute, hour, day, month, weekday] => {
+            format!("0 {} {} {} {} {}", minute, hour, day, month, weekday_names(weekday))
+        }
+        _ => expr.to_string(),
+    };
+    cron::Schedule::from_str(&normalized).map_err(|e| format!("Invalid cron expression '{}': {}", expr, e))
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.trim().parse::<Tz>().map_err(|_| format!("Unknown timezone '{}'", name))
+}
+
+/// The instant a wall-clock time in `tz` refers to, resolving DST as described above
+fn local_to_utc(tz: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    let instant = match tz.from_local_datetime(&local) {
+        LocalResult::Single(t) => Some(t),
+        LocalResult::Ambiguous(earliest, _) => Some(earliest),
+        LocalResult::None => tz.from_local_datetime(&(local + Duration::hours(1))).earliest(),
+    };
+    instant.map(|t| t.with_timezone(&Utc))
+}
+
+/// Occurrences of `cron` in `tz` strictly after `after`, as UTC instants in order
+pub fn occurrences(cron: &cron::Schedule, tz: Tz, after: DateTime<Utc>) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+    // Walk wall-clock times, using a UTC-labelled value as a plain local time
+    let local_after = Utc.from_utc_datetime(&after.with_timezone(&tz).naive_local());
+    let mut last = after;
+    cron.after(&local_after).take(10_000).filter_map(move |wall| {
+        let instant = local_to_utc(tz, wall.naive_utc())?;
+        // Drops the repeat of a fall-back hour and gap times shifted onto existing ones
+        (instant > last).then(|| {
+            last = instant;
+            instant
+        })
+    })
+}
+
+/// First occurrence of `expr` in `timezone` strictly after `after`
+pub fn next_run(expr: &str, timezone: &str, after: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let cron = parse_cron(expr)?;
+    let tz = parse_timezone(timezone)?;
+    let next = occurrences(&cron, tz, after).next();
+    next.ok_or_else(|| format!("Cron expression '{}' never fires", expr))
+}
+
+const SCHEDULE_COLUMNS: &str =
+    "id, owner, cron, timezone, keyword, engine, options, enabled, catch_up, next_run_at, last_run_at, created_at, updated_at";
+
+async fn fetch_schedule(pool: &PgPool, id: &str) -> Result<Option<Schedule>, sqlx::Error> {
+    sqlx::query_as(&format!("SELECT {} FROM schedules WHERE id = $1", SCHEDULE_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Schedules] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+/// Load a schedule the caller may manage (their own, or any for admins)
+async fn owned_schedule(pool: &PgPool, id: &str, user: &AuthUser) -> Result<Schedule, ApiError> {
+    match fetch_schedule(pool, id).await.map_err(db_error)? {
+        Some(schedule) if schedule.owner == user.id || user.is_admin() => Ok(schedule),
+        _ => Err((StatusCode::NOT_FOUND, "Schedule not found".to_string())),
+    }
+}
+
+fn resolve_owner(user: &AuthUser, requested: Option<String>) -> Result<Option<String>, ApiError> {
+    match requested {
+        Some(owner) if owner != user.id && !user.is_admin() => {
+            Err((StatusCode::FORBIDDEN, "Only admins can manage schedules for other users".to_string()))
+        }
+        owner => Ok(owner),
+    }
+}
+
+// ============================================================================
+// CRUD API
+// ============================================================================
+
+/// List the caller's schedules (all schedules for admins)
+#[utoipa::path(
+    get,
+    path = "/schedules",
+    tag = "schedules",
+    responses(
+        (status = 200, description = "List schedules", body = Vec<Schedule>)
+    )
+)]
+pub async fn list_schedules(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<Vec<Schedule>>, ApiError> {
+    let schedules: Vec<Schedule> = sqlx::query_as(&format!(
+        "SELECT {} FROM schedules WHERE owner = $1 OR $2 ORDER BY created_at",
+        SCHEDULE_COLUMNS
+    ))
+    .bind(&user.id)
+    .bind(user.is_admin())
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(schedules.into_iter().map(Schedule::with_preview).collect()))
+}
+
+/// Get a schedule by ID
+#[utoipa::path(
+    get,
+    path = "/schedules/{id}",
+    tag = "schedules",
+    params(
+        ("id" = String, Path, description = "Schedule ID")
+    ),
+    responses(
+        (status = 200, description = "Schedule", body = ScheduleResponse),
+        (status = 404, description = "Schedule not found")
+    )
+)]
+pub async fn get_schedule(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<ScheduleResponse>, ApiError> {
+    let schedule = owned_schedule(&state.pool, &id, &user).await?;
+    Ok(Json(ScheduleResponse {
+        success: true,
+        schedule: Some(schedule.with_preview()),
+        message: None,
+    }))
+}
+
+/// Create a recurring crawl
+#[utoipa::path(
+    post,
+    path = "/schedules",
+    tag = "schedules",
+    request_body = CreateScheduleRequest,
+    responses(
+        (status = 200, description = "Schedule created", body = ScheduleResponse),
+        (status = 400, description = "Invalid cron expression"),
+        (status = 422, description = "Invalid keyword, engine or crawl options, as `field: message` pairs"),
+        (status = 403, description = "Owner is another user and the caller is not an admin, or the owner's plan doesn't allow the crawl options")
+    )
+)]
+pub async fn create_schedule(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<CreateScheduleRequest>,
+) -> Result<Json<ScheduleResponse>, ApiError> {
+    if req.keyword.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Keyword is required".to_string()));
+    }
+    let engine = req.engine.unwrap_or_else(|| "bing".to_string());
+    crate::validation::validate_crawl(&req.keyword, &engine, &req.options, &state.config.engines).await?;
+    let timezone = req.timezone.as_deref().map(str::trim).unwrap_or("UTC").to_string();
+    let next_run_at = next_run(&req.cron, &timezone, Utc::now()).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let owner = resolve_owner(&user, req.owner)?.unwrap_or_else(|| user.id.clone());
+    let mut options = req.options;
+    crate::subscriptions::enforce_plan(&state.pool, &owner, &mut options).await?;
+    crate::organizations::check_pinned_proxy(&state.pool, &owner, options.proxy_id.as_deref()).await?;
+    let enabled = req.enabled.unwrap_or(true);
+    let next_run_at = enabled.then_some(next_run_at);
+    let id = Uuid::new_v4().to_string();
+    let secret_key = crate::login_secrets::stored_key("schedule", &id);
+    crate::login_secrets::seal_stored(&state.pool, &secret_key, &owner, &mut options).await.map_err(db_error)?;
+
+    let inserted = sqlx::query_as(&format!(
+        r#"INSERT INTO schedules (id, owner, cron, timezone, keyword, engine, options, enabled, catch_up, next_run_at)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+           RETURNING {}"#,
+        SCHEDULE_COLUMNS
+    ))
+    .bind(&id)
+    .bind(&owner)
+    .bind(req.cron.trim())
+    .bind(&timezone)
+    .bind(&req.keyword)
+    .bind(&engine)
+    .bind(SqlJson(&options))
+    .bind(enabled)
+    .bind(req.catch_up.unwrap_or_default().as_str())
+    .bind(next_run_at)
+    .fetch_one(&state.pool)
+    .await;
+    let schedule: Schedule = match inserted {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            let _ = crate::login_secrets::discard(&state.pool, &secret_key).await;
+            return Err(db_error(e));
+        }
+    };
+
+    info!("🗓️ [Schedules] Created {} for {} ({})", schedule.id, owner, schedule.cron);
+    Ok(Json(ScheduleResponse {
+        success: true,
+        schedule: Some(schedule.with_preview()),
+        message: Some("Schedule created".to_string()),
+    }))
+}
+
+/// Update a schedule. Set `enabled` to pause or resume it; changing the cron
+/// expression or resuming recomputes the next run.
+#[utoipa::path(
+    patch,
+    path = "/schedules/{id}",
+    tag = "schedules",
+    params(
+        ("id" = String, Path, description = "Schedule ID")
+    ),
+    request_body = UpdateScheduleRequest,
+    responses(
+        (status = 200, description = "Schedule updated", body = ScheduleResponse),
+        (status = 400, description = "Invalid cron expression"),
+        (status = 422, description = "Invalid keyword, engine or crawl options, as `field: message` pairs"),
+        (status = 403, description = "The owner's plan doesn't allow the crawl options"),
+        (status = 404, description = "Schedule not found")
+    )
+)]
+pub async fn update_schedule(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateScheduleRequest>,
+) -> Result<Json<ScheduleResponse>, ApiError> {
+    let mut schedule = owned_schedule(&state.pool, &id, &user).await?;
+    let reschedule = req.cron.is_some() || req.timezone.is_some() || (req.enabled == Some(true) && !schedule.enabled);
+
+    if let Some(owner) = resolve_owner(&user, req.owner)? {
+        schedule.owner = owner;
+    }
+    if let Some(cron) = req.cron {
+        schedule.cron = cron.trim().to_string();
+    }
+    if let Some(timezone) = req.timezone {
+        schedule.timezone = timezone.trim().to_string();
+    }
+    if let Some(keyword) = req.keyword {
+        if keyword.trim().is_empty() {
+            return Err((StatusCode::BAD_REQUEST, "Keyword is required".to_string()));
+        }
+        schedule.keyword = keyword;
+    }
+    if let Some(engine) = req.engine {
+        schedule.engine = engine;
+    }
+    if let Some(options) = req.options {
+        schedule.options = SqlJson(options);
+    }
+    if let Some(enabled) = req.enabled {
+        schedule.enabled = enabled;
+    }
+    if let Some(catch_up) = req.catch_up {
+        schedule.catch_up = catch_up;
+    }
+    crate::validation::validate_crawl(&schedule.keyword, &schedule.engine, &schedule.options, &state.config.engines).await?;
+    crate::subscriptions::enforce_plan(&state.pool, &schedule.owner, &mut schedule.options.0).await?;
+    crate::organizations::check_pinned_proxy(&state.pool, &schedule.owner, schedule.options.proxy_id.as_deref()).await?;
+    let secret_key = crate::login_secrets::stored_key("schedule", &id);
+    crate::login_secrets::seal_stored(&state.pool, &secret_key, &schedule.owner, &mut schedule.options.0)
+        .await
+        .map_err(db_error)?;
+    if reschedule {
+        let next = next_run(&schedule.cron, &schedule.timezone, Utc::now()).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        schedule.next_run_at = schedule.enabled.then_some(next);
+    } else if !schedule.enabled {
+        schedule.next_run_at = None;
+    }
+
+    let schedule: Schedule = sqlx::query_as(&format!(
+        r#"UPDATE schedules SET
+           owner = $2, cron = $3, timezone = $4, keyword = $5, engine = $6, options = $7, enabled = $8, catch_up = $9, next_run_at = $10,
+           updated_at = now()
+           WHERE id = $1
+           RETURNING {}"#,
+        SCHEDULE_COLUMNS
+    ))
+    .bind(&id)
+    .bind(&schedule.owner)
+    .bind(&schedule.cron)
+    .bind(&schedule.timezone)
+    .bind(&schedule.keyword)
+    .bind(&schedule.engine)
+    .bind(&schedule.options)
+    .bind(schedule.enabled)
+    .bind(schedule.catch_up.as_str())
+    .bind(schedule.next_run_at)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?
+    .ok_or((StatusCode::NOT_FOUND, "Schedule not found".to_string()))?;
+
+    Ok(Json(ScheduleResponse {
+        success: true,
+        schedule: Some(schedule.with_preview()),
+        message: Some("Schedule updated".to_string()),
+    }))
+}
+
+/// Delete a schedule
+#[utoipa::path(
+    delete,
+    path = "/schedules/{id}",
+    tag = "schedules",
+    params(
+        ("id" = String, Path, description = "Schedule ID")
+    ),
+    responses(
+        (status = 200, description = "Schedule deleted", body = ScheduleResponse),
+        (status = 404, description = "Schedule not found")
+    )
+)]
+pub async fn delete_schedule(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<ScheduleResponse>, ApiError> {
+    owned_schedule(&state.pool, &id, &user).await?;
+    sqlx::query("DELETE FROM schedules WHERE id = $1")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+        .map_err(db_error)?;
+    let secret_key = crate::login_secrets::stored_key("schedule", &id);
+    if let Err(e) = crate::login_secrets::discard(&state.pool, &secret_key).await {
+        warn!("⚠️ [Schedules] Failed to delete the login password of {}: {}", id, e);
+    }
+
+    Ok(Json(ScheduleResponse {
+        success: true,
+        schedule: None,
+        message: Some("Schedule deleted".to_string()),
+    }))
+}
+
+/// Queue a crawl for a schedule immediately. Works on paused schedules too and
+/// leaves `next_run_at` unchanged.
+#[utoipa::path(
+    post,
+    path = "/schedules/{id}/run-now",
+    tag = "schedules",
+    params(
+        ("id" = String, Path, description = "Schedule ID")
+    ),
+    responses(
+        (status = 200, description = "Crawl queued", body = ScheduleRunResponse),
+        (status = 404, description = "Schedule not found"),
+        (status = 402, description = "Owner doesn't have enough credits"),
+        (status = 403, description = "Owner's plan doesn't allow the schedule's crawl options"),
+        (status = 429, description = "Owner's crawl quota exceeded")
+    )
+)]
+pub async fn run_schedule_now(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<ScheduleRunResponse>, Response> {
+    let schedule = owned_schedule(&state.pool, &id, &user).await.map_err(IntoResponse::into_response)?;
+    let task_id = queue_run(&state, &schedule).await.map_err(IntoResponse::into_response)?;
+
+    if let Err(e) = sqlx::query("UPDATE schedules SET last_run_at = now() WHERE id = $1").bind(&id).execute(&state.pool).await {
+        warn!("⚠️ [Schedules] Failed to record run of {}: {}", id, e);
+    }
+    Ok(Json(ScheduleRunResponse {
+        success: true,
+        task_id: Some(task_id),
+        message: Some("Crawl queued".to_string()),
+    }))
+}
+
+// ============================================================================
+// Scheduler hook
+// ============================================================================
+
+/// Why a schedule's crawl wasn't queued
+pub enum RunError {
+    /// The owner's plan no longer allows the schedule's options
+    Plan((StatusCode, String)),
+    Quota(QuotaError),
+    Credits(CreditError),
+    Queue(anyhow::Error),
+}
+
+impl IntoResponse for RunError {
+    fn into_response(self) -> Response {
+        match self {
+            RunError::Plan(e) => e.into_response(),
+            RunError::Quota(e) => e.into_response(),
+            RunError::Credits(e) => e.into_response(),
+            RunError::Queue(e) => {
+                error!("❌ [Schedules] Failed to queue job: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to queue job".to_string()).into_response()
+            }
+        }
+    }
+}
+
+/// Queue one crawl of `schedule` as its owner, counting it against their quota
+/// and charging their credits like an API crawl
+async fn queue_run(state: &AppState, schedule: &Schedule) -> Result<String, RunError> {
+    // The plan may have changed since the schedule was saved
+    let mut options = schedule.options.0.clone();
+    crate::subscriptions::enforce_plan(&state.pool, &schedule.owner, &mut options).await.map_err(RunError::Plan)?;
+    quotas::reserve(&state.pool, &schedule.owner, 1).await.map_err(RunError::Quota)?;
+
+    let task_id = Uuid::new_v4().to_string();
+    let kind = crate::credits::CrawlKind::of(&schedule.engine, &options);
+    if let Err(e) = crate::credits::debit(&state.pool, &schedule.owner, &task_id, kind).await {
+        release_quota(state, &schedule.owner).await;
+        return Err(RunError::Credits(e));
+    }
+    if let Err(e) = crate::login_secrets::seal_run(&state.pool, &task_id, &schedule.owner, &mut options).await {
+        if let Err(e) = crate::credits::refund(&state.pool, &task_id).await {
+            warn!("⚠️ [Schedules] Failed to refund credits for {}: {}", task_id, e);
+        }
+        release_quota(state, &schedule.owner).await;
+        return Err(RunError::Queue(e));
+    }
+
+    let job = crate::queue::CrawlJob {
+        id: task_id.clone(),
+        user_id: schedule.owner.clone(),
+        keyword: schedule.keyword.clone(),
+        engine: schedule.engine.clone(),
+        options,
+        max_retries: crate::queue::DEFAULT_MAX_RETRIES,
+        backoff_secs: crate::queue::DEFAULT_BACKOFF_SECS,
+        attempt: 0,
+        // Recurring work yields to interactive crawls
+        priority: crate::queue::Priority::Low,
+        run_at: None,
+        monitor_id: None,
+        callback_url: None,
+        receipt: None,
+    };
+    let queued_event = crate::events::JobEvent::new(crate::events::JobEventKind::Queued, &job);
+
+    if let Err(e) = state.queue.push_job(job).await {
+        let _ = crate::login_secrets::discard(&state.pool, &task_id).await;
+        if let Err(e) = crate::credits::refund(&state.pool, &task_id).await {
+            warn!("⚠️ [Schedules] Failed to refund credits for {}: {}", task_id, e);
+        }
+        release_quota(state, &schedule.owner).await;
+        return Err(RunError::Queue(e));
+    }
+    info!("✅ [Schedules] Queued {} for schedule {}", task_id, schedule.id);
+    crate::events::publish(queued_event);
+    Ok(task_id)
+}
+
+/// Give back the quota reserved for a run that wasn't queued
+async fn release_quota(state: &AppState, owner: &str) {
+    if let Err(e) = quotas::release(&state.pool, owner, 1).await {
+        warn!("⚠️ [Schedules] Failed to release quota for {}: {}", owner, e);
+    }
+}
+
+/// Queue crawls for every enabled schedule that is due, then advance it. Runs
+/// missed while the service was down are handled by the schedule's catch-up policy.
+/// Returns the number of crawls queued.
+pub async fn enqueue_due(state: &AppState) -> anyhow::Result<usize> {
+    let due: Vec<Schedule> = sqlx::query_as(&format!(
+        "SELECT {} FROM schedules WHERE enabled AND next_run_at <= now() ORDER BY next_run_at LIMIT 500",
+        SCHEDULE_COLUMNS
+    ))
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut queued = 0;
+    for schedule in due {
+        let now = Utc::now();
+        let Some(first_due) = schedule.next_run_at else { continue };
+        let (runs, next_run_at) = match (parse_cron(&schedule.cron), parse_timezone(&schedule.timezone)) {
+            (Ok(cron), Ok(tz)) => {
+                let due_times: Vec<DateTime<Utc>> = std::iter::once(first_due)
+                    .chain(occurrences(&cron, tz, first_due).take_while(|t| *t <= now))
+                    .collect();
+                let next = occurrences(&cron, tz, now).next();
+                if next.is_none() {
+                    warn!("⚠️ [Schedules] {} has no further runs; disabling it", schedule.id);
+                }
+                (schedule.catch_up.runs_to_queue(&due_times, now), next)
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                warn!("⚠️ [Schedules] Disabling {}: {}", schedule.id, e);
+                (1, None)
+            }
+        };
+
+        // Compare-and-set on next_run_at, so only one replica fires each occurrence
+        let claimed = sqlx::query(
+            r#"UPDATE schedules SET next_run_at = $3, last_run_at = $4, enabled = $3 IS NOT NULL
+               WHERE id = $1 AND next_run_at = $2"#,
+        )
+        .bind(&schedule.id)
+        .bind(first_due)
+        .bind(next_run_at)
+        .bind(now)
+        .execute(&state.pool)
+        .await?;
+        if claimed.rows_affected() == 0 {
+            continue;
+        }
+
+        if (now - first_due).num_seconds() > MISSED_RUN_GRACE_SECS {
+            info!(
+                "⏰ [Schedules] {} missed runs since {} ({}); queueing {}",
+                schedule.id,
+                first_due.to_rfc3339(),
+                schedule.catch_up.as_str(),
+                runs
+            );
+        }
+
+        for _ in 0..runs {
+            match queue_run(state, &schedule).await {
+                Ok(_) => queued += 1,
+                Err(RunError::Plan((_, reason))) => {
+                    info!("⏭️ [Schedules] Skipping {} this run: {}", schedule.id, reason);
+                    break;
+                }
+                Err(RunError::Quota(QuotaError::Exceeded(_))) => {
+                    info!("⏭️ [Schedules] Skipping {} this run: {} is over quota", schedule.id, schedule.owner);
+                    break;
+                }
+                Err(RunError::Quota(QuotaError::Database(e))) => {
+                    warn!("⚠️ [Schedules] Quota check failed for {}: {}", schedule.id, e);
+                    break;
+                }
+                Err(RunError::Credits(CreditError::Insufficient { balance, cost })) => {
+                    info!(
+                        "⏭️ [Schedules] Skipping {} this run: {} has {} credits, the crawl costs {}",
+                        schedule.id, schedule.owner, balance, cost
+                    );
+                    break;
+                }
+                Err(RunError::Credits(CreditError::Database(e))) => {
+                    warn!("⚠️ [Schedules] Credit debit failed for {}: {}", schedule.id, e);
+                    break;
+                }
+                Err(RunError::Queue(e)) => {
+                    error!("❌ [Schedules] Failed to queue schedule {}: {}", schedule.id, e);
+                    break;
+                }
+            }
+        }
+    }
+    Ok(queued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_cron_parsing_and_next_run() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 6, 30, 0).unwrap();
+        // Five-field expressions run at second 0
+        assert_eq!(next_run("0 6 * * *", "UTC", now).unwrap(), Utc.with_ymd_and_hms(2026, 3, 11, 6, 0, 0).unwrap());
+        assert_eq!(next_run("0 */15 * * * *", "UTC", now).unwrap(), Utc.with_ymd_and_hms(2026, 3, 10, 6, 45, 0).unwrap());
+        // Weekdays use standard numbering (1 = Monday): 2026-03-10 is a Tuesday
+        assert_eq!(next_run("0 9 * * 1", "UTC", now).unwrap(), Utc.with_ymd_and_hms(2026, 3, 16, 9, 0, 0).unwrap());
+        assert_eq!(next_run("0 9 * * 0,6", "UTC", now).unwrap(), Utc.with_ymd_and_hms(2026, 3, 14, 9, 0, 0).unwrap());
+        assert_eq!(weekday_names("1-5,0/2"), "MON-FRI,SUN/2");
+        assert!(parse_cron("every day").is_err());
+        assert!(parse_cron("61 * * * *").is_err());
+    }
+
+    #[test]
+    fn test_schedule_response_carries_no_password() {
+        let mut options: CrawlOptions = serde_json::from_value(serde_json::json!({
+            "login": {
+                "username": "alice", "password": "hunter2",
+                "username_selector": "#u", "password_selector": "#p", "submit_selector": "button"
+            }
+        }))
+        .unwrap();
+        // What `seal_stored` does before the options are saved
+        let key = crate::login_secrets::stored_key("schedule", "s1");
+        assert_eq!(crate::login_secrets::take_password(&key, &mut options).as_deref(), Some("hunter2"));
+
+        let schedule = Schedule {
+            id: "s1".to_string(),
+            owner: "user-1".to_string(),
+            cron: "0 6 * * *".to_string(),
+            timezone: "UTC".to_string(),
+            keyword: "https://forum.example.com/".to_string(),
+            engine: "generic".to_string(),
+            options: SqlJson(options),
+            enabled: true,
+            catch_up: CatchUpPolicy::default(),
+            next_run_at: None,
+            upcoming_runs: Vec::new(),
+            last_run_at: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let response = serde_json::to_value(ScheduleResponse {
+            success: true,
+            schedule: Some(schedule),
+            message: None,
+        })
+        .unwrap();
+        let login = &response["schedule"]["options"]["login"];
+        assert!(login.get("password").is_none());
+        assert_eq!(login["password_ref"], "schedule:s1");
+        assert!(!response.to_string().contains("hunter2"));
+    }
+
+    #[test]
+    fn test_next_run_in_timezone_across_dst() {
+        let ny = "America/New_York";
+        // 08:00 local is 13:00 UTC in EST and 12:00 UTC once DST starts (2026-03-08)
+        let before = Utc.with_ymd_and_hms(2026, 3, 6, 14, 0, 0).unwrap();
+        assert_eq!(next_run("0 8 * * *", ny, before).unwrap(), Utc.with_ymd_and_hms(2026, 3, 7, 13, 0, 0).unwrap());
+        let saturday = Utc.with_ymd_and_hms(2026, 3, 7, 14, 0, 0).unwrap();
+        assert_eq!(next_run("0 8 * * *", ny, saturday).unwrap(), Utc.with_ymd_and_hms(2026, 3, 8, 12, 0, 0).unwrap());
+
+        // 02:30 doesn't exist on 2026-03-08; it runs at 03:30 EDT instead
+        let night = Utc.with_ymd_and_hms(2026, 3, 8, 0, 0, 0).unwrap();
+        assert_eq!(next_run("30 2 * * *", ny, night).unwrap(), Utc.with_ymd_and_hms(2026, 3, 8, 7, 30, 0).unwrap());
+
+        // 01:30 happens twice on 2026-11-01; only the first (EDT) one fires
+        let fall_back = Utc.with_ymd_and_hms(2026, 11, 1, 4, 0, 0).unwrap();
+        let first = next_run("30 1 * * *", ny, fall_back).unwrap();
+        assert_eq!(first, Utc.with_ymd_and_hms(2026, 11, 1, 5, 30, 0).unwrap());
+        assert_eq!(next_run("30 1 * * *", ny, first).unwrap(), Utc.with_ymd_and_hms(2026, 11, 2, 6, 30, 0).unwrap());
+
+        assert!(next_run("0 8 * * *", "Mars/Olympus", before).is_err());
+    }
+
+    #[test]
+    fn test_catch_up_policies() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 30).unwrap();
+        let hourly: Vec<DateTime<Utc>> = (9..=12).map(|h| Utc.with_ymd_and_hms(2026, 3, 10, h, 0, 0).unwrap()).collect();
+        // Down from 08:30 to 12:00: 09:00-11:00 were missed, 12:00 is on time
+        assert_eq!(CatchUpPolicy::Skip.runs_to_queue(&hourly, now), 1);
+        assert_eq!(CatchUpPolicy::RunOnce.runs_to_queue(&hourly, now), 1);
+        assert_eq!(CatchUpPolicy::RunAllMissed.runs_to_queue(&hourly, now), 4);
+
+        // Back up at 11:30, between occurrences
+        let later = Utc.with_ymd_and_hms(2026, 3, 10, 11, 30, 0).unwrap();
+        assert_eq!(CatchUpPolicy::Skip.runs_to_queue(&hourly[..3], later), 0);
+        assert_eq!(CatchUpPolicy::RunOnce.runs_to_queue(&hourly[..3], later), 1);
+        assert_eq!(CatchUpPolicy::RunAllMissed.runs_to_queue(&hourly[..3], later), 3);
+        assert_eq!(CatchUpPolicy::from("bogus".to_string()), CatchUpPolicy::RunOnce);
+    }
+}
@@ -0,0 +1,303 @@
+//! Optional Elasticsearch / OpenSearch sink.
+//!
+//! With `ELASTICSEARCH_URL` set, every completed task is indexed as one document
+//! (title, URL, page text, entities, category, sentiment) right after it's saved,
+//! and removed again when the task is deleted. The index and its mapping are
+//! created or extended at startup; `cargo run --bin search_backfill` indexes tasks
+//! completed before the sink was enabled. Talks to the REST API directly, so
+//! Elasticsearch 7+ and OpenSearch both work.
+
+use once_cell::sync::Lazy;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use std::time::Duration;
+use crate::crawler::SerpData;
+use crate::ml::Entity;
+use tracing::info;
+
+/// Page text is cut to this many characters before indexing
+const MAX_TEXT_CHARS: usize = 100_000;
+
+/// The configured cluster, or `None` when indexing is off
+pub static SEARCH_INDEX: Lazy<Option<SearchIndex>> = Lazy::new(SearchIndex::from_env);
+
+pub struct SearchIndex {
+    client: Client,
+    base_url: String,
+    index: String,
+    auth: Auth,
+}
+
+enum Auth {
+    None,
+    Basic { username: String, password: String },
+    ApiKey(String),
+}
+
+/// One indexed task
+#[derive(Debug, Serialize)]
+pub struct TaskDocument {
+    pub task_id: String,
+    pub user_id: Option<String>,
+    pub keyword: String,
+    pub engine: String,
+    /// Title of the first result, the page whose text was extracted
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub text: String,
+    pub meta_description: Option<String>,
+    pub entities: Vec<Entity>,
+    pub category: Option<String>,
+    pub sentiment: Option<String>,
+    /// RFC 3339
+    pub created_at: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+/// Field mapping of the index; new fields are added to existing indexes at startup
+fn mapping() -> serde_json::Value {
+    let keyword = serde_json::json!({ "type": "keyword" });
+    let text_and_keyword = serde_json::json!({ "type": "text", "fields": { "raw": { "type": "keyword", "ignore_above": 256 } } });
+    serde_json::json!({
+        "dynamic": false,
+        "properties": {
+            "task_id": keyword,
+            "user_id": keyword,
+            "keyword": text_and_keyword,
+            "engine": keyword,
+            "title": text_and_keyword,
+            "url": keyword,
+            "text": { "type": "text" },
+            "meta_description": { "type": "text" },
+            "entities": {
+                "properties": {
+                    "text": text_and_keyword,
+                    "label": keyword,
+                }
+            },
+            "category": keyword,
+            "sentiment": keyword,
+            "created_at": { "type": "date" },
+            "completed_at": { "type": "date" },
+        }
+    })
+}
+
+impl SearchIndex {
+    fn from_env() -> Option<Self> {
+        let base_url = std::env::var("ELASTICSEARCH_URL").ok().filter(|s| !s.is_empty())?;
+        let auth = if let Ok(key) = std::env::var("ELASTICSEARCH_API_KEY") {
+            Auth::ApiKey(key)
+        } else if let Ok(username) = std::env::var("ELASTICSEARCH_USERNAME") {
+            Auth::Basic { username, password: std::env::var("ELASTICSEARCH_PASSWORD").unwrap_or_default() }
+        } else {
+            Auth::None
+        };
+        Some(Self {
+            client: Client::builder().timeout(Duration::from_secs(30)).build().ok()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            index: std::env::var("ELASTICSEARCH_INDEX").unwrap_or_else(|_| "crawl-tasks".to_string()),
+            auth,
+        })
+    }
+
+    pub fn index_name(&self) -> &str {
+        &self.index
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.client.request(method, format!("{}/{}", self.base_url, path));
+        match &self.auth {
+            Auth::None => request,
+            Auth::Basic { username, password } => request.basic_auth(username, Some(password)),
+            Auth::ApiKey(key) => request.header("Authorization", format!("ApiKey {}", key)),
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> anyhow::Result<serde_json::Value> {
+        let response = request.send().await?;
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            anyhow::bail!("Elasticsearch returned {}: {}", status, body);
+        }
+        Ok(body)
+    }
+
+    /// Create the index with its mapping, or add missing fields to an existing one
+    pub async fn ensure_index(&self) -> anyhow::Result<()> {
+        let exists = self.request(Method::HEAD, &self.index).send().await?.status();
+        if exists == StatusCode::NOT_FOUND {
+            let body = serde_json::json!({ "mappings": mapping() });
+            self.send(self.request(Method::PUT, &self.index).json(&body)).await?;
+            info!("🔎 [Search] Created index {}", self.index);
+        } else {
+            self.send(self.request(Method::PUT, &format!("{}/_mapping", self.index)).json(&mapping())).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn index(&self, doc: &TaskDocument) -> anyhow::Result<()> {
+        let path = format!("{}/_doc/{}", self.index, urlencoding::encode(&doc.task_id));
+        self.send(self.request(Method::PUT, &path).json(doc)).await?;
+        Ok(())
+    }
+
+    /// Index many documents in one `_bulk` request; returns how many failed
+    pub async fn bulk_index(&self, docs: &[TaskDocument]) -> anyhow::Result<usize> {
+        if docs.is_empty() {
+            return Ok(0);
+        }
+        let response = self
+            .send(
+                self.request(Method::POST, "_bulk")
+                    .header("Content-Type", "application/x-ndjson")
+                    .body(bulk_body(&self.index, docs)?),
+            )
+            .await?;
+        if response["errors"].as_bool() != Some(true) {
+            return Ok(0);
+        }
+        let failed = response["items"]
+            .as_array()
+            .map(|items| items.iter().filter(|item| item["index"]["error"].is_object()).count())
+            .unwrap_or(0);
+        Ok(failed)
+    }
+
+    pub async fn delete(&self, task_id: &str) -> anyhow::Result<()> {
+        let path = format!("{}/_doc/{}", self.index, urlencoding::encode(task_id));
+        let response = self.request(Method::DELETE, &path).send().await?;
+        // Tasks from before the sink was enabled were never indexed
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            anyhow::bail!("Elasticsearch returned {}", response.status());
+        }
+        Ok(())
+    }
+
+    /// Remove every document of a user (account deletion)
+    pub async fn delete_user(&self, user_id: &str) -> anyhow::Result<u64> {
+        let body = serde_json::json!({ "query": { "term": { "user_id": user_id } } });
+        let response = self
+            .send(self.request(Method::POST, &format!("{}/_delete_by_query?conflicts=proceed", self.index)).json(&body))
+            .await?;
+        Ok(response["deleted"].as_u64().unwrap_or(0))
+    }
+}
+
+fn bulk_body(index: &str, docs: &[TaskDocument]) -> serde_json::Result<String> {
+    let mut body = String::new();
+    for doc in docs {
+        let action = serde_json::json!({ "index": { "_index": index, "_id": doc.task_id } });
+        body.push_str(&serde_json::to_string(&action)?);
+        body.push('\n');
+        body.push_str(&serde_json::to_string(doc)?);
+        body.push('\n');
+    }
+    Ok(body)
+}
+
+/// The task columns a document is built from
+#[derive(Debug, Default, FromRow)]
+pub struct IndexRow {
+    pub id: String,
+    pub user_id: Option<String>,
+    pub keyword: String,
+    pub engine: String,
+    pub results_json: Option<String>,
+    pub extracted_text: Option<String>,
+    pub meta_description: Option<String>,
+    pub entities: Option<serde_json::Value>,
+    pub category: Option<String>,
+    pub sentiment: Option<String>,
+    pub created_at: Option<chrono::NaiveDateTime>,
+    pub completed_at: Option<chrono::NaiveDateTime>,
+}
+
+/// Selects an `IndexRow` from `tasks`
+pub const INDEX_COLUMNS: &str = "id, user_id, keyword, engine, results_json, extracted_text, meta_description, \
+    entities, category, sentiment, created_at, completed_at";
+
+impl IndexRow {
+    pub fn into_document(self) -> TaskDocument {
+        let serp: SerpData = self.results_json.as_deref().and_then(|j| serde_json::from_str(j).ok()).unwrap_or_default();
+        let first = serp.results.into_iter().next();
+        let mut text = self.extracted_text.unwrap_or_default();
+        if let Some((cut, _)) = text.char_indices().nth(MAX_TEXT_CHARS) {
+            text.truncate(cut);
+        }
+        let rfc3339 = |t: chrono::NaiveDateTime| t.and_utc().to_rfc3339();
+        TaskDocument {
+            task_id: self.id,
+            user_id: self.user_id,
+            keyword: self.keyword,
+            engine: self.engine,
+            title: first.as_ref().map(|r| r.title.clone()),
+            url: first.map(|r| r.link),
+            text,
+            meta_description: self.meta_description,
+            entities: self.entities.and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default(),
+            category: self.category,
+            sentiment: self.sentiment,
+            created_at: self.created_at.map(rfc3339),
+            completed_at: self.completed_at.map(rfc3339),
+        }
+    }
+}
+
+/// Index a task that just completed or was restored; a no-op when indexing is off or the task is deleted
+pub async fn index_task(pool: &PgPool, task_id: &str) -> anyhow::Result<()> {
+    let Some(index) = SEARCH_INDEX.as_ref() else { return Ok(()) };
+    let row: Option<IndexRow> = sqlx::query_as(&format!("SELECT {} FROM tasks WHERE id = $1 AND deleted_at IS NULL", INDEX_COLUMNS))
+        .bind(task_id)
+        .fetch_optional(pool)
+        .await?;
+    if let Some(row) = row {
+        index.index(&row.into_document()).await?;
+    }
+    Ok(())
+}
+
+/// Remove a deleted task from the index; a no-op when indexing is off
+pub async fn remove_task(task_id: &str) -> anyhow::Result<()> {
+    match SEARCH_INDEX.as_ref() {
+        Some(index) => index.delete(task_id).await,
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_document() {
+        let row = IndexRow {
+            id: "t1".to_string(),
+            keyword: "rust jobs".to_string(),
+            engine: "bing".to_string(),
+            results_json: Some(
+                r#"{"results": [{"title": "Rust Jobs", "link": "https://jobs.example", "snippet": ""}],
+                    "people_also_ask": [], "related_searches": [], "featured_snippet": null, "total_results": null}"#
+                    .to_string(),
+            ),
+            extracted_text: Some("é".repeat(MAX_TEXT_CHARS + 10)),
+            entities: Some(serde_json::json!([{"text": "Mozilla", "label": "ORG"}])),
+            created_at: chrono::NaiveDate::from_ymd_opt(2024, 5, 1).and_then(|d| d.and_hms_opt(12, 0, 0)),
+            ..Default::default()
+        };
+        let doc = row.into_document();
+        assert_eq!(doc.title.as_deref(), Some("Rust Jobs"));
+        assert_eq!(doc.url.as_deref(), Some("https://jobs.example"));
+        assert_eq!(doc.text.chars().count(), MAX_TEXT_CHARS);
+        assert_eq!(doc.entities[0].label, "ORG");
+        assert_eq!(doc.created_at.as_deref(), Some("2024-05-01T12:00:00+00:00"));
+
+        let body = bulk_body("crawl-tasks", &[doc]).unwrap();
+        let lines: Vec<serde_json::Value> = body.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines[0]["index"]["_id"], "t1");
+        assert_eq!(lines[1]["keyword"], "rust jobs");
+        assert!(mapping()["properties"]["entities"]["properties"]["label"].is_object());
+    }
+}
@@ -0,0 +1,222 @@
+//! SERP diffing between consecutive crawls of the same keyword.
+//!
+//! Compares the organic results stored in `results_json` of the two most recent
+//! completed crawls and reports what entered, what dropped out and what moved.
+//! Only crawls of the caller and their teammates are compared (admins: anyone's).
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use crate::api::AppState;
+use crate::crawler::{SearchResult, SerpData};
+
+/// A result present in the latest crawl only
+#[derive(Debug, Serialize, Clone, PartialEq, ToSchema)]
+pub struct SerpEntry {
+    pub url: String,
+    pub title: String,
+    /// 1-based organic position
+    pub position: usize,
+}
+
+/// A result present in both crawls at different positions
+#[derive(Debug, Serialize, Clone, PartialEq, ToSchema)]
+pub struct RankMovement {
+    pub url: String,
+    pub title: String,
+    pub previous_position: usize,
+    pub position: usize,
+    /// Positions gained (positive) or lost (negative)
+    pub change: i64,
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq, ToSchema)]
+pub struct SerpChanges {
+    /// In the latest crawl but not the previous one
+    pub new_entries: Vec<SerpEntry>,
+    /// In the previous crawl but gone from the latest; `position` is the old one
+    pub dropped: Vec<SerpEntry>,
+    pub moved: Vec<RankMovement>,
+    /// Results at the same position in both crawls
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SerpDiff {
+    pub keyword: String,
+    pub engine: String,
+    pub previous_task_id: String,
+    pub current_task_id: String,
+    #[schema(value_type = Option<String>)]
+    pub previous_crawled_at: Option<chrono::NaiveDateTime>,
+    #[schema(value_type = Option<String>)]
+    pub current_crawled_at: Option<chrono::NaiveDateTime>,
+    #[serde(flatten)]
+    pub changes: SerpChanges,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SerpDiffResponse {
+    pub success: bool,
+    pub diff: Option<SerpDiff>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SerpDiffQuery {
+    /// Engine to compare; defaults to the engine of the latest crawl
+    pub engine: Option<String>,
+}
+
+/// Identity of a result across crawls: the link without fragment or trailing slash
+fn url_key(link: &str) -> String {
+    let link = link.split('#').next().unwrap_or(link);
+    link.trim_end_matches('/').to_lowercase()
+}
+
+/// Position (1-based) of each distinct URL, keeping the first occurrence
+fn positions(results: &[SearchResult]) -> Vec<(String, usize, &SearchResult)> {
+    let mut seen = std::collections::HashSet::new();
+    results
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| {
+            let key = url_key(&r.link);
+            seen.insert(key.clone()).then_some((key, i + 1, r))
+        })
+        .collect()
+}
+
+/// Compare two result lists, `previous` being the older crawl
+pub fn diff_results(previous: &[SearchResult], current: &[SearchResult]) -> SerpChanges {
+    let previous = positions(previous);
+    let current = positions(current);
+    let before: HashMap<&str, usize> = previous.iter().map(|(key, pos, _)| (key.as_str(), *pos)).collect();
+    let after: HashMap<&str, usize> = current.iter().map(|(key, pos, _)| (key.as_str(), *pos)).collect();
+
+    let mut changes = SerpChanges::default();
+    for (key, position, result) in &current {
+        match before.get(key.as_str()) {
+            None => changes.new_entries.push(SerpEntry {
+                url: result.link.clone(),
+                title: result.title.clone(),
+                position: *position,
+            }),
+            Some(previous_position) if previous_position != position => changes.moved.push(RankMovement {
+                url: result.link.clone(),
+                title: result.title.clone(),
+                previous_position: *previous_position,
+                position: *position,
+                change: *previous_position as i64 - *position as i64,
+            }),
+            Some(_) => changes.unchanged += 1,
+        }
+    }
+    changes.dropped = previous
+        .iter()
+        .filter(|(key, _, _)| !after.contains_key(key.as_str()))
+        .map(|(_, position, result)| SerpEntry {
+            url: result.link.clone(),
+            title: result.title.clone(),
+            position: *position,
+        })
+        .collect();
+    changes
+}
+
+/// Compare the two most recent completed crawls of a keyword
+#[utoipa::path(
+    get,
+    path = "/keywords/{keyword}/diff",
+    tag = "crawler",
+    params(
+        ("keyword" = String, Path, description = "Keyword exactly as crawled"),
+        SerpDiffQuery
+    ),
+    responses(
+        (status = 200, description = "Changes between the last two crawls", body = SerpDiffResponse),
+        (status = 404, description = "Fewer than two completed crawls of the keyword")
+    )
+)]
+pub async fn keyword_diff(
+    State(state): State<Arc<AppState>>,
+    user: crate::auth::AuthUser,
+    Path(keyword): Path<String>,
+    Query(query): Query<SerpDiffQuery>,
+) -> Result<Json<SerpDiffResponse>, (StatusCode, String)> {
+    let visible = format!("deleted_at IS NULL AND (user_id = $3 OR $4 OR {})", crate::organizations::teammates_filter("$3"));
+    let snapshots: Vec<(String, String, Option<chrono::NaiveDateTime>, Option<String>)> = sqlx::query_as(&format!(
+        r#"SELECT id, engine, created_at, results_json FROM tasks
+           WHERE keyword = $1 AND status IN ('completed', 'partial') AND {visible}
+             AND engine = COALESCE($2, (
+                 SELECT engine FROM tasks WHERE keyword = $1 AND status IN ('completed', 'partial') AND {visible}
+                 ORDER BY created_at DESC LIMIT 1
+             ))
+           ORDER BY created_at DESC
+           LIMIT 2"#,
+    ))
+    .bind(&keyword)
+    .bind(&query.engine)
+    .bind(&user.id)
+    .bind(user.is_admin())
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let [current, previous] = <[_; 2]>::try_from(snapshots).map_err(|_| {
+        (StatusCode::NOT_FOUND, format!("Need two completed crawls of '{}' to compare", keyword))
+    })?;
+    let parse = |json: &Option<String>| -> Vec<SearchResult> {
+        json.as_deref()
+            .and_then(|j| serde_json::from_str::<SerpData>(j).ok())
+            .map(|serp| serp.results)
+            .unwrap_or_default()
+    };
+
+    let changes = diff_results(&parse(&previous.3), &parse(&current.3));
+    Ok(Json(SerpDiffResponse {
+        success: true,
+        diff: Some(SerpDiff {
+            keyword,
+            engine: current.1,
+            previous_task_id: previous.0,
+            current_task_id: current.0,
+            previous_crawled_at: previous.2,
+            current_crawled_at: current.2,
+            changes,
+        }),
+        error: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(links: &[&str]) -> Vec<SearchResult> {
+        links
+            .iter()
+            .map(|l| SearchResult { title: l.to_string(), link: l.to_string(), snippet: String::new() })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_results() {
+        let previous = results(&["https://a.com/", "https://b.com", "https://c.com", "https://d.com"]);
+        let current = results(&["https://b.com", "https://a.com", "https://e.com", "https://d.com#top"]);
+        let changes = diff_results(&previous, &current);
+
+        assert_eq!(changes.new_entries, vec![SerpEntry { url: "https://e.com".into(), title: "https://e.com".into(), position: 3 }]);
+        assert_eq!(changes.dropped.iter().map(|e| (e.url.as_str(), e.position)).collect::<Vec<_>>(), vec![("https://c.com", 3)]);
+        let moved: Vec<(&str, i64)> = changes.moved.iter().map(|m| (m.url.as_str(), m.change)).collect();
+        assert_eq!(moved, vec![("https://b.com", 1), ("https://a.com", -1)]);
+        assert_eq!(changes.unchanged, 1);
+    }
+}
@@ -6,9 +6,6 @@
 //! - Behavioral emulation scripts
 //! - Randomized hardware profiles
 
-use once_cell::sync::Lazy;
-use rand::seq::SliceRandom;
-
 /// Generate the main stealth injection script
 /// This script runs before any other script on the page (via Page.addScriptToEvaluateOnNewDocument)
 pub fn get_stealth_script() -> String {
@@ -407,6 +404,16 @@ pub async fn move_mouse_to_element(tab: &std::sync::Arc<Tab>, selector: &str) ->
     Ok(())
 }
 
+/// Type text into the focused element one key at a time with human-like delays
+pub async fn type_human(tab: &std::sync::Arc<Tab>, text: &str) -> Result<()> {
+    for ch in text.chars() {
+        tab.type_str(&ch.to_string())?;
+        let delay = rand::thread_rng().gen_range(80..180);
+        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+    }
+    Ok(())
+}
+
 /// Simulate human-like scrolling using CDP (Trusted Events)
 pub async fn scroll_human(tab: &std::sync::Arc<Tab>, delta_y: f64) -> Result<()> {
     let steps = 10;
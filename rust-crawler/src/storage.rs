@@ -1,92 +1,160 @@
-use aws_sdk_s3::{Client, config::Region};
-use aws_config::meta::region::RegionProviderChain;
-use aws_sdk_s3::config::Credentials;
-use aws_sdk_s3::primitives::ByteStream;
+//! Object storage for raw HTML, debug artifacts, archives and exports.
+//!
+//! `StorageManager` fronts a pluggable `ObjectStore`: MinIO or another
+//! S3-compatible service (`STORAGE_BACKEND=s3`, the default), AWS S3
+//! (`STORAGE_BACKEND=aws`), a local directory (`STORAGE_BACKEND=filesystem`),
+//! or an in-process store (`STORAGE_BACKEND=memory`, the default in dev mode).
+
 use anyhow::Result;
-use std::env;
+use axum::async_trait;
+use std::sync::Arc;
+use tracing::info;
+use crate::config::{StorageBackendKind, StorageConfig};
+
+/// Object key of a task's raw HTML
+pub fn html_key(engine: &str, task_id: &str) -> String {
+    format!("{}/{}.html", engine, task_id)
+}
+
+/// Prefix shared by every object stored for a task (its HTML, and any other artifacts)
+pub fn task_prefix(engine: &str, task_id: &str) -> String {
+    format!("{}/{}.", engine, task_id)
+}
+
+/// Prefix of a task's debug artifacts (screenshots, page dumps)
+pub fn artifacts_prefix(engine: &str, task_id: &str) -> String {
+    format!("{}/{}.debug/", engine, task_id)
+}
+
+/// A stored object read back from storage
+#[derive(Clone)]
+pub struct StoredObject {
+    pub body: Vec<u8>,
+    pub content_type: Option<String>,
+}
+
+/// Key and size of a listed object
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectSummary {
+    pub key: String,
+    pub size: i64,
+}
+
+/// Where objects are kept. Keys are `/`-separated paths such as `bing/<task_id>.html`.
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    /// Short name for logs and `GET /health`
+    fn name(&self) -> &'static str;
+
+    /// Check the store is reachable
+    async fn ping(&self) -> Result<()>;
+
+    /// Store `body` under `key`, replacing any object there
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()>;
+
+    /// Read an object; `None` if there is none under `key`
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>>;
+
+    /// Remove an object; removing one that doesn't exist succeeds
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// Objects whose key starts with `prefix`, in any order
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectSummary>>;
+}
 
 #[derive(Clone)]
 pub struct StorageManager {
-    client: Client,
-    bucket: String,
+    store: Arc<dyn ObjectStore>,
 }
 
 impl StorageManager {
-    pub async fn new() -> Result<Self> {
-        let endpoint = env::var("MINIO_ENDPOINT").unwrap_or_else(|_| "http://localhost:9000".to_string());
-        let access_key = env::var("MINIO_ROOT_USER").unwrap_or_else(|_| "minio_user".to_string());
-        let secret_key = env::var("MINIO_ROOT_PASSWORD").unwrap_or_else(|_| "minio_password".to_string());
-        let bucket = env::var("MINIO_BUCKET").unwrap_or_else(|_| "crawler-data".to_string());
-
-        let region_provider = RegionProviderChain::default_provider().or_else(Region::new("us-east-1"));
-        let config = aws_config::from_env()
-            .region(region_provider)
-            .endpoint_url(&endpoint)
-            .credentials_provider(Credentials::new(
-                access_key,
-                secret_key,
-                None,
-                None,
-                "static",
-            ))
-            .load()
-            .await;
-
-        let client_config = aws_sdk_s3::config::Builder::from(&config)
-            .force_path_style(true)
-            .build();
-        let client = Client::from_conf(client_config);
-
-        // Robust Retry Loop for Bucket Initialization
-        let mut attempts = 0;
-        loop {
-            match client.head_bucket().bucket(&bucket).send().await {
-                Ok(_) => {
-                    println!("✅ MinIO Bucket '{}' exists", bucket);
-                    break;
-                },
-                Err(e) => {
-                    // Check if error is "NotFound" (404) or something else (DNS, Conn)
-                    let is_not_found = e.into_service_error().is_not_found();
-                    
-                    if is_not_found {
-                        println!("⚠️ MinIO Bucket '{}' not found, creating...", bucket);
-                        match client.create_bucket().bucket(&bucket).send().await {
-                            Ok(_) => {
-                                println!("✅ Created bucket '{}'", bucket);
-                                break; 
-                            },
-                            Err(create_err) => {
-                                eprintln!("🔥 Failed to create bucket: {}", create_err);
-                                // Don't break, retry loop (might be transient)
-                            }
-                        }
-                    } else {
-                        // DNS/Connection Error
-                        attempts += 1;
-                        if attempts >= 30 {
-                            return Err(anyhow::anyhow!("Failed to connect to MinIO after 30 attempts"));
-                        }
-                        println!("⚠️ MinIO Connect failed (Attempt {}/30). Retrying in 2s...", attempts);
-                        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                    }
-                }
+    /// Connect the store selected by `[storage] backend` / `STORAGE_BACKEND` (`s3`,
+    /// `aws`, `filesystem` or `memory`; defaults to `memory` in dev mode and `s3` otherwise)
+    pub async fn new(config: &StorageConfig) -> Result<Self> {
+        let store: Arc<dyn ObjectStore> = match config.backend() {
+            StorageBackendKind::S3 | StorageBackendKind::Aws => Arc::new(crate::storage_s3::S3Store::connect(config).await?),
+            StorageBackendKind::Filesystem => Arc::new(crate::storage_fs::FsStore::open(&config.root).await?),
+            StorageBackendKind::Memory => {
+                info!("🗄️ Using in-memory storage (objects are lost on restart)");
+                Arc::new(crate::storage_memory::MemoryStore::default())
             }
-        }
+        };
+        info!("🗄️ Storage backend: {}", store.name());
+        Ok(Self { store })
+    }
+
+    pub fn in_memory() -> Self {
+        Self::from_store(crate::storage_memory::MemoryStore::default())
+    }
+
+    pub fn from_store(store: impl ObjectStore + 'static) -> Self {
+        Self { store: Arc::new(store) }
+    }
 
-        Ok(Self { client, bucket })
+    /// Check the store is reachable
+    pub async fn ping(&self) -> Result<()> {
+        self.store.ping().await
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.store.name()
     }
 
     pub async fn store_html(&self, key: &str, content: &str) -> Result<()> {
-        let body = ByteStream::from(content.as_bytes().to_vec());
-        self.client
-            .put_object()
-            .bucket(&self.bucket)
-            .key(key)
-            .body(body)
-            .content_type("text/html")
-            .send()
-            .await?;
-        Ok(())
+        self.put_object(key, content.as_bytes().to_vec(), "text/html").await
+    }
+
+    /// Store `body` under `key`, replacing any object there
+    pub async fn put_object(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        self.store.put(key, body, content_type).await
+    }
+
+    /// Read an object; `None` if there is none under `key`
+    pub async fn get_object(&self, key: &str) -> Result<Option<StoredObject>> {
+        self.store.get(key).await
+    }
+
+    /// Remove an object; removing one that doesn't exist succeeds
+    pub async fn delete_object(&self, key: &str) -> Result<()> {
+        self.store.delete(key).await
+    }
+
+    /// Objects whose key starts with `prefix`, sorted by key
+    pub async fn list_prefix(&self, prefix: &str) -> Result<Vec<ObjectSummary>> {
+        let mut listed = self.store.list(prefix).await?;
+        listed.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(listed)
+    }
+
+    /// Remove every object whose key starts with `prefix`, returning the removed keys
+    pub async fn delete_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        let keys: Vec<String> = self.list_prefix(prefix).await?.into_iter().map(|o| o.key).collect();
+        for key in &keys {
+            self.delete_object(key).await?;
+        }
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_and_delete_task_objects() {
+        let storage = StorageManager::in_memory();
+        storage.store_html(&html_key("bing", "t1"), "<html>").await.unwrap();
+        storage.store_html("bing/t1.png", "png").await.unwrap();
+        storage.store_html(&html_key("bing", "t10"), "<html>").await.unwrap();
+
+        let stored = storage.get_object(&html_key("bing", "t1")).await.unwrap().unwrap();
+        assert_eq!(stored.body, b"<html>");
+        assert!(storage.get_object("bing/missing.html").await.unwrap().is_none());
+
+        let mut deleted = storage.delete_prefix(&task_prefix("bing", "t1")).await.unwrap();
+        deleted.sort();
+        assert_eq!(deleted, vec!["bing/t1.html".to_string(), "bing/t1.png".to_string()]);
+        // Another task whose ID starts the same is untouched
+        assert_eq!(storage.delete_prefix(&task_prefix("bing", "t10")).await.unwrap().len(), 1);
     }
 }
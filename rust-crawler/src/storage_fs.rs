@@ -0,0 +1,184 @@
+//! Local filesystem object store (`STORAGE_BACKEND=filesystem`).
+//!
+//! Each object is a file under `STORAGE_ROOT`, at its key's path (e.g.
+//! `bing/<task_id>.html`), so a single VM or a test run needs no S3-compatible
+//! service. Writes go to a temporary file first and are renamed into place, so
+//! readers never see half-written objects. Content types aren't stored; they
+//! are derived from the key's extension.
+
+use anyhow::{bail, Context, Result};
+use axum::async_trait;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use crate::storage::{ObjectStore, ObjectSummary, StoredObject};
+
+/// Directory under the root for in-progress writes; never listed
+const TMP_DIR: &str = ".tmp";
+
+pub struct FsStore {
+    root: PathBuf,
+}
+
+impl FsStore {
+    /// Use `root` as the store, creating it if needed
+    pub async fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        tokio::fs::create_dir_all(root.join(TMP_DIR))
+            .await
+            .with_context(|| format!("Failed to create storage root {}", root.display()))?;
+        Ok(Self { root })
+    }
+
+    /// File of `key`; keys must be relative paths without `..`
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        let relative = Path::new(key);
+        if key.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) || relative.starts_with(TMP_DIR) {
+            bail!("Invalid object key '{}'", key);
+        }
+        Ok(self.root.join(relative))
+    }
+
+    /// Remove directories left empty by a delete, up to (not including) the root
+    async fn prune_empty_dirs(&self, mut dir: &Path) {
+        while dir != self.root && dir.starts_with(&self.root) {
+            // Fails on directories that still hold something, which ends the walk
+            if tokio::fs::remove_dir(dir).await.is_err() {
+                break;
+            }
+            match dir.parent() {
+                Some(parent) => dir = parent,
+                None => break,
+            }
+        }
+    }
+}
+
+/// Content type for a key, from its extension (the types the crawler stores)
+fn content_type_for(key: &str) -> Option<&'static str> {
+    let extension = key.rsplit_once('.')?.1.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "html" | "htm" => "text/html",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "json" => "application/json",
+        "ndjson" => "application/x-ndjson",
+        "gz" => "application/gzip",
+        "zip" => "application/zip",
+        "parquet" => "application/vnd.apache.parquet",
+        "txt" => "text/plain",
+        _ => return None,
+    })
+}
+
+#[async_trait]
+impl ObjectStore for FsStore {
+    fn name(&self) -> &'static str {
+        "filesystem"
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let metadata = tokio::fs::metadata(&self.root).await?;
+        if !metadata.is_dir() {
+            bail!("Storage root {} is not a directory", self.root.display());
+        }
+        Ok(())
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>, _content_type: &str) -> Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = self.root.join(TMP_DIR).join(uuid::Uuid::new_v4().to_string());
+        tokio::fs::write(&tmp, body).await?;
+        if let Err(e) = tokio::fs::rename(&tmp, &path).await {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(body) => Ok(Some(StoredObject { body, content_type: content_type_for(key).map(str::to_string) })),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let path = self.path(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+        if let Some(parent) = path.parent() {
+            self.prune_empty_dirs(parent).await;
+        }
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectSummary>> {
+        // Only the directory holding the prefix can contain matches
+        let start = match prefix.rsplit_once('/') {
+            Some((dir, _)) => self.path(dir)?,
+            None => self.root.clone(),
+        };
+        let mut listed = Vec::new();
+        let mut pending = vec![start];
+        while let Some(dir) = pending.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                let Ok(relative) = path.strip_prefix(&self.root) else { continue };
+                let key = relative.components().filter_map(|c| c.as_os_str().to_str()).collect::<Vec<_>>().join("/");
+                let file_type = entry.file_type().await?;
+                if file_type.is_dir() {
+                    if key != TMP_DIR && key.starts_with(prefix) {
+                        pending.push(path);
+                    }
+                } else if file_type.is_file() && key.starts_with(prefix) {
+                    let size = entry.metadata().await?.len() as i64;
+                    listed.push(ObjectSummary { key, size });
+                }
+            }
+        }
+        Ok(listed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{html_key, task_prefix, StorageManager};
+
+    #[tokio::test]
+    async fn test_objects_are_files_under_the_root() {
+        let root = std::env::temp_dir().join(format!("crawler-storage-{}", uuid::Uuid::new_v4()));
+        let storage = StorageManager::from_store(FsStore::open(&root).await.unwrap());
+        storage.store_html(&html_key("bing", "t1"), "<html>").await.unwrap();
+        storage.put_object("bing/t1.debug/1/bing_challenge.png", b"png".to_vec(), "image/png").await.unwrap();
+        storage.store_html(&html_key("bing", "t10"), "<html>").await.unwrap();
+        assert!(root.join("bing/t1.html").is_file());
+
+        let stored = storage.get_object(&html_key("bing", "t1")).await.unwrap().unwrap();
+        assert_eq!((stored.body.as_slice(), stored.content_type.as_deref()), (&b"<html>"[..], Some("text/html")));
+        assert!(storage.get_object("bing/missing.html").await.unwrap().is_none());
+        assert!(storage.get_object("../outside.html").await.is_err());
+
+        let listed = storage.list_prefix(&task_prefix("bing", "t1")).await.unwrap();
+        let keys: Vec<&str> = listed.iter().map(|o| o.key.as_str()).collect();
+        assert_eq!(keys, ["bing/t1.debug/1/bing_challenge.png", "bing/t1.html"]);
+        assert_eq!(storage.delete_prefix(&task_prefix("bing", "t1")).await.unwrap().len(), 2);
+        // Emptied directories go too; another task whose ID starts the same is untouched
+        assert!(!root.join("bing/t1.debug").exists());
+        assert_eq!(storage.list_prefix("bing/").await.unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
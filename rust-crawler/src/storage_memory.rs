@@ -0,0 +1,52 @@
+//! In-process object store (`STORAGE_BACKEND=memory`, the default with `APP_MODE=dev`).
+//!
+//! Objects live only as long as the process, so this is for development and
+//! tests, not deployments.
+
+use anyhow::Result;
+use axum::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use crate::storage::{ObjectStore, ObjectSummary, StoredObject};
+
+#[derive(Default)]
+pub struct MemoryStore {
+    objects: Mutex<HashMap<String, StoredObject>>,
+}
+
+#[async_trait]
+impl ObjectStore for MemoryStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        let object = StoredObject { body, content_type: Some(content_type.to_string()) };
+        self.objects.lock().unwrap().insert(key.to_string(), object);
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>> {
+        Ok(self.objects.lock().unwrap().get(key).cloned())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.objects.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectSummary>> {
+        Ok(self
+            .objects
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, object)| ObjectSummary { key: key.clone(), size: object.body.len() as i64 })
+            .collect())
+    }
+}
@@ -0,0 +1,156 @@
+//! S3 object store: MinIO or another S3-compatible service (`STORAGE_BACKEND=s3`)
+//! or AWS S3 itself (`STORAGE_BACKEND=aws`).
+//!
+//! MinIO is reached at `MINIO_ENDPOINT` with the static `MINIO_ROOT_USER` /
+//! `MINIO_ROOT_PASSWORD` keys and path-style URLs, and a missing bucket is
+//! created. AWS uses the SDK's default credential chain (`AWS_ACCESS_KEY_ID` /
+//! `AWS_SECRET_ACCESS_KEY`, a profile, or the IAM role of the instance or pod)
+//! and virtual-hosted URLs; its bucket must already exist.
+
+use anyhow::Result;
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_s3::config::{Credentials, Region};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use axum::async_trait;
+use tracing::{error, info, warn};
+use crate::config::{StorageBackendKind, StorageConfig};
+use crate::storage::{ObjectStore, ObjectSummary, StoredObject};
+
+/// Region when neither `S3_REGION` nor the SDK's region chain (`AWS_REGION`, profile) sets one
+const DEFAULT_REGION: &str = "us-east-1";
+
+pub struct S3Store {
+    client: Client,
+    bucket: String,
+    name: &'static str,
+}
+
+impl S3Store {
+    /// Build the client for `config` and wait for the bucket to be reachable
+    pub async fn connect(config: &StorageConfig) -> Result<Self> {
+        let aws = config.backend() == StorageBackendKind::Aws;
+        let bucket = config.bucket.clone();
+
+        let region_provider = RegionProviderChain::first_try(config.region.clone().map(Region::new))
+            .or_default_provider()
+            .or_else(Region::new(DEFAULT_REGION));
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest()).region(region_provider);
+        if !aws {
+            loader = loader.endpoint_url(&config.endpoint).credentials_provider(Credentials::new(
+                config.access_key.clone(),
+                config.secret_key.clone(),
+                None,
+                None,
+                "static",
+            ));
+        }
+        let sdk_config = loader.load().await;
+
+        let client_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+            .force_path_style(config.force_path_style.unwrap_or(!aws))
+            .build();
+        let client = Client::from_conf(client_config);
+        let store = Self { client, bucket, name: if aws { "aws" } else { "s3" } };
+        store.ensure_bucket(!aws).await?;
+        Ok(store)
+    }
+
+    /// Wait for the bucket, retrying connection errors; a missing bucket is
+    /// created if `create` is set (MinIO) and an error otherwise (AWS)
+    async fn ensure_bucket(&self, create: bool) -> Result<()> {
+        let bucket = &self.bucket;
+        let mut attempts = 0;
+        loop {
+            match self.client.head_bucket().bucket(bucket).send().await {
+                Ok(_) => {
+                    info!("✅ Bucket '{}' exists", bucket);
+                    return Ok(());
+                },
+                Err(e) => {
+                    // Check if error is "NotFound" (404) or something else (DNS, Conn)
+                    let is_not_found = e.into_service_error().is_not_found();
+
+                    if is_not_found && !create {
+                        return Err(anyhow::anyhow!("Bucket '{}' does not exist", bucket));
+                    }
+                    if is_not_found {
+                        warn!("⚠️ Bucket '{}' not found, creating...", bucket);
+                        match self.client.create_bucket().bucket(bucket).send().await {
+                            Ok(_) => {
+                                info!("✅ Created bucket '{}'", bucket);
+                                return Ok(());
+                            },
+                            Err(create_err) => {
+                                error!("🔥 Failed to create bucket: {}", create_err);
+                                // Don't break, retry loop (might be transient)
+                            }
+                        }
+                    } else {
+                        // DNS/Connection Error
+                        attempts += 1;
+                        if attempts >= 30 {
+                            return Err(anyhow::anyhow!("Failed to connect to {} after 30 attempts", self.name));
+                        }
+                        warn!("⚠️ {} connect failed (Attempt {}/30). Retrying in 2s...", self.name, attempts);
+                        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3Store {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn ping(&self) -> Result<()> {
+        self.client.head_bucket().bucket(&self.bucket).send().await?;
+        Ok(())
+    }
+
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(ByteStream::from(body))
+            .content_type(content_type)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>> {
+        let output = match self.client.get_object().bucket(&self.bucket).key(key).send().await {
+            Ok(output) => output,
+            Err(e) => {
+                let e = e.into_service_error();
+                if e.is_no_such_key() {
+                    return Ok(None);
+                }
+                return Err(e.into());
+            }
+        };
+        let content_type = output.content_type().map(str::to_string);
+        let body = output.body.collect().await?.into_bytes().to_vec();
+        Ok(Some(StoredObject { body, content_type }))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.client.delete_object().bucket(&self.bucket).key(key).send().await?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectSummary>> {
+        let listed = self.client.list_objects_v2().bucket(&self.bucket).prefix(prefix).send().await?;
+        Ok(listed
+            .contents()
+            .iter()
+            .filter_map(|o| Some(ObjectSummary { key: o.key()?.to_string(), size: o.size().unwrap_or(0) }))
+            .collect())
+    }
+}
@@ -0,0 +1,415 @@
+//! Subscription plans.
+//!
+//! Every user is on a plan: `free` unless they hold an active Stripe
+//! subscription to `pro` or `business`. A plan grants the monthly crawl quota
+//! (see `quotas`, where a per-profile override still wins) and gates features
+//! at submission: whether jobs may go through the proxy pool and how many
+//! pages a crawl may follow. Schedules and monitors are checked against their
+//! owner's plan when saved and again before each run, so a downgrade applies
+//! to recurring work too.
+//!
+//! Subscriptions are kept in sync from Stripe's `customer.subscription.*`
+//! webhook events; the subscription's `metadata.user_id` names the user and
+//! `metadata.plan` (or the price ID, see `STRIPE_PRICE_PRO` /
+//! `STRIPE_PRICE_BUSINESS`) the plan.
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::crawler::CrawlOptions;
+use tracing::{error, info, warn};
+
+pub const FREE_PLAN: &str = "free";
+
+/// Stripe statuses that keep the plan's benefits (`past_due` is Stripe's grace period)
+pub(crate) const ACTIVE_STATUSES: [&str; 3] = ["active", "trialing", "past_due"];
+
+#[derive(Debug, Serialize, Clone, PartialEq, ToSchema, FromRow)]
+pub struct Plan {
+    /// free, pro or business
+    pub id: String,
+    pub name: String,
+    /// Monthly price in cents
+    pub price_cents: i32,
+    pub monthly_crawl_quota: i32,
+    /// Whether jobs may run through the proxy pool (and pin `proxy_id`)
+    pub proxies_allowed: bool,
+    /// Most pages a crawl may follow via `next_page_selector`
+    pub max_crawl_depth: i32,
+    /// Days finished tasks are kept before archiving; the server default if unset
+    pub retention_days: Option<i32>,
+    #[serde(skip)]
+    pub stripe_price_id: Option<String>,
+}
+
+impl Plan {
+    /// Used when the `plans` table has no free row
+    pub fn free() -> Self {
+        Self {
+            id: FREE_PLAN.to_string(),
+            name: "Free".to_string(),
+            price_cents: 0,
+            monthly_crawl_quota: 1_000,
+            proxies_allowed: false,
+            max_crawl_depth: 3,
+            retention_days: None,
+            stripe_price_id: None,
+        }
+    }
+
+    /// Check a job's options against the plan's features, filling in what the
+    /// plan decides: jobs of plans without proxies connect directly, and
+    /// pagination without `max_pages` is capped at the plan's depth.
+    pub fn enforce(&self, options: &mut CrawlOptions) -> Result<(), String> {
+        if !self.proxies_allowed {
+            if options.proxy_id.is_some() {
+                return Err(format!("The {} plan doesn't include proxies", self.name));
+            }
+            options.direct = Some(true);
+        }
+
+        let depth = self.max_crawl_depth.max(1) as u32;
+        match options.max_pages {
+            Some(pages) if pages > depth => {
+                return Err(format!("The {} plan allows crawling up to {} pages", self.name, depth));
+            }
+            None if options.next_page_selector.is_some() => {
+                options.max_pages = Some(crate::crawler::DEFAULT_MAX_PAGES.min(depth));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// The caller's plan and the Stripe subscription behind it, if any
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct Subscription {
+    pub plan_id: String,
+    /// Stripe status: active, trialing, past_due, canceled, ...
+    pub status: String,
+    pub stripe_subscription_id: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub current_period_end: Option<DateTime<Utc>>,
+    pub cancel_at_period_end: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SubscriptionResponse {
+    pub success: bool,
+    /// Plan in effect (free when the subscription isn't active)
+    pub plan: Option<Plan>,
+    pub subscription: Option<Subscription>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SubscribeRequest {
+    /// pro or business
+    #[schema(example = "pro")]
+    pub plan: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SubscribeResponse {
+    pub success: bool,
+    pub checkout_url: Option<String>,
+    pub message: String,
+}
+
+type ApiError = (StatusCode, String);
+
+/// Point the paid plans at the Stripe prices in `STRIPE_PRICE_PRO` / `STRIPE_PRICE_BUSINESS`
+pub async fn sync_stripe_prices(pool: &PgPool) -> Result<(), sqlx::Error> {
+    for (plan, var) in [("pro", "STRIPE_PRICE_PRO"), ("business", "STRIPE_PRICE_BUSINESS")] {
+        if let Ok(price) = std::env::var(var) {
+            sqlx::query("UPDATE plans SET stripe_price_id = $2 WHERE id = $1")
+                .bind(plan)
+                .bind(price)
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+const PLAN_COLUMNS: &str = "id, name, price_cents, monthly_crawl_quota, proxies_allowed, max_crawl_depth, retention_days, stripe_price_id";
+
+/// The plan a user is on right now
+pub async fn current_plan(pool: &PgPool, user_id: &str) -> Result<Plan, sqlx::Error> {
+    let plan: Option<Plan> = sqlx::query_as(&format!(
+        r#"SELECT {} FROM plans
+           WHERE id = COALESCE(
+               (SELECT plan_id FROM subscriptions WHERE user_id = $1 AND status = ANY($2)),
+               'free'
+           )"#,
+        PLAN_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(&ACTIVE_STATUSES[..])
+    .fetch_optional(pool)
+    .await?;
+    Ok(plan.unwrap_or_else(Plan::free))
+}
+
+/// Enforce `user_id`'s current plan on `options`, as an API error: 403 when the
+/// plan doesn't allow them
+pub async fn enforce_plan(pool: &PgPool, user_id: &str, options: &mut CrawlOptions) -> Result<(), (StatusCode, String)> {
+    let plan = current_plan(pool, user_id).await.map_err(|e| {
+        error!("❌ [Plans] Failed to load plan for {}: {}", user_id, e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to load subscription plan".to_string())
+    })?;
+    plan.enforce(options).map_err(|e| (StatusCode::FORBIDDEN, e))
+}
+
+// ============================================================================
+// Stripe sync
+// ============================================================================
+
+/// The fields of a Stripe subscription object we keep
+#[derive(Debug, PartialEq)]
+pub struct StripeSubscription {
+    pub id: String,
+    pub customer: Option<String>,
+    pub status: String,
+    pub user_id: Option<String>,
+    pub plan: Option<String>,
+    pub price_id: Option<String>,
+    pub current_period_end: Option<DateTime<Utc>>,
+    pub cancel_at_period_end: bool,
+}
+
+impl StripeSubscription {
+    /// Read `data.object` of a `customer.subscription.*` event
+    pub fn from_object(object: &serde_json::Value) -> Option<Self> {
+        let text = |v: &serde_json::Value| v.as_str().map(str::to_string);
+        Some(Self {
+            id: text(&object["id"])?,
+            customer: text(&object["customer"]),
+            status: text(&object["status"])?,
+            user_id: text(&object["metadata"]["user_id"]),
+            plan: text(&object["metadata"]["plan"]),
+            price_id: text(&object["items"]["data"][0]["price"]["id"]),
+            current_period_end: object["current_period_end"].as_i64().and_then(|t| DateTime::from_timestamp(t, 0)),
+            cancel_at_period_end: object["cancel_at_period_end"].as_bool().unwrap_or(false),
+        })
+    }
+}
+
+/// Apply a `customer.subscription.created/updated/deleted` event
+pub async fn sync_from_stripe(pool: &PgPool, event_type: &str, object: &serde_json::Value) -> Result<(), sqlx::Error> {
+    let Some(mut sub) = StripeSubscription::from_object(object) else {
+        warn!("⚠️ [Billing] Ignoring malformed subscription in {}", event_type);
+        return Ok(());
+    };
+    if event_type == "customer.subscription.deleted" {
+        sub.status = "canceled".to_string();
+    }
+
+    // Known subscriptions are matched by ID; new ones need the user in the metadata
+    let plan_id: Option<String> = sqlx::query_scalar(
+        "SELECT id FROM plans WHERE id = $1 OR ($1 IS NULL AND stripe_price_id = $2) LIMIT 1",
+    )
+    .bind(&sub.plan)
+    .bind(&sub.price_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let updated = sqlx::query(
+        r#"UPDATE subscriptions SET
+               plan_id = COALESCE($2, plan_id), status = $3, current_period_end = $4,
+               cancel_at_period_end = $5, updated_at = now()
+           WHERE stripe_subscription_id = $1"#,
+    )
+    .bind(&sub.id)
+    .bind(&plan_id)
+    .bind(&sub.status)
+    .bind(sub.current_period_end)
+    .bind(sub.cancel_at_period_end)
+    .execute(pool)
+    .await?;
+    if updated.rows_affected() > 0 {
+        info!("💳 [Billing] Subscription {} is now {}", sub.id, sub.status);
+        return Ok(());
+    }
+
+    let (Some(user_id), Some(plan_id)) = (sub.user_id.as_deref(), plan_id) else {
+        warn!("⚠️ [Billing] Subscription {} has no known user or plan", sub.id);
+        return Ok(());
+    };
+    sqlx::query(
+        r#"INSERT INTO subscriptions
+               (user_id, plan_id, status, stripe_customer_id, stripe_subscription_id, current_period_end, cancel_at_period_end)
+           VALUES ($1, $2, $3, $4, $5, $6, $7)
+           ON CONFLICT (user_id) DO UPDATE SET
+               plan_id = EXCLUDED.plan_id, status = EXCLUDED.status,
+               stripe_customer_id = EXCLUDED.stripe_customer_id,
+               stripe_subscription_id = EXCLUDED.stripe_subscription_id,
+               current_period_end = EXCLUDED.current_period_end,
+               cancel_at_period_end = EXCLUDED.cancel_at_period_end, updated_at = now()"#,
+    )
+    .bind(user_id)
+    .bind(&plan_id)
+    .bind(&sub.status)
+    .bind(&sub.customer)
+    .bind(&sub.id)
+    .bind(sub.current_period_end)
+    .bind(sub.cancel_at_period_end)
+    .execute(pool)
+    .await?;
+    info!("💳 [Billing] {} subscribed to {} ({})", user_id, plan_id, sub.status);
+    Ok(())
+}
+
+// ============================================================================
+// API
+// ============================================================================
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Billing] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+/// Available plans, cheapest first
+#[utoipa::path(
+    get,
+    path = "/plans",
+    tag = "payments",
+    responses(
+        (status = 200, description = "Plans and what they include", body = Vec<Plan>)
+    )
+)]
+pub async fn list_plans(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Plan>>, ApiError> {
+    let plans: Vec<Plan> = sqlx::query_as(&format!("SELECT {} FROM plans ORDER BY price_cents, id", PLAN_COLUMNS))
+        .fetch_all(&state.pool)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(plans))
+}
+
+/// The caller's plan and subscription
+#[utoipa::path(
+    get,
+    path = "/subscription",
+    tag = "payments",
+    responses(
+        (status = 200, description = "Current plan", body = SubscriptionResponse)
+    )
+)]
+pub async fn get_subscription(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<SubscriptionResponse>, ApiError> {
+    let plan = current_plan(&state.pool, &user.id).await.map_err(db_error)?;
+    let subscription: Option<Subscription> = sqlx::query_as(
+        r#"SELECT plan_id, status, stripe_subscription_id, current_period_end, cancel_at_period_end
+           FROM subscriptions WHERE user_id = $1"#,
+    )
+    .bind(&user.id)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(SubscriptionResponse {
+        success: true,
+        plan: Some(plan),
+        subscription,
+        message: None,
+    }))
+}
+
+/// Start a Stripe checkout for a paid plan
+#[utoipa::path(
+    post,
+    path = "/subscription/checkout",
+    tag = "payments",
+    request_body = SubscribeRequest,
+    responses(
+        (status = 200, description = "Checkout started", body = SubscribeResponse),
+        (status = 400, description = "Unknown or free plan")
+    )
+)]
+pub async fn create_subscription_checkout(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(req): Json<SubscribeRequest>,
+) -> Result<Json<SubscribeResponse>, ApiError> {
+    let plan: Option<Plan> = sqlx::query_as(&format!("SELECT {} FROM plans WHERE id = $1", PLAN_COLUMNS))
+        .bind(req.plan.trim().to_lowercase())
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?;
+    let plan = match plan {
+        Some(plan) if plan.id != FREE_PLAN => plan,
+        _ => return Err((StatusCode::BAD_REQUEST, format!("'{}' is not a paid plan", req.plan))),
+    };
+
+    // The session carries metadata {user_id, plan} onto the subscription, which the webhook reads back
+    let session = Uuid::new_v4();
+    let (checkout_url, message) = match (std::env::var("STRIPE_SECRET_KEY"), &plan.stripe_price_id) {
+        (Ok(_), Some(_)) => (
+            format!("https://checkout.stripe.com/demo/{}", session),
+            format!("Stripe checkout for the {} plan created", plan.name),
+        ),
+        _ => (
+            format!("http://localhost:3000/payments/demo/{}", session),
+            "Demo mode: Set STRIPE_SECRET_KEY and STRIPE_PRICE_* for real subscriptions".to_string(),
+        ),
+    };
+    info!("💳 [Billing] {} started checkout for {}", user.id, plan.id);
+
+    Ok(Json(SubscribeResponse {
+        success: true,
+        checkout_url: Some(checkout_url),
+        message,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_enforces_proxies_and_depth() {
+        let free = Plan::free();
+        let mut options = CrawlOptions { next_page_selector: Some("a.next".to_string()), ..Default::default() };
+        free.enforce(&mut options).unwrap();
+        assert_eq!(options.direct, Some(true));
+        assert_eq!(options.max_pages, Some(3));
+
+        let mut pinned = CrawlOptions { proxy_id: Some("1.2.3.4:8080".to_string()), ..Default::default() };
+        assert!(free.enforce(&mut pinned).is_err());
+        let mut deep = CrawlOptions { max_pages: Some(4), ..Default::default() };
+        assert!(free.enforce(&mut deep).is_err());
+
+        let pro = Plan { proxies_allowed: true, max_crawl_depth: 10, ..Plan::free() };
+        let mut options = CrawlOptions { proxy_id: Some("1.2.3.4:8080".to_string()), max_pages: Some(10), ..Default::default() };
+        pro.enforce(&mut options).unwrap();
+        assert_eq!(options.direct, None);
+    }
+
+    #[test]
+    fn test_parse_stripe_subscription() {
+        let object = serde_json::json!({
+            "id": "sub_123",
+            "customer": "cus_9",
+            "status": "active",
+            "metadata": {"user_id": "user-1"},
+            "items": {"data": [{"price": {"id": "price_pro"}}]},
+            "current_period_end": 1_800_000_000,
+            "cancel_at_period_end": false
+        });
+        let sub = StripeSubscription::from_object(&object).unwrap();
+        assert_eq!(sub.user_id.as_deref(), Some("user-1"));
+        assert_eq!(sub.plan, None);
+        assert_eq!(sub.price_id.as_deref(), Some("price_pro"));
+        assert_eq!(sub.current_period_end.map(|t| t.timestamp()), Some(1_800_000_000));
+        assert!(StripeSubscription::from_object(&serde_json::json!({"status": "active"})).is_none());
+    }
+}
@@ -0,0 +1,225 @@
+//! Per-task execution logs.
+//!
+//! Everything logged while the worker runs a job (attempts, proxy choice,
+//! challenge pages, extraction fallbacks, retries) is also captured for that task
+//! and written to `task_logs` when the attempt ends, so a failed crawl can be
+//! debugged with `GET /tasks/:task_id/logs` instead of the container's output.
+//! Capture is a `tracing` layer keyed by the worker's `crawl.job` span.
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{span, warn, Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+use utoipa::ToSchema;
+use crate::api::AppState;
+use crate::queue::CrawlJob;
+
+/// Name of the worker span whose events are captured
+pub const JOB_SPAN: &str = "crawl.job";
+
+/// Lines kept per task attempt; later lines are dropped
+const MAX_LINES_PER_ATTEMPT: usize = 500;
+
+/// Fields already implied by the log being the task's
+const IMPLIED_FIELDS: [&str; 3] = ["task_id", "engine", "attempt"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TaskLogLine {
+    /// Attempt the line belongs to (1 = first run)
+    pub attempt: i32,
+    #[schema(example = "WARN")]
+    pub level: String,
+    #[schema(example = "⚠️ Attempt 1/3: Bing returned 0 results.")]
+    pub message: String,
+    /// Structured fields of the event, e.g. `proxy_id` or `retry`
+    #[schema(value_type = Object)]
+    pub fields: serde_json::Value,
+    #[schema(value_type = String)]
+    pub logged_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskLogsResponse {
+    pub task_id: String,
+    pub lines: Vec<TaskLogLine>,
+}
+
+/// Lines of running attempts, by task ID, until `flush`
+static BUFFERS: Lazy<Mutex<HashMap<String, Vec<TaskLogLine>>>> = Lazy::new(Default::default);
+
+/// Collects an event's or span's fields; `message` is kept apart
+#[derive(Default)]
+struct FieldCollector {
+    message: Option<String>,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Visit for FieldCollector {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let value = format!("{:?}", value);
+        if field.name() == "message" {
+            self.message = Some(value);
+        } else {
+            self.fields.insert(field.name().to_string(), value.into());
+        }
+    }
+}
+
+/// Task and attempt of a job span, stored in its extensions
+struct JobContext {
+    task_id: String,
+    attempt: i32,
+}
+
+/// Buffers events emitted inside a job span for that task
+pub struct TaskLogLayer;
+
+impl<S> Layer<S> for TaskLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != JOB_SPAN {
+            return;
+        }
+        let mut collector = FieldCollector::default();
+        attrs.record(&mut collector);
+        let Some(task_id) = collector.fields.get("task_id").and_then(|v| v.as_str()) else { return };
+        let attempt = collector.fields.get("attempt").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(JobContext { task_id: task_id.to_string(), attempt });
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(scope) = ctx.event_scope(event) else { return };
+        let job = scope
+            .from_root()
+            .find_map(|span| span.extensions().get::<JobContext>().map(|job| (job.task_id.clone(), job.attempt)));
+        let Some((task_id, attempt)) = job else { return };
+
+        let mut collector = FieldCollector::default();
+        event.record(&mut collector);
+        for field in IMPLIED_FIELDS {
+            collector.fields.remove(field);
+        }
+        let line = TaskLogLine {
+            attempt,
+            level: event.metadata().level().to_string(),
+            message: collector.message.unwrap_or_default(),
+            fields: serde_json::Value::Object(collector.fields),
+            logged_at: Utc::now(),
+        };
+        let mut buffers = BUFFERS.lock().unwrap();
+        let lines = buffers.entry(task_id).or_default();
+        if lines.len() < MAX_LINES_PER_ATTEMPT {
+            lines.push(line);
+        }
+    }
+}
+
+/// Write the lines captured for a job's attempt to `task_logs`
+pub async fn flush(pool: &PgPool, job: &CrawlJob) {
+    let Some(lines) = BUFFERS.lock().unwrap().remove(&job.id) else { return };
+    let result = sqlx::query(
+        r#"INSERT INTO task_logs (task_id, user_id, attempt, level, message, fields, logged_at)
+           SELECT $1, $2, l.attempt, l.level, l.message, l.fields, l.logged_at
+           FROM jsonb_to_recordset($3) AS l(attempt INTEGER, level TEXT, message TEXT, fields JSONB, logged_at TIMESTAMPTZ)"#,
+    )
+    .bind(&job.id)
+    .bind(&job.user_id)
+    .bind(serde_json::to_value(&lines).unwrap_or_default())
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        // Outside the job span, or the warning would be captured again
+        warn!(parent: None, task_id = %job.id, "⚠️ [TaskLogs] Failed to save {} log lines for {}: {}", lines.len(), job.id, e);
+    }
+}
+
+/// Execution log of a task, across all attempts
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/logs",
+    tag = "crawler",
+    params(
+        ("task_id" = String, Path, description = "Task ID")
+    ),
+    responses(
+        (status = 200, description = "Log lines, oldest first", body = TaskLogsResponse),
+        (status = 404, description = "Unknown task or a task outside your organization")
+    )
+)]
+pub async fn get_task_logs(
+    State(state): State<Arc<AppState>>,
+    user: crate::auth::AuthUser,
+    Path(task_id): Path<String>,
+) -> Result<Json<TaskLogsResponse>, (StatusCode, String)> {
+    let db_error = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    if crate::api::visible_task_engine(&state.pool, &user, &task_id).await.map_err(db_error)?.is_none() {
+        return Err((StatusCode::NOT_FOUND, "Task not found".to_string()));
+    }
+
+    let lines = sqlx::query_as::<_, TaskLogLine>(
+        "SELECT attempt, level, message, fields, logged_at FROM task_logs WHERE task_id = $1 ORDER BY id",
+    )
+    .bind(&task_id)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(TaskLogsResponse { task_id, lines }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_captures_events_inside_job_spans() {
+        let subscriber = tracing_subscriber::registry().with(TaskLogLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside any job");
+            let job = tracing::info_span!("crawl.job", task_id = %"log-test-1", engine = %"bing", attempt = 2);
+            let _entered = job.enter();
+            let browser = tracing::info_span!("browser.bing");
+            let _browser = browser.enter();
+            tracing::warn!(proxy_id = %"10.0.0.1:8080", retry = 1, task_id = %"log-test-1", "⚠️ Bing returned 0 results");
+        });
+
+        let lines = BUFFERS.lock().unwrap().remove("log-test-1").unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!((lines[0].attempt, lines[0].level.as_str()), (2, "WARN"));
+        assert_eq!(lines[0].message, "⚠️ Bing returned 0 results");
+        assert_eq!(lines[0].fields, serde_json::json!({ "proxy_id": "10.0.0.1:8080", "retry": 1 }));
+    }
+}
@@ -0,0 +1,106 @@
+//! Logging and distributed tracing.
+//!
+//! Logs go to stdout through `tracing` (filtered by `RUST_LOG`, default `info`),
+//! as text or, with `LOG_FORMAT=json`, one JSON object per line for container log
+//! pipelines. Job events carry `task_id`, `engine` and `attempt` fields, proxy
+//! events `proxy_id`, and JSON lines also list the enclosing spans' fields.
+//! With `OTEL_EXPORTER_OTLP_ENDPOINT` set, spans are also exported over OTLP/gRPC
+//! (Jaeger, Tempo, Honeycomb, an OpenTelemetry Collector...): HTTP requests, queue
+//! pushes and pops, worker stages and browser sessions. Job spans carry the
+//! `task_id` attribute, so a crawl can be followed from `POST /crawl` to the stored
+//! result. The service name is `OTEL_SERVICE_NAME` (default `rust-crawler`).
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Flushes buffered spans when dropped; keep it alive for the life of the process
+pub struct TelemetryGuard {
+    provider: Option<TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                tracing::warn!("⚠️ [Telemetry] Failed to flush spans: {}", e);
+            }
+        }
+    }
+}
+
+fn service_name() -> String {
+    std::env::var("OTEL_SERVICE_NAME").ok().filter(|s| !s.is_empty()).unwrap_or_else(|| "rust-crawler".to_string())
+}
+
+fn json_logs() -> bool {
+    std::env::var("LOG_FORMAT").map(|f| f.eq_ignore_ascii_case("json")).unwrap_or(false)
+}
+
+/// OTLP exporter, when an endpoint is configured
+fn tracer_provider() -> anyhow::Result<Option<TracerProvider>> {
+    if std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").map(|s| s.is_empty()).unwrap_or(true) {
+        return Ok(None);
+    }
+    // Endpoint, headers and timeout come from the standard OTEL_EXPORTER_OTLP_* variables
+    let exporter = opentelemetry_otlp::SpanExporter::builder().with_tonic().build()?;
+    let resource = Resource::default().merge(&Resource::new([KeyValue::new("service.name", service_name())]));
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, opentelemetry_sdk::runtime::Tokio)
+        .with_resource(resource)
+        .build();
+    Ok(Some(provider))
+}
+
+/// Span of one HTTP request, named after the matched route
+pub fn request_span<B>(request: &axum::http::Request<B>) -> tracing::Span {
+    let route = request
+        .extensions()
+        .get::<axum::extract::MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    tracing::info_span!(
+        "http.request",
+        otel.name = %format!("{} {}", request.method(), route),
+        http.method = %request.method(),
+        http.route = %route,
+        task_id = tracing::field::Empty,
+    )
+}
+
+/// Tag the current span (e.g. the request's) with the task it's about
+pub fn record_task_id(task_id: &str) {
+    tracing::Span::current().record("task_id", task_id);
+}
+
+/// Install the log subscriber and, if configured, the OTLP span exporter.
+/// Must run inside the Tokio runtime.
+pub fn init() -> TelemetryGuard {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let (provider, error) = match tracer_provider() {
+        Ok(provider) => (provider, None),
+        Err(e) => (None, Some(e)),
+    };
+    let otel_layer = provider
+        .as_ref()
+        .map(|p| tracing_opentelemetry::layer().with_tracer(p.tracer("rust-crawler")));
+    let json = json_logs();
+    tracing_subscriber::registry()
+        .with(filter)
+        .with((!json).then(tracing_subscriber::fmt::layer))
+        .with(json.then(|| tracing_subscriber::fmt::layer().json().flatten_event(true).with_span_list(true)))
+        .with(otel_layer)
+        .with(crate::task_logs::TaskLogLayer)
+        .init();
+
+    match (&provider, error) {
+        (Some(_), _) => tracing::info!("🔭 Exporting traces over OTLP as {}", service_name()),
+        (None, Some(e)) => tracing::warn!("⚠️ [Telemetry] OTLP exporter unavailable, traces won't be exported: {}", e),
+        (None, None) => {}
+    }
+    TelemetryGuard { provider }
+}
@@ -0,0 +1,223 @@
+//! Usage metering.
+//!
+//! The worker records a usage event per completed crawl (by engine), per byte
+//! of HTML stored and per byte relayed through proxies. `GET /usage` sums them
+//! over a day, week or month, for the dashboard and for usage-based billing.
+//!
+//! Proxy bandwidth is measured by the local forwarder that metered jobs'
+//! browsers connect through.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use tracing::error;
+
+/// What a usage event counts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    /// One completed crawl
+    Crawl,
+    /// Bytes of raw HTML written to object storage
+    StorageBytes,
+    /// Bytes sent and received through proxies
+    ProxyBytes,
+}
+
+impl Metric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::Crawl => "crawl",
+            Metric::StorageBytes => "storage_bytes",
+            Metric::ProxyBytes => "proxy_bytes",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UsagePeriod {
+    Day,
+    /// ISO week, starting Monday
+    Week,
+    #[default]
+    Month,
+}
+
+impl UsagePeriod {
+    /// First day of the period containing `date` and first day of the next one
+    pub fn bounds(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            UsagePeriod::Day => (date, date + Duration::days(1)),
+            UsagePeriod::Week => {
+                let start = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (start, start + Duration::days(7))
+            }
+            UsagePeriod::Month => {
+                let start = date.with_day(1).unwrap_or(date);
+                let end = if start.month() == 12 {
+                    NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+                };
+                (start, end.unwrap_or(start + Duration::days(31)))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UsageQuery {
+    /// day, week or month (default)
+    pub period: Option<UsagePeriod>,
+    /// Any date in the period to report (default today, UTC)
+    #[param(value_type = Option<String>, example = "2026-05-01")]
+    pub date: Option<NaiveDate>,
+    /// Report another user's usage (admins only)
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UsageReport {
+    pub user_id: String,
+    pub period: UsagePeriod,
+    /// First day of the period
+    #[schema(value_type = String)]
+    pub start: NaiveDate,
+    /// First day after the period
+    #[schema(value_type = String)]
+    pub end: NaiveDate,
+    /// Completed crawls
+    pub crawls: i64,
+    #[schema(example = json!({"bing": 120, "google": 30}))]
+    pub crawls_by_engine: BTreeMap<String, i64>,
+    pub storage_bytes: i64,
+    pub proxy_bytes: i64,
+}
+
+type ApiError = (StatusCode, String);
+
+/// Record a usage event; zero quantities are skipped
+pub async fn record(
+    pool: &PgPool,
+    user_id: &str,
+    metric: Metric,
+    engine: Option<&str>,
+    task_id: Option<&str>,
+    quantity: i64,
+) -> Result<(), sqlx::Error> {
+    if quantity == 0 {
+        return Ok(());
+    }
+    sqlx::query("INSERT INTO usage_events (user_id, metric, engine, task_id, quantity) VALUES ($1, $2, $3, $4, $5)")
+        .bind(user_id)
+        .bind(metric.as_str())
+        .bind(engine)
+        .bind(task_id)
+        .bind(quantity)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Sum a user's usage between two dates (UTC, end exclusive)
+pub async fn report(pool: &PgPool, user_id: &str, period: UsagePeriod, date: NaiveDate) -> Result<UsageReport, sqlx::Error> {
+    let (start, end) = period.bounds(date);
+    let rows: Vec<(String, Option<String>, i64)> = sqlx::query_as(
+        r#"SELECT metric, engine, SUM(quantity)::BIGINT FROM usage_events
+           WHERE user_id = $1 AND recorded_at >= $2 AND recorded_at < $3
+           GROUP BY metric, engine"#,
+    )
+    .bind(user_id)
+    .bind(start.and_hms_opt(0, 0, 0).map(|t| t.and_utc()))
+    .bind(end.and_hms_opt(0, 0, 0).map(|t| t.and_utc()))
+    .fetch_all(pool)
+    .await?;
+
+    let mut report = UsageReport {
+        user_id: user_id.to_string(),
+        period,
+        start,
+        end,
+        crawls: 0,
+        crawls_by_engine: BTreeMap::new(),
+        storage_bytes: 0,
+        proxy_bytes: 0,
+    };
+    for (metric, engine, quantity) in rows {
+        match metric.as_str() {
+            "crawl" => {
+                report.crawls += quantity;
+                *report.crawls_by_engine.entry(engine.unwrap_or_else(|| "unknown".to_string())).or_default() += quantity;
+            }
+            "storage_bytes" => report.storage_bytes += quantity,
+            "proxy_bytes" => report.proxy_bytes += quantity,
+            _ => {}
+        }
+    }
+    Ok(report)
+}
+
+// ============================================================================
+// API
+// ============================================================================
+
+/// Usage totals for the caller over a day, week or month
+#[utoipa::path(
+    get,
+    path = "/usage",
+    tag = "payments",
+    params(UsageQuery),
+    responses(
+        (status = 200, description = "Usage in the period", body = UsageReport),
+        (status = 403, description = "user_id given by a non-admin")
+    )
+)]
+pub async fn get_usage(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<UsageReport>, ApiError> {
+    let user_id = match query.user_id {
+        Some(other) if other != user.id && !user.is_admin() => {
+            return Err((StatusCode::FORBIDDEN, "Only admins can view other users' usage".to_string()));
+        }
+        Some(other) => other,
+        None => user.id.clone(),
+    };
+    let date = query.date.unwrap_or_else(|| Utc::now().date_naive());
+
+    let report = report(&state.pool, &user_id, query.period.unwrap_or_default(), date)
+        .await
+        .map_err(|e| {
+            error!("❌ [Usage] Database error: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+        })?;
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_period_bounds() {
+        let date = NaiveDate::from_ymd_opt(2026, 12, 17).unwrap();
+        let ymd = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(UsagePeriod::Day.bounds(date), (date, ymd(2026, 12, 18)));
+        // 2026-12-17 is a Thursday
+        assert_eq!(UsagePeriod::Week.bounds(date), (ymd(2026, 12, 14), ymd(2026, 12, 21)));
+        assert_eq!(UsagePeriod::Month.bounds(date), (ymd(2026, 12, 1), ymd(2027, 1, 1)));
+        assert_eq!(UsagePeriod::Month.bounds(ymd(2026, 2, 28)), (ymd(2026, 2, 1), ymd(2026, 3, 1)));
+    }
+}
@@ -0,0 +1,330 @@
+//! Validation of crawl submissions.
+//!
+//! Checks a crawl's keyword (or URL, for generic crawls), engine and options
+//! before it's queued: CSS selectors must parse, regexes compile, URLs be
+//! absolute http(s) on a public host and numeric options stay within bounds. Every problem is
+//! reported at once with the path of the offending field (e.g.
+//! `steps[2].selector`), as a 422, so a bad job never reaches a worker.
+
+use axum::http::header::{HeaderName, HeaderValue};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use scraper::Selector;
+use serde::Serialize;
+use utoipa::ToSchema;
+use crate::api::CrawlRequest;
+use crate::config::EnginesConfig;
+use crate::crawler::{CrawlOptions, InteractionStep};
+use crate::recipes::{FieldType, PostProcess, RecipeField};
+
+pub const MAX_KEYWORD_LEN: usize = 500;
+pub const MAX_URL_LEN: usize = 2048;
+pub const MAX_STEPS: usize = 50;
+pub const MAX_FIELDS: usize = 100;
+pub const MAX_WAIT_MS: u64 = 60_000;
+pub const MAX_SCROLL_ITEMS: usize = 5_000;
+pub const MAX_SCROLLS: u32 = 200;
+/// Bytes; the worker refuses longer scripts too
+pub const MAX_CUSTOM_SCRIPT_LEN: usize = 20_000;
+pub const MAX_RETRIES: u32 = 10;
+pub const MAX_BACKOFF_SECS: u64 = 3_600;
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Path of the field in the request body
+    #[schema(example = "steps[0].selector")]
+    pub field: String,
+    #[schema(example = "invalid CSS selector 'div[' ")]
+    pub message: String,
+}
+
+/// Body of a 422 response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValidationErrors {
+    #[schema(example = "Validation failed")]
+    pub error: String,
+    pub errors: Vec<FieldError>,
+}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(self)).into_response()
+    }
+}
+
+/// For handlers whose errors are `(StatusCode, String)`: the messages, one per field
+impl From<ValidationErrors> for (StatusCode, String) {
+    fn from(errors: ValidationErrors) -> Self {
+        let messages: Vec<String> = errors.errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+        (StatusCode::UNPROCESSABLE_ENTITY, messages.join("; "))
+    }
+}
+
+/// Collects field errors while walking a request
+#[derive(Default)]
+struct Validator {
+    errors: Vec<FieldError>,
+    /// Well-formed URLs whose hosts still have to resolve to public addresses
+    hosts: Vec<(String, reqwest::Url)>,
+}
+
+impl Validator {
+    fn error(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError { field: field.into(), message: message.into() });
+    }
+
+    fn selector(&mut self, field: impl Into<String>, selector: &str) {
+        if selector.trim().is_empty() {
+            self.error(field, "selector is empty");
+        } else if Selector::parse(selector).is_err() {
+            self.error(field, format!("invalid CSS selector '{}'", selector));
+        }
+    }
+
+    fn url(&mut self, field: impl Into<String>, url: &str) {
+        let field = field.into();
+        if url.len() > MAX_URL_LEN {
+            return self.error(field, format!("URL is longer than {} characters", MAX_URL_LEN));
+        }
+        match reqwest::Url::parse(url.trim()) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some() => self.hosts.push((field, parsed)),
+            Ok(_) => self.error(field, "must be an http(s) URL"),
+            Err(e) => self.error(field, format!("invalid URL: {}", e)),
+        }
+    }
+
+    fn within<T: PartialOrd + std::fmt::Display>(&mut self, field: &str, value: Option<T>, min: T, max: T) {
+        if let Some(value) = value {
+            if value < min || value > max {
+                self.error(field, format!("must be between {} and {}", min, max));
+            }
+        }
+    }
+
+    /// Same check as callback URLs: the browser mustn't be pointed at internal hosts
+    async fn check_hosts(&mut self) {
+        for (field, url) in std::mem::take(&mut self.hosts) {
+            if let Err(e) = crate::webhooks::check_public_url(&url).await {
+                self.error(field, e);
+            }
+        }
+    }
+
+    fn finish(self) -> Result<(), ValidationErrors> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        Err(ValidationErrors { error: "Validation failed".to_string(), errors: self.errors })
+    }
+}
+
+fn validate_fields(v: &mut Validator, fields: &[RecipeField]) {
+    if fields.len() > MAX_FIELDS {
+        v.error("fields", format!("at most {} fields", MAX_FIELDS));
+    }
+    for (i, field) in fields.iter().enumerate() {
+        if field.name.trim().is_empty() {
+            v.error(format!("fields[{}].name", i), "name is empty");
+        }
+        v.selector(format!("fields[{}].selector", i), &field.selector);
+        if field.field_type == FieldType::Attribute && field.attribute.as_deref().is_none_or(|a| a.trim().is_empty()) {
+            v.error(format!("fields[{}].attribute", i), "required for attribute fields");
+        }
+        for (j, step) in field.post_process.iter().enumerate() {
+            if let PostProcess::Regex { pattern } = step {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    v.error(format!("fields[{}].post_process[{}].pattern", i, j), format!("invalid regex: {}", e));
+                }
+            }
+        }
+    }
+}
+
+fn validate_options(v: &mut Validator, engine: &str, options: &CrawlOptions) {
+    if let Some(selectors) = &options.selectors {
+        for (name, selector) in selectors {
+            v.selector(format!("selectors.{}", name), selector);
+        }
+    }
+    if let Some(fields) = &options.fields {
+        validate_fields(v, fields);
+    }
+    if let Some(selector) = &options.next_page_selector {
+        v.selector("next_page_selector", selector);
+    }
+    v.within("max_pages", options.max_pages, 1, crate::crawler::MAX_PAGES_LIMIT);
+    if let Some(scroll) = &options.infinite_scroll {
+        v.selector("infinite_scroll.item_selector", &scroll.item_selector);
+        v.within("infinite_scroll.max_items", scroll.max_items, 1, MAX_SCROLL_ITEMS);
+        v.within("infinite_scroll.max_scrolls", scroll.max_scrolls, 1, MAX_SCROLLS);
+        v.within("infinite_scroll.idle_rounds", scroll.idle_rounds, 1, MAX_SCROLLS);
+    }
+    if let Some(wait) = &options.wait_for {
+        if let Some(selector) = &wait.selector {
+            v.selector("wait_for.selector", selector);
+        }
+        v.within("wait_for.timeout_ms", wait.timeout_ms, 0, MAX_WAIT_MS);
+    }
+    if let Some(steps) = &options.steps {
+        if steps.len() > MAX_STEPS {
+            v.error("steps", format!("at most {} steps", MAX_STEPS));
+        }
+        for (i, step) in steps.iter().enumerate() {
+            let selector = match step {
+                InteractionStep::Click { selector }
+                | InteractionStep::Type { selector, .. }
+                | InteractionStep::Select { selector, .. } => selector,
+                InteractionStep::WaitFor { selector, timeout_ms } => {
+                    v.within(&format!("steps[{}].timeout_ms", i), *timeout_ms, 0, MAX_WAIT_MS);
+                    selector
+                }
+            };
+            v.selector(format!("steps[{}].selector", i), selector);
+        }
+    }
+    if let Some(login) = &options.login {
+        if let Some(url) = &login.url {
+            v.url("login.url", url);
+        }
+        v.selector("login.username_selector", &login.username_selector);
+        v.selector("login.password_selector", &login.password_selector);
+        v.selector("login.submit_selector", &login.submit_selector);
+        if let Some(selector) = &login.success_selector {
+            v.selector("login.success_selector", selector);
+        }
+    }
+    if let Some(script) = &options.custom_script {
+        if script.len() > MAX_CUSTOM_SCRIPT_LEN {
+            v.error("custom_script", format!("longer than {} bytes", MAX_CUSTOM_SCRIPT_LEN));
+        }
+    }
+    if let Some(headers) = &options.headers {
+        for (name, value) in headers {
+            if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                v.error(format!("headers.{}", name), "invalid header name");
+            } else if HeaderValue::from_str(value).is_err() {
+                v.error(format!("headers.{}", name), "invalid header value");
+            }
+        }
+    }
+    if let Some(gl) = &options.gl {
+        if gl.len() != 2 || !gl.chars().all(|c| c.is_ascii_alphabetic()) {
+            v.error("gl", "must be a 2-letter ISO country code");
+        }
+    }
+    let generic_only = [
+        ("selectors", options.selectors.is_some()),
+        ("login", options.login.is_some()),
+        ("steps", options.steps.is_some()),
+        ("next_page_selector", options.next_page_selector.is_some()),
+        ("infinite_scroll", options.infinite_scroll.is_some()),
+        ("custom_script", options.custom_script.is_some()),
+    ];
+    if engine != "generic" {
+        for (field, set) in generic_only {
+            if set {
+                v.error(field, "only applies to generic crawls");
+            }
+        }
+    }
+}
+
+fn validate_target(v: &mut Validator, keyword: &str, engine: &str, options: &CrawlOptions, engines: &EnginesConfig) {
+    if let Err(e) = engines.check(engine) {
+        v.error("engine", e);
+    }
+    if keyword.trim().is_empty() {
+        v.error("keyword", "keyword is empty");
+    } else if engine == "generic" {
+        v.url("keyword", keyword);
+    } else if keyword.chars().count() > MAX_KEYWORD_LEN {
+        v.error("keyword", format!("longer than {} characters", MAX_KEYWORD_LEN));
+    }
+    validate_options(v, engine, options);
+}
+
+/// Check a crawl's keyword (the target URL for generic crawls), engine and options
+pub async fn validate_crawl(keyword: &str, engine: &str, options: &CrawlOptions, engines: &EnginesConfig) -> Result<(), ValidationErrors> {
+    let mut v = Validator::default();
+    validate_target(&mut v, keyword, engine, options, engines);
+    v.check_hosts().await;
+    v.finish()
+}
+
+/// Check a `POST /crawl` body: the crawl itself and its retry settings
+pub async fn validate_crawl_request(req: &CrawlRequest, engine: &str, engines: &EnginesConfig) -> Result<(), ValidationErrors> {
+    let mut v = Validator::default();
+    validate_target(&mut v, &req.keyword, engine, &req.options, engines);
+    v.within("max_retries", req.max_retries, 0, MAX_RETRIES);
+    v.within("retry_backoff_secs", req.retry_backoff_secs, 0, MAX_BACKOFF_SECS);
+    v.check_hosts().await;
+    v.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(json: serde_json::Value) -> CrawlOptions {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reports_every_invalid_field() {
+        let engines = EnginesConfig::default();
+        let ok = options(serde_json::json!({
+            "selectors": {"title": "h1"},
+            "steps": [{"action": "click", "selector": "button.more"}],
+            "wait_for": {"selector": "#app", "timeout_ms": 5000}
+        }));
+        assert!(validate_crawl("https://93.184.215.14/t/1", "generic", &ok, &engines).await.is_ok());
+
+        let bad = options(serde_json::json!({
+            "steps": [
+                {"action": "click", "selector": "button.more"},
+                {"action": "wait_for", "selector": "div[", "timeout_ms": 600000}
+            ],
+            "fields": [{"name": "link", "selector": "a", "field_type": "attribute",
+                        "post_process": [{"op": "regex", "pattern": "("}]}],
+            "max_pages": 0,
+            "gl": "usa"
+        }));
+        let errors = validate_crawl("not a url", "generic", &bad, &engines).await.unwrap_err().errors;
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "keyword",
+                "fields[0].attribute",
+                "fields[0].post_process[0].pattern",
+                "max_pages",
+                "steps[1].timeout_ms",
+                "steps[1].selector",
+                "gl"
+            ]
+        );
+
+        let serp = options(serde_json::json!({"selectors": {"title": "h1"}, "custom_script": "return 1"}));
+        let errors = validate_crawl(&"x".repeat(MAX_KEYWORD_LEN + 1), "yahoo", &serp, &engines).await.unwrap_err().errors;
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["engine", "keyword", "selectors", "custom_script"]);
+
+        let request: CrawlRequest = serde_json::from_value(serde_json::json!({
+            "keyword": "rust", "engine": "bing", "max_retries": 50, "wait_for": {"timeout_ms": 1000}
+        }))
+        .unwrap();
+        let (status, message) = <(StatusCode, String)>::from(validate_crawl_request(&request, "bing", &engines).await.unwrap_err());
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(message, "max_retries: must be between 0 and 10");
+
+        let login = options(serde_json::json!({"login": {
+            "url": "http://10.0.0.5/login", "username": "u", "password": "p",
+            "username_selector": "#u", "password_selector": "#p", "submit_selector": "button"
+        }}));
+        let errors = validate_crawl("http://169.254.169.254/latest/meta-data", "generic", &login, &engines).await.unwrap_err().errors;
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["keyword", "login.url"]);
+        assert!(errors.iter().all(|e| e.message.contains("non-public")));
+    }
+}
@@ -0,0 +1,538 @@
+//! Completion webhooks.
+//!
+//! A crawl submitted with a `callback_url` gets its final task record POSTed
+//! there once the worker completes it or gives up on it, so callers don't have
+//! to poll `GET /crawl/:task_id`. Deliveries run in the background and are
+//! retried with exponential backoff on network errors and non-2xx responses.
+//!
+//! Each body is signed with the owner's webhook secret (`X-Signature:
+//! sha256=<hex HMAC-SHA256 of the body>`) and every attempt is logged in
+//! `webhook_deliveries`, from where failed deliveries can be sent again. So can
+//! deliveries left `retrying` past their retry window, e.g. by a restart.
+//!
+//! Callback hosts must resolve to public addresses only: loopback, private,
+//! link-local and unique-local targets are refused when the crawl is submitted
+//! and again before every attempt (the connection is pinned to the addresses
+//! just checked, and redirects aren't followed), so DNS changes can't point a
+//! delivery at internal services. Only the response's status code is kept.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::{FromRow, PgPool};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use tracing::{error, info, warn};
+
+/// Delivery attempts per event (or per redelivery), including the first
+const MAX_ATTEMPTS: u32 = 4;
+/// Delay before the first retry, doubled on each further one
+const RETRY_BASE_SECS: u64 = 5;
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Seconds after which a delivery still marked `retrying` has missed its next
+/// attempt: the longest retry delay plus the request timeout, with slack
+fn stalled_after_secs() -> i64 {
+    (retry_delay(MAX_ATTEMPTS - 1).as_secs() + 2 * REQUEST_TIMEOUT_SECS) as i64
+}
+
+/// Latest attempt of a delivery
+#[derive(Debug, Serialize, Clone, ToSchema, FromRow)]
+pub struct WebhookDelivery {
+    pub delivery_id: String,
+    pub task_id: String,
+    /// `task.completed`, `task.partial` or `task.failed`
+    pub event: String,
+    pub url: String,
+    /// Attempts made so far, redeliveries included
+    pub attempts: i32,
+    /// delivered, retrying or failed
+    pub status: String,
+    /// HTTP status of the last attempt; null on network errors
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    #[schema(value_type = String)]
+    pub attempted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDeliveryResponse {
+    pub success: bool,
+    pub delivery: Option<WebhookDelivery>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookSecretResponse {
+    pub success: bool,
+    /// Key for verifying `X-Signature`
+    pub secret: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeliveryListQuery {
+    /// Only deliveries that ran out of attempts or stalled while retrying
+    #[serde(default)]
+    pub failed: bool,
+    /// Most recent deliveries to return (default 50, max 200)
+    pub limit: Option<i64>,
+}
+
+type ApiError = (StatusCode, String);
+
+/// Check a submitted callback URL: absolute http(s) with a host that resolves
+/// to public addresses only
+pub async fn validate_callback_url(url: &str) -> Result<String, String> {
+    let parsed = parse_callback_url(url)?;
+    check_public_url(&parsed).await.map_err(|e| format!("callback_url {}", e))?;
+    Ok(parsed.to_string())
+}
+
+/// Refuse a URL whose host doesn't resolve, or resolves to any non-public
+/// address. Shared with crawl targets, which the browser would fetch from inside
+/// the network the same way deliveries do.
+pub async fn check_public_url(url: &reqwest::Url) -> Result<(), String> {
+    let addrs = resolve(url).await?;
+    check_public(url, &addrs)
+}
+
+fn parse_callback_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|_| format!("Invalid callback_url '{}'", url))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err("callback_url must be an http(s) URL".to_string());
+    }
+    Ok(parsed)
+}
+
+/// Whether deliveries may connect to `ip`: not loopback, private, link-local,
+/// unique-local (fc00::/7), carrier-grade NAT, multicast or unspecified
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// The URL's host, without the brackets of IPv6 literals
+fn bare_host(url: &reqwest::Url) -> &str {
+    url.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']')
+}
+
+/// Addresses the URL's host resolves to
+async fn resolve(url: &reqwest::Url) -> Result<Vec<SocketAddr>, String> {
+    let host = bare_host(url);
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("host '{}' failed to resolve: {}", host, e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("host '{}' has no addresses", host));
+    }
+    Ok(addrs)
+}
+
+/// Refuse the host unless every address it resolved to is public
+fn check_public(url: &reqwest::Url, addrs: &[SocketAddr]) -> Result<(), String> {
+    match addrs.iter().find(|addr| !is_public(addr.ip())) {
+        Some(blocked) => Err(format!("host '{}' resolves to a non-public address ({})", bare_host(url), blocked.ip())),
+        None => Ok(()),
+    }
+}
+
+/// Client that connects only to `addrs` for the URL's host and doesn't follow redirects
+fn pinned_client(url: &reqwest::Url, addrs: &[SocketAddr]) -> reqwest::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .redirect(reqwest::redirect::Policy::none());
+    if let Some(domain) = url.domain() {
+        builder = builder.resolve_to_addrs(domain, addrs);
+    }
+    builder.build()
+}
+
+/// `X-Signature` value for a body: `sha256=<hex HMAC-SHA256>`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    format!("sha256={}", digest.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+fn generate_secret() -> String {
+    let bytes: [u8; 24] = rand::random();
+    format!("whsec_{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+/// The user's signing secret, created on first use
+async fn secret_for(pool: &PgPool, user_id: &str) -> Result<String, sqlx::Error> {
+    sqlx::query("INSERT INTO webhook_secrets (user_id, secret) VALUES ($1, $2) ON CONFLICT (user_id) DO NOTHING")
+        .bind(user_id)
+        .bind(generate_secret())
+        .execute(pool)
+        .await?;
+    sqlx::query_scalar("SELECT secret FROM webhook_secrets WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+}
+
+/// Delay before retry number `attempt` (1-based)
+fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_secs(RETRY_BASE_SECS << (attempt - 1).min(10))
+}
+
+/// One delivery: event, target and where its attempt numbering continues
+struct Delivery {
+    id: String,
+    user_id: String,
+    task_id: String,
+    event: String,
+    url: String,
+    /// Attempts already logged (non-zero for redeliveries)
+    previous_attempts: u32,
+}
+
+/// POST the task's current record to `url` in the background.
+/// `event` is `task.completed`, `task.partial` or `task.failed` and is sent as `X-Crawler-Event`.
+pub fn spawn_delivery(pool: PgPool, user_id: String, url: String, task_id: String, event: &'static str) {
+    let delivery = Delivery {
+        id: Uuid::new_v4().to_string(),
+        user_id,
+        task_id,
+        event: event.to_string(),
+        url,
+        previous_attempts: 0,
+    };
+    tokio::spawn(async move { deliver(&pool, delivery).await });
+}
+
+async fn deliver(pool: &PgPool, delivery: Delivery) {
+    let task = match crate::api::load_task(pool, &delivery.task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => {
+            warn!("⚠️ [Webhook] Task {} not found; nothing to deliver", delivery.task_id);
+            return;
+        }
+        Err(e) => {
+            warn!("⚠️ [Webhook] Failed to load task {}: {}", delivery.task_id, e);
+            return;
+        }
+    };
+    let body = match serde_json::to_vec(&task) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("⚠️ [Webhook] Failed to serialize task {}: {}", delivery.task_id, e);
+            return;
+        }
+    };
+    let signature = match secret_for(pool, &delivery.user_id).await {
+        Ok(secret) => sign(&secret, &body),
+        Err(e) => {
+            warn!("⚠️ [Webhook] Failed to load signing secret for {}: {}", delivery.user_id, e);
+            return;
+        }
+    };
+    let url = match parse_callback_url(&delivery.url) {
+        Ok(url) => url,
+        Err(e) => {
+            log_attempt(pool, &delivery, delivery.previous_attempts + 1, "failed", None, Some(&e)).await;
+            return;
+        }
+    };
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        // Resolved again on every attempt: the host may have moved since submission
+        let result = match resolve(&url).await {
+            Ok(addrs) => {
+                if let Err(e) = check_public(&url, &addrs) {
+                    error!("❌ [Webhook] Refusing {} for {}: {}", delivery.event, delivery.task_id, e);
+                    log_attempt(pool, &delivery, delivery.previous_attempts + attempt, "failed", None, Some(&e)).await;
+                    return;
+                }
+                match pinned_client(&url, &addrs) {
+                    Ok(client) => client
+                        .post(url.clone())
+                        .header("Content-Type", "application/json")
+                        .header("X-Crawler-Event", &delivery.event)
+                        .header("X-Crawler-Task-Id", &delivery.task_id)
+                        .header("X-Webhook-Delivery", &delivery.id)
+                        .header("X-Signature", &signature)
+                        .body(body.clone())
+                        .send()
+                        .await
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(format!("Failed to build HTTP client: {}", e)),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        // Only the status is kept: response bodies may echo internal details
+        let (status_code, error) = match result {
+            Ok(response) if response.status().is_success() => (Some(response.status().as_u16() as i32), None),
+            Ok(response) => (Some(response.status().as_u16() as i32), Some(format!("HTTP {}", response.status()))),
+            Err(e) => (None, Some(e)),
+        };
+
+        let status = match (&error, attempt) {
+            (None, _) => "delivered",
+            (Some(_), MAX_ATTEMPTS) => "failed",
+            (Some(_), _) => "retrying",
+        };
+        log_attempt(pool, &delivery, delivery.previous_attempts + attempt, status, status_code, error.as_deref()).await;
+
+        let Some(error) = error else {
+            info!("📬 [Webhook] Delivered {} for {} to {}", delivery.event, delivery.task_id, delivery.url);
+            return;
+        };
+        if attempt == MAX_ATTEMPTS {
+            error!("❌ [Webhook] Giving up on {} for {} after {} attempts: {}", delivery.event, delivery.task_id, attempt, error);
+            return;
+        }
+        let delay = retry_delay(attempt);
+        info!("🔁 [Webhook] Delivery of {} for {} failed ({}); retrying in {}s", delivery.event, delivery.task_id, error, delay.as_secs());
+        tokio::time::sleep(delay).await;
+    }
+}
+
+async fn log_attempt(pool: &PgPool, delivery: &Delivery, attempt: u32, status: &str, status_code: Option<i32>, error: Option<&str>) {
+    let result = sqlx::query(
+        r#"INSERT INTO webhook_deliveries (delivery_id, user_id, task_id, event, url, attempt, status, status_code, error)
+           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+    )
+    .bind(&delivery.id)
+    .bind(&delivery.user_id)
+    .bind(&delivery.task_id)
+    .bind(&delivery.event)
+    .bind(&delivery.url)
+    .bind(attempt as i32)
+    .bind(status)
+    .bind(status_code)
+    .bind(error)
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        warn!("⚠️ [Webhook] Failed to log attempt {} of delivery {}: {}", attempt, delivery.id, e);
+    }
+}
+
+// ============================================================================
+// API
+// ============================================================================
+
+/// Latest attempt per delivery, newest first
+const LATEST_ATTEMPTS: &str = r#"SELECT * FROM (
+        SELECT DISTINCT ON (delivery_id) delivery_id, user_id, task_id, event, url, attempt AS attempts,
+               status, status_code, error, attempted_at
+        FROM webhook_deliveries
+        WHERE user_id = $1
+        ORDER BY delivery_id, attempt DESC
+    ) latest"#;
+
+/// A `retrying` delivery whose next attempt is overdue (seconds bound as `$4`)
+const STALLED: &str = "(status = 'retrying' AND attempted_at < now() - make_interval(secs => $4))";
+
+fn db_error(e: sqlx::Error) -> ApiError {
+    error!("❌ [Webhook] Database error: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+}
+
+/// The caller's webhook signing secret (created on first request)
+#[utoipa::path(
+    get,
+    path = "/webhooks/secret",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Signing secret", body = WebhookSecretResponse)
+    )
+)]
+pub async fn get_webhook_secret(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<WebhookSecretResponse>, ApiError> {
+    let secret = secret_for(&state.pool, &user.id).await.map_err(db_error)?;
+    Ok(Json(WebhookSecretResponse {
+        success: true,
+        secret: Some(secret),
+        message: None,
+    }))
+}
+
+/// Replace the caller's signing secret; deliveries signed afterwards use the new one
+#[utoipa::path(
+    post,
+    path = "/webhooks/secret/rotate",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "New signing secret", body = WebhookSecretResponse)
+    )
+)]
+pub async fn rotate_webhook_secret(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<WebhookSecretResponse>, ApiError> {
+    let secret: String = sqlx::query_scalar(
+        r#"INSERT INTO webhook_secrets (user_id, secret) VALUES ($1, $2)
+           ON CONFLICT (user_id) DO UPDATE SET secret = EXCLUDED.secret, created_at = now()
+           RETURNING secret"#,
+    )
+    .bind(&user.id)
+    .bind(generate_secret())
+    .fetch_one(&state.pool)
+    .await
+    .map_err(db_error)?;
+
+    Ok(Json(WebhookSecretResponse {
+        success: true,
+        secret: Some(secret),
+        message: Some("Webhook secret rotated".to_string()),
+    }))
+}
+
+/// The caller's recent webhook deliveries with the outcome of their last attempt
+#[utoipa::path(
+    get,
+    path = "/webhooks/deliveries",
+    tag = "webhooks",
+    params(DeliveryListQuery),
+    responses(
+        (status = 200, description = "Deliveries, newest first", body = Vec<WebhookDelivery>)
+    )
+)]
+pub async fn list_deliveries(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Query(query): Query<DeliveryListQuery>,
+) -> Result<Json<Vec<WebhookDelivery>>, ApiError> {
+    let deliveries: Vec<WebhookDelivery> = sqlx::query_as(&format!(
+        "{} WHERE NOT $2 OR status = 'failed' OR {} ORDER BY attempted_at DESC LIMIT $3",
+        LATEST_ATTEMPTS, STALLED
+    ))
+    .bind(&user.id)
+    .bind(query.failed)
+    .bind(query.limit.unwrap_or(50).clamp(1, 200))
+    .bind(stalled_after_secs())
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_error)?;
+    Ok(Json(deliveries))
+}
+
+/// Send a failed (or stalled) delivery again, with the task's current record and
+/// a fresh round of retries
+#[utoipa::path(
+    post,
+    path = "/webhooks/deliveries/{id}/redeliver",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path, description = "Delivery ID (`X-Webhook-Delivery`)")
+    ),
+    responses(
+        (status = 200, description = "Redelivery started", body = WebhookDeliveryResponse),
+        (status = 404, description = "Delivery not found"),
+        (status = 409, description = "Delivery succeeded or is still within its retry window")
+    )
+)]
+pub async fn redeliver(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookDeliveryResponse>, ApiError> {
+    let delivery: WebhookDelivery = sqlx::query_as(&format!("{} WHERE delivery_id = $2", LATEST_ATTEMPTS))
+        .bind(&user.id)
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Delivery not found".to_string()))?;
+    let stalled = delivery.status == "retrying"
+        && Utc::now() - delivery.attempted_at > chrono::Duration::seconds(stalled_after_secs());
+    if delivery.status != "failed" && !stalled {
+        return Err((StatusCode::CONFLICT, format!("Delivery is {}; only failed or stalled deliveries can be sent again", delivery.status)));
+    }
+
+    let pool = state.pool.clone();
+    let next = Delivery {
+        id: delivery.delivery_id.clone(),
+        user_id: user.id.clone(),
+        task_id: delivery.task_id.clone(),
+        event: delivery.event.clone(),
+        url: delivery.url.clone(),
+        previous_attempts: delivery.attempts.max(0) as u32,
+    };
+    tokio::spawn(async move { deliver(&pool, next).await });
+
+    Ok(Json(WebhookDeliveryResponse {
+        success: true,
+        delivery: Some(delivery),
+        message: Some("Redelivery started".to_string()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_validate_callback_url() {
+        assert_eq!(validate_callback_url(" https://93.184.215.14/crawl ").await.unwrap(), "https://93.184.215.14/crawl");
+        assert!(validate_callback_url("ftp://example.com/").await.is_err());
+        assert!(validate_callback_url("not a url").await.is_err());
+        for internal in ["http://127.0.0.1:8080/", "http://localhost/", "http://10.0.0.5/", "http://169.254.169.254/latest", "http://[::1]/", "http://[fd00::1]/", "http://[::ffff:192.168.1.1]/"] {
+            let error = validate_callback_url(internal).await.unwrap_err();
+            assert!(error.contains("non-public"), "{}: {}", internal, error);
+        }
+        assert!(is_public("2606:4700::1111".parse().unwrap()));
+        assert!(!is_public("100.64.0.1".parse().unwrap()));
+        assert_eq!(retry_delay(1).as_secs(), 5);
+        assert_eq!(retry_delay(3).as_secs(), 20);
+        assert_eq!(stalled_after_secs(), 40);
+    }
+
+    #[test]
+    fn test_sign_matches_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(generate_secret().starts_with("whsec_"));
+        assert_ne!(generate_secret(), generate_secret());
+    }
+}
@@ -1,90 +1,527 @@
-use std::sync::Arc;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::Semaphore;
 use tokio::time::{sleep, Duration};
 use crate::api::AppState;
 use crate::crawler;
-use crate::queue::CrawlJob;
+use crate::events::{self, JobEvent, JobEventKind};
+use crate::notifications::{NotificationDetails, NotificationEvent};
+use crate::proxy::PROXY_MANAGER;
+use crate::queue::{CrawlJob, Lane};
+use crate::usage::Metric;
+use tracing::{error, info, info_span, warn, Instrument};
+
+/// Concurrency of one lane in this process
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct LaneConcurrency {
+    #[schema(example = "bing")]
+    pub lane: String,
+    /// Configured maximum of simultaneous jobs
+    pub concurrency: usize,
+    /// Jobs running right now
+    pub active: usize,
+}
+
+/// A running lane's configured concurrency and its job slots
+type LaneSlots = (Lane, usize, Arc<Semaphore>);
+
+/// Job slots of each running lane, for `GET /queue/stats`
+static LANE_SLOTS: Lazy<RwLock<Vec<LaneSlots>>> = Lazy::new(Default::default);
+
+/// Configured and active concurrency of this process's lanes (empty if the worker isn't running)
+pub fn lane_concurrency() -> Vec<LaneConcurrency> {
+    LANE_SLOTS
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(lane, concurrency, slots)| LaneConcurrency {
+            lane: lane.as_str().to_string(),
+            concurrency: *concurrency,
+            active: concurrency.saturating_sub(slots.available_permits()),
+        })
+        .collect()
+}
 
 pub async fn start_worker(state: Arc<AppState>) {
-    println!("👷 Worker started, polling Redis...");
+    let worker_id = WORKER_ID.clone();
+    tokio::spawn(send_heartbeats(state.clone(), worker_id.clone()));
+    tokio::spawn(crate::exports::start_export_runner(state.clone()));
+
+    let default_concurrency = concurrency_from_env("WORKER_CONCURRENCY").unwrap_or(1);
+    let mut lanes = Vec::new();
+    for lane in Lane::ALL {
+        let var = format!("WORKER_CONCURRENCY_{}", lane.as_str().to_uppercase());
+        let concurrency = concurrency_from_env(&var).unwrap_or(default_concurrency);
+        info!("👷 Worker {} serving {} queue ({} concurrent jobs), polling {}...", worker_id, lane.as_str(), concurrency, state.queue.backend_name());
+        lanes.push(tokio::spawn(run_lane(state.clone(), worker_id.clone(), lane, concurrency)));
+    }
+    for lane in lanes {
+        let _ = lane.await;
+    }
+}
+
+fn concurrency_from_env(var: &str) -> Option<usize> {
+    std::env::var(var).ok().and_then(|s| s.parse().ok()).filter(|&n| n > 0)
+}
+
+/// Poll one lane's queue, running up to `concurrency` of its jobs at a time
+async fn run_lane(state: Arc<AppState>, worker_id: String, lane: Lane, concurrency: usize) {
+    let slots = Arc::new(Semaphore::new(concurrency));
+    LANE_SLOTS.write().unwrap().push((lane, concurrency, slots.clone()));
 
     loop {
-        // Poll for 1 job
-        match state.queue.pop_job().await {
+        // Only pop when a processor is free, so queued jobs stay visible to other replicas
+        let permit = slots.clone().acquire_owned().await.expect("worker semaphore is never closed");
+
+        // Paused via POST /worker/pause: running jobs finish, nothing new is claimed.
+        // Checked once a slot is free, since waiting for it can outlast a pause.
+        match state.queue.is_paused().await {
+            Ok(true) => {
+                drop(permit);
+                sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+            Ok(false) => {}
+            Err(e) => warn!("⚠️ [Worker] Failed to read pause flag: {}", e),
+        }
+
+        match state.queue.pop_job(&worker_id, lane).await {
             Ok(Some(job)) => {
-                println!("👷 [Worker] Picked up job: {} ({})", job.id, job.keyword);
-                if let Err(e) = process_job(state.clone(), job).await {
-                    eprintln!("❌ [Worker] Job failed: {}", e);
-                    // TODO: Implement DLQ or Retry here
-                }
+                info!(task_id = %job.id, engine = %job.engine, attempt = job.attempt + 1, "👷 [Worker] Picked up {} job: {} ({})", lane.as_str(), job.id, job.keyword);
+                let state = state.clone();
+                let span = job_span(&job);
+                tokio::spawn(
+                    async move {
+                        run_job(state, job).await;
+                        drop(permit);
+                    }
+                    .instrument(span),
+                );
             },
             Ok(None) => {
                 // Queue empty, sleep backoff
+                drop(permit);
                 sleep(Duration::from_millis(1000)).await;
             },
             Err(e) => {
-                eprintln!("🔥 [Worker] Redis error: {}", e);
+                drop(permit);
+                error!("🔥 [Worker] Queue error: {}", e);
                 sleep(Duration::from_secs(5)).await;
             }
         }
     }
 }
 
-async fn process_job(state: Arc<AppState>, job: CrawlJob) -> anyhow::Result<()> {
-    println!("🚀 [Worker] Processing: {}", job.keyword);
-    let pool = state.pool.clone();
-    let engine_clone = job.engine.clone();
+/// Identifies this process's claims and heartbeat (`WORKER_ID`, else hostname plus a random suffix)
+static WORKER_ID: Lazy<String> = Lazy::new(|| {
+    std::env::var("WORKER_ID").ok().filter(|s| !s.is_empty()).unwrap_or_else(|| {
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "worker".to_string());
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        format!("{}-{}", host, &suffix[..8])
+    })
+});
 
-    // 1. Search (Google/Bing/Generic)
-    let search_results = if job.engine == "google" {
-        crawler::search_google(&job.keyword).await
-    } else if job.engine == "generic" {
-        crawler::generic_crawl(&job.keyword, job.selectors).await
-    } else {
-        crawler::search_bing(&job.keyword).await
-    };
+pub fn worker_id() -> &'static str {
+    WORKER_ID.as_str()
+}
+
+fn heartbeat_ttl_secs() -> u64 {
+    std::env::var("WORKER_HEARTBEAT_TTL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(30)
+        .max(5)
+}
+
+/// Keep this worker's heartbeat key alive for as long as the process runs
+async fn send_heartbeats(state: Arc<AppState>, worker_id: String) {
+    let ttl = heartbeat_ttl_secs();
+    let mut ticker = tokio::time::interval(Duration::from_secs((ttl / 3).max(1)));
+    loop {
+        ticker.tick().await;
+        if let Err(e) = state.queue.heartbeat(&worker_id, ttl).await {
+            warn!("⚠️ [Worker] Heartbeat failed: {}", e);
+        }
+    }
+}
+
+/// Recover jobs owned by workers whose heartbeat expired (crashed or restarted
+/// containers). Each counts as a failed attempt, so it's retried with backoff or
+/// marked failed like any other failure.
+pub async fn start_janitor(state: Arc<AppState>) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(heartbeat_ttl_secs()));
+    loop {
+        ticker.tick().await;
+        let orphaned = match state.queue.take_orphaned_jobs(&WORKER_ID).await {
+            Ok(orphaned) => orphaned,
+            Err(e) => {
+                warn!("⚠️ [Janitor] Failed to scan claimed jobs: {}", e);
+                continue;
+            }
+        };
+        for (job, owner) in orphaned {
+            info!(task_id = %job.id, engine = %job.engine, attempt = job.attempt + 1, worker_id = %owner, "🧹 [Janitor] Recovering job {} from dead worker {}", job.id, owner);
+            let error = anyhow::anyhow!("worker {} stopped responding", owner);
+            let span = job_span(&job);
+            handle_failure(&state, job.clone(), &error, None).instrument(span).await;
+            crate::task_logs::flush(&state.pool, &job).await;
+        }
+    }
+}
+
+/// Trace span of one attempt at a job; everything the worker does for it nests under this
+fn job_span(job: &CrawlJob) -> tracing::Span {
+    info_span!(crate::task_logs::JOB_SPAN, task_id = %job.id, engine = %job.engine, keyword = %job.keyword, attempt = job.attempt + 1)
+}
+
+/// Process one job end to end, including retry bookkeeping on failure
+async fn run_job(state: Arc<AppState>, job: CrawlJob) {
+    let job_id = job.id.clone();
+    events::publish(JobEvent::new(JobEventKind::Started, &job).with_message(format!("attempt {}", job.attempt + 1)));
 
-    let serp_data = match search_results {
-        Ok(data) => data,
+    // Proxy traffic is metered per attempt, whatever its outcome
+    let proxy_meter = Arc::new(AtomicU64::new(0));
+    let mut metered = job.clone();
+    metered.options.proxy_meter = Some(proxy_meter.clone());
+    // Screenshots and page dumps are stored with the task, whatever the outcome
+    let artifacts = crate::crawler::ArtifactSink::default();
+    metered.options.debug_artifacts = Some(artifacts.clone());
+    let proxy_used = crate::crawler::ProxyRecorder::default();
+    metered.options.proxy_used = Some(proxy_used.clone());
+    let started = std::time::Instant::now();
+    let status = match process_job(state.clone(), metered).await {
+        Ok(status) => {
+            record_outcome(&state, true).await;
+            discard_login(&state, &job).await;
+            status
+        }
         Err(e) => {
-             // Log failure to DB?
-             return Err(e);
+            error!(task_id = %job.id, engine = %job.engine, attempt = job.attempt + 1, error = %e, "❌ [Worker] Job failed: {}", e);
+            let proxy_id = proxy_used.lock().unwrap().clone();
+            handle_failure(&state, job.clone(), &e, proxy_id).await
         }
     };
+    crate::metrics::observe_crawl(&job.engine, status, started.elapsed());
+    let captured = std::mem::take(&mut *artifacts.lock().unwrap());
+    crate::artifacts::store(&state.storage, &job, captured).await;
+    let proxy_bytes = proxy_meter.load(Ordering::Relaxed) as i64;
+    if let Err(e) = crate::usage::record(&state.pool, &job.user_id, Metric::ProxyBytes, Some(&job.engine), Some(&job_id), proxy_bytes).await {
+        warn!(task_id = %job_id, "⚠️ [Worker] Failed to meter proxy bandwidth for {}: {}", job_id, e);
+    }
+    // Retries were re-enqueued as new entries, so the claim is done either way
+    if let Err(e) = state.queue.ack_job(&job).await {
+        warn!(task_id = %job_id, "⚠️ [Worker] Failed to ack job {}: {}", job_id, e);
+    }
+    PROXY_MANAGER.release_session(&job_id);
+    crate::task_logs::flush(&state.pool, &job).await;
+}
+
+async fn record_outcome(state: &AppState, succeeded: bool) {
+    if let Err(e) = state.queue.record_outcome(succeeded).await {
+        warn!("⚠️ [Worker] Failed to record job outcome: {}", e);
+    }
+}
+
+/// Part of a job an attempt failed in, recorded as the task's `failure_stage`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureStage {
+    /// Fetching the results page
+    Serp,
+    /// Crawling and extracting a page: a generic job's crawl, or the first result's deep extraction
+    Extract,
+    /// Saving the results
+    Store,
+}
+
+impl FailureStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureStage::Serp => "serp",
+            FailureStage::Extract => "extract",
+            FailureStage::Store => "store",
+        }
+    }
+}
+
+/// How a stage of a finished job went, recorded in the task's `stages`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StageOutcome {
+    Succeeded,
+    Failed,
+    /// Nothing to do, e.g. no result to extract
+    Skipped,
+}
+
+/// Outcome of the stages after the results page, which a job can finish without
+#[derive(Debug, Clone, Copy, Serialize)]
+struct JobStages {
+    serp: StageOutcome,
+    /// Deep extraction of the first result
+    extract: StageOutcome,
+    /// Raw HTML upload to object storage
+    store: StageOutcome,
+}
+
+impl JobStages {
+    /// First stage that failed, recorded as the task's `failure_stage`
+    fn failed_stage(&self) -> Option<FailureStage> {
+        if self.extract == StageOutcome::Failed {
+            Some(FailureStage::Extract)
+        } else if self.store == StageOutcome::Failed {
+            Some(FailureStage::Store)
+        } else {
+            None
+        }
+    }
 
-    // 2. Extract Content (Deep Crawl)
+    /// `partial` when the results page was saved but a later stage failed
+    fn status(&self) -> &'static str {
+        if self.failed_stage().is_some() { "partial" } else { "completed" }
+    }
+}
+
+/// A job error tagged with the stage it happened in; displays as the error itself
+#[derive(Debug)]
+struct StageError {
+    stage: FailureStage,
+    error: anyhow::Error,
+}
+
+impl std::fmt::Display for StageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.error)
+    }
+}
+
+impl std::error::Error for StageError {}
+
+fn failed_in(stage: FailureStage) -> impl FnOnce(anyhow::Error) -> anyhow::Error {
+    move |error| anyhow::Error::new(StageError { stage, error })
+}
+
+/// Stage a job error was tagged with; `None` for failures outside a stage (e.g. a dead worker)
+fn failure_stage(error: &anyhow::Error) -> Option<FailureStage> {
+    error.downcast_ref::<StageError>().map(|e| e.stage)
+}
+
+/// Re-enqueue a failed job with exponential backoff (task status `retrying`),
+/// or mark it `failed` once its retries are exhausted. The error, its stage and
+/// the proxy used are recorded on the task. Returns the task's new status.
+async fn handle_failure(state: &AppState, mut job: CrawlJob, error: &anyhow::Error, proxy_id: Option<String>) -> &'static str {
+    job.attempt += 1;
+    let error_text = format!("{:#}", error);
+
+    let lower = error_text.to_lowercase();
+    if lower.contains("challenge") || lower.contains("captcha") {
+        crate::metrics::CHALLENGES.with_label_values(&[&job.engine]).inc();
+        events::publish(JobEvent::new(JobEventKind::ChallengeDetected, &job).with_message(error_text.clone()));
+    }
+
+    let status = if job.can_retry() {
+        let delay = job.retry_delay();
+        info!(task_id = %job.id, engine = %job.engine, attempt = job.attempt + 1, delay_secs = delay, "🔁 [Worker] Retrying job {} in {}s (attempt {}/{})", job.id, delay, job.attempt + 1, job.max_retries + 1);
+        match state.queue.push_job_delayed(job.clone(), delay).await {
+            Ok(()) => {
+                events::publish(JobEvent::new(JobEventKind::Retrying, &job).with_message(format!("retry in {}s: {}", delay, error_text)));
+                "retrying"
+            }
+            Err(e) => {
+                error!(task_id = %job.id, "🔥 [Worker] Failed to re-enqueue job {}: {}", job.id, e);
+                "failed"
+            }
+        }
+    } else {
+        info!(task_id = %job.id, engine = %job.engine, attempt = job.attempt, "💀 [Worker] Job {} failed after {} attempts", job.id, job.attempt);
+        "failed"
+    };
+
+    let _ = sqlx::query(
+        r#"
+        INSERT INTO tasks (id, keyword, engine, status, attempts, last_error, user_id, failure_stage, proxy_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (id) DO UPDATE SET
+            status = EXCLUDED.status, attempts = EXCLUDED.attempts, last_error = EXCLUDED.last_error,
+            failure_stage = EXCLUDED.failure_stage, proxy_id = COALESCE(EXCLUDED.proxy_id, tasks.proxy_id)
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.keyword)
+    .bind(&job.engine)
+    .bind(status)
+    .bind(job.attempt as i32)
+    .bind(&error_text)
+    .bind(&job.user_id)
+    .bind(failure_stage(error).map(|s| s.as_str()))
+    .bind(&proxy_id)
+    .execute(&state.pool)
+    .await;
+
+    if status == "failed" {
+        record_outcome(state, false).await;
+        discard_login(state, &job).await;
+        if let Err(e) = crate::credits::refund(&state.pool, &job.id).await {
+            warn!(task_id = %job.id, "⚠️ [Worker] Failed to refund credits for {}: {}", job.id, e);
+        }
+        events::publish(JobEvent::new(JobEventKind::Failed, &job).with_message(error_text.clone()));
+        crate::event_bus::publish(crate::event_bus::TaskEvent::failed(&job, &error_text));
+        if let Some(ref url) = job.callback_url {
+            crate::webhooks::spawn_delivery(state.pool.clone(), job.user_id.clone(), url.clone(), job.id.clone(), "task.failed");
+        }
+        let message = format!("Crawl failed for '{}' after {} attempts: {}", job.keyword, job.attempt, error_text);
+        let details = NotificationDetails {
+            keyword: Some(job.keyword.clone()),
+            task_id: Some(job.id.clone()),
+            error: Some(error_text.clone()),
+            ..Default::default()
+        };
+        let _ = crate::notifications::notify_with(&state.pool, &job.user_id, NotificationEvent::CrawlFailed, "Crawl Failed", &message, details).await;
+    }
+    status
+}
+
+/// Delete a finished task's login password; retries still need it
+async fn discard_login(state: &AppState, job: &CrawlJob) {
+    if let Err(e) = crate::login_secrets::discard(&state.pool, &job.id).await {
+        warn!(task_id = %job.id, "⚠️ [Worker] Failed to delete the login password of {}: {}", job.id, e);
+    }
+}
+
+/// Record the stage a running task is in, so GET /crawl/:task_id shows progress
+async fn report_progress(pool: &sqlx::PgPool, job: &CrawlJob, stage: &str, progress: i32) {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO tasks (id, keyword, engine, status, stage, progress, user_id)
+        VALUES ($1, $2, $3, 'running', $4, $5, $6)
+        ON CONFLICT (id) DO UPDATE SET status = 'running', stage = EXCLUDED.stage, progress = EXCLUDED.progress
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.keyword)
+    .bind(&job.engine)
+    .bind(stage)
+    .bind(progress)
+    .bind(&job.user_id)
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        warn!(task_id = %job.id, stage, "⚠️ [Worker] Failed to record progress for {}: {}", job.id, e);
+    }
+}
+
+/// Run a job and save its results; returns the task's status, `completed` or `partial`
+async fn process_job(state: Arc<AppState>, job: CrawlJob) -> anyhow::Result<&'static str> {
+    info!(task_id = %job.id, engine = %job.engine, attempt = job.attempt + 1, "🚀 [Worker] Processing: {}", job.keyword);
+    let pool = state.pool.clone();
+    report_progress(&pool, &job, "searching", 10).await;
+
+    let mut options = job.options.clone();
+    // Only this copy holds the login password; the queued job has a reference
+    crate::login_secrets::unseal(&pool, &job.user_id, &mut options).await?;
+    // Keep SERP, deep extraction and screenshots on one exit IP
+    if PROXY_MANAGER.sticky_sessions() {
+        options.proxy_session = Some(job.id.clone());
+    }
+    // Members of an organization with dedicated proxies crawl through those only
+    match crate::organizations::proxy_pool(&pool, &job.user_id).await {
+        Ok(proxy_pool) => options.proxy_pool = proxy_pool,
+        Err(e) => warn!(task_id = %job.id, "⚠️ [Worker] Failed to load proxy pool for {}: {}", job.user_id, e),
+    }
+
+    // 1. Search (Google/Bing/Generic)
+    let search_results = async {
+        if job.engine == "google" {
+            crawler::search_google(&job.keyword, &options).await
+        } else if job.engine == "generic" {
+            // Resolve stored recipe into inline fields
+            if let Some(ref recipe_id) = options.recipe_id {
+                let recipe = crate::recipes::fetch_recipe(&pool, recipe_id)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Recipe {} not found", recipe_id))?;
+                info!(task_id = %job.id, recipe_id = %recipe_id, "📋 [Worker] Using recipe '{}'", recipe.name);
+                options.fields = Some(recipe.fields.0);
+            }
+            crawler::generic_crawl(&job.keyword, &options).await
+        } else {
+            crawler::search_bing(&job.keyword, &options).await
+        }
+    }
+    .instrument(info_span!("crawl.search"))
+    .await;
+
+    // Generic jobs have no results page: their crawl is the extraction
+    let stage = if job.engine == "generic" { FailureStage::Extract } else { FailureStage::Serp };
+    let serp_data = search_results.map_err(failed_in(stage))?;
+
+    // 2. Extract Content (Deep Crawl); the results page is saved even if this fails
+    report_progress(&pool, &job, "extracting", 40).await;
+    let mut stages = JobStages { serp: StageOutcome::Succeeded, extract: StageOutcome::Skipped, store: StageOutcome::Skipped };
+    let mut stage_error = None;
     let first_result_data: Option<crawler::WebsiteData> = if let Some(first_result) = serp_data.results.first() {
-        println!("🔍 [Worker] Deep extracting: {}", first_result.link);
-        crawler::extract_website_data(&first_result.link).await.ok()
+        info!(task_id = %job.id, "🔍 [Worker] Deep extracting: {}", first_result.link);
+        match crawler::extract_website_data(&first_result.link, &options)
+            .instrument(info_span!("crawl.extract", url = %first_result.link))
+            .await
+        {
+            Ok(data) => {
+                stages.extract = StageOutcome::Succeeded;
+                Some(data)
+            }
+            Err(e) => {
+                warn!(task_id = %job.id, error = %e, "⚠️ [Worker] Deep extraction failed, keeping SERP results only: {:#}", e);
+                stages.extract = StageOutcome::Failed;
+                stage_error = Some(format!("{:#}", e));
+                None
+            }
+        }
     } else {
         None
     };
 
     let results_json = serde_json::to_string(&serp_data).unwrap_or_default();
 
-    // 3. Save to MinIO (Raw HTML)
-    // Example: Store first page HTML if exists
+    // 3. Save to MinIO (Raw HTML); the row only keeps the key
+    report_progress(&pool, &job, "storing", 60).await;
+    let mut stored_keys = Vec::new();
+    let mut html_key = None;
     if let Some(ref data) = first_result_data {
         if !data.html.is_empty() {
-            let s3_key = format!("{}/{}.html", job.engine, job.id);
-            if let Err(e) = state.storage.store_html(&s3_key, &data.html).await {
-                eprintln!("⚠️ [Worker] MinIO upload failed: {}", e);
+            let s3_key = crate::storage::html_key(&job.engine, &job.id);
+            let stored = state.storage.store_html(&s3_key, &data.html).instrument(info_span!("crawl.store", key = %s3_key)).await;
+            if let Err(e) = stored {
+                warn!(task_id = %job.id, "⚠️ [Worker] MinIO upload failed: {}", e);
+                stages.store = StageOutcome::Failed;
+                stage_error.get_or_insert_with(|| format!("{:#}", e));
             } else {
-                println!("💾 [Worker] HTML saved to MinIO: {}", s3_key);
+                stages.store = StageOutcome::Succeeded;
+                info!(task_id = %job.id, "💾 [Worker] HTML saved to MinIO: {}", s3_key);
+                stored_keys.push(s3_key.clone());
+                html_key = Some(s3_key.clone());
+                let bytes = data.html.len() as i64;
+                if let Err(e) = crate::usage::record(&pool, &job.user_id, Metric::StorageBytes, Some(&job.engine), Some(&job.id), bytes).await {
+                    warn!(task_id = %job.id, "⚠️ [Worker] Failed to meter storage for {}: {}", job.id, e);
+                }
             }
         }
     }
 
     // Prepare data for DB
-    let (extracted_text, extracted_html, md, ma, mdate, emails, phones, links, images, sentiment, entities, category, marketing) = if let Some(data) = &first_result_data {
+    report_progress(&pool, &job, "enriching", 75).await;
+    let (extracted_text, md, ma, mdate, emails, phones, links, images, sentiment, entities, category, marketing) = if let Some(data) = &first_result_data {
         
         // --- AI/ML ENRICHMENT (Running Locally) ---
         // We call the Python Sidecar on localhost:8000
-        let entities = crate::ml::extract_entities_remote(&data.main_text).await;
-        let category = crate::ml::classify_content_remote(&data.main_text).await;
+        let (entities, category) = async {
+            let entities = crate::ml::extract_entities_remote(&data.main_text).await;
+            (entities, crate::ml::classify_content_remote(&data.main_text).await)
+        }
+        .instrument(info_span!("crawl.enrich"))
+        .await;
 
         (
             data.main_text.clone(),
-            data.html.clone(),
             data.meta_description.clone(),
             data.meta_author.clone(),
             data.meta_date.clone(),
@@ -99,7 +536,6 @@ async fn process_job(state: Arc<AppState>, job: CrawlJob) -> anyhow::Result<()>
         )
     } else {
         (
-            String::new(), 
             String::new(), 
             None, 
             None, 
@@ -117,7 +553,7 @@ async fn process_job(state: Arc<AppState>, job: CrawlJob) -> anyhow::Result<()>
 
     // 4. Save to DB
     // 4. Save to DB with Workaround for Supabase
-    let mut conn = pool.acquire().await?;
+    let mut conn = pool.acquire().await.map_err(|e| failed_in(FailureStage::Store)(e.into()))?;
     // Workaround: generic deallocate to prevent "prepared statement already exists"
     let _ = sqlx::query("DEALLOCATE ALL").execute(&mut *conn).await;
 
@@ -125,11 +561,22 @@ async fn process_job(state: Arc<AppState>, job: CrawlJob) -> anyhow::Result<()>
         r#"
         INSERT INTO tasks (
             id, keyword, engine, status, results_json, 
-            extracted_text, first_page_html, meta_description, meta_author, meta_date,
+            extracted_text, html_key, meta_description, meta_author, meta_date,
             emails, phone_numbers, outbound_links, images, sentiment,
-            entities, category, marketing_data
+            entities, category, marketing_data, attempts, stage, progress, user_id, completed_at, proxy_id,
+            stages, last_error, failure_stage
         ) 
-        VALUES ($1, $2, $3, 'completed', $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+        VALUES ($1, $2, $3, $21, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, 'done', 100, $19, now(), $20, $22, $23, $24)
+        ON CONFLICT (id) DO UPDATE SET
+            status = EXCLUDED.status, results_json = EXCLUDED.results_json,
+            extracted_text = EXCLUDED.extracted_text, html_key = EXCLUDED.html_key, first_page_html = NULL,
+            meta_description = EXCLUDED.meta_description, meta_author = EXCLUDED.meta_author,
+            meta_date = EXCLUDED.meta_date, emails = EXCLUDED.emails, phone_numbers = EXCLUDED.phone_numbers,
+            outbound_links = EXCLUDED.outbound_links, images = EXCLUDED.images, sentiment = EXCLUDED.sentiment,
+            entities = EXCLUDED.entities, category = EXCLUDED.category,
+            marketing_data = EXCLUDED.marketing_data, attempts = EXCLUDED.attempts,
+            stage = 'done', progress = 100, completed_at = now(), proxy_id = EXCLUDED.proxy_id,
+            stages = EXCLUDED.stages, last_error = EXCLUDED.last_error, failure_stage = EXCLUDED.failure_stage
         "#
     )
     .bind(&job.id)
@@ -137,7 +584,7 @@ async fn process_job(state: Arc<AppState>, job: CrawlJob) -> anyhow::Result<()>
     .bind(&job.engine)
     .bind(&results_json)
     .bind(&extracted_text)
-    .bind(&extracted_html)
+    .bind(&html_key)
     .bind(&md)
     .bind(&ma)
     .bind(&mdate)
@@ -149,27 +596,104 @@ async fn process_job(state: Arc<AppState>, job: CrawlJob) -> anyhow::Result<()>
     .bind(&entities)
     .bind(&category)
     .bind(&marketing)
-    .execute(&mut *conn)
-    .await?;
-
-    println!("✅ [Worker] Job {} completed successfully!", job.id);
-
-    // 5. Send Notification
-    // We manually insert into DB because the worker doesn't have the API state/auth/endpoints handy, 
-    // but sharing the DB pool is sufficient.
-    let notification_id = uuid::Uuid::new_v4().to_string();
-    let message = format!("Crawl finished for '{}'. Category: {:?}", job.keyword, category.as_deref().unwrap_or("Unknown"));
-    
-    // We skip the email sending part here for simplicity/speed (or we could duplicate the logic),
-    // primarily ensuring the in-app notification exists for the test flow.
-    let _ = sqlx::query(
-        "INSERT INTO notifications (id, user_id, notification_type, subject, message) VALUES ($1, $2, 'system', 'Crawl Completed', $3)"
-    )
-    .bind(&notification_id)
+    .bind((job.attempt + 1) as i32)
     .bind(&job.user_id)
-    .bind(&message)
-    .execute(&pool) // using the pool clone
-    .await;
+    .bind(options.proxy_used.as_ref().and_then(|p| p.lock().unwrap().clone()))
+    .bind(stages.status())
+    .bind(serde_json::to_value(stages).unwrap_or_default())
+    .bind(&stage_error)
+    .bind(stages.failed_stage().map(|s| s.as_str()))
+    .execute(&mut *conn)
+    .instrument(info_span!("crawl.save"))
+    .await
+    .map_err(|e| failed_in(FailureStage::Store)(e.into()))?;
+
+    let status = stages.status();
+    if status == "partial" {
+        info!(task_id = %job.id, engine = %job.engine, attempt = job.attempt + 1, "🟡 [Worker] Job {} saved with SERP results only", job.id);
+    } else {
+        info!(task_id = %job.id, engine = %job.engine, attempt = job.attempt + 1, "✅ [Worker] Job {} completed successfully!", job.id);
+    }
+    if let Err(e) = crate::usage::record(&pool, &job.user_id, Metric::Crawl, Some(&job.engine), Some(&job.id), 1).await {
+        warn!(task_id = %job.id, "⚠️ [Worker] Failed to meter crawl {}: {}", job.id, e);
+    }
 
-    Ok(())
+    match crate::rankings::record_rankings(&pool, &job, &serp_data.results).await {
+        Ok(0) => {}
+        Ok(n) => info!(task_id = %job.id, "📈 [Worker] Recorded {} tracked domain position(s) for {}", n, job.id),
+        Err(e) => warn!(task_id = %job.id, "⚠️ [Worker] Failed to record rankings for {}: {}", job.id, e),
+    }
+
+    if let Err(e) = crate::search_index::index_task(&pool, &job.id).await {
+        warn!(task_id = %job.id, "⚠️ [Worker] Failed to index {} in Elasticsearch: {}", job.id, e);
+    }
+
+    match crate::alerts::evaluate(&pool, &job, &serp_data.results, sentiment.as_deref()).await {
+        Ok(0) => {}
+        Ok(n) => info!(task_id = %job.id, "🚨 [Worker] {} alert rule(s) fired for {}", n, job.id),
+        Err(e) => warn!(task_id = %job.id, "⚠️ [Worker] Failed to evaluate alert rules for {}: {}", job.id, e),
+    }
+
+    // An empty text would read as the page having changed
+    if let (Some(monitor_id), true) = (&job.monitor_id, stages.extract != StageOutcome::Failed) {
+        if let Err(e) = crate::monitors::record_check(&pool, monitor_id, &job.id, &extracted_text).await {
+            warn!(task_id = %job.id, "⚠️ [Worker] Failed to record monitor check for {}: {}", job.id, e);
+        }
+    }
+    events::publish(JobEvent::new(JobEventKind::Completed, &job).with_message(format!("{} results", serp_data.results.len())));
+    let stages_json = serde_json::to_value(stages).unwrap_or_default();
+    crate::event_bus::publish(crate::event_bus::TaskEvent::finished(&job, status, stages_json, stored_keys));
+    if let Some(ref url) = job.callback_url {
+        let event = crate::event_bus::event_name(status);
+        crate::webhooks::spawn_delivery(pool.clone(), job.user_id.clone(), url.clone(), job.id.clone(), event);
+    }
+
+    // 5. Send Notification (routed by the user's preferences)
+    let mut message = format!("Crawl finished for '{}'. Category: {:?}", job.keyword, category.as_deref().unwrap_or("Unknown"));
+    if let Some(stage) = stages.failed_stage() {
+        message.push_str(&format!(" Only search results were saved; the {} stage failed.", stage.as_str()));
+    }
+    let details = NotificationDetails {
+        keyword: Some(job.keyword.clone()),
+        task_id: Some(job.id.clone()),
+        result_count: Some(serp_data.results.len()),
+        category: category.clone(),
+        failed_stage: stages.failed_stage().map(|s| s.as_str().to_string()),
+        ..Default::default()
+    };
+    let subject = if status == "partial" { "Crawl Partially Completed" } else { "Crawl Completed" };
+    let _ = crate::notifications::notify_with(&pool, &job.user_id, NotificationEvent::CrawlCompleted, subject, &message, details).await;
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_stage_tagging() {
+        let cause = anyhow::anyhow!("connection reset").context("Bing search failed after 3 attempts");
+        let tagged = failed_in(FailureStage::Serp)(cause);
+        assert_eq!(failure_stage(&tagged), Some(FailureStage::Serp));
+        // last_error reads the same as the untagged error
+        assert_eq!(format!("{:#}", tagged), "Bing search failed after 3 attempts: connection reset");
+        assert_eq!(failure_stage(&anyhow::anyhow!("worker w1 stopped responding")), None);
+        assert_eq!(FailureStage::Store.as_str(), "store");
+    }
+
+    #[test]
+    fn test_partial_when_a_later_stage_fails() {
+        use StageOutcome::*;
+        let done = JobStages { serp: Succeeded, extract: Succeeded, store: Skipped };
+        assert_eq!((done.status(), done.failed_stage()), ("completed", None));
+        let no_extract = JobStages { serp: Succeeded, extract: Failed, store: Skipped };
+        assert_eq!((no_extract.status(), no_extract.failed_stage()), ("partial", Some(FailureStage::Extract)));
+        let no_upload = JobStages { serp: Succeeded, extract: Succeeded, store: Failed };
+        assert_eq!(no_upload.failed_stage(), Some(FailureStage::Store));
+        assert_eq!(
+            serde_json::to_value(no_extract).unwrap(),
+            serde_json::json!({"serp": "succeeded", "extract": "failed", "store": "skipped"})
+        );
+    }
 }
@@ -55,7 +55,7 @@ NOTIF_RES=$(curl -s -H "Authorization: Bearer $TOKEN" "$API_URL/notifications")
 echo $NOTIF_RES | jq .
 
 # Check if we have a notification about this crawl
-HAS_NOTIF=$(echo $NOTIF_RES | jq -r ".[] | select(.message | contains(\"$KEYWORD\")) | .id")
+HAS_NOTIF=$(echo $NOTIF_RES | jq -r ".notifications[] | select(.message | contains(\"$KEYWORD\")) | .id")
 
 if [ -n "$HAS_NOTIF" ]; then
   echo "✅ Found notification for this crawl!"